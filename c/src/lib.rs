@@ -89,6 +89,7 @@ pub const PF_GL_VERSION_GL4:    u8 = 2;
 
 pub const PF_RENDERER_OPTIONS_FLAGS_HAS_BACKGROUND_COLOR: u8 = 0x1;
 pub const PF_RENDERER_OPTIONS_FLAGS_SHOW_DEBUG_UI: u8 = 0x2;
+pub const PF_RENDERER_OPTIONS_FLAGS_STRAIGHT_ALPHA_OUTPUT: u8 = 0x4;
 
 pub const PF_RENDERER_LEVEL_D3D9: u8 = 0x1;
 pub const PF_RENDERER_LEVEL_D3D11: u8 = 0x2;
@@ -511,7 +512,34 @@ pub unsafe extern "C" fn PFPathEllipse(path: PFPathRef,
                                        rotation: f32,
                                        start_angle: f32,
                                        end_angle: f32) {
-    (*path).ellipse((*center).to_rust(), (*axes).to_rust(), rotation, start_angle, end_angle)
+    PFPathEllipseWithDirection(path,
+                               center,
+                               axes,
+                               rotation,
+                               start_angle,
+                               end_angle,
+                               PF_ARC_DIRECTION_CW)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn PFPathEllipseWithDirection(path: PFPathRef,
+                                                    center: *const PFVector2F,
+                                                    axes: *const PFVector2F,
+                                                    rotation: f32,
+                                                    start_angle: f32,
+                                                    end_angle: f32,
+                                                    direction: PFArcDirection) {
+    let direction = match direction {
+        PF_ARC_DIRECTION_CW  => ArcDirection::CW,
+        PF_ARC_DIRECTION_CCW => ArcDirection::CCW,
+        _                    => panic!("Invalid Pathfinder arc direction!"),
+    };
+    (*path).ellipse((*center).to_rust(),
+                    (*axes).to_rust(),
+                    rotation,
+                    start_angle,
+                    end_angle,
+                    direction)
 }
 
 #[no_mangle]
@@ -779,6 +807,12 @@ pub unsafe extern "C" fn PFBuildOptionsSetSubpixelAAEnabled(options: PFBuildOpti
     (*options).subpixel_aa_enabled = subpixel_aa_enabled
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn PFBuildOptionsSetViewBox(options: PFBuildOptionsRef,
+                                                  view_box: *const PFRectF) {
+    (*options).view_box = Some((*view_box).to_rust())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn PFSceneDestroy(scene: PFSceneRef) {
     drop(Box::from_raw(scene))
@@ -945,6 +979,8 @@ impl PFRendererOptions {
     pub fn to_rust<D>(&self) -> RendererOptions<D> where D: Device {
         let has_background_color = self.flags & PF_RENDERER_OPTIONS_FLAGS_HAS_BACKGROUND_COLOR;
         let show_debug_ui = (self.flags & PF_RENDERER_OPTIONS_FLAGS_SHOW_DEBUG_UI) != 0;
+        let premultiplied_output =
+            (self.flags & PF_RENDERER_OPTIONS_FLAGS_STRAIGHT_ALPHA_OUTPUT) == 0;
         unsafe {
             RendererOptions {
                 background_color: if has_background_color != 0 {
@@ -953,7 +989,18 @@ impl PFRendererOptions {
                     None
                 },
                 dest: *Box::from_raw(self.dest as *mut DestFramebuffer<D>),
+                premultiplied_output,
+                output_transform: Transform2F::default(),
+                max_mask_pages: None,
+                max_cached_gpu_bytes: None,
+                initial_fill_count: None,
+                initial_microline_count: None,
+                text_gamma_correction: true,
+                text_gamma_lut: None,
+                dither_gradients: false,
                 show_debug_ui,
+                show_tile_outlines: false,
+                time_render_commands: false,
             }
         }
     }
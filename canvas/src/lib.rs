@@ -13,31 +13,32 @@
 pub use pathfinder_color::{ColorF, ColorU, rgbaf, rgbau, rgbf, rgbu};
 pub use pathfinder_color::{color_slice_to_u8_slice, u8_slice_to_color_slice, u8_vec_to_color_vec};
 pub use pathfinder_content::fill::FillRule;
-pub use pathfinder_content::stroke::LineCap;
+pub use pathfinder_content::stroke::{LineCap, ParseKeywordError};
 pub use pathfinder_content::outline::ArcDirection;
 pub use pathfinder_geometry::rect::{RectF, RectI};
 pub use pathfinder_geometry::transform2d::Transform2F;
 pub use pathfinder_geometry::vector::{IntoVector2F, Vector2F, Vector2I, vec2f, vec2i};
 
 use pathfinder_content::dash::OutlineDash;
-use pathfinder_content::effects::{BlendMode, BlurDirection, PatternFilter};
-use pathfinder_content::gradient::Gradient;
+use pathfinder_content::effects::{BlendMode, DropShadow};
+use pathfinder_content::gradient::{ColorStop, Gradient};
 use pathfinder_content::outline::{Contour, Outline};
 use pathfinder_content::pattern::{Image, Pattern};
-use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_content::stroke::{LineJoin as StrokeLineJoin};
 use pathfinder_content::stroke::{OutlineStrokeToFill, StrokeStyle};
 use pathfinder_geometry::line_segment::LineSegment2F;
-use pathfinder_renderer::paint::{Paint, PaintCompositeOp};
+use pathfinder_renderer::paint::Paint;
 use pathfinder_renderer::scene::{ClipPath, ClipPathId, DrawPath, RenderTarget, Scene};
 use std::borrow::Cow;
 use std::default::Default;
 use std::f32::consts::PI;
 use std::f32;
-use std::fmt::{Debug, Error as FmtError, Formatter};
+use std::fmt::{Debug, Display, Error as FmtError, Formatter};
 use std::mem;
+use std::str::FromStr;
 use std::sync::Arc;
 
+pub use path_data::ParseError;
 pub use text::CanvasFontContext;
 
 #[cfg(feature = "pf-text")]
@@ -51,12 +52,17 @@ pub use text::TextMetrics;
 const HAIRLINE_STROKE_WIDTH: f32 = 0.0333;
 const DEFAULT_FONT_SIZE: f32 = 10.0;
 
+mod path_data;
+
 #[cfg(feature = "pf-text")]
 mod text;
 
 // For users who don't want text capability, include a tiny convenience stub.
 #[cfg(not(feature = "pf-text"))]
 mod text {
+    use crate::CanvasRenderingContext2D;
+    use pathfinder_geometry::vector::Vector2F;
+
     #[derive(Clone)]
     pub struct CanvasFontContext;
 
@@ -67,6 +73,26 @@ mod text {
     }
 
     pub struct FontCollection;
+
+    // Mirrors the trait of the same name in the `pf-text` version, so that `fill_text()` and
+    // `stroke_text()` accept the same strings whether or not text support is enabled.
+    pub trait ToTextLayout {}
+
+    impl ToTextLayout for str {}
+
+    impl ToTextLayout for String {}
+
+    impl CanvasRenderingContext2D {
+        /// Does nothing, because text support is disabled. Enable the `pf-text` feature to draw
+        /// text.
+        #[inline]
+        pub fn fill_text<T>(&mut self, _: &T, _: Vector2F) where T: ToTextLayout + ?Sized {}
+
+        /// Does nothing, because text support is disabled. Enable the `pf-text` feature to draw
+        /// text.
+        #[inline]
+        pub fn stroke_text<T>(&mut self, _: &T, _: Vector2F) where T: ToTextLayout + ?Sized {}
+    }
 }
 
 #[cfg(test)]
@@ -112,11 +138,7 @@ impl Canvas {
 
     pub fn get_context_2d(self, canvas_font_context: CanvasFontContext)
                           -> CanvasRenderingContext2D {
-        #[cfg(feature = "pf-text")]
-        let default_font_collection =
-            canvas_font_context.0.borrow().default_font_collection.clone();
-        #[cfg(not(feature = "pf-text"))]
-        let default_font_collection = Arc::new(FontCollection);
+        let default_font_collection = default_font_collection(&canvas_font_context);
         CanvasRenderingContext2D {
             canvas: self,
             current_state: State::default(default_font_collection),
@@ -137,11 +159,20 @@ impl Canvas {
     }
 }
 
+#[cfg(feature = "pf-text")]
+fn default_font_collection(canvas_font_context: &CanvasFontContext) -> Arc<FontCollection> {
+    canvas_font_context.0.borrow().default_font_collection.clone()
+}
+
+#[cfg(not(feature = "pf-text"))]
+fn default_font_collection(_: &CanvasFontContext) -> Arc<FontCollection> {
+    Arc::new(FontCollection)
+}
+
 pub struct CanvasRenderingContext2D {
     canvas: Canvas,
     current_state: State,
     saved_states: Vec<State>,
-    #[allow(dead_code)]
     canvas_font_context: CanvasFontContext,
 }
 
@@ -186,6 +217,10 @@ impl CanvasRenderingContext2D {
         self.stroke_path(path);
     }
 
+    /// Clears the given rectangle to transparent black.
+    ///
+    /// The rectangle is transformed by the current transform and bounded by the current clip
+    /// path. The fill style, global alpha, and composite operation don't apply.
     pub fn clear_rect(&mut self, rect: RectF) {
         let mut path = Path2D::new();
         path.rect(rect);
@@ -198,10 +233,28 @@ impl CanvasRenderingContext2D {
         outline.transform(&self.current_state.transform);
 
         let mut path = DrawPath::new(outline, paint_id);
+        path.set_clip_path(self.current_state.clip_path);
         path.set_blend_mode(BlendMode::Clear);
         self.canvas.scene.push_draw_path(path);
     }
 
+    /// Fills the entire view box of the scene with the current fill style.
+    ///
+    /// The filled area is specified in device space, so it covers the whole output regardless of
+    /// the current transform. The fill style itself, the clip path, the global alpha, and the
+    /// composite operation are applied as they would be for `fill_rect()`; shadows are not drawn.
+    /// An opaque fill occludes everything drawn before it.
+    pub fn fill_background(&mut self) {
+        let paint = self.current_state.resolve_paint(&self.current_state.fill_paint);
+        let paint_id = self.canvas.scene.push_paint(&paint);
+
+        let outline = Outline::from_rect(self.canvas.scene.view_box());
+        let mut path = DrawPath::new(outline, paint_id);
+        path.set_clip_path(self.current_state.clip_path);
+        path.set_blend_mode(self.current_state.global_composite_operation.to_blend_mode());
+        self.canvas.scene.push_draw_path(path);
+    }
+
     // Line styles
 
     #[inline]
@@ -250,15 +303,8 @@ impl CanvasRenderingContext2D {
     }
 
     #[inline]
-    pub fn set_line_dash(&mut self, mut new_line_dash: Vec<f32>) {
-        // Duplicate and concatenate if an odd number of dashes are present.
-        if new_line_dash.len() % 2 == 1 {
-            let mut real_line_dash = new_line_dash.clone();
-            real_line_dash.extend(new_line_dash.into_iter());
-            new_line_dash = real_line_dash;
-        }
-
-        self.current_state.line_dash = new_line_dash
+    pub fn set_line_dash(&mut self, new_line_dash: Vec<f32>) {
+        self.current_state.line_dash = normalize_line_dash(new_line_dash)
     }
 
     #[inline]
@@ -324,6 +370,27 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn stroke_path(&mut self, path: Path2D) {
+        let outline = self.stroke_outline(path.into_outline());
+        self.push_path(outline, PathOp::Stroke, FillRule::Winding);
+    }
+
+    /// Returns true if the given point, in canvas coordinates, lies inside the area that
+    /// `fill_path()` would fill with the given path and fill rule under the current transform.
+    ///
+    /// Open subpaths are treated as implicitly closed, and points exactly on an edge count as
+    /// inside. An empty path contains no points, and neither does any path if the current
+    /// transform is not invertible.
+    pub fn is_point_in_path(&self, path: &Path2D, point: Vector2F, fill_rule: FillRule) -> bool {
+        let transform = self.current_state.transform;
+        if transform.matrix.det() == 0.0 {
+            return false;
+        }
+        let outline = path.clone().into_outline();
+        outline.contains_point(transform.inverse() * point, fill_rule)
+    }
+
+    // Converts an outline to the filled outline of its stroke, using the current line style.
+    fn stroke_outline(&self, mut outline: Outline) -> Outline {
         let mut stroke_style = self.current_state.resolve_stroke_style();
 
         // The smaller scale is relevant here, as we multiply by it and want to ensure it is always
@@ -336,7 +403,6 @@ impl CanvasRenderingContext2D {
             stroke_style.line_width = HAIRLINE_STROKE_WIDTH / transform_scale;
         }
 
-        let mut outline = path.into_outline();
         if !self.current_state.line_dash.is_empty() {
             let mut dash = OutlineDash::new(&outline,
                                             &self.current_state.line_dash,
@@ -347,11 +413,16 @@ impl CanvasRenderingContext2D {
 
         let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, stroke_style);
         stroke_to_fill.offset();
-        outline = stroke_to_fill.into_outline();
-
-        self.push_path(outline, PathOp::Stroke, FillRule::Winding);
+        stroke_to_fill.into_outline()
     }
 
+    /// Intersects the current clip region with the given path, transformed by the current
+    /// transform.
+    ///
+    /// Clips accumulate: the new clip path is nested inside the existing one, so drawing is
+    /// confined to the intersection of every path clipped to since the last `restore()`. The clip
+    /// region is part of the saved state, so `restore()` returns to the region in effect at the
+    /// matching `save()`; there is no other way to widen it.
     pub fn clip_path(&mut self, path: Path2D, fill_rule: FillRule) {
         let mut outline = path.into_outline();
         outline.transform(&self.current_state.transform);
@@ -379,126 +450,34 @@ impl CanvasRenderingContext2D {
 
         outline.transform(&transform);
 
-        if !self.current_state.shadow_color.is_fully_transparent() {
-            let mut outline = outline.clone();
-            outline.transform(&Transform2F::from_translation(self.current_state.shadow_offset));
-
-            let shadow_blur_info =
-                push_shadow_blur_render_targets_if_needed(&mut self.canvas.scene,
-                                                          &self.current_state,
-                                                          outline.bounds());
-
-            if let Some(ref shadow_blur_info) = shadow_blur_info {
-                outline.transform(&Transform2F::from_translation(-shadow_blur_info.bounds
-                                                                                  .origin()
-                                                                                  .to_f32()));
-            }
-
-            // Per spec the shadow must respect the alpha of the shadowed path, but otherwise have
-            // the color of the shadow paint.
-            let mut shadow_paint = (*paint).clone();
-            let shadow_base_alpha = shadow_paint.base_color().a;
-            let mut shadow_color = self.current_state.shadow_color.to_f32();
-            shadow_color.set_a(shadow_color.a() * shadow_base_alpha as f32 / 255.0);
-            shadow_paint.set_base_color(shadow_color.to_u8());
-            if let &mut Some(ref mut shadow_paint_overlay) = shadow_paint.overlay_mut() {
-                shadow_paint_overlay.set_composite_op(PaintCompositeOp::DestIn);
-            }
-            let shadow_paint_id = self.canvas.scene.push_paint(&shadow_paint);
-
-            let mut path = DrawPath::new(outline, shadow_paint_id);
-            if shadow_blur_info.is_none() {
-                path.set_clip_path(clip_path);
-            }
-            path.set_fill_rule(fill_rule);
-            path.set_blend_mode(blend_mode);
-            self.canvas.scene.push_draw_path(path);
-
-            composite_shadow_blur_render_targets_if_needed(&mut self.canvas.scene,
-                                                           shadow_blur_info,
-                                                           clip_path);
-        }
-
         let mut path = DrawPath::new(outline, paint_id);
         path.set_clip_path(clip_path);
         path.set_fill_rule(fill_rule);
         path.set_blend_mode(blend_mode);
-        self.canvas.scene.push_draw_path(path);
-
-        fn push_shadow_blur_render_targets_if_needed(scene: &mut Scene,
-                                                     current_state: &State,
-                                                     outline_bounds: RectF)
-                                                    -> Option<ShadowBlurRenderTargetInfo> {
-            if current_state.shadow_blur == 0.0 {
-                return None;
-            }
-
-            let sigma = current_state.shadow_blur * 0.5;
-            let bounds = outline_bounds.dilate(sigma * 3.0).round_out().to_i32();
-
-            let render_target_y = RenderTarget::new(bounds.size(), String::new());
-            let render_target_id_y = scene.push_render_target(render_target_y);
-            let render_target_x = RenderTarget::new(bounds.size(), String::new());
-            let render_target_id_x = scene.push_render_target(render_target_x);
-
-            Some(ShadowBlurRenderTargetInfo {
-                id_x: render_target_id_x,
-                id_y: render_target_id_y,
-                bounds,
-                sigma,
-            })
-        }
-
-        fn composite_shadow_blur_render_targets_if_needed(scene: &mut Scene,
-                                                          info: Option<ShadowBlurRenderTargetInfo>,
-                                                          clip_path: Option<ClipPathId>) {
-            let info = match info {
-                None => return,
-                Some(info) => info,
-            };
-
-            let mut paint_x = Pattern::from_render_target(info.id_x, info.bounds.size());
-            let mut paint_y = Pattern::from_render_target(info.id_y, info.bounds.size());
-            paint_y.apply_transform(Transform2F::from_translation(info.bounds.origin().to_f32()));
-
-            let sigma = info.sigma;
-            paint_x.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::X, sigma }));
-            paint_y.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::Y, sigma }));
-
-            let paint_id_x = scene.push_paint(&Paint::from_pattern(paint_x));
-            let paint_id_y = scene.push_paint(&Paint::from_pattern(paint_y));
-
-            // TODO(pcwalton): Apply clip as necessary.
-            let outline_x = Outline::from_rect(RectF::new(vec2f(0.0, 0.0),
-                                                        info.bounds.size().to_f32()));
-            let path_x = DrawPath::new(outline_x, paint_id_x);
-            let outline_y = Outline::from_rect(info.bounds.to_f32());
-            let mut path_y = DrawPath::new(outline_y, paint_id_y);
-            path_y.set_clip_path(clip_path);
-
-            scene.pop_render_target();
-            scene.push_draw_path(path_x);
-            scene.pop_render_target();
-            scene.push_draw_path(path_y);
-        }
 
+        let shadow = DropShadow {
+            offset: self.current_state.shadow_offset,
+            sigma: self.current_state.shadow_blur * 0.5,
+            color: self.current_state.shadow_color,
+        };
+        self.canvas.scene.push_draw_path_with_drop_shadow(path, &shadow);
     }
 
     // Transformations
 
     #[inline]
     pub fn rotate(&mut self, angle: f32) {
-        self.current_state.transform *= Transform2F::from_rotation(angle)
+        self.multiply_transform(&Transform2F::from_rotation(angle))
     }
 
     #[inline]
     pub fn scale<S>(&mut self, scale: S) where S: IntoVector2F {
-        self.current_state.transform *= Transform2F::from_scale(scale)
+        self.multiply_transform(&Transform2F::from_scale(scale))
     }
 
     #[inline]
     pub fn translate(&mut self, offset: Vector2F) {
-        self.current_state.transform *= Transform2F::from_translation(offset)
+        self.multiply_transform(&Transform2F::from_translation(offset))
     }
 
     #[inline]
@@ -506,11 +485,39 @@ impl CanvasRenderingContext2D {
         self.current_state.transform
     }
 
+    /// Multiplies the current transform by the given one, so that it applies to coordinates
+    /// before the current transform does.
+    ///
+    /// This is the HTML canvas `transform()` method; `rotate()`, `scale()`, and `translate()` are
+    /// shorthands for it.
+    #[inline]
+    pub fn multiply_transform(&mut self, transform: &Transform2F) {
+        self.current_state.transform *= *transform
+    }
+
     #[inline]
     pub fn set_transform(&mut self, new_transform: &Transform2F) {
         self.current_state.transform = *new_transform;
     }
 
+    /// Returns the current transform as the six components `[a, b, c, d, e, f]` that the HTML
+    /// canvas `getTransform()` method reports.
+    #[inline]
+    pub fn transform_components(&self) -> [f32; 6] {
+        let transform = &self.current_state.transform;
+        [transform.m11(), transform.m21(), transform.m12(), transform.m22(), transform.m13(),
+         transform.m23()]
+    }
+
+    /// Replaces the current transform with the one given by its six components, like the HTML
+    /// canvas `setTransform(a, b, c, d, e, f)` method.
+    ///
+    /// A point (x, y) is transformed to (a·x + c·y + e, b·x + d·y + f).
+    #[inline]
+    pub fn set_transform_components(&mut self, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) {
+        self.current_state.transform = Transform2F::row_major(a, c, e, b, d, f);
+    }
+
     #[inline]
     pub fn reset_transform(&mut self) {
         self.current_state.transform = Transform2F::default();
@@ -562,15 +569,75 @@ impl CanvasRenderingContext2D {
         self.current_state.fill_paint = old_fill_paint;
     }
 
+    /// Draws triangles with per-vertex colors, transformed by the current transform.
+    ///
+    /// Colors are interpolated linearly across each triangle and multiplied by the global alpha.
+    /// Every three entries of `indices` refer to the vertices of one triangle. As with
+    /// `Scene::push_mesh()`, the triangles are rasterized directly rather than tiled: their edges
+    /// aren't antialiased, and the current clip path, shadow, and compositing operation don't
+    /// apply.
+    pub fn draw_mesh(&mut self, vertices: &[(Vector2F, ColorU)], indices: &[u32]) {
+        let transform = self.current_state.transform;
+        let global_alpha = self.current_state.global_alpha;
+        let vertices: Vec<_> = vertices.iter().map(|&(position, mut color)| {
+            color.a = (color.a as f32 * global_alpha).round() as u8;
+            (transform * position, color)
+        }).collect();
+        self.canvas.scene.push_mesh(&vertices, indices);
+    }
+
     // Pixel manipulation
 
+    /// Copies `image_data` into the canvas with its upper left corner at the origin of
+    /// `dest_location`, replacing the pixels underneath.
+    ///
+    /// As with `putImageData()` in the HTML canvas, the pixels are copied 1:1 at the nearest
+    /// whole pixel position: the current transform, global alpha, compositing operation, shadow,
+    /// and clip path don't apply. If `dest_location` has a size, only that much of the image,
+    /// starting from its upper left corner, is copied.
     pub fn put_image_data<L>(&mut self, image_data: ImageData, dest_location: L)
                              where L: CanvasImageDestLocation {
-        let origin = dest_location.origin();
         let size = dest_location.size().unwrap_or(image_data.size.to_f32());
-        let pattern = Pattern::from_image(image_data.into_image());
+        let dirty_rect = RectI::new(Vector2I::zero(), size.round().to_i32());
+        self.put_image_data_dirty(image_data, dest_location.origin(), dirty_rect);
+    }
+
+    /// Like `put_image_data()`, but copies only the pixels of `image_data` within `dirty_rect`,
+    /// each to the same position relative to `dest` that it has in the image.
+    ///
+    /// This corresponds to the seven-argument form of `putImageData()` in the HTML canvas. The
+    /// parts of `dirty_rect` outside the image are ignored.
+    pub fn put_image_data_dirty(&mut self,
+                                image_data: ImageData,
+                                dest: Vector2F,
+                                dirty_rect: RectI) {
+        let image_rect = RectI::new(Vector2I::zero(), image_data.size);
+        let dirty_rect = match dirty_rect.intersection(image_rect) {
+            None => return,
+            Some(dirty_rect) => dirty_rect,
+        };
+
+        let image = if dirty_rect == image_rect {
+            image_data.into_image()
+        } else {
+            // Copy out the rows of the dirty rect, which are strided by the full image width.
+            let mut pixels = Vec::with_capacity(dirty_rect.area() as usize);
+            for y in dirty_rect.min_y()..dirty_rect.max_y() {
+                let start = (y * image_data.size.x() + dirty_rect.min_x()) as usize;
+                let end = start + dirty_rect.width() as usize;
+                pixels.extend_from_slice(&image_data.data[start..end]);
+            }
+            Image::new(dirty_rect.size(), Arc::new(pixels))
+        };
+
+        let origin = dest.round() + dirty_rect.origin().to_f32();
+        let mut pattern = Pattern::from_image(image);
+        pattern.apply_transform(Transform2F::from_translation(origin));
+        pattern.set_smoothing_enabled(false);
         let paint_id = self.canvas.scene.push_paint(&Paint::from_pattern(pattern));
-        let draw_path = DrawPath::new(Outline::from_rect(RectF::new(origin, size)), paint_id);
+        let outline = Outline::from_rect(RectF::new(origin, dirty_rect.size().to_f32()));
+        let mut draw_path = DrawPath::new(outline, paint_id);
+        draw_path.set_blend_mode(BlendMode::Copy);
         self.canvas.scene.push_draw_path(draw_path);
     }
 
@@ -610,20 +677,52 @@ impl CanvasRenderingContext2D {
         }
     }
 
+    /// Clears the canvas and resets all drawing state to its defaults, including the transform,
+    /// clip, styles, and font, and discards all saved states.
+    ///
+    /// The canvas keeps its size. This allows one context to be reused across frames.
+    pub fn reset(&mut self) {
+        drop(self.canvas.take_scene());
+        self.saved_states.clear();
+        self.current_state = State::default(default_font_collection(&self.canvas_font_context));
+    }
+
     // Extensions
 
     pub fn create_pattern_from_canvas(&mut self, canvas: Canvas, transform: Transform2F)
                                       -> Pattern {
         let subscene_size = canvas.size();
-        let subscene = canvas.into_scene();
-        let render_target = RenderTarget::new(subscene_size, String::new());
+        let mut pattern = self.render_subscene_to_pattern(canvas.into_scene(), subscene_size);
+        pattern.apply_transform(transform);
+        pattern
+    }
+
+    /// Creates a fill style that uses the contents of another scene, rendered at `size` device
+    /// pixels, as a pattern repeating as specified.
+    ///
+    /// This allows scenes to be composed hierarchically: for example, a complex decorative scene
+    /// can serve as the fill of shapes in this one. The subscene is appended to this context's
+    /// scene inside a render target that is pushed and popped before any path using the returned
+    /// fill style, so the renderer draws it to a texture before sampling it.
+    ///
+    /// Each call appends another copy of the subscene, which costs one extra render pass (and one
+    /// render target) per frame. To use the same subscene many times, call this once and reuse the
+    /// returned fill style, which may be set on this context as often as desired.
+    pub fn create_pattern_from_scene(&mut self,
+                                     scene: Scene,
+                                     size: Vector2I,
+                                     repetition: Repetition)
+                                     -> FillStyle {
+        let pattern = self.render_subscene_to_pattern(scene, size);
+        FillStyle::pattern_from_image(pattern, repetition)
+    }
+
+    fn render_subscene_to_pattern(&mut self, subscene: Scene, size: Vector2I) -> Pattern {
+        let render_target = RenderTarget::new(size, String::new());
         let render_target_id = self.canvas.scene.push_render_target(render_target);
         self.canvas.scene.append_scene(subscene);
         self.canvas.scene.pop_render_target();
-
-        let mut pattern = Pattern::from_render_target(render_target_id, subscene_size);
-        pattern.apply_transform(transform);
-        pattern
+        Pattern::from_render_target(render_target_id, size)
     }
 }
 
@@ -691,6 +790,8 @@ impl State {
             return Cow::Borrowed(paint);
         }
 
+        // Paints are mapped forward through the current transform, so no inverse is needed here.
+        // The render transform's inverse is computed once per build, not once per path.
         let mut paint = (*paint).clone();
         paint.apply_transform(&self.transform);
 
@@ -745,6 +846,15 @@ impl Path2D {
         self.current_contour.push_endpoint(to);
     }
 
+    /// Adds a straight line to each of the given points in turn, like repeated calls to
+    /// `line_to()` but without the per-call overhead.
+    ///
+    /// If the current subpath is empty, the first point begins it.
+    #[inline]
+    pub fn extend_line_points<I>(&mut self, points: I) where I: IntoIterator<Item = Vector2F> {
+        self.current_contour.push_endpoints(points);
+    }
+
     #[inline]
     pub fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
         self.current_contour.push_quadratic(ctrl, to);
@@ -792,19 +902,70 @@ impl Path2D {
         self.current_contour.close();
     }
 
+    /// Adds a rectangle with rounded corners as a new, closed contour.
+    ///
+    /// `radii` gives the radius of the top-left, top-right, bottom-right, and bottom-left corners,
+    /// in that order. Each radius is clamped independently to lie between zero and half the
+    /// shorter side of the rectangle. A corner with a radius of zero is square, so all-zero radii
+    /// produce the same contour as `rect()`.
+    pub fn round_rect(&mut self, rect: RectF, radii: [f32; 4]) {
+        self.flush_current_contour();
+
+        let max_radius = f32::min(rect.width(), rect.height()).abs() * 0.5;
+        let corners = [
+            (rect.origin(),      vec2f( 1.0,  1.0), PI),
+            (rect.upper_right(), vec2f(-1.0,  1.0), PI * 1.5),
+            (rect.lower_right(), vec2f(-1.0, -1.0), 0.0),
+            (rect.lower_left(),  vec2f( 1.0, -1.0), PI * 0.5),
+        ];
+        for (&(corner, inward, start_angle), &radius) in corners.iter().zip(radii.iter()) {
+            let radius = f32::max(0.0, f32::min(radius, max_radius));
+            if radius == 0.0 {
+                self.current_contour.push_endpoint(corner);
+                continue;
+            }
+            let transform = Transform2F::from_scale(radius).translate(corner + inward * radius);
+            self.current_contour.push_arc(&transform,
+                                          start_angle,
+                                          start_angle + PI * 0.5,
+                                          ArcDirection::CW);
+        }
+
+        self.current_contour.close();
+    }
+
+    /// Adds a rectangle whose corners are all rounded with the same radius as a new, closed
+    /// contour.
+    ///
+    /// This is equivalent to calling `round_rect()` with `radius` for every corner.
+    #[inline]
+    pub fn round_rect_uniform(&mut self, rect: RectF, radius: f32) {
+        self.round_rect(rect, [radius; 4])
+    }
+
+    /// Adds an elliptical arc, rotated by `rotation` radians around `center`, as a new contour.
+    ///
+    /// As with `arc()`, the arc runs from `start_angle` to `end_angle` in `direction`, and
+    /// `end_angle` may be less than `start_angle` in either direction. If the sweep in `direction`
+    /// is a full turn or more, the whole ellipse is added and the contour is closed.
     pub fn ellipse<A>(&mut self,
                       center: Vector2F,
                       axes: A,
                       rotation: f32,
                       start_angle: f32,
-                      end_angle: f32)
+                      end_angle: f32,
+                      direction: ArcDirection)
                       where A: IntoVector2F {
         self.flush_current_contour();
 
         let transform = Transform2F::from_scale(axes).rotate(rotation).translate(center);
-        self.current_contour.push_arc(&transform, start_angle, end_angle, ArcDirection::CW);
+        self.current_contour.push_arc(&transform, start_angle, end_angle, direction);
 
-        if end_angle - start_angle >= 2.0 * PI {
+        let sweep = match direction {
+            ArcDirection::CW => end_angle - start_angle,
+            ArcDirection::CCW => start_angle - end_angle,
+        };
+        if sweep >= 2.0 * PI {
             self.current_contour.close();
         }
     }
@@ -821,6 +982,31 @@ impl Path2D {
         self.current_contour = last_contour.unwrap_or_else(Contour::new);
     }
 
+    /// Returns a copy of this path consisting of the "on" segments of the given dash pattern,
+    /// each as an open subpath.
+    ///
+    /// `dash` and `offset` have the same meaning as the arguments to `set_line_dash()` and
+    /// `set_line_dash_offset()`, respectively.
+    pub fn dashed(&self, dash: &[f32], offset: f32) -> Path2D {
+        let outline = self.clone().into_outline();
+        let dash = normalize_line_dash(dash.to_vec());
+        if dash.is_empty() {
+            return Path2D { outline, current_contour: Contour::new() };
+        }
+
+        let mut dasher = OutlineDash::new(&outline, &dash, offset);
+        dasher.dash();
+        Path2D { outline: dasher.into_outline(), current_contour: Contour::new() }
+    }
+
+    /// Removes points that lie within `tolerance` of the straight line between their neighbors.
+    ///
+    /// Curves and the ends of each subpath are preserved. See `Outline::simplify_collinear()`.
+    pub fn simplify_collinear(&mut self, tolerance: f32) {
+        self.outline.simplify_collinear(tolerance);
+        self.current_contour.simplify_collinear(tolerance);
+    }
+
     pub fn into_outline(mut self) -> Outline {
         self.flush_current_contour();
         self.outline
@@ -833,7 +1019,8 @@ impl Path2D {
     }
 }
 
-#[derive(Clone)]
+/// Fill styles compare and hash by value, so they can be used as keys when deduplicating paints.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum FillStyle {
     Color(ColorU),
     Gradient(Gradient),
@@ -841,6 +1028,28 @@ pub enum FillStyle {
 }
 
 impl FillStyle {
+    /// Creates a pattern fill style that repeats as specified, like the HTML canvas
+    /// `createPattern()` method.
+    ///
+    /// Image smoothing is not set here: like all patterns, this one is drawn smoothed or not
+    /// according to the context's `image_smoothing_enabled()` at the time it's used to fill.
+    pub fn pattern_from_image(mut pattern: Pattern, repetition: Repetition) -> FillStyle {
+        pattern.set_repeat_x(repetition == Repetition::Repeat || repetition == Repetition::RepeatX);
+        pattern.set_repeat_y(repetition == Repetition::Repeat || repetition == Repetition::RepeatY);
+        FillStyle::Pattern(pattern)
+    }
+
+    /// Creates a conic gradient fill style that sweeps clockwise around `center`, starting at
+    /// `start_angle` radians from the positive X axis, like the HTML canvas
+    /// `createConicGradient()` method.
+    pub fn conic_gradient(center: Vector2F, start_angle: f32, stops: &[ColorStop]) -> FillStyle {
+        let mut gradient = Gradient::conic(center, start_angle);
+        for &stop in stops {
+            gradient.add(stop);
+        }
+        FillStyle::Gradient(gradient)
+    }
+
     fn into_paint(self) -> Paint {
         match self {
             FillStyle::Color(color) => Paint::from_color(color),
@@ -850,6 +1059,35 @@ impl FillStyle {
     }
 }
 
+/// How a pattern repeats, corresponding to the `repetition` argument of the HTML canvas
+/// `createPattern()` method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Repetition {
+    /// Repeat in both directions.
+    Repeat,
+    /// Repeat horizontally only.
+    RepeatX,
+    /// Repeat vertically only.
+    RepeatY,
+    /// Draw the pattern once.
+    NoRepeat,
+}
+
+impl Repetition {
+    /// Parses one of the HTML keywords `"repeat"`, `"repeat-x"`, `"repeat-y"`, or `"no-repeat"`.
+    ///
+    /// Per the HTML specification, the empty string means `"repeat"`.
+    pub fn from_keyword(keyword: &str) -> Option<Repetition> {
+        match keyword {
+            "" | "repeat" => Some(Repetition::Repeat),
+            "repeat-x" => Some(Repetition::RepeatX),
+            "repeat-y" => Some(Repetition::RepeatY),
+            "no-repeat" => Some(Repetition::NoRepeat),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TextAlign {
     Left,
@@ -879,6 +1117,33 @@ pub enum LineJoin {
     Round,
 }
 
+/// Parses the HTML canvas name of a line join: `"miter"`, `"bevel"`, or `"round"`.
+///
+/// As in HTML canvas, the miter limit is not part of the keyword; set it separately with
+/// `set_miter_limit()`.
+impl FromStr for LineJoin {
+    type Err = ParseKeywordError;
+
+    fn from_str(keyword: &str) -> Result<LineJoin, ParseKeywordError> {
+        match keyword {
+            "miter" => Ok(LineJoin::Miter),
+            "bevel" => Ok(LineJoin::Bevel),
+            "round" => Ok(LineJoin::Round),
+            _ => Err(ParseKeywordError),
+        }
+    }
+}
+
+impl Display for LineJoin {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), FmtError> {
+        formatter.write_str(match *self {
+            LineJoin::Miter => "miter",
+            LineJoin::Bevel => "bevel",
+            LineJoin::Round => "round",
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CompositeOperation {
     SourceOver,
@@ -910,6 +1175,15 @@ pub enum CompositeOperation {
 }
 
 impl CompositeOperation {
+    /// True if this composite operation requires the renderer to read back the destination.
+    ///
+    /// Such operations force rendering into an intermediate framebuffer, which is more
+    /// expensive than the operations that map directly onto GPU blending.
+    #[inline]
+    pub fn needs_readable_framebuffer(self) -> bool {
+        self.to_blend_mode().needs_readable_framebuffer()
+    }
+
     fn to_blend_mode(self) -> BlendMode {
         match self {
             CompositeOperation::Copy => BlendMode::Copy,
@@ -975,6 +1249,17 @@ impl CanvasImageSource for Canvas {
     }
 }
 
+/// Drawing a rendering context draws the contents of its canvas, like `drawImage()` with a canvas
+/// source in HTML. The contents are rendered to a render target, which is then drawn with the
+/// current transform, clip path, global alpha, and composite operation.
+impl CanvasImageSource for CanvasRenderingContext2D {
+    #[inline]
+    fn to_pattern(self, dest_context: &mut CanvasRenderingContext2D, transform: Transform2F)
+                  -> Pattern {
+        dest_context.create_pattern_from_canvas(self.into_canvas(), transform)
+    }
+}
+
 impl CanvasImageDestLocation for RectF {
     #[inline]
     fn origin(&self) -> Vector2F {
@@ -1041,13 +1326,18 @@ impl From<Pattern> for FillStyle {
     }
 }
 
-struct ShadowBlurRenderTargetInfo {
-    id_x: RenderTargetId,
-    id_y: RenderTargetId,
-    bounds: RectI,
-    sigma: f32,
+// Duplicates and concatenates the dash list if an odd number of dashes are present, per the HTML
+// canvas specification.
+fn normalize_line_dash(mut line_dash: Vec<f32>) -> Vec<f32> {
+    if line_dash.len() % 2 == 1 {
+        let mut real_line_dash = line_dash.clone();
+        real_line_dash.extend(line_dash.into_iter());
+        line_dash = real_line_dash;
+    }
+    line_dash
 }
 
+#[derive(Clone, Copy)]
 enum PathOp {
     Fill,
     Stroke,
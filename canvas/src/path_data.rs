@@ -0,0 +1,302 @@
+// pathfinder/canvas/src/path_data.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing of SVG path data (the `d` attribute).
+
+use crate::{ArcDirection, Path2D};
+use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+/// An error encountered while parsing SVG path data.
+///
+/// Each variant carries the byte offset into the string at which the error was detected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseError {
+    /// A character that cannot start a command or a number was found.
+    UnexpectedCharacter(usize),
+    /// A command was missing one or more of its arguments.
+    ExpectedNumber(usize),
+    /// An arc flag other than `0` or `1` was found.
+    ExpectedFlag(usize),
+    /// The path data did not begin with a moveto command.
+    ExpectedMoveTo(usize),
+}
+
+impl Path2D {
+    /// Parses SVG path data, as found in the `d` attribute of an SVG `<path>` element, into a new
+    /// path.
+    ///
+    /// All commands are supported, in both absolute and relative forms, including smooth curves
+    /// (`S` and `T`) and elliptical arcs (`A`). Unlike SVG renderers, which draw the path up to the
+    /// first error, this returns an error if the data is malformed anywhere.
+    pub fn from_svg_path_data(data: &str) -> Result<Path2D, ParseError> {
+        let mut parser = PathDataParser {
+            data: data.as_bytes(),
+            position: 0,
+            path: Path2D::new(),
+            current_point: Vector2F::zero(),
+            subpath_start: Vector2F::zero(),
+            last_ctrl: None,
+            closed: false,
+        };
+        parser.parse()?;
+        Ok(parser.path)
+    }
+}
+
+struct PathDataParser<'a> {
+    data: &'a [u8],
+    position: usize,
+    path: Path2D,
+    current_point: Vector2F,
+    subpath_start: Vector2F,
+    // The last control point of the previous command, if it was a curve, along with whether it was
+    // cubic, for the reflection performed by `S` and `T`.
+    last_ctrl: Option<(Vector2F, bool)>,
+    // Whether the previous command was `Z`, so that a following drawing command must begin a new
+    // subpath at the start of the closed one.
+    closed: bool,
+}
+
+impl<'a> PathDataParser<'a> {
+    fn parse(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => return Ok(()),
+            Some(b'M') | Some(b'm') => {}
+            Some(_) => return Err(ParseError::ExpectedMoveTo(self.position)),
+        }
+
+        while let Some(command) = self.peek() {
+            if !command.is_ascii_alphabetic() {
+                return Err(ParseError::UnexpectedCharacter(self.position));
+            }
+            self.position += 1;
+            self.parse_command(command)?;
+            self.skip_whitespace();
+        }
+        Ok(())
+    }
+
+    fn parse_command(&mut self, command: u8) -> Result<(), ParseError> {
+        let relative = command.is_ascii_lowercase();
+        let command = command.to_ascii_uppercase();
+
+        if command == b'Z' {
+            self.path.close_path();
+            self.current_point = self.subpath_start;
+            self.last_ctrl = None;
+            self.closed = true;
+            return Ok(());
+        }
+
+        // Every other command takes at least one set of arguments and may repeat them.
+        let mut first = true;
+        loop {
+            if !first && !self.at_number() {
+                return Ok(());
+            }
+
+            if self.closed && command != b'M' {
+                self.path.move_to(self.subpath_start);
+            }
+            self.closed = false;
+
+            let origin = if relative { self.current_point } else { Vector2F::zero() };
+            match command {
+                b'M' if first => {
+                    let to = origin + self.parse_point()?;
+                    self.path.move_to(to);
+                    self.subpath_start = to;
+                    self.current_point = to;
+                    self.last_ctrl = None;
+                }
+                // Subsequent coordinate pairs after a moveto are implicit linetos.
+                b'M' | b'L' => {
+                    let to = origin + self.parse_point()?;
+                    self.line_to(to);
+                }
+                b'H' => {
+                    let x = origin.x() + self.parse_number()?;
+                    self.line_to(vec2f(x, self.current_point.y()));
+                }
+                b'V' => {
+                    let y = origin.y() + self.parse_number()?;
+                    self.line_to(vec2f(self.current_point.x(), y));
+                }
+                b'C' | b'S' => {
+                    let ctrl0 = if command == b'C' {
+                        origin + self.parse_point()?
+                    } else {
+                        self.reflected_ctrl(true)
+                    };
+                    let ctrl1 = origin + self.parse_point()?;
+                    let to = origin + self.parse_point()?;
+                    self.path.bezier_curve_to(ctrl0, ctrl1, to);
+                    self.current_point = to;
+                    self.last_ctrl = Some((ctrl1, true));
+                }
+                b'Q' | b'T' => {
+                    let ctrl = if command == b'Q' {
+                        origin + self.parse_point()?
+                    } else {
+                        self.reflected_ctrl(false)
+                    };
+                    let to = origin + self.parse_point()?;
+                    self.path.quadratic_curve_to(ctrl, to);
+                    self.current_point = to;
+                    self.last_ctrl = Some((ctrl, false));
+                }
+                b'A' => {
+                    let radius = self.parse_point()?;
+                    let x_axis_rotation = self.parse_number()?.to_radians();
+                    let large_arc = self.parse_flag()?;
+                    let sweep = self.parse_flag()?;
+                    let to = origin + self.parse_point()?;
+                    self.arc_to(radius, x_axis_rotation, large_arc, sweep, to);
+                }
+                _ => return Err(ParseError::UnexpectedCharacter(self.position - 1)),
+            }
+            first = false;
+        }
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        self.path.line_to(to);
+        self.current_point = to;
+        self.last_ctrl = None;
+    }
+
+    // Per the SVG implementation notes, out-of-range arc parameters are corrected rather than
+    // rejected: zero radii produce a straight line, and coincident endpoints produce nothing.
+    fn arc_to(&mut self,
+              radius: Vector2F,
+              x_axis_rotation: f32,
+              large_arc: bool,
+              sweep: bool,
+              to: Vector2F) {
+        if to == self.current_point {
+            self.last_ctrl = None;
+            return;
+        }
+        if radius.x() == 0.0 || radius.y() == 0.0 {
+            self.line_to(to);
+            return;
+        }
+
+        // A positive-angle sweep is clockwise in the y-down coordinate system.
+        let direction = if sweep { ArcDirection::CW } else { ArcDirection::CCW };
+        self.path.current_contour.push_svg_arc(radius, x_axis_rotation, large_arc, direction, to);
+        self.current_point = to;
+        self.last_ctrl = None;
+    }
+
+    // Returns the first control point of a smooth curve: the reflection of the previous curve's
+    // last control point if that curve was of the same kind, or the current point otherwise.
+    fn reflected_ctrl(&self, cubic: bool) -> Vector2F {
+        match self.last_ctrl {
+            Some((ctrl, last_cubic)) if last_cubic == cubic => self.current_point * 2.0 - ctrl,
+            _ => self.current_point,
+        }
+    }
+
+    fn parse_point(&mut self) -> Result<Vector2F, ParseError> {
+        let x = self.parse_number()?;
+        let y = self.parse_number()?;
+        Ok(vec2f(x, y))
+    }
+
+    fn parse_number(&mut self) -> Result<f32, ParseError> {
+        self.skip_separators();
+        let start = self.position;
+
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let integer_digits = self.skip_digits();
+        let mut fraction_digits = 0;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            fraction_digits = self.skip_digits();
+        }
+        if integer_digits == 0 && fraction_digits == 0 {
+            self.position = start;
+            return Err(ParseError::ExpectedNumber(start));
+        }
+
+        // Only consume an exponent if digits follow it, so that e.g. `1e` is not misparsed.
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.position = mantissa_end;
+            }
+        }
+
+        let text = std::str::from_utf8(&self.data[start..self.position]).unwrap();
+        text.parse().map_err(|_| ParseError::ExpectedNumber(start))
+    }
+
+    // Flags may be written without separators, as in `a10 10 0 0110 10`.
+    fn parse_flag(&mut self) -> Result<bool, ParseError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(ParseError::ExpectedFlag(self.position)),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+
+    // Returns true if the next argument, after any separators, begins a number.
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        match self.peek() {
+            Some(byte) => byte.is_ascii_digit() || byte == b'+' || byte == b'-' || byte == b'.',
+            None => false,
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(byte) = self.peek() {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            if !byte.is_ascii_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).cloned()
+    }
+}
@@ -3,8 +3,25 @@
 // For this file only, any copyright is dedicated to the Public Domain.
 // https://creativecommons.org/publicdomain/zero/1.0/
 
-use pathfinder_geometry::vector::{Vector2F, vec2f};
-use super::Path2D;
+use pathfinder_color::ColorU;
+use pathfinder_content::fill::FillRule;
+use pathfinder_content::gradient::{ColorStop, Gradient, GradientGeometry};
+use pathfinder_content::pattern::{Image, Pattern, PatternSource};
+use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, vec2f, vec2i};
+use pathfinder_renderer::paint::PaintId;
+use pathfinder_renderer::scene::{DrawPathId, MeshId};
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use super::{ArcDirection, Canvas, CanvasFontContext, FillStyle, ImageData, LineCap, LineJoin};
+use super::{ParseError, ParseKeywordError, Path2D, Repetition};
+
+#[cfg(feature = "pf-text")]
+use font_kit::loaders::default::Font;
+#[cfg(feature = "pf-text")]
+use super::TextAlign;
 
 #[test]
 pub fn test_path2d_formatting() {
@@ -17,3 +34,660 @@ pub fn test_path2d_formatting() {
     path.close_path();
     assert_eq!(format!("{:?}", path), "M 0 1 L 2 3 L 4 5 z");
 }
+
+#[test]
+pub fn test_path2d_dashed() {
+    let mut path = Path2D::new();
+    path.ellipse(vec2f(0.0, 0.0),
+                 Vector2F::splat(10.0),
+                 0.0,
+                 0.0,
+                 PI * 2.0,
+                 ArcDirection::CW);
+
+    // The circumference is ~62.8, so a period of 10 yields 7 dashes.
+    let dashed = path.dashed(&[5.0, 5.0], 1.0);
+    assert_eq!(dashed.into_outline().contours().len(), 7);
+
+    // Odd-length dash lists are duplicated.
+    let dashed = path.dashed(&[5.0], 1.0);
+    assert_eq!(dashed.into_outline().contours().len(), 7);
+
+    // An empty dash list leaves the path unchanged.
+    let dashed = path.dashed(&[], 0.0);
+    assert_eq!(dashed.into_outline().contours().len(), 1);
+}
+
+#[test]
+pub fn test_path2d_simplify_collinear() {
+    let mut path = Path2D::new();
+    path.move_to(vec2f(0.0, 0.0));
+    path.line_to(vec2f(1.0, 0.001));
+    path.line_to(vec2f(2.0, 0.0));
+    path.line_to(vec2f(3.0, 0.0));
+    path.line_to(vec2f(3.0, 3.0));
+    path.close_path();
+    path.simplify_collinear(0.01);
+    assert_eq!(format!("{:?}", path), "M 0 0 L 3 0 L 3 3 z");
+}
+
+#[test]
+pub fn test_fill_style_dedup() {
+    let mut gradient_a = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(1.0, 0.0));
+    gradient_a.add_color_stop(ColorU::black(), 0.0);
+    let mut gradient_b = Gradient::linear_from_points(vec2f(-0.0, 0.0), vec2f(1.0, 0.0));
+    gradient_b.add_color_stop(ColorU::black(), -0.0);
+
+    let mut fill_styles = HashSet::new();
+    fill_styles.insert(FillStyle::Gradient(gradient_a));
+    fill_styles.insert(FillStyle::Gradient(gradient_b));
+    fill_styles.insert(FillStyle::Color(ColorU::black()));
+    fill_styles.insert(FillStyle::Color(ColorU::black()));
+    assert_eq!(fill_styles.len(), 2);
+}
+
+#[test]
+pub fn test_device_space_pattern_ignores_transform() {
+    // A graph paper grid behind content that's drawn at two scales.
+    let grid_image = Image::new(vec2i(8, 8), Arc::new(vec![ColorU::white(); 64]));
+    let mut grid = Pattern::from_image(grid_image.clone());
+    grid.set_device_space(true);
+    let scaled_grid = Pattern::from_image(grid_image);
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_fill_style(FillStyle::Pattern(grid));
+    context.translate(vec2f(10.0, 20.0));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(40.0, 40.0)));
+    context.scale(2.0);
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(40.0, 40.0)));
+    context.set_fill_style(FillStyle::Pattern(scaled_grid));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(40.0, 40.0)));
+
+    // The grid stays put however the drawing is transformed, so both fills share one paint...
+    let scene = context.into_canvas().into_scene();
+    let pattern_transform = |index| scene.get_paint(PaintId(index)).pattern().unwrap().transform();
+    assert_eq!(pattern_transform(0), Transform2F::default());
+
+    // ...while an ordinary pattern follows it.
+    assert_eq!(pattern_transform(1),
+               Transform2F::from_translation(vec2f(10.0, 20.0)) * Transform2F::from_scale(2.0));
+}
+
+#[test]
+pub fn test_extend_line_points() {
+    let points = [vec2f(2.0, 3.0), vec2f(4.0, 5.0), vec2f(-1.0, 6.0)];
+
+    let mut expected = Path2D::new();
+    expected.move_to(vec2f(0.0, 1.0));
+    for &point in &points {
+        expected.line_to(point);
+    }
+
+    let mut path = Path2D::new();
+    path.move_to(vec2f(0.0, 1.0));
+    path.extend_line_points(points.iter().cloned());
+    assert_eq!(format!("{:?}", path), format!("{:?}", expected));
+    assert_eq!(path.into_outline().bounds(), expected.into_outline().bounds());
+}
+
+#[test]
+pub fn test_draw_mesh() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.translate(vec2f(10.0, 20.0));
+    context.set_global_alpha(0.5);
+    context.draw_mesh(&[
+        (vec2f(0.0, 0.0), ColorU::black()),
+        (vec2f(4.0, 0.0), ColorU::white()),
+        (vec2f(0.0, 4.0), ColorU::white()),
+    ], &[0, 1, 2]);
+
+    let scene = context.into_canvas().into_scene();
+    let mesh = scene.get_mesh(MeshId(0));
+    assert_eq!(mesh.positions()[1], vec2f(14.0, 20.0));
+    assert_eq!(mesh.colors()[0].a, 128);
+    assert_eq!(mesh.indices(), &[0, 1, 2]);
+}
+
+#[cfg(feature = "pf-text")]
+#[test]
+pub fn test_fill_text_with_gradient() {
+    let mut gradient = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(100.0, 0.0));
+    gradient.add_color_stop(ColorU::black(), 0.0);
+    gradient.add_color_stop(ColorU::white(), 1.0);
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_fill_style(gradient.clone());
+    context.fill_text("Hello", vec2f(10.0, 50.0));
+
+    let scene = context.into_canvas().into_scene();
+    let draw_path = scene.get_draw_path(DrawPathId(scene.draw_path_count() - 1));
+    assert_eq!(scene.get_paint(draw_path.paint()).gradient(), Some(&gradient));
+}
+
+#[cfg(feature = "pf-text")]
+#[test]
+pub fn test_measure_text() {
+    const FONT_PATH: &str = "../resources/fonts/Roboto-Regular.ttf";
+    const FONT_SIZE: f32 = 32.0;
+    const TEXT: &str = "Hello";
+
+    let font = Font::from_path(FONT_PATH, 0).unwrap();
+    let scale_factor = FONT_SIZE / font.metrics().units_per_em as f32;
+    let expected_width: f32 = TEXT.chars().map(|character| {
+        let glyph_id = font.glyph_for_char(character).unwrap();
+        font.advance(glyph_id).unwrap().x() * scale_factor
+    }).sum();
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_font(font);
+    context.set_font_size(FONT_SIZE);
+
+    let left_metrics = context.measure_text(TEXT);
+    assert!((left_metrics.width() - expected_width).abs() < 0.01);
+    assert!(left_metrics.font_bounding_box_ascent() > 0.0);
+    assert!(left_metrics.font_bounding_box_descent() < 0.0);
+
+    // Alignment shifts the horizontal extents but not the width.
+    context.set_text_align(TextAlign::Center);
+    let center_metrics = context.measure_text(TEXT);
+    assert_eq!(center_metrics.width(), left_metrics.width());
+    let shift = -0.5 * left_metrics.width();
+    assert!((center_metrics.actual_bounding_box_left() -
+             (left_metrics.actual_bounding_box_left() + shift)).abs() < 0.01);
+    assert!((center_metrics.actual_bounding_box_right() -
+             (left_metrics.actual_bounding_box_right() + shift)).abs() < 0.01);
+
+    context.set_text_align(TextAlign::Right);
+    let right_metrics = context.measure_text(TEXT);
+    assert!((right_metrics.actual_bounding_box_right() -
+             (left_metrics.actual_bounding_box_right() - left_metrics.width())).abs() < 0.01);
+}
+
+#[test]
+pub fn test_shadow_with_gradient_fill() {
+    let mut gradient = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+    gradient.add_color_stop(ColorU::black(), 0.0);
+    gradient.add_color_stop(ColorU::white(), 1.0);
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    let shadow_color = ColorU::new(255, 0, 0, 128);
+    context.set_shadow_color(shadow_color);
+    context.set_shadow_offset(vec2f(2.0, 2.0));
+    context.set_fill_style(gradient);
+    context.translate(vec2f(10.0, 10.0));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+    assert_eq!(context.shadow_color(), shadow_color);
+
+    // The shadow takes its color from the shadow color, not from the gradient.
+    let scene = context.into_canvas().into_scene();
+    let shadow_path = scene.get_draw_path(DrawPathId(0));
+    let shadow_paint_color = scene.get_paint(shadow_path.paint()).base_color();
+    assert!(shadow_paint_color.r >= 254 && shadow_paint_color.g == 0 && shadow_paint_color.b == 0);
+    assert!((shadow_paint_color.a as i32 - shadow_color.a as i32).abs() <= 1);
+}
+
+#[test]
+pub fn test_line_dash_readback() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    assert!(context.line_dash().is_empty());
+
+    // Odd-length dash lists are stored duplicated.
+    context.set_line_dash(vec![5.0]);
+    assert_eq!(context.line_dash(), &[5.0, 5.0]);
+    context.set_line_dash(vec![1.0, 2.0]);
+    assert_eq!(context.line_dash(), &[1.0, 2.0]);
+
+    context.set_line_dash_offset(3.0);
+    assert_eq!(context.line_dash_offset(), 3.0);
+}
+
+#[test]
+pub fn test_reset() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 50.0)).get_context_2d(font_context);
+    context.set_line_width(4.0);
+    context.save();
+    context.translate(vec2f(10.0, 10.0));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+
+    context.reset();
+    assert_eq!(context.line_width(), 1.0);
+    assert!(context.transform().is_identity());
+
+    // Restoring after a reset has no effect, because the saved states are gone.
+    context.restore();
+    assert_eq!(context.line_width(), 1.0);
+
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.draw_path_count(), 0);
+    assert_eq!(scene.view_box(), RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 50.0)));
+}
+
+#[test]
+pub fn test_paint_follows_transform_across_save_restore() {
+    let mut gradient = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+    gradient.add_color_stop(ColorU::black(), 0.0);
+    gradient.add_color_stop(ColorU::white(), 1.0);
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_fill_style(gradient.clone());
+    context.translate(vec2f(10.0, 0.0));
+    context.save();
+    context.scale(vec2f(2.0, 2.0));
+    context.rotate(PI * 0.5);
+    context.restore();
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+
+    let mut expected_gradient = gradient;
+    expected_gradient.apply_transform(Transform2F::from_translation(vec2f(10.0, 0.0)));
+    let scene = context.into_canvas().into_scene();
+    let draw_path = scene.get_draw_path(DrawPathId(scene.draw_path_count() - 1));
+    assert_eq!(scene.get_paint(draw_path.paint()).gradient(), Some(&expected_gradient));
+}
+
+#[test]
+pub fn test_conic_gradient() {
+    // Four stops, one per quadrant, starting straight up from the center of the canvas.
+    let colors = [ColorU::new(255, 0, 0, 255),
+                  ColorU::new(0, 255, 0, 255),
+                  ColorU::new(0, 0, 255, 255),
+                  ColorU::new(255, 255, 0, 255)];
+    let stops: Vec<_> = colors.iter().enumerate().map(|(index, &color)| {
+        ColorStop::new(color, index as f32 * 0.25)
+    }).collect();
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_fill_style(FillStyle::conic_gradient(vec2f(50.0, 50.0), -0.5 * PI, &stops));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 100.0)));
+
+    let scene = context.into_canvas().into_scene();
+    let draw_path = scene.get_draw_path(DrawPathId(scene.draw_path_count() - 1));
+    let gradient = scene.get_paint(draw_path.paint()).gradient().unwrap();
+    assert_eq!(gradient.stops().len(), 4);
+    let (center, start_angle) = match gradient.geometry {
+        GradientGeometry::Conic { center, start_angle, .. } => (center, start_angle),
+        _ => panic!("expected a conic gradient"),
+    };
+
+    // The top, right, bottom, and left edges get the four colors in turn.
+    let points = [vec2f(50.0, 0.0), vec2f(100.0, 50.0), vec2f(50.0, 100.0), vec2f(0.0, 50.0)];
+    for (index, point) in points.iter().enumerate() {
+        let offset = GradientGeometry::conic_offset(center, start_angle, *point);
+        assert!((offset - index as f32 * 0.25).abs() < 0.001);
+        let (sample, color) = (gradient.sample(offset), colors[index]);
+        for &(a, b) in &[(sample.r, color.r), (sample.g, color.g), (sample.b, color.b)] {
+            assert!((a as i32 - b as i32).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn test_ellipse_direction() {
+    // Sweeping from 0 to π clockwise in y-down space covers the lower half of the ellipse;
+    // counterclockwise covers the upper half. The outline's bounds include the control points,
+    // which lie outside the ellipse, so only the on-curve points are measured.
+    let ellipse_endpoints = |start_angle, end_angle, direction| {
+        let mut path = Path2D::new();
+        path.ellipse(vec2f(0.0, 0.0), vec2f(20.0, 10.0), 0.0, start_angle, end_angle, direction);
+        let outline = path.into_outline();
+        let contour = &outline.contours()[0];
+        (0..contour.len()).filter(|&point_index| contour.point_is_endpoint(point_index))
+                          .map(|point_index| contour.position_of(point_index))
+                          .collect::<Vec<_>>()
+    };
+    let half_ellipse_bounds = |start_angle, end_angle, direction| {
+        let endpoints = ellipse_endpoints(start_angle, end_angle, direction);
+        endpoints.iter().fold(RectF::from_points(endpoints[0], endpoints[0]),
+                              |bounds, &endpoint| bounds.union_point(endpoint))
+    };
+
+    let cw = half_ellipse_bounds(0.0, PI, ArcDirection::CW);
+    assert!((cw.max_y() - 10.0).abs() < 0.01 && cw.min_y().abs() < 0.01);
+    let ccw = half_ellipse_bounds(0.0, PI, ArcDirection::CCW);
+    assert!((ccw.min_y() + 10.0).abs() < 0.01 && ccw.max_y().abs() < 0.01);
+
+    // `end_angle < start_angle` wraps around in either direction.
+    let cw = half_ellipse_bounds(PI, 0.0, ArcDirection::CW);
+    assert!((cw.min_y() + 10.0).abs() < 0.01 && cw.max_y().abs() < 0.01);
+    let ccw = half_ellipse_bounds(PI, 0.0, ArcDirection::CCW);
+    assert!((ccw.max_y() - 10.0).abs() < 0.01 && ccw.min_y().abs() < 0.01);
+
+    // A full turn yields the whole ellipse, starting at the start angle.
+    let full = half_ellipse_bounds(PI * 2.0, 0.0, ArcDirection::CCW);
+    assert!((full.width() - 40.0).abs() < 0.01 && (full.height() - 20.0).abs() < 0.01);
+    for &direction in &[ArcDirection::CW, ArcDirection::CCW] {
+        let start = ellipse_endpoints(PI * 0.5, PI * 2.5, direction)[0];
+        assert!(start.x().abs() < 0.01 && (start.y() - 10.0).abs() < 0.01);
+    }
+}
+
+#[test]
+pub fn test_path2d_from_svg_path_data() {
+    // Relative moves, implicit linetos after a moveto, and H/V.
+    let path = Path2D::from_svg_path_data("m10,10 20 0 v20 h-20 z m5-5 l1 1").unwrap();
+    assert_eq!(format!("{:?}", path), "M 10 10 L 30 10 L 30 30 L 10 30 z M 15 5 L 16 6");
+
+    // Drawing after a closepath starts a new subpath at the start of the closed one.
+    let path = Path2D::from_svg_path_data("M0 0L1 0 1 1Z L2 2").unwrap();
+    assert_eq!(format!("{:?}", path), "M 0 0 L 1 0 L 1 1 z M 0 0 L 2 2");
+
+    // Numbers may run together when unambiguous.
+    let path = Path2D::from_svg_path_data("M.5.5-1-1e1").unwrap();
+    assert_eq!(format!("{:?}", path), "M 0.5 0.5 L -1 -10");
+
+    // Smooth curves reflect the previous control point.
+    let path = Path2D::from_svg_path_data("M0 0 C0 10 10 10 10 0 S20 -10 20 0").unwrap();
+    assert_eq!(format!("{:?}", path), "M 0 0 C 0 10 10 10 10 0 C 10 -10 20 -10 20 0");
+    let path = Path2D::from_svg_path_data("M0 0 Q5 10 10 0 t10 0").unwrap();
+    assert_eq!(format!("{:?}", path), "M 0 0 Q 5 10 10 0 Q 15 -10 20 0");
+
+    assert_eq!(Path2D::from_svg_path_data("L 0 0").unwrap_err(), ParseError::ExpectedMoveTo(0));
+    assert_eq!(Path2D::from_svg_path_data("M 0").unwrap_err(), ParseError::ExpectedNumber(3));
+    assert_eq!(Path2D::from_svg_path_data("M 0 0 A 1 1 0 2 0 1 1").unwrap_err(),
+               ParseError::ExpectedFlag(14));
+}
+
+#[test]
+pub fn test_path2d_from_svg_path_data_arc_flags() {
+    // Half circles of radius 10 from (0, 0) to (20, 0). A positive-angle sweep is clockwise, so
+    // it passes above the chord in the y-down coordinate system.
+    let arc_bounds = |data: &str| Path2D::from_svg_path_data(data).unwrap().into_outline().bounds();
+    let above = arc_bounds("M0 0 A10 10 0 0 1 20 0");
+    assert!((above.min_y() + 10.0).abs() < 0.01 && above.max_y().abs() < 0.01);
+    let below = arc_bounds("M0 0 a10,10 0 0,0 20,0");
+    assert!((below.max_y() - 10.0).abs() < 0.01 && below.min_y().abs() < 0.01);
+
+    // With a smaller chord, the large-arc flag selects the longer way around; flags may be written
+    // without separators.
+    let small = arc_bounds("M0 0 A10 10 0 0 1 10 0");
+    let large = arc_bounds("M0 0 A10 10 0 1110 0");
+    // Bounds include control points, so allow some slack beyond the curve.
+    assert!(small.min_y() > -3.0);
+    assert!(large.min_y() < -18.0);
+
+    // Zero radii degenerate to a line.
+    let path = Path2D::from_svg_path_data("M0 0 A0 10 0 0 1 20 0").unwrap();
+    assert_eq!(format!("{:?}", path), "M 0 0 L 20 0");
+}
+
+#[test]
+pub fn test_fill_background_ignores_transform() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 80.0)).get_context_2d(font_context);
+    context.translate(vec2f(30.0, -20.0));
+    context.scale(vec2f(0.25, 4.0));
+    context.set_fill_style(ColorU::new(0, 128, 255, 255));
+    context.fill_background();
+
+    let scene = context.into_canvas().into_scene();
+    let draw_path = scene.get_draw_path(DrawPathId(0));
+    assert_eq!(draw_path.outline().bounds(), RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 80.0)));
+    assert_eq!(scene.get_paint(draw_path.paint()).base_color(), ColorU::new(0, 128, 255, 255));
+    assert!(scene.get_paint(draw_path.paint()).is_opaque());
+}
+
+#[test]
+pub fn test_pattern_repetition() {
+    assert_eq!(Repetition::from_keyword("repeat-x"), Some(Repetition::RepeatX));
+    assert_eq!(Repetition::from_keyword(""), Some(Repetition::Repeat));
+    assert_eq!(Repetition::from_keyword("repeat-z"), None);
+
+    let image = Image::new(vec2i(4, 4), Arc::new(vec![ColorU::white(); 16]));
+    let fill_style = FillStyle::pattern_from_image(Pattern::from_image(image), Repetition::RepeatX);
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_image_smoothing_enabled(false);
+    context.set_fill_style(fill_style);
+    context.translate(vec2f(10.0, 20.0));
+    context.scale(2.0);
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(40.0, 4.0)));
+
+    let scene = context.into_canvas().into_scene();
+    let paint = scene.get_paint(scene.get_draw_path(DrawPathId(0)).paint());
+    let pattern = paint.pattern().unwrap();
+    assert!(pattern.repeat_x());
+    assert!(!pattern.repeat_y());
+    assert!(!pattern.smoothing_enabled());
+    assert_eq!(pattern.transform(),
+               Transform2F::from_translation(vec2f(10.0, 20.0)) * Transform2F::from_scale(2.0));
+}
+
+#[test]
+pub fn test_is_point_in_path() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+
+    // An open triangle, implicitly closed, containing a smaller one wound the same way, which is
+    // a hole only under the even-odd rule.
+    let mut path = Path2D::new();
+    path.move_to(vec2f(0.0, 0.0));
+    path.line_to(vec2f(40.0, 0.0));
+    path.line_to(vec2f(0.0, 40.0));
+    path.move_to(vec2f(5.0, 5.0));
+    path.line_to(vec2f(15.0, 5.0));
+    path.line_to(vec2f(5.0, 15.0));
+
+    assert!(context.is_point_in_path(&path, vec2f(20.0, 10.0), FillRule::Winding));
+    assert!(context.is_point_in_path(&path, vec2f(7.0, 7.0), FillRule::Winding));
+    assert!(!context.is_point_in_path(&path, vec2f(7.0, 7.0), FillRule::EvenOdd));
+    assert!(!context.is_point_in_path(&path, vec2f(30.0, 30.0), FillRule::Winding));
+    assert!(!context.is_point_in_path(&path, vec2f(-1.0, 10.0), FillRule::Winding));
+
+    // Points on edges, including the implicit closing edge, are inside.
+    assert!(context.is_point_in_path(&path, vec2f(0.0, 20.0), FillRule::EvenOdd));
+    assert!(context.is_point_in_path(&path, vec2f(20.0, 20.0), FillRule::EvenOdd));
+    assert!(context.is_point_in_path(&path, vec2f(10.0, 0.0), FillRule::EvenOdd));
+
+    // The point is in canvas coordinates, so it is mapped through the inverse transform.
+    context.translate(vec2f(50.0, 50.0));
+    context.scale(0.5);
+    assert!(!context.is_point_in_path(&path, vec2f(20.0, 10.0), FillRule::Winding));
+    assert!(context.is_point_in_path(&path, vec2f(60.0, 55.0), FillRule::Winding));
+
+    context.scale(0.0);
+    assert!(!context.is_point_in_path(&path, vec2f(50.0, 50.0), FillRule::Winding));
+    assert!(!context.is_point_in_path(&Path2D::new(), vec2f(0.0, 0.0), FillRule::Winding));
+}
+
+#[test]
+pub fn test_transform_helpers() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.translate(vec2f(10.0, 20.0));
+    context.fill_rect(RectF::new(vec2f(1.0, 2.0), vec2f(3.0, 4.0)));
+
+    // `multiply_transform()` applies before the existing translation.
+    context.multiply_transform(&Transform2F::from_scale(2.0));
+    context.fill_rect(RectF::new(vec2f(1.0, 2.0), vec2f(3.0, 4.0)));
+    assert_eq!(context.transform(),
+               Transform2F::from_translation(vec2f(10.0, 20.0)) * Transform2F::from_scale(2.0));
+
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.get_draw_path(DrawPathId(0)).outline().bounds(),
+               RectF::new(vec2f(11.0, 22.0), vec2f(3.0, 4.0)));
+    assert_eq!(scene.get_draw_path(DrawPathId(1)).outline().bounds(),
+               RectF::new(vec2f(12.0, 24.0), vec2f(6.0, 8.0)));
+}
+
+#[test]
+pub fn test_transform_components() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_transform_components(2.0, 0.5, -1.0, 3.0, 10.0, 20.0);
+    assert_eq!(context.transform(), Transform2F::row_major(2.0, -1.0, 10.0, 0.5, 3.0, 20.0));
+    assert_eq!(context.transform() * vec2f(1.0, 1.0), vec2f(11.0, 23.5));
+    assert_eq!(context.transform_components(), [2.0, 0.5, -1.0, 3.0, 10.0, 20.0]);
+
+    // Further transforms compose with it as with one set from a `Transform2F`.
+    context.translate(vec2f(1.0, 0.0));
+    assert_eq!(context.transform(),
+               Transform2F::row_major(2.0, -1.0, 10.0, 0.5, 3.0, 20.0) *
+               Transform2F::from_translation(vec2f(1.0, 0.0)));
+}
+
+#[test]
+pub fn test_clip_paths_intersect() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    let mut left = Path2D::new();
+    left.rect(RectF::new(vec2f(0.0, 0.0), vec2f(60.0, 100.0)));
+    context.clip_path(left, FillRule::Winding);
+    let mut right = Path2D::new();
+    right.rect(RectF::new(vec2f(40.0, 0.0), vec2f(60.0, 100.0)));
+    context.clip_path(right, FillRule::Winding);
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 100.0)));
+    let scene = context.into_canvas().into_scene();
+
+    // Only the overlap of the two clip rects is drawn.
+    assert_eq!(scene.pick_draw_path(vec2f(50.0, 50.0)), Some(DrawPathId(0)));
+    assert_eq!(scene.pick_draw_path(vec2f(20.0, 50.0)), None);
+    assert_eq!(scene.pick_draw_path(vec2f(80.0, 50.0)), None);
+}
+
+#[test]
+pub fn test_clear_rect_respects_clip() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.rotate(PI * 0.25);
+    let mut clip = Path2D::new();
+    clip.rect(RectF::new(vec2f(0.0, 0.0), vec2f(40.0, 40.0)));
+    context.clip_path(clip, FillRule::Winding);
+    context.clear_rect(RectF::new(vec2f(-100.0, -100.0), vec2f(200.0, 200.0)));
+    let scene = context.into_canvas().into_scene();
+
+    // Only the rotated clip square is cleared.
+    assert_eq!(scene.pick_draw_path(vec2f(0.0, 20.0)), Some(DrawPathId(0)));
+    assert_eq!(scene.pick_draw_path(vec2f(20.0, 5.0)), None);
+    assert_eq!(scene.pick_draw_path(vec2f(-20.0, 5.0)), None);
+}
+
+#[test]
+pub fn test_round_rect() {
+    let rect = RectF::new(vec2f(10.0, 20.0), vec2f(40.0, 30.0));
+
+    // With zero radii, a rounded rectangle is a plain rectangle.
+    let mut square = Path2D::new();
+    square.rect(rect);
+    let mut rounded = Path2D::new();
+    rounded.round_rect(rect, [0.0; 4]);
+    let (square, rounded) = (square.into_outline(), rounded.into_outline());
+    assert_eq!(rounded.contours().len(), 1);
+    assert_eq!(rounded.contours()[0].points(), square.contours()[0].points());
+    assert_eq!(rounded.contours()[0].is_closed(), square.contours()[0].is_closed());
+
+    // Oversized radii are clamped to half the shorter side, so the rectangle's bounds survive.
+    let mut rounded = Path2D::new();
+    rounded.round_rect_uniform(rect, 100.0);
+    let rounded = rounded.into_outline();
+    assert!(rounded.contains_point(vec2f(30.0, 35.0), FillRule::Winding));
+    assert!(!rounded.contains_point(vec2f(11.0, 21.0), FillRule::Winding));
+    assert!((rounded.bounds().min_x() - 10.0).abs() < 0.01);
+    assert!((rounded.bounds().max_y() - 50.0).abs() < 0.01);
+}
+
+#[test]
+pub fn test_create_pattern_from_scene() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut subcontext = Canvas::new(vec2f(16.0, 16.0)).get_context_2d(font_context.clone());
+    subcontext.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(8.0, 8.0)));
+    subcontext.fill_rect(RectF::new(vec2f(8.0, 8.0), vec2f(8.0, 8.0)));
+    let subscene = subcontext.into_canvas().into_scene();
+
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    let fill_style = context.create_pattern_from_scene(subscene, vec2i(16, 16), Repetition::Repeat);
+    match fill_style {
+        FillStyle::Pattern(ref pattern) => {
+            assert_eq!(pattern.size(), vec2i(16, 16));
+            assert!(pattern.repeat_x() && pattern.repeat_y());
+        }
+        _ => panic!("expected a pattern fill style"),
+    }
+    context.set_fill_style(fill_style);
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 100.0)));
+
+    // The subscene's paths are drawn into the render target, ahead of the path that samples it.
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.draw_path_count(), 3);
+    assert!(scene.get_paint(scene.get_draw_path(DrawPathId(2)).paint()).pattern().is_some());
+}
+
+#[test]
+pub fn test_draw_image_from_context() {
+    let font_context = CanvasFontContext::from_system_source();
+    let mut subcontext = Canvas::new(vec2f(10.0, 10.0)).get_context_2d(font_context.clone());
+    subcontext.set_fill_style(ColorU::new(255, 0, 0, 255));
+    subcontext.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_global_alpha(0.2);
+    context.draw_image(subcontext, RectF::new(vec2f(20.0, 30.0), vec2f(40.0, 20.0)));
+
+    // The red rect is drawn into a render target, which is then drawn at the destination.
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.draw_path_count(), 2);
+    let red_path = scene.get_draw_path(DrawPathId(0));
+    assert_eq!(scene.get_paint(red_path.paint()).base_color(), ColorU::new(255, 0, 0, 255));
+    let dest_path = scene.get_draw_path(DrawPathId(1));
+    assert_eq!(dest_path.outline().bounds(), RectF::new(vec2f(20.0, 30.0), vec2f(40.0, 20.0)));
+    let dest_paint = scene.get_paint(dest_path.paint());
+    let pattern = dest_paint.pattern().expect("expected a pattern paint");
+    assert_eq!(pattern.size(), vec2i(10, 10));
+    assert_eq!(dest_paint.base_color().a, 51);
+}
+
+#[test]
+pub fn test_put_image_data() {
+    let red = ColorU::new(255, 0, 0, 255);
+    let green = ColorU::new(0, 255, 0, 255);
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_transform(&Transform2F::from_scale(3.0));
+
+    // A 2×2 red block is copied 1:1 to the nearest pixel, ignoring the transform.
+    let image_data = ImageData { data: vec![red; 4], size: vec2i(2, 2) };
+    context.put_image_data(image_data, vec2f(10.4, 20.6));
+
+    // Only the right half of a 4×2 image, whose rows are strided by the full width, is copied.
+    let image_data = ImageData { data: vec![green, green, red, red, green, green, red, red],
+                                 size: vec2i(4, 2) };
+    let dirty_rect = RectI::new(vec2i(2, 0), vec2i(4, 4));
+    context.put_image_data_dirty(image_data, vec2f(40.0, 50.0), dirty_rect);
+
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.draw_path_count(), 2);
+    for (index, &origin) in [vec2f(10.0, 21.0), vec2f(42.0, 50.0)].iter().enumerate() {
+        let draw_path = scene.get_draw_path(DrawPathId(index as u32));
+        assert_eq!(draw_path.outline().bounds(), RectF::new(origin, vec2f(2.0, 2.0)));
+        let pattern = scene.get_paint(draw_path.paint()).pattern().expect("expected a pattern");
+        assert_eq!(pattern.transform(), Transform2F::from_translation(origin));
+        match *pattern.source() {
+            PatternSource::Image(ref image) => {
+                assert_eq!(image.size(), vec2i(2, 2));
+                assert_eq!(**image.pixels(), vec![red; 4]);
+            }
+            _ => panic!("expected an image pattern"),
+        }
+    }
+}
+
+#[test]
+pub fn test_line_style_keywords() {
+    for &line_cap in &[LineCap::Butt, LineCap::Round, LineCap::Square] {
+        assert_eq!(line_cap.to_string().parse::<LineCap>(), Ok(line_cap));
+    }
+    for &line_join in &[LineJoin::Miter, LineJoin::Bevel, LineJoin::Round] {
+        assert_eq!(line_join.to_string().parse::<LineJoin>(), Ok(line_join));
+    }
+    assert_eq!("square".parse::<LineCap>(), Ok(LineCap::Square));
+    assert_eq!("Round".parse::<LineJoin>(), Err(ParseKeywordError));
+    assert_eq!("miter".parse::<LineCap>(), Err(ParseKeywordError));
+}
@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::{CanvasRenderingContext2D, State, TextAlign, TextBaseline};
+use crate::{CanvasRenderingContext2D, FillRule, PathOp, State, TextAlign, TextBaseline};
 use font_kit::canvas::RasterizationOptions;
 use font_kit::family_name::FamilyName;
 use font_kit::handle::Handle;
@@ -17,11 +17,11 @@ use font_kit::loaders::default::Font;
 use font_kit::properties::Properties;
 use font_kit::source::{Source, SystemSource};
 use font_kit::sources::mem::MemSource;
+use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::util;
 use pathfinder_geometry::vector::{Vector2F, vec2f};
-use pathfinder_renderer::paint::PaintId;
-use pathfinder_text::{FontContext, FontRenderOptions, TextRenderMode};
+use pathfinder_text::{FontContext, FontRenderOptions};
 use skribo::{FontCollection, FontFamily, FontRef, Layout as SkriboLayout, TextStyle};
 use std::borrow::Cow;
 use std::cell::{Cell, RefCell};
@@ -31,14 +31,16 @@ use std::sync::Arc;
 impl CanvasRenderingContext2D {
     /// Fills the given text using the current style.
     ///
+    /// Every fill style is supported, including gradients and patterns. The glyphs are filled
+    /// exactly like a path passed to `fill_path()`, so gradients and patterns are positioned in
+    /// canvas coordinates rather than relative to the text.
+    ///
     /// As an extension, you may pass in the `TextMetrics` object returned by `measure_text()` to
     /// fill the text that you passed into `measure_text()` with the layout-related style
     /// properties set at the time you called that function. This allows Pathfinder to skip having
     /// to lay out the text again.
     pub fn fill_text<T>(&mut self, text: &T, position: Vector2F) where T: ToTextLayout + ?Sized {
-        let paint = self.current_state.resolve_paint(&self.current_state.fill_paint);
-        let paint_id = self.canvas.scene.push_paint(&paint);
-        self.fill_or_stroke_text(text, position, paint_id, TextRenderMode::Fill);
+        self.fill_or_stroke_text(text, position, PathOp::Fill);
     }
 
     /// Strokes the given text using the current style.
@@ -48,10 +50,7 @@ impl CanvasRenderingContext2D {
     /// properties set at the time you called that function. This allows Pathfinder to skip having
     /// to lay out the text again.
     pub fn stroke_text<T>(&mut self, text: &T, position: Vector2F) where T: ToTextLayout + ?Sized {
-        let paint = self.current_state.resolve_paint(&self.current_state.stroke_paint);
-        let paint_id = self.canvas.scene.push_paint(&paint);
-        let render_mode = TextRenderMode::Stroke(self.current_state.resolve_stroke_style());
-        self.fill_or_stroke_text(text, position, paint_id, render_mode);
+        self.fill_or_stroke_text(text, position, PathOp::Stroke);
     }
 
     /// Returns metrics of the given text using the current style.
@@ -63,36 +62,70 @@ impl CanvasRenderingContext2D {
         text.layout(CanvasState(&self.current_state)).into_owned()
     }
 
-    fn fill_or_stroke_text<T>(&mut self,
-                              text: &T,
-                              mut position: Vector2F,
-                              paint_id: PaintId,
-                              render_mode: TextRenderMode)
+    /// Fills the given multi-line text using the current style.
+    ///
+    /// The text is split on `\n`, and each line is laid out and filled exactly as `fill_text()`
+    /// would, with the `i`th line positioned at `position.y + i * line_height`. The current text
+    /// alignment and baseline apply to each line individually. Tabs and other control characters
+    /// receive no special treatment.
+    pub fn fill_text_multiline(&mut self, text: &str, position: Vector2F, line_height: f32) {
+        for (line_index, line) in text.split('\n').enumerate() {
+            self.fill_text(line, position + vec2f(0.0, line_index as f32 * line_height));
+        }
+    }
+
+    /// Returns the bounds of the given multi-line text, as it would be drawn by
+    /// `fill_text_multiline()` at the origin, using the current style.
+    ///
+    /// Each line contributes its typographic advance horizontally and its font bounding box
+    /// vertically.
+    pub fn measure_text_multiline(&self, text: &str, line_height: f32) -> RectF {
+        let mut bounds: Option<RectF> = None;
+        for (line_index, line) in text.split('\n').enumerate() {
+            let metrics = self.measure_text(line);
+            let baseline_y = line_index as f32 * line_height;
+            let left = metrics.text_x_offset();
+            let line_bounds =
+                RectF::from_points(vec2f(left, baseline_y - metrics.font_bounding_box_ascent()),
+                                   vec2f(left + metrics.width(),
+                                         baseline_y - metrics.font_bounding_box_descent()));
+            bounds = Some(match bounds {
+                None => line_bounds,
+                Some(bounds) => bounds.union_rect(line_bounds),
+            });
+        }
+        bounds.unwrap_or_default()
+    }
+
+    // The glyphs are drawn like any other path, so the current transform, line style, and shadow
+    // apply to them as they would in `fill_path()` and `stroke_path()`.
+    fn fill_or_stroke_text<T>(&mut self, text: &T, mut position: Vector2F, path_op: PathOp)
                               where T: ToTextLayout + ?Sized {
         let layout = text.layout(CanvasState(&self.current_state));
 
-        let clip_path = self.current_state.clip_path;
-        let blend_mode = self.current_state.global_composite_operation.to_blend_mode();
-
         position += layout.text_origin();
-        let transform = self.current_state.transform * Transform2F::from_translation(position);
+        let render_options = FontRenderOptions {
+            transform: Transform2F::from_translation(position),
+            ..FontRenderOptions::default()
+        };
 
         // TODO(pcwalton): Report errors.
-        drop(self.canvas_font_context
-                 .0
-                 .borrow_mut()
-                 .font_context
-                 .push_layout(&mut self.canvas.scene,
-                              &layout.skribo_layout,
-                              &TextStyle { size: layout.font_size },
-                              &FontRenderOptions {
-                                  transform,
-                                  render_mode,
-                                  hinting_options: HintingOptions::None,
-                                  clip_path,
-                                  blend_mode,
-                                  paint_id,
-                              }));
+        let outline = match self.canvas_font_context
+                                .0
+                                .borrow_mut()
+                                .font_context
+                                .layout_outline(&layout.skribo_layout,
+                                                &TextStyle { size: layout.font_size },
+                                                &render_options) {
+            Ok(outline) => outline,
+            Err(_) => return,
+        };
+
+        let outline = match path_op {
+            PathOp::Fill => outline,
+            PathOp::Stroke => self.stroke_outline(outline),
+        };
+        self.push_path(outline, path_op, FillRule::Winding);
     }
 
     // Text styles
@@ -349,6 +382,10 @@ impl TextMetrics {
         vec2f(self.text_x_offset(), self.text_y_offset())
     }
 
+    /// Returns the advance width of the text, in pixels.
+    ///
+    /// This is the pen position after the last glyph, so trailing whitespace and glyphs whose
+    /// outlines don't reach their advance are measured as the font intends.
     pub fn width(&self) -> f32 {
         if self.width.get().is_none() {
             match self.skribo_layout.glyphs.last() {
@@ -357,12 +394,10 @@ impl TextMetrics {
                     let glyph_id = last_glyph.glyph_id;
                     let font_metrics = last_glyph.font.font.metrics();
                     let scale_factor = self.skribo_layout.size / font_metrics.units_per_em as f32;
-                    let glyph_rect = last_glyph.font.font.typographic_bounds(glyph_id).unwrap();
-                    self.width.set(Some(last_glyph.offset.x() +
-                                        glyph_rect.max_x() * scale_factor));
+                    let advance = last_glyph.font.font.advance(glyph_id).unwrap_or_default();
+                    self.width.set(Some(last_glyph.offset.x() + advance.x() * scale_factor));
                 }
             }
-
         }
         self.width.get().unwrap()
     }
@@ -17,6 +17,7 @@ pub mod matrix;
 
 // TODO(pcwalton): Maybe this should be a u32? Need to be aware of endianness issues if we do that.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ColorU {
     pub r: u8,
@@ -101,6 +102,7 @@ impl Debug for ColorU {
 }
 
 #[derive(Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorF(pub F32x4);
 
 impl ColorF {
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::ColorF;
 use pathfinder_simd::default::F32x4;
 use std::ops::{Add, Mul, Deref};
 
@@ -15,6 +16,7 @@ use std::ops::{Add, Mul, Deref};
 ///
 /// The entries are stored in 5 columns of F32x4, each containing a row.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorMatrix(pub [F32x4; 5]);
 
 impl ColorMatrix {
@@ -29,6 +31,26 @@ impl ColorMatrix {
         ])
     }
 
+    /// Creates a color matrix filter that leaves colors unchanged.
+    ///
+    /// See the `identity` value of the `type` attribute of the `feColorMatrix` element in the SVG
+    /// specification.
+    #[inline]
+    pub fn identity() -> ColorMatrix {
+        ColorMatrix::from_rows([
+            [1.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Creates a grayscale color matrix filter, equivalent to `saturate(0.0)`.
+    #[inline]
+    pub fn grayscale() -> ColorMatrix {
+        ColorMatrix::saturate(0.0)
+    }
+
     /// Creates a hue-rotate color matrix filter from the given angle in radians.
     ///
     /// See the `hueRotate` attribute of the `feColorMatrix` element in the SVG specification.
@@ -85,6 +107,19 @@ impl ColorMatrix {
             [ 0.2125, 0.7154, 0.0721, 0.0, 0.0],
         ])
     }
+
+    /// Applies this matrix to a color, as the `ColorMatrix` pattern filter does on the GPU.
+    ///
+    /// Note that the GPU applies the matrix to the premultiplied colors of the pattern.
+    #[inline]
+    pub fn transform(&self, color: ColorF) -> ColorF {
+        let color = color.0;
+        ColorF(self[0] * color.xxxx() +
+               self[1] * color.yyyy() +
+               self[2] * color.zzzz() +
+               self[3] * color.wwww() +
+               self[4])
+    }
 }
 impl Deref for ColorMatrix {
     type Target = [F32x4; 5];
@@ -124,3 +159,30 @@ impl Mul<f32> for ColorMatrix {
         ])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::ColorF;
+    use super::ColorMatrix;
+
+    fn approx_eq(a: ColorF, b: ColorF) -> bool {
+        (0..4).all(|i| (a.0[i] - b.0[i]).abs() < 0.001)
+    }
+
+    #[test]
+    fn test_identity() {
+        let color = ColorF::new(0.25, 0.5, 0.75, 1.0);
+        assert!(approx_eq(ColorMatrix::identity().transform(color), color));
+        assert!(approx_eq(ColorMatrix::saturate(1.0).transform(color), color));
+        assert!(approx_eq(ColorMatrix::hue_rotate(0.0).transform(color), color));
+    }
+
+    #[test]
+    fn test_grayscale() {
+        let color = ColorMatrix::grayscale().transform(ColorF::new(1.0, 0.5, 0.0, 0.5));
+        assert!((color.r() - color.g()).abs() < 0.001);
+        assert!((color.g() - color.b()).abs() < 0.001);
+        assert!((color.r() - (0.213 + 0.715 * 0.5)).abs() < 0.001);
+        assert!((color.a() - 0.5).abs() < 0.001);
+    }
+}
@@ -0,0 +1,52 @@
+// pathfinder/content/benches/polyline.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Compares loading a long polyline point by point with loading it in bulk.
+//!
+//! Run with `cargo bench -p pathfinder_content`.
+
+use pathfinder_content::outline::Contour;
+use pathfinder_geometry::vector::{Vector2F, vec2f};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const POINT_COUNT: usize = 100_000;
+const ITERATIONS: u32 = 100;
+
+fn main() {
+    let points: Vec<Vector2F> = (0..POINT_COUNT).map(|i| {
+        let t = i as f32 * 0.01;
+        vec2f(t.cos() * t, t.sin() * t)
+    }).collect();
+
+    let push_endpoint = time(|| {
+        let mut contour = Contour::new();
+        for &point in &points {
+            contour.push_endpoint(point);
+        }
+        contour
+    });
+    let push_endpoints = time(|| Contour::from_endpoints(points.iter().cloned()));
+
+    println!("{} points, mean of {} iterations:", POINT_COUNT, ITERATIONS);
+    println!("    push_endpoint():  {:?}", push_endpoint);
+    println!("    push_endpoints(): {:?}", push_endpoints);
+}
+
+fn time<F>(mut f: F) -> Duration where F: FnMut() -> Contour {
+    // Warm up the allocator and caches.
+    black_box(f());
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
@@ -10,7 +10,7 @@
 
 //! Special effects that can be applied to layers.
 
-use pathfinder_color::{ColorF, matrix::ColorMatrix};
+use pathfinder_color::{ColorF, ColorU, matrix::ColorMatrix};
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::vector::Vector2F;
 use pathfinder_simd::default::F32x2;
@@ -41,6 +41,7 @@ pub const MAX_STEM_DARKENING_PIXELS_PER_EM: f32 = 72.0;
 
 /// The shader that should be used when compositing this layer onto its destination.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     /// No special filter.
     None,
@@ -55,12 +56,23 @@ pub enum Filter {
         uv_origin: Vector2F,
     },
 
+    /// Converts a linear gradient to a conic one.
+    ConicGradient {
+        /// The point that the gradient sweeps around.
+        center: Vector2F,
+        /// The angle, in radians from the positive X axis, at which the gradient starts.
+        start_angle: f32,
+        /// The origin of the linearized gradient in the texture.
+        uv_origin: Vector2F,
+    },
+
     /// One of the `PatternFilter` filters.
     PatternFilter(PatternFilter),
 }
 
 /// Shaders applicable to patterns.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum PatternFilter {
     /// Performs postprocessing operations useful for monochrome text.
     Text {
@@ -96,6 +108,7 @@ pub enum PatternFilter {
 
 /// Blend modes that can be applied to individual paths.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendMode {
     // Porter-Duff, supported by GPU blender
     /// No regions are enabled.
@@ -170,10 +183,27 @@ pub enum BlendMode {
 /// fourth element of this kernel is applied to the center of the pixel, the third element is
 /// applied one pixel to the left, and so on.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefringingKernel(pub [f32; 4]);
 
+/// A shadow cast by a path onto the content underneath it.
+///
+/// See `Scene::push_draw_path_with_drop_shadow()` in the renderer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DropShadow {
+    /// How far the shadow is displaced from the path, in device pixels.
+    pub offset: Vector2F,
+    /// The standard deviation of the Gaussian blur applied to the shadow, in device pixels.
+    ///
+    /// If zero, the shadow is sharp.
+    pub sigma: f32,
+    /// The color of the shadow.
+    pub color: ColorU,
+}
+
 /// The axis a Gaussian blur is applied to.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlurDirection {
     /// The horizontal axis.
     X,
@@ -230,6 +260,43 @@ impl BlendMode {
         }
     }
 
+    /// True if this blend mode can't be implemented with fixed-function GPU blending and so
+    /// requires the destination to be read back in the shader.
+    ///
+    /// Using such a blend mode on the main framebuffer forces the renderer to render into an
+    /// intermediate framebuffer, which costs additional memory and a final copy.
+    pub fn needs_readable_framebuffer(self) -> bool {
+        match self {
+            BlendMode::Clear |
+            BlendMode::SrcOver |
+            BlendMode::DestOver |
+            BlendMode::SrcIn |
+            BlendMode::DestIn |
+            BlendMode::SrcOut |
+            BlendMode::DestOut |
+            BlendMode::SrcAtop |
+            BlendMode::DestAtop |
+            BlendMode::Xor |
+            BlendMode::Lighter |
+            BlendMode::Copy => false,
+            BlendMode::Lighten |
+            BlendMode::Darken |
+            BlendMode::Multiply |
+            BlendMode::Screen |
+            BlendMode::HardLight |
+            BlendMode::Overlay |
+            BlendMode::ColorDodge |
+            BlendMode::ColorBurn |
+            BlendMode::SoftLight |
+            BlendMode::Difference |
+            BlendMode::Exclusion |
+            BlendMode::Hue |
+            BlendMode::Saturation |
+            BlendMode::Color |
+            BlendMode::Luminosity => true,
+        }
+    }
+
     /// True if this blend mode does not preserve destination areas outside the source.
     pub fn is_destructive(self) -> bool {
         match self {
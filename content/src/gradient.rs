@@ -19,13 +19,20 @@ use pathfinder_geometry::util as geometry_util;
 use pathfinder_simd::default::F32x2;
 use std::cmp::Ordering;
 use std::convert;
+use std::f32::consts::PI;
 use std::hash::{Hash, Hasher};
-use std::mem;
 
-/// A gradient, either linear or radial.
+// The number of segments that a transition with a midpoint is split into. This must be even so
+// that a stop lands exactly on the midpoint.
+const MIDPOINT_STEP_COUNT: u32 = 16;
+
+// Keeps the exponent of the midpoint curve finite.
+const MIN_MIDPOINT: f32 = 0.001;
+
+/// A gradient: linear, radial, or conic.
 #[derive(Clone, PartialEq, Debug)]
 pub struct Gradient {
-    /// Information specific to the type of gradient (linear or radial).
+    /// Information specific to the type of gradient (linear, radial, or conic).
     pub geometry: GradientGeometry,
     stops: Vec<ColorStop>,
     /// What should be rendered upon reaching the end of the color stops.
@@ -43,7 +50,7 @@ pub struct ColorStop {
     pub color: ColorU,
 }
 
-/// The type of gradient: linear or radial.
+/// The type of gradient: linear, radial, or conic.
 #[derive(Clone, PartialEq, Debug)]
 pub enum GradientGeometry {
     /// A linear gradient that follows a line.
@@ -67,7 +74,19 @@ pub enum GradientGeometry {
         /// Like `gradientTransform` in SVG. Note that this is the inverse of Cairo's gradient
         /// transform.
         transform: Transform2F,
-    }
+    },
+    /// A conic gradient that sweeps clockwise around a center point, like the HTML canvas
+    /// `createConicGradient()` method.
+    Conic {
+        /// The point that the gradient sweeps around.
+        ///
+        /// This is in scene coordinates, not relative to the bounding box of the path.
+        center: Vector2F,
+        /// The angle, in radians from the positive X axis, at which the gradient starts.
+        start_angle: f32,
+        /// Transform from conic gradient space into screen space.
+        transform: Transform2F,
+    },
 }
 
 /// What should be rendered outside the color stops.
@@ -101,6 +120,18 @@ impl Hash for Gradient {
                 util::hash_f32(transform.m22(), state);
                 util::hash_f32(transform.m23(), state);
             }
+            GradientGeometry::Conic { center, start_angle, transform } => {
+                (2).hash(state);
+                util::hash_f32(center.x(), state);
+                util::hash_f32(center.y(), state);
+                util::hash_f32(start_angle, state);
+                util::hash_f32(transform.m11(), state);
+                util::hash_f32(transform.m12(), state);
+                util::hash_f32(transform.m13(), state);
+                util::hash_f32(transform.m21(), state);
+                util::hash_f32(transform.m22(), state);
+                util::hash_f32(transform.m23(), state);
+            }
         }
         self.stops.hash(state);
     }
@@ -110,11 +141,8 @@ impl Eq for ColorStop {}
 
 impl Hash for ColorStop {
     fn hash<H>(&self, state: &mut H) where H: Hasher {
-        unsafe {
-            self.color.hash(state);
-            let offset = mem::transmute::<f32, u32>(self.offset);
-            offset.hash(state);
-        }
+        self.color.hash(state);
+        util::hash_f32(self.offset, state);
     }
 }
 
@@ -154,7 +182,21 @@ impl Gradient {
         }
     }
 
-    /// Adds a new color stop to the radial gradient.
+    /// Creates a new conic gradient that sweeps clockwise around `center`, starting at
+    /// `start_angle` radians from the positive X axis.
+    ///
+    /// The center is in scene coordinates, not relative to the bounding box of the current path.
+    #[inline]
+    pub fn conic(center: Vector2F, start_angle: f32) -> Gradient {
+        let transform = Transform2F::default();
+        Gradient {
+            geometry: GradientGeometry::Conic { center, start_angle, transform },
+            stops: Vec::new(),
+            wrap: GradientWrap::Clamp,
+        }
+    }
+
+    /// Adds a new color stop to the gradient.
     #[inline]
     pub fn add(&mut self, stop: ColorStop) {
         let index = self.stops.binary_search_by(|other| {
@@ -170,6 +212,33 @@ impl Gradient {
         self.add(ColorStop::new(color, offset))
     }
 
+    /// Adds a new color stop, biasing the transition from the preceding stop so that the color
+    /// halfway between the two is reached at `midpoint`.
+    ///
+    /// `midpoint` is a fraction of the distance between the preceding stop and this one, so 0.5
+    /// yields an ordinary linear transition. Like color hints in CSS and midpoints in design
+    /// tools, the bias is realized by inserting intermediate stops along the curve. If there is no
+    /// preceding stop, this is equivalent to `add_color_stop()`.
+    pub fn add_color_stop_with_midpoint(&mut self, color: ColorU, offset: f32, midpoint: f32) {
+        let prev_stop = match self.stops.iter().rev().find(|stop| stop.offset <= offset) {
+            Some(&prev_stop) if midpoint != 0.5 => prev_stop,
+            _ => return self.add_color_stop(color, offset),
+        };
+
+        // Invert the CSS color hint curve `ratio = position^(ln 0.5 / ln midpoint)` so that the
+        // inserted stops are evenly spaced in color and one of them lands exactly on the midpoint.
+        let midpoint = geometry_util::clamp(midpoint, MIN_MIDPOINT, 1.0 - MIN_MIDPOINT);
+        let exponent = midpoint.ln() / 0.5f32.ln();
+        let (prev_color, next_color) = (prev_stop.color.to_f32(), color.to_f32());
+        for step in 1..MIDPOINT_STEP_COUNT {
+            let ratio = step as f32 / MIDPOINT_STEP_COUNT as f32;
+            let position = ratio.powf(exponent);
+            self.add_color_stop(prev_color.lerp(next_color, ratio).to_u8(),
+                                geometry_util::lerp(prev_stop.offset, offset, position));
+        }
+        self.add_color_stop(color, offset);
+    }
+
     /// Returns the list of color stops in this gradient.
     #[inline]
     pub fn stops(&self) -> &[ColorStop] {
@@ -234,13 +303,27 @@ impl Gradient {
 
         match self.geometry {
             GradientGeometry::Linear(ref mut line) => *line = new_transform * *line,
-            GradientGeometry::Radial { ref mut transform, .. } => {
+            GradientGeometry::Radial { ref mut transform, .. } |
+            GradientGeometry::Conic { ref mut transform, .. } => {
                 *transform = new_transform * *transform
             }
         }
     }
 }
 
+impl GradientGeometry {
+    /// Returns the offset of the color at `point`, in conic gradient space, for a conic gradient
+    /// around `center` starting at `start_angle`.
+    ///
+    /// This is the same calculation that the tile shader performs. The result is between 0.0
+    /// inclusive and 1.0 exclusive.
+    pub fn conic_offset(center: Vector2F, start_angle: f32, point: Vector2F) -> f32 {
+        let vector = point - center;
+        let turns = (vector.y().atan2(vector.x()) - start_angle) / (2.0 * PI);
+        turns - turns.floor()
+    }
+}
+
 impl ColorStop {
     /// Creates a new color stop from a color and offset between 0.0 and 1.0 inclusive.
     #[inline]
@@ -271,9 +354,10 @@ impl RadialGradientLine for Vector2F {
 
 #[cfg(test)]
 mod test {
-    use crate::gradient::Gradient;
+    use crate::gradient::{Gradient, GradientGeometry};
     use pathfinder_color::ColorU;
-    use pathfinder_geometry::vector::Vector2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+    use std::f32::consts::PI;
 
     #[test]
     fn stable_order() {
@@ -288,6 +372,19 @@ mod test {
         }));
     }
 
+    #[test]
+    fn midpoint() {
+        let mut grad = Gradient::linear_from_points(Vector2F::default(), Vector2F::default());
+        grad.add_color_stop(ColorU::new(0, 0, 0, 255), 0.0);
+        grad.add_color_stop_with_midpoint(ColorU::new(254, 254, 254, 255), 1.0, 0.25);
+
+        assert_eq!(grad.sample(0.0).r, 0);
+        // The color halfway between the stops is reached a quarter of the way along.
+        assert!((grad.sample(0.25).r as i32 - 127).abs() <= 1);
+        assert_eq!(grad.sample(1.0).r, 254);
+        assert!(grad.sample(0.5).r > 127 && grad.sample(0.5).r < 254);
+    }
+
     #[test]
     fn never_sample_zero_width() {
         let mut grad = Gradient::linear_from_points(Vector2F::default(), Vector2F::default());
@@ -301,4 +398,32 @@ mod test {
             assert!(sample.r == 0, "{} {}", i, sample.r);
         }
     }
+
+    #[test]
+    fn conic_offset() {
+        // Four stops, one per quadrant, starting straight up.
+        let center = vec2f(50.0, 50.0);
+        let start_angle = -0.5 * PI;
+        let colors = [ColorU::new(255, 0, 0, 255),
+                      ColorU::new(0, 255, 0, 255),
+                      ColorU::new(0, 0, 255, 255),
+                      ColorU::new(255, 255, 0, 255)];
+        let mut grad = Gradient::conic(center, start_angle);
+        for (index, &color) in colors.iter().enumerate() {
+            grad.add_color_stop(color, index as f32 * 0.25);
+        }
+
+        // Y points down, so the gradient sweeps up, right, down, left.
+        let points = [vec2f(50.0, 0.0), vec2f(100.0, 50.0), vec2f(50.0, 100.0), vec2f(0.0, 50.0)];
+        for (index, (point, &color)) in points.iter().zip(colors.iter()).enumerate() {
+            let offset = GradientGeometry::conic_offset(center, start_angle, *point);
+            assert!((offset - index as f32 * 0.25).abs() < 0.001, "{} {}", index, offset);
+            let sample = grad.sample(index as f32 * 0.25);
+            assert_eq!(sample, color);
+        }
+
+        // Just before the start, the gradient wraps around to the end.
+        let offset = GradientGeometry::conic_offset(center, start_angle, vec2f(49.0, 0.0));
+        assert!(offset > 0.99 && offset < 1.0);
+    }
 }
@@ -12,6 +12,7 @@
 
 use crate::clip::{self, ContourPolygonClipper, ContourRectClipper};
 use crate::dilation::ContourDilator;
+use crate::fill::FillRule;
 use crate::orientation::Orientation;
 use crate::segment::{Segment, SegmentFlags, SegmentKind};
 use crate::util::safe_sqrt;
@@ -21,7 +22,7 @@ use pathfinder_geometry::transform2d::{Transform2F, Matrix2x2F};
 use pathfinder_geometry::transform3d::Perspective;
 use pathfinder_geometry::unit_vector::UnitVector;
 use pathfinder_geometry::vector::{Vector2F, vec2f};
-use std::f32::consts::PI;
+use std::f32::consts::{FRAC_PI_4, PI};
 use std::fmt::{self, Debug, Formatter};
 use std::mem;
 
@@ -225,6 +226,20 @@ impl Outline {
         self
     }
 
+    /// Removes on-curve points that lie within `tolerance` of the line between their neighbors.
+    ///
+    /// Only runs of straight line segments are simplified; curves, their endpoints, and the first
+    /// and last points of each contour are left untouched, so contour closure is preserved. This
+    /// is useful for reducing the amount of tiling work for verbose polyline data.
+    pub fn simplify_collinear(&mut self, tolerance: f32) {
+        let mut new_bounds = None;
+        for contour in &mut self.contours {
+            contour.simplify_collinear(tolerance);
+            contour.update_bounds(&mut new_bounds);
+        }
+        self.bounds = new_bounds.unwrap_or_else(|| RectF::default());
+    }
+
     /// Applies a perspective transform to this outline.
     #[deprecated]
     #[allow(deprecated)]
@@ -303,6 +318,29 @@ impl Outline {
         self.contours.len()
     }
 
+    /// Returns true if the given point lies inside the filled area of this outline under the
+    /// given fill rule.
+    ///
+    /// Open contours are treated as implicitly closed, as they are when filling, and points lying
+    /// on an edge count as inside. Curves are flattened for the test, so points within a small
+    /// fraction of a pixel of a curved edge may be misclassified.
+    pub fn contains_point(&self, point: Vector2F, fill_rule: FillRule) -> bool {
+        const EDGE_TOLERANCE: f32 = 0.0001;
+
+        if !self.bounds.contains_point(point) {
+            return false;
+        }
+        if self.contours.iter().any(|contour| contour.point_is_on_edge(point, EDGE_TOLERANCE)) {
+            return true;
+        }
+        let winding_number: i32 =
+            self.contours.iter().map(|contour| contour.winding_number(point)).sum();
+        match fill_rule {
+            FillRule::Winding => winding_number != 0,
+            FillRule::EvenOdd => winding_number % 2 != 0,
+        }
+    }
+
     /// Appends the contours of another outline to this one.
     pub fn push_outline(&mut self, other: Outline) {
         if other.is_empty() {
@@ -355,6 +393,14 @@ impl Contour {
         }
     }
 
+    /// Creates a new unclosed contour from a polyline.
+    #[inline]
+    pub fn from_endpoints<I>(points: I) -> Contour where I: IntoIterator<Item = Vector2F> {
+        let mut contour = Contour::new();
+        contour.push_endpoints(points);
+        contour
+    }
+
     /// Creates a closed subpath representing the given axis-aligned rectangle.
     #[inline]
     pub fn from_rect(rect: RectF) -> Contour {
@@ -497,6 +543,51 @@ impl Contour {
         self.closed
     }
 
+    /// Returns the number of times this contour, implicitly closed, winds around the given point.
+    ///
+    /// Clockwise windings (in a y-down coordinate system) count positively.
+    pub fn winding_number(&self, point: Vector2F) -> i32 {
+        let mut winding_number = 0;
+        self.for_each_flattened_edge(|edge| winding_number += line_winding_number(edge, point));
+        winding_number
+    }
+
+    // Returns true if the given point lies on this contour, implicitly closed, to within
+    // `tolerance`.
+    fn point_is_on_edge(&self, point: Vector2F, tolerance: f32) -> bool {
+        let mut on_edge = false;
+        self.for_each_flattened_edge(|edge| {
+            on_edge = on_edge || distance_to_segment(point, edge.from(), edge.to()) <= tolerance
+        });
+        on_edge
+    }
+
+    // Calls the given function with each edge of this contour, including the implicit closing
+    // edge, after flattening curves into lines.
+    fn for_each_flattened_edge<F>(&self, mut f: F) where F: FnMut(LineSegment2F) {
+        const FLATTENING_STEPS: u32 = 16;
+
+        let (first_position, last_position) = match (self.first_position(),
+                                                     self.last_position()) {
+            (Some(first_position), Some(last_position)) => (first_position, last_position),
+            _ => return,
+        };
+
+        for segment in self.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT) {
+            if segment.is_line() {
+                f(segment.as_line_segment());
+                continue;
+            }
+            let mut from = segment.baseline.from();
+            for step in 1..=FLATTENING_STEPS {
+                let to = segment.sample(step as f32 / FLATTENING_STEPS as f32);
+                f(LineSegment2F::new(from, to));
+                from = to;
+            }
+        }
+        f(LineSegment2F::new(last_position, first_position));
+    }
+
     /// Returns the points (on curve and control points) of the Contour
     #[inline]
     pub fn points(&self) -> &[Vector2F] {
@@ -543,6 +634,28 @@ impl Contour {
         self.push_point(to, PointFlags::empty(), true);
     }
 
+    /// Adds many new on-curve points to this contour at once.
+    ///
+    /// This is equivalent to calling `push_endpoint()` for each point, but it avoids per-point
+    /// overhead when loading long polylines.
+    pub fn push_endpoints<I>(&mut self, points: I) where I: IntoIterator<Item = Vector2F> {
+        let points = points.into_iter();
+        let (min_count, _) = points.size_hint();
+        self.points.reserve(min_count);
+        self.flags.reserve(min_count);
+
+        let mut first = self.is_empty();
+        let mut bounds = self.bounds;
+        for point in points {
+            debug_assert!(!point.x().is_nan() && !point.y().is_nan());
+            union_rect(&mut bounds, point, first);
+            first = false;
+            self.points.push(point);
+        }
+        self.bounds = bounds;
+        self.flags.resize(self.points.len(), PointFlags::empty());
+    }
+
     /// Adds a new quadratic Bézier curve to the given on-curve position and control point to this
     /// contour.
     #[inline]
@@ -623,13 +736,31 @@ impl Contour {
     ///
     /// * `direction`: Whether the arc should be drawn clockwise or counterclockwise from the +x
     ///   axis.
+    ///
+    /// If the sweep from `start_angle` to `end_angle` in `direction` is at least a full turn, a
+    /// whole ellipse starting at `start_angle` is added. Otherwise, the angles are taken modulo a
+    /// full turn, so `end_angle` may be less than `start_angle` in either direction.
     pub fn push_arc(&mut self,
                     transform: &Transform2F,
                     start_angle: f32,
                     end_angle: f32,
                     direction: ArcDirection) {
-        if end_angle - start_angle >= PI * 2.0 {
-            self.push_ellipse(transform);
+        let sweep = match direction {
+            ArcDirection::CW => end_angle - start_angle,
+            ArcDirection::CCW => start_angle - end_angle,
+        };
+        if sweep >= PI * 2.0 {
+            // `push_ellipse()` starts 45° before the +x axis, so rotate it forward by that much.
+            let transform = match direction {
+                ArcDirection::CW => {
+                    *transform * Transform2F::from_rotation(start_angle + FRAC_PI_4)
+                }
+                ArcDirection::CCW => {
+                    *transform * Transform2F::from_rotation(start_angle - FRAC_PI_4) *
+                        Transform2F::from_scale(vec2f(1.0, -1.0))
+                }
+            };
+            self.push_ellipse(&transform);
         } else {
             let start = vec2f(start_angle.cos(), start_angle.sin());
             let end   = vec2f(end_angle.cos(),   end_angle.sin());
@@ -911,6 +1042,42 @@ impl Contour {
         self
     }
 
+    /// Removes on-curve points that lie within `tolerance` of the line between their neighbors.
+    ///
+    /// Each maximal run of line segments is simplified with the Douglas-Peucker algorithm. Curves
+    /// and the first and last points of the contour are preserved.
+    pub fn simplify_collinear(&mut self, tolerance: f32) {
+        let point_count = self.points.len();
+        if point_count < 3 {
+            return;
+        }
+
+        let mut keep = vec![true; point_count];
+        let mut run_start = 0;
+        for point_index in 0..point_count {
+            if !self.flags[point_index].is_empty() {
+                if point_index > 0 {
+                    let run_end = point_index - 1;
+                    simplify_line_run(&self.points, &mut keep, run_start, run_end, tolerance);
+                }
+                run_start = point_index + 1;
+            }
+        }
+        simplify_line_run(&self.points, &mut keep, run_start, point_count - 1, tolerance);
+
+        if keep.iter().all(|&keep| keep) {
+            return;
+        }
+
+        let (mut point_index, mut flags_index) = (0, 0);
+        self.points.retain(|_| { point_index += 1; keep[point_index - 1] });
+        self.flags.retain(|_| { flags_index += 1; keep[flags_index - 1] });
+
+        for (point_index, &point) in self.points.iter().enumerate() {
+            union_rect(&mut self.bounds, point, point_index == 0);
+        }
+    }
+
     /// Applies a perspective transform to this subpath.
     #[deprecated]
     pub fn apply_perspective(&mut self, perspective: &Perspective) {
@@ -1093,6 +1260,47 @@ bitflags! {
     }
 }
 
+// Douglas-Peucker simplification of the polyline `points[start..=end]`, clearing `keep` for each
+// interior point that can be removed.
+fn simplify_line_run(points: &[Vector2F],
+                     keep: &mut [bool],
+                     start: usize,
+                     end: usize,
+                     tolerance: f32) {
+    let mut stack = vec![(start, end)];
+    while let Some((start, end)) = stack.pop() {
+        if end <= start + 1 {
+            continue;
+        }
+
+        let (mut max_distance, mut max_index) = (0.0, start);
+        for index in (start + 1)..end {
+            let distance = distance_to_segment(points[index], points[start], points[end]);
+            if distance > max_distance {
+                max_distance = distance;
+                max_index = index;
+            }
+        }
+
+        if max_distance <= tolerance {
+            keep[(start + 1)..end].iter_mut().for_each(|keep| *keep = false);
+        } else {
+            stack.push((start, max_index));
+            stack.push((max_index, end));
+        }
+    }
+}
+
+fn distance_to_segment(point: Vector2F, from: Vector2F, to: Vector2F) -> f32 {
+    let vector = to - from;
+    let square_length = vector.square_length();
+    if square_length == 0.0 {
+        return (point - from).length();
+    }
+    let t = ((point - from).dot(vector) / square_length).max(0.0).min(1.0);
+    (point - (from + vector * t)).length()
+}
+
 #[inline]
 pub(crate) fn union_rect(bounds: &mut RectF, new_point: Vector2F, first: bool) {
     if first {
@@ -1101,3 +1309,92 @@ pub(crate) fn union_rect(bounds: &mut RectF, new_point: Vector2F, first: bool) {
         *bounds = bounds.union_point(new_point)
     }
 }
+
+// Returns +1 if the line crosses the horizontal ray extending rightward from the point in the
+// positive y direction, -1 if it crosses in the negative y direction, and 0 otherwise.
+fn line_winding_number(line: LineSegment2F, point: Vector2F) -> i32 {
+    let (from, to) = (line.from(), line.to());
+    let side = (to - from).det(point - from);
+    if from.y() <= point.y() {
+        if to.y() > point.y() && side > 0.0 {
+            return 1;
+        }
+    } else if to.y() <= point.y() && side < 0.0 {
+        return -1;
+    }
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fill::FillRule;
+    use crate::outline::{Contour, Outline};
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+    fn assert_contours_equal(a: &Contour, b: &Contour) {
+        assert_eq!(a.points(), b.points());
+        for index in 0..a.len() {
+            assert_eq!(a.flags_of(index), b.flags_of(index));
+        }
+        assert_eq!(a.bounds(), b.bounds());
+    }
+
+    #[test]
+    fn test_push_endpoints() {
+        let points: Vec<Vector2F> = (0..100).map(|i| {
+            let i = i as f32;
+            vec2f(i * 3.0 - 50.0, (i * 0.1).sin() * 20.0)
+        }).collect();
+
+        let mut expected = Contour::new();
+        for &point in &points {
+            expected.push_endpoint(point);
+        }
+        assert_contours_equal(&Contour::from_endpoints(points.iter().cloned()), &expected);
+
+        // Appending to a contour that already has points extends its bounds.
+        let mut expected = Contour::new();
+        expected.push_endpoint(vec2f(-100.0, 100.0));
+        let mut contour = expected.clone();
+        for &point in &points {
+            expected.push_endpoint(point);
+        }
+        contour.push_endpoints(points.iter().cloned());
+        assert_contours_equal(&contour, &expected);
+    }
+
+    #[test]
+    fn test_contains_point() {
+        // Two nested rectangles wound the same way.
+        let mut outline = Outline::new();
+        outline.push_contour(Contour::from_rect(RectF::new(vec2f(10.0, 10.0), vec2f(40.0, 40.0))));
+        outline.push_contour(Contour::from_rect(RectF::new(vec2f(20.0, 20.0), vec2f(20.0, 20.0))));
+
+        assert!(outline.contains_point(vec2f(15.0, 15.0), FillRule::EvenOdd));
+        assert!(!outline.contains_point(vec2f(30.0, 30.0), FillRule::EvenOdd));
+        assert!(outline.contains_point(vec2f(30.0, 30.0), FillRule::Winding));
+        assert!(!outline.contains_point(vec2f(60.0, 30.0), FillRule::Winding));
+
+        // Points on an edge count as inside, even the inner edge under the even-odd rule.
+        assert!(outline.contains_point(vec2f(10.0, 30.0), FillRule::EvenOdd));
+        assert!(outline.contains_point(vec2f(20.0, 30.0), FillRule::EvenOdd));
+    }
+
+    #[test]
+    fn test_winding_number() {
+        // Rectangles go clockwise in y-down space.
+        let rect = Contour::from_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+        assert_eq!(rect.winding_number(vec2f(5.0, 5.0)), 1);
+        assert_eq!(rect.winding_number(vec2f(15.0, 5.0)), 0);
+
+        // Curves are flattened, and an open contour is implicitly closed.
+        let mut circle = Contour::new();
+        circle.push_ellipse(&(Transform2F::from_translation(vec2f(50.0, 50.0)) *
+                              Transform2F::from_scale(20.0)));
+        assert_eq!(circle.winding_number(vec2f(50.0, 50.0)).abs(), 1);
+        assert_eq!(circle.winding_number(vec2f(63.0, 63.0)).abs(), 1);
+        assert_eq!(circle.winding_number(vec2f(66.0, 66.0)), 0);
+    }
+}
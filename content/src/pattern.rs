@@ -76,6 +76,10 @@ bitflags! {
         /// image will be pixelated). If unset, bilinear interpolation is used when compositing
         /// this pattern (i.e. the image will be smooth).
         const NO_SMOOTHING  = 0x04;
+        /// If set, the pattern is positioned in device space: the transform of the path being
+        /// painted is not applied to it, so repeats stay evenly spaced regardless of that
+        /// transform.
+        const DEVICE_SPACE  = 0x08;
     }
 }
 
@@ -185,6 +189,20 @@ impl Pattern {
         self.flags.set(PatternFlags::NO_SMOOTHING, !enable);
     }
 
+    /// Returns true if this pattern is positioned in device space, ignoring the transform of the
+    /// path it paints.
+    #[inline]
+    pub fn device_space(&self) -> bool {
+        self.flags.contains(PatternFlags::DEVICE_SPACE)
+    }
+
+    /// Set to true if this pattern should be positioned in device space (i.e. should ignore the
+    /// transform of the path it paints) or false if it should be transformed along with the path.
+    #[inline]
+    pub fn set_device_space(&mut self, device_space: bool) {
+        self.flags.set(PatternFlags::DEVICE_SPACE, device_space);
+    }
+
     /// Returns true if this pattern is obviously fully opaque.
     ///
     /// This is a best-effort quick check, so it might return false even if the image is actually
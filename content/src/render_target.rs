@@ -12,6 +12,7 @@
 
 /// Identifies a drawing surface for vector graphics that can be later used as a pattern.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RenderTargetId {
     /// The ID of the scene that this render target ID belongs to.
     pub scene: u32,
@@ -18,6 +18,8 @@ use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::util::EPSILON;
 use pathfinder_geometry::vector::{Vector2F, vec2f};
 use std::f32;
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::str::FromStr;
 
 const TOLERANCE: f32 = 0.01;
 
@@ -59,6 +61,35 @@ pub enum LineCap {
     Round,
 }
 
+/// The error returned when parsing a style keyword, such as a line cap name, fails.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseKeywordError;
+
+/// Parses the HTML canvas name of a line cap: `"butt"`, `"round"`, or `"square"`.
+impl FromStr for LineCap {
+    type Err = ParseKeywordError;
+
+    fn from_str(keyword: &str) -> Result<LineCap, ParseKeywordError> {
+        match keyword {
+            "butt" => Ok(LineCap::Butt),
+            "round" => Ok(LineCap::Round),
+            "square" => Ok(LineCap::Square),
+            _ => Err(ParseKeywordError),
+        }
+    }
+}
+
+/// Formats a line cap as its HTML canvas name, so that it round-trips through `FromStr`.
+impl Display for LineCap {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), FmtError> {
+        formatter.write_str(match *self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        })
+    }
+}
+
 /// The shape used to join two line segments where they meet.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LineJoin {
@@ -13,8 +13,8 @@
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_simd::default::{F32x2, F32x4};
+use std::f32;
 use std::hash::{Hash, Hasher};
-use std::mem;
 
 pub(crate) fn hash_line_segment<H>(line_segment: LineSegment2F, state: &mut H) where H: Hasher {
     hash_f32x4(line_segment.0, state);
@@ -25,24 +25,33 @@ pub(crate) fn hash_transform2f<H>(transform: Transform2F, state: &mut H) where H
     hash_f32x2(transform.vector.0, state);
 }
 
-pub(crate) fn hash_f32<H>(value: f32, state: &mut H) where H: Hasher {
-    unsafe {
-        let data: u32 = mem::transmute::<f32, u32>(value);
-        data.hash(state);
+// Floats are hashed by their bit patterns. Because `0.0 == -0.0`, negative zero is canonicalized
+// to positive zero first so that values that compare equal hash equally. All NaNs are likewise
+// canonicalized to a single bit pattern.
+#[inline]
+fn canonical_f32_bits(value: f32) -> u32 {
+    if value.is_nan() {
+        f32::NAN.to_bits()
+    } else if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
     }
 }
 
+pub(crate) fn hash_f32<H>(value: f32, state: &mut H) where H: Hasher {
+    canonical_f32_bits(value).hash(state);
+}
+
 pub(crate) fn hash_f32x2<H>(vector: F32x2, state: &mut H) where H: Hasher {
-    unsafe {
-        let data: [u32; 2] = mem::transmute::<F32x2, [u32; 2]>(vector);
-        data.hash(state);
+    for index in 0..2 {
+        hash_f32(vector[index], state);
     }
 }
 
 pub(crate) fn hash_f32x4<H>(vector: F32x4, state: &mut H) where H: Hasher {
-    unsafe {
-        let data: [u32; 4] = mem::transmute::<F32x4, [u32; 4]>(vector);
-        data.hash(state);
+    for index in 0..4 {
+        hash_f32(vector[index], state);
     }
 }
 
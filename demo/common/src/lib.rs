@@ -160,7 +160,18 @@ impl<W> DemoApp<W> where W: Window {
         let render_options = RendererOptions {
             dest: dest_framebuffer,
             background_color: None,
+            premultiplied_output: true,
+            output_transform: Transform2F::default(),
+            max_mask_pages: None,
+            max_cached_gpu_bytes: None,
+            initial_fill_count: None,
+            initial_microline_count: None,
+            text_gamma_correction: true,
+            text_gamma_lut: None,
+            dither_gradients: false,
             show_debug_ui: true,
+            show_tile_outlines: false,
+            time_render_commands: false,
         };
 
         let filter = build_filter(&ui_model);
@@ -277,6 +288,7 @@ impl<W> DemoApp<W> where W: Window {
                 Vector2F::zero()
             },
             subpixel_aa_enabled: self.ui_model.subpixel_aa_effect_enabled,
+            ..BuildOptions::default()
         };
 
         self.scene_proxy.build(build_options);
@@ -18,6 +18,7 @@ use pathfinder_color::{ColorF, ColorU};
 use pathfinder_gpu::{ClearOps, DepthFunc, DepthState, Device, Primitive, RenderOptions};
 use pathfinder_gpu::{RenderState, RenderTarget, TextureData, TextureFormat, UniformData};
 use pathfinder_geometry::rect::RectI;
+use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::transform3d::Transform4F;
 use pathfinder_geometry::vector::{Vector2I, Vector4F};
 use pathfinder_renderer::gpu::options::{DestFramebuffer, RendererOptions};
@@ -77,7 +78,18 @@ impl<W> DemoApp<W> where W: Window {
                 *self.renderer.options_mut() = RendererOptions {
                     dest: DestFramebuffer::Other(self.scene_framebuffer.take().unwrap()),
                     background_color: clear_color,
+                    premultiplied_output: true,
+                    output_transform: Transform2F::default(),
+                    max_mask_pages: None,
+                    max_cached_gpu_bytes: None,
+                    initial_fill_count: None,
+                    initial_microline_count: None,
+                    text_gamma_correction: true,
+                    text_gamma_lut: None,
+                    dither_gradients: false,
                     show_debug_ui: self.options.ui != UIVisibility::None,
+                    show_tile_outlines: false,
+                    time_render_commands: false,
                 };
                 2
             }
@@ -88,7 +100,18 @@ impl<W> DemoApp<W> where W: Window {
                         window_size,
                     },
                     background_color: clear_color,
+                    premultiplied_output: true,
+                    output_transform: Transform2F::default(),
+                    max_mask_pages: None,
+                    max_cached_gpu_bytes: None,
+                    initial_fill_count: None,
+                    initial_microline_count: None,
+                    text_gamma_correction: true,
+                    text_gamma_lut: None,
+                    dither_gradients: false,
                     show_debug_ui: self.options.ui != UIVisibility::None,
+                    show_tile_outlines: false,
+                    time_render_commands: false,
                 };
                 1
             }
@@ -118,6 +141,7 @@ impl<W> DemoApp<W> where W: Window {
                     viewport: self.window.viewport(View::Mono),
                     window_size: self.window_size.device_size(),
                 },
+                text_gamma_lut: self.renderer.options().text_gamma_lut.clone(),
                 ..*self.renderer.options()
             };
             if let DestFramebuffer::Other(scene_framebuffer) = mem::replace(self.renderer
@@ -11,6 +11,7 @@
 use euclid::default::Size2D;
 use pathfinder_canvas::{Canvas, CanvasFontContext, CanvasRenderingContext2D, FillStyle, Path2D};
 use pathfinder_color::{ColorF, ColorU};
+use pathfinder_content::outline::ArcDirection;
 use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f, vec2i};
 use pathfinder_gl::{GLDevice, GLVersion};
 use pathfinder_renderer::concurrent::rayon::RayonExecutor;
@@ -181,7 +182,7 @@ impl MoireRenderer {
         for index in 0..CIRCLE_COUNT {
             let radius = (index + 1) as f32 * CIRCLE_SPACING * self.device_pixel_ratio;
             let mut path = Path2D::new();
-            path.ellipse(center, radius, 0.0, 0.0, PI * 2.0);
+            path.ellipse(center, radius, 0.0, 0.0, PI * 2.0, ArcDirection::CW);
             canvas.stroke_path(path);
         }
     }
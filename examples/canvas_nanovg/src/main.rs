@@ -187,8 +187,9 @@ fn draw_eyes(context: &mut CanvasRenderingContext2D,
     gradient.add_color_stop(rgbau(0, 0, 0, 32), 0.0);
     gradient.add_color_stop(rgbau(0, 0, 0, 16), 1.0);
     let mut path = Path2D::new();
-    path.ellipse(eyes_left_position  + vec2f(3.0, 16.0), eyes_radii, 0.0, 0.0, PI_2);
-    path.ellipse(eyes_right_position + vec2f(3.0, 16.0), eyes_radii, 0.0, 0.0, PI_2);
+    let shadow_offset = vec2f(3.0, 16.0);
+    path.ellipse(eyes_left_position  + shadow_offset, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
+    path.ellipse(eyes_right_position + shadow_offset, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.set_fill_style(gradient);
     context.fill_path(path, FillRule::Winding);
 
@@ -198,8 +199,8 @@ fn draw_eyes(context: &mut CanvasRenderingContext2D,
     gradient.add_color_stop(rgbu(220, 220, 220), 0.0);
     gradient.add_color_stop(rgbu(128, 128, 128), 1.0);
     let mut path = Path2D::new();
-    path.ellipse(eyes_left_position, eyes_radii, 0.0, 0.0, PI_2);
-    path.ellipse(eyes_right_position, eyes_radii, 0.0, 0.0, PI_2);
+    path.ellipse(eyes_left_position, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
+    path.ellipse(eyes_right_position, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.set_fill_style(gradient);
     context.fill_path(path, FillRule::Winding);
 
@@ -214,12 +215,14 @@ fn draw_eyes(context: &mut CanvasRenderingContext2D,
                  vec2f(eyes_center, eyes_center * blink),
                  0.0,
                  0.0,
-                 PI_2);
+                 PI_2,
+                 ArcDirection::CW);
     path.ellipse(eyes_right_position + delta + vec2f(0.0, eyes_radii.y() * 0.25 * (1.0 - blink)),
                  vec2f(eyes_center, eyes_center * blink),
                  0.0,
                  0.0,
-                 PI_2);
+                 PI_2,
+                 ArcDirection::CW);
     context.set_fill_style(rgbu(32, 32, 32));
     context.fill_path(path, FillRule::Winding);
 
@@ -230,7 +233,7 @@ fn draw_eyes(context: &mut CanvasRenderingContext2D,
     gloss.add_color_stop(rgbau(255, 255, 255, 0), 1.0);
     context.set_fill_style(gloss);
     let mut path = Path2D::new();
-    path.ellipse(eyes_left_position, eyes_radii, 0.0, 0.0, PI_2);
+    path.ellipse(eyes_left_position, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.fill_path(path, FillRule::Winding);
 
     let gloss_position = eyes_right_position - eyes_radii * vec2f(0.25, 0.5);
@@ -239,7 +242,7 @@ fn draw_eyes(context: &mut CanvasRenderingContext2D,
     gloss.add_color_stop(rgbau(255, 255, 255, 0), 1.0);
     context.set_fill_style(gloss);
     let mut path = Path2D::new();
-    path.ellipse(eyes_right_position, eyes_radii, 0.0, 0.0, PI_2);
+    path.ellipse(eyes_right_position, eyes_radii, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.fill_path(path, FillRule::Winding);
 }
 
@@ -584,13 +587,13 @@ fn draw_graph(context: &mut CanvasRenderingContext2D, rect: RectF, time: f32) {
     context.set_fill_style(rgbu(0, 160, 192));
     let mut path = Path2D::new();
     for &sample_point in &sample_points {
-        path.ellipse(sample_point, vec2f(4.0, 4.0), 0.0, 0.0, PI_2);
+        path.ellipse(sample_point, vec2f(4.0, 4.0), 0.0, 0.0, PI_2, ArcDirection::CW);
     }
     context.fill_path(path, FillRule::Winding);
     context.set_fill_style(rgbu(220, 220, 220));
     let mut path = Path2D::new();
     for &sample_point in &sample_points {
-        path.ellipse(sample_point, vec2f(2.0, 2.0), 0.0, 0.0, PI_2);
+        path.ellipse(sample_point, vec2f(2.0, 2.0), 0.0, 0.0, PI_2, ArcDirection::CW);
     }
     context.fill_path(path, FillRule::Winding);
 
@@ -637,8 +640,8 @@ fn draw_color_wheel(context: &mut CanvasRenderingContext2D,
     context.set_stroke_style(rgbau(0, 0, 0, 64));
     context.set_line_width(1.0);
     let mut path = Path2D::new();
-    path.ellipse(center, inner_radius - 0.5, 0.0, 0.0, PI_2);
-    path.ellipse(center, outer_radius + 0.5, 0.0, 0.0, PI_2);
+    path.ellipse(center, inner_radius - 0.5, 0.0, 0.0, PI_2, ArcDirection::CW);
+    path.ellipse(center, outer_radius + 0.5, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.stroke_path(path);
 
     // Prepare to draw the selector.
@@ -687,7 +690,7 @@ fn draw_color_wheel(context: &mut CanvasRenderingContext2D,
     context.set_stroke_style(rgbau(255, 255, 255, 192));
     context.set_line_width(2.0);
     let mut path = Path2D::new();
-    path.ellipse(selection_circle_center, vec2f(5.0, 5.0), 0.0, 0.0, PI_2);
+    path.ellipse(selection_circle_center, vec2f(5.0, 5.0), 0.0, 0.0, PI_2, ArcDirection::CW);
     context.stroke_path(path);
 
     // Fill the selection circle.
@@ -697,7 +700,7 @@ fn draw_color_wheel(context: &mut CanvasRenderingContext2D,
     context.set_fill_style(gradient);
     let mut path = Path2D::new();
     path.rect(RectF::new(selection_circle_center - vec2f(20.0, 20.0), vec2f(40.0, 40.0)));
-    path.ellipse(selection_circle_center, vec2f(7.0, 7.0), 0.0, 0.0, PI_2);
+    path.ellipse(selection_circle_center, vec2f(7.0, 7.0), 0.0, 0.0, PI_2, ArcDirection::CW);
     context.fill_path(path, FillRule::EvenOdd);
 
     context.restore();
@@ -1133,7 +1136,7 @@ fn draw_slider(context: &mut CanvasRenderingContext2D,
     background_gradient.add_color_stop(rgbau(255, 255, 255, 16), 0.0);
     background_gradient.add_color_stop(rgbau(0,   0,   0,   16), 1.0);
     let mut path = Path2D::new();
-    path.ellipse(knob_position, knob_radius - 1.0, 0.0, 0.0, PI_2);
+    path.ellipse(knob_position, knob_radius - 1.0, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.set_fill_style(rgbu(40, 43, 48));
     context.set_shadow_blur(6.0 * hidpi_factor);
     context.set_shadow_color(rgbau(0, 0, 0, 128));
@@ -1145,7 +1148,7 @@ fn draw_slider(context: &mut CanvasRenderingContext2D,
 
     // Outline knob.
     let mut path = Path2D::new();
-    path.ellipse(knob_position, knob_radius - 0.5, 0.0, 0.0, PI_2);
+    path.ellipse(knob_position, knob_radius - 0.5, 0.0, 0.0, PI_2, ArcDirection::CW);
     context.set_stroke_style(rgbau(0, 0, 0, 92));
     context.stroke_path(path);
 
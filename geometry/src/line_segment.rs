@@ -17,6 +17,7 @@ use pathfinder_simd::default::F32x4;
 use std::ops::{Add, Mul, MulAssign, Sub};
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineSegment2F(pub F32x4);
 
 impl LineSegment2F {
@@ -292,6 +293,7 @@ impl MulAssign<Vector2F> for LineSegment2F {
 }
 
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct LineSegmentU16 {
     pub from_x: u16,
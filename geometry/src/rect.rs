@@ -15,6 +15,7 @@ use pathfinder_simd::default::{F32x4, I32x4};
 use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RectF(pub F32x4);
 
 impl RectF {
@@ -282,6 +283,7 @@ impl SubAssign<f32> for RectF {
 
 /// NB: The origin is inclusive, while the lower right point is exclusive.
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RectI(pub I32x4);
 
 impl RectI {
@@ -20,6 +20,7 @@ use std::ops::{Mul, MulAssign, Sub};
 
 /// A 2x2 matrix, optimized with SIMD, in column-major order.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix2x2F(pub F32x4);
 
 impl Default for Matrix2x2F {
@@ -131,6 +132,7 @@ impl Mul<Vector2F> for Matrix2x2F {
 
 /// An affine transform, optimized with SIMD.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform2F {
     pub matrix: Matrix2x2F,
     pub vector: Vector2F,
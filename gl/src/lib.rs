@@ -356,6 +356,14 @@ impl Device for GLDevice {
         }
     }
 
+    fn max_texture_size(&self) -> i32 {
+        let mut max_texture_size = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size); ck();
+        }
+        max_texture_size
+    }
+
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> GLTexture {
         let mut texture = GLTexture { gl_texture: 0, size, format };
         unsafe {
@@ -376,6 +384,41 @@ impl Device for GLDevice {
         texture
     }
 
+    fn try_create_texture(&self, format: TextureFormat, size: Vector2I) -> Option<GLTexture> {
+        let mut texture = GLTexture { gl_texture: 0, size, format };
+        unsafe {
+            // Clear errors left by earlier calls, so that any error seen below comes from this
+            // allocation. An earlier out-of-memory error would otherwise go unnoticed.
+            if take_gl_error() == gl::OUT_OF_MEMORY {
+                warn!("GL ran out of memory before allocating a {:?} texture", format);
+            }
+
+            gl::GenTextures(1, &mut texture.gl_texture); ck();
+            self.bind_texture(&texture, 0);
+            gl::TexImage2D(gl::TEXTURE_2D,
+                           0,
+                           format.gl_internal_format(),
+                           size.x() as GLsizei,
+                           size.y() as GLsizei,
+                           0,
+                           format.gl_format(),
+                           format.gl_type(),
+                           ptr::null());
+            // On failure, dropping the texture deletes its name.
+            match take_gl_error() {
+                gl::NO_ERROR => {}
+                gl::OUT_OF_MEMORY => return None,
+                error => {
+                    warn!("failed to allocate a {:?} texture: GL error 0x{:x}", format, error);
+                    return None;
+                }
+            }
+        }
+
+        self.set_texture_sampling_mode(&texture, TextureSamplingFlags::empty());
+        Some(texture)
+    }
+
     fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
                                 -> GLTexture {
         let data_ptr = data.check_and_extract_data_ptr(size, format);
@@ -1400,6 +1443,7 @@ impl BufferUploadModeExt for BufferUploadMode {
         match self {
             BufferUploadMode::Static => gl::STATIC_DRAW,
             BufferUploadMode::Dynamic => gl::DYNAMIC_DRAW,
+            BufferUploadMode::Stream => gl::STREAM_DRAW,
         }
     }
 }
@@ -1578,6 +1622,20 @@ fn ck() {
 #[cfg(not(debug_assertions))]
 fn ck() {}
 
+// Clears all pending GL errors and returns the one to report: the first out-of-memory error if
+// there was one, since it's the one callers can recover from, or else the first error.
+unsafe fn take_gl_error() -> GLenum {
+    let mut reported_error = gl::NO_ERROR;
+    loop {
+        match gl::GetError() {
+            gl::NO_ERROR => return reported_error,
+            gl::OUT_OF_MEMORY => reported_error = gl::OUT_OF_MEMORY,
+            error if reported_error == gl::NO_ERROR => reported_error = error,
+            _ => {}
+        }
+    }
+}
+
 // Utilities
 
 // Flips a buffer of image data upside-down.
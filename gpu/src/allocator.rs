@@ -10,7 +10,7 @@
 
 //! GPU memory management.
 
-use crate::{BufferData, BufferTarget, BufferUploadMode, Device, TextureFormat};
+use crate::{BufferData, BufferTarget, BufferUploadMode, Device, FramebufferError, TextureFormat};
 use instant::Instant;
 use fxhash::FxHashMap;
 use pathfinder_geometry::vector::Vector2I;
@@ -41,13 +41,16 @@ pub struct GPUMemoryAllocator<D> where D: Device {
     next_index_buffer_id: IndexBufferID,
     next_texture_id: TextureID,
     next_framebuffer_id: FramebufferID,
-    bytes_committed: u64,
-    bytes_allocated: u64,
+    bytes: ByteCounts,
+    max_cached_bytes: Option<u64>,
+    reuse_hits: u32,
+    reuse_misses: u32,
 }
 
 struct BufferAllocation<D> where D: Device {
     buffer: D::Buffer,
     size: u64,
+    mode: BufferUploadMode,
     tag: BufferTag,
 }
 
@@ -120,13 +123,27 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             next_index_buffer_id: IndexBufferID(0),
             next_texture_id: TextureID(0),
             next_framebuffer_id: FramebufferID(0),
-            bytes_committed: 0,
-            bytes_allocated: 0,
+            bytes: ByteCounts::default(),
+            max_cached_bytes: None,
+            reuse_hits: 0,
+            reuse_misses: 0,
         }
     }
 
     pub fn allocate_general_buffer<T>(&mut self, device: &D, size: u64, tag: BufferTag)
                                       -> GeneralBufferID {
+        self.allocate_general_buffer_with_mode::<T>(device, size, BufferUploadMode::Dynamic, tag)
+    }
+
+    /// Like `allocate_general_buffer()`, but passes the given usage hint to the device.
+    ///
+    /// Freed buffers are only reused for allocations with the same mode.
+    pub fn allocate_general_buffer_with_mode<T>(&mut self,
+                                                device: &D,
+                                                size: u64,
+                                                mode: BufferUploadMode,
+                                                tag: BufferTag)
+                                                -> GeneralBufferID {
         let mut byte_size = size * mem::size_of::<T>() as u64;
         if byte_size < MAX_BUFFER_SIZE_CLASS {
             byte_size = byte_size.next_power_of_two();
@@ -139,7 +156,7 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                 FreeObject {
                     ref timestamp,
                     kind: FreeObjectKind::GeneralBuffer { ref allocation, .. },
-                } if allocation.size == byte_size &&
+                } if allocation.size == byte_size && allocation.mode == mode &&
                     (now - *timestamp).as_secs_f32() >= REUSE_TIME => {}
                 _ => continue,
             }
@@ -155,12 +172,15 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             };
 
             allocation.tag = tag;
-            self.bytes_committed += allocation.size;
+            self.bytes.reuse(allocation.size);
+            self.reuse_hits += 1;
             self.general_buffers_in_use.insert(id, allocation);
             return id;
         }
 
-        let buffer = device.create_buffer(BufferUploadMode::Dynamic);
+        self.reuse_misses += 1;
+
+        let buffer = device.create_buffer(mode);
         device.allocate_buffer::<u8>(&buffer,
                                      BufferData::Uninitialized(byte_size as usize),
                                      BufferTarget::Vertex);
@@ -175,9 +195,13 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                mem::size_of::<T>(),
                tag);
 
-        self.general_buffers_in_use.insert(id, BufferAllocation { buffer, size: byte_size, tag });
-        self.bytes_allocated += byte_size;
-        self.bytes_committed += byte_size;
+        self.general_buffers_in_use.insert(id, BufferAllocation {
+            buffer,
+            size: byte_size,
+            mode,
+            tag,
+        });
+        self.bytes.allocate(byte_size);
 
         id
     }
@@ -209,11 +233,14 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             };
 
             allocation.tag = tag;
-            self.bytes_committed += allocation.size;
+            self.bytes.reuse(allocation.size);
+            self.reuse_hits += 1;
             self.index_buffers_in_use.insert(id, allocation);
             return id;
         }
 
+        self.reuse_misses += 1;
+
         let buffer = device.create_buffer(BufferUploadMode::Dynamic);
         device.allocate_buffer::<u8>(&buffer,
                                      BufferData::Uninitialized(byte_size as usize),
@@ -229,9 +256,13 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                mem::size_of::<T>(),
                tag);
 
-        self.index_buffers_in_use.insert(id, BufferAllocation { buffer, size: byte_size, tag });
-        self.bytes_allocated += byte_size;
-        self.bytes_committed += byte_size;
+        self.index_buffers_in_use.insert(id, BufferAllocation {
+            buffer,
+            size: byte_size,
+            mode: BufferUploadMode::Dynamic,
+            tag,
+        });
+        self.bytes.allocate(byte_size);
 
         id
     }
@@ -264,11 +295,14 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             };
 
             allocation.tag = tag;
-            self.bytes_committed += allocation.descriptor.byte_size();
+            self.bytes.reuse(allocation.descriptor.byte_size());
+            self.reuse_hits += 1;
             self.textures_in_use.insert(id, allocation);
             return id;
         }
 
+        self.reuse_misses += 1;
+
         debug!("mapping texture: {:?} {:?}", descriptor, tag);
 
         let texture = device.create_texture(format, size);
@@ -277,8 +311,7 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
 
         self.textures_in_use.insert(id, TextureAllocation { texture, descriptor, tag });
 
-        self.bytes_allocated += byte_size;
-        self.bytes_committed += byte_size;
+        self.bytes.allocate(byte_size);
 
         id
     }
@@ -294,12 +327,67 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             height: size.y() as u32,
             format,
         };
-        let byte_size = descriptor.byte_size();
+        if let Some(id) = self.reuse_free_framebuffer(&descriptor, tag) {
+            return id;
+        }
+
+        debug!("mapping framebuffer: {:?} {:?}", descriptor, tag);
+
+        let texture = device.create_texture(format, size);
+        let framebuffer = device.create_framebuffer(texture);
+        self.insert_framebuffer(framebuffer, descriptor, tag)
+    }
 
+    /// Like `allocate_framebuffer()`, but returns `None` if the device ran out of memory while
+    /// creating the backing texture or can't render to the format.
+    pub fn try_allocate_framebuffer(&mut self,
+                                    device: &D,
+                                    size: Vector2I,
+                                    format: TextureFormat,
+                                    tag: FramebufferTag)
+                                    -> Option<FramebufferID> {
+        let descriptor = TextureDescriptor {
+            width: size.x() as u32,
+            height: size.y() as u32,
+            format,
+        };
+        if let Some(id) = self.reuse_free_framebuffer(&descriptor, tag) {
+            return Some(id);
+        }
+
+        debug!("mapping framebuffer: {:?} {:?}", descriptor, tag);
+
+        // Check the format first, so that no texture is created just to be thrown away.
+        if !device.supported_texture_formats().contains_format(format) {
+            warn!("failed to map framebuffer: {:?} {:?}: {}",
+                  descriptor,
+                  tag,
+                  FramebufferError::UnsupportedFormat(format));
+            return None;
+        }
+
+        let texture = match device.try_create_texture(format, size) {
+            Some(texture) => texture,
+            None => {
+                warn!("out of memory mapping framebuffer: {:?} {:?}", descriptor, tag);
+                return None;
+            }
+        };
+        match device.try_create_framebuffer(texture) {
+            Ok(framebuffer) => Some(self.insert_framebuffer(framebuffer, descriptor, tag)),
+            Err(err) => {
+                warn!("failed to map framebuffer: {:?} {:?}: {}", descriptor, tag, err);
+                None
+            }
+        }
+    }
+
+    fn reuse_free_framebuffer(&mut self, descriptor: &TextureDescriptor, tag: FramebufferTag)
+                              -> Option<FramebufferID> {
         for free_object_index in 0..self.free_objects.len() {
             match self.free_objects[free_object_index].kind {
                 FreeObjectKind::Framebuffer { ref allocation, .. } if allocation.descriptor ==
-                        descriptor => {}
+                        *descriptor => {}
                 _ => continue,
             }
 
@@ -311,15 +399,21 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             };
 
             allocation.tag = tag;
-            self.bytes_committed += allocation.descriptor.byte_size();
+            self.bytes.reuse(allocation.descriptor.byte_size());
+            self.reuse_hits += 1;
             self.framebuffers_in_use.insert(id, allocation);
-            return id;
+            return Some(id);
         }
+        self.reuse_misses += 1;
+        None
+    }
 
-        debug!("mapping framebuffer: {:?} {:?}", descriptor, tag);
-
-        let texture = device.create_texture(format, size);
-        let framebuffer = device.create_framebuffer(texture);
+    fn insert_framebuffer(&mut self,
+                          framebuffer: D::Framebuffer,
+                          descriptor: TextureDescriptor,
+                          tag: FramebufferTag)
+                          -> FramebufferID {
+        let byte_size = descriptor.byte_size();
         let id = self.next_framebuffer_id;
         self.next_framebuffer_id.0 += 1;
 
@@ -329,12 +423,14 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
             tag,
         });
 
-        self.bytes_allocated += byte_size;
-        self.bytes_committed += byte_size;
+        self.bytes.allocate(byte_size);
 
         id
     }
 
+    /// Destroys free objects that have gone unused for a while, and then, if a cache budget was
+    /// set with `set_max_cached_bytes()`, destroys the least recently freed objects until the
+    /// free objects fit within it.
     pub fn purge_if_needed(&mut self) {
         let now = Instant::now();
         loop {
@@ -343,36 +439,62 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                     DECAY_TIME => {}
                 _ => break,
             }
-            match self.free_objects.pop_front() {
-                None => break,
-                Some(FreeObject {
-                    kind: FreeObjectKind::GeneralBuffer { allocation, .. },
-                    ..
-                }) => {
-                    debug!("purging general buffer: {}", allocation.size);
-                    self.bytes_allocated -= allocation.size;
-                }
-                Some(FreeObject { kind: FreeObjectKind::IndexBuffer { allocation, .. }, .. }) => {
-                    debug!("purging index buffer: {}", allocation.size);
-                    self.bytes_allocated -= allocation.size;
-                }
-                Some(FreeObject { kind: FreeObjectKind::Texture { allocation, .. }, .. }) => {
-                    debug!("purging texture: {:?}", allocation.descriptor);
-                    self.bytes_allocated -= allocation.descriptor.byte_size();
-                }
-                Some(FreeObject { kind: FreeObjectKind::Framebuffer { allocation, .. }, .. }) => {
-                    debug!("purging framebuffer: {:?}", allocation.descriptor);
-                    self.bytes_allocated -= allocation.descriptor.byte_size();
-                }
+            self.purge_least_recently_freed();
+        }
+
+        if let Some(max_cached_bytes) = self.max_cached_bytes {
+            let free_object_sizes = self.free_objects.iter().map(|object| object.kind.byte_size());
+            let purge_count = free_objects_over_budget(free_object_sizes,
+                                                       self.bytes.cached(),
+                                                       max_cached_bytes);
+            for _ in 0..purge_count {
+                self.purge_least_recently_freed();
+            }
+        }
+    }
+
+    // Objects are pushed onto the back of the free list when freed and are removed from it when
+    // reused, so the front is always the least recently used free object.
+    fn purge_least_recently_freed(&mut self) -> bool {
+        match self.free_objects.pop_front() {
+            None => return false,
+            Some(FreeObject {
+                kind: FreeObjectKind::GeneralBuffer { allocation, .. },
+                ..
+            }) => {
+                debug!("purging general buffer: {}", allocation.size);
+                self.bytes.purge(allocation.size);
+            }
+            Some(FreeObject { kind: FreeObjectKind::IndexBuffer { allocation, .. }, .. }) => {
+                debug!("purging index buffer: {}", allocation.size);
+                self.bytes.purge(allocation.size);
+            }
+            Some(FreeObject { kind: FreeObjectKind::Texture { allocation, .. }, .. }) => {
+                debug!("purging texture: {:?}", allocation.descriptor);
+                self.bytes.purge(allocation.descriptor.byte_size());
+            }
+            Some(FreeObject { kind: FreeObjectKind::Framebuffer { allocation, .. }, .. }) => {
+                debug!("purging framebuffer: {:?}", allocation.descriptor);
+                self.bytes.purge(allocation.descriptor.byte_size());
             }
         }
+        true
+    }
+
+    /// Sets the maximum number of bytes that freed objects may occupy while they wait to be
+    /// reused, or `None` to only purge them once they've gone unused for a while.
+    ///
+    /// The budget is enforced by `purge_if_needed()`.
+    #[inline]
+    pub fn set_max_cached_bytes(&mut self, max_cached_bytes: Option<u64>) {
+        self.max_cached_bytes = max_cached_bytes;
     }
 
     pub fn free_general_buffer(&mut self, id: GeneralBufferID) {
         let allocation = self.general_buffers_in_use
                              .remove(&id)
                              .expect("Attempted to free unallocated general buffer!");
-        self.bytes_committed -= allocation.size;
+        self.bytes.free(allocation.size);
         self.free_objects.push_back(FreeObject {
             timestamp: Instant::now(),
             kind: FreeObjectKind::GeneralBuffer { id, allocation },
@@ -383,7 +505,7 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
         let allocation = self.index_buffers_in_use
                              .remove(&id)
                              .expect("Attempted to free unallocated index buffer!");
-        self.bytes_committed -= allocation.size;
+        self.bytes.free(allocation.size);
         self.free_objects.push_back(FreeObject {
             timestamp: Instant::now(),
             kind: FreeObjectKind::IndexBuffer { id, allocation },
@@ -395,7 +517,7 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                              .remove(&id)
                              .expect("Attempted to free unallocated texture!");
         let byte_size = allocation.descriptor.byte_size();
-        self.bytes_committed -= byte_size;
+        self.bytes.free(byte_size);
         self.free_objects.push_back(FreeObject {
             timestamp: Instant::now(),
             kind: FreeObjectKind::Texture { id, allocation },
@@ -407,7 +529,7 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
                              .remove(&id)
                              .expect("Attempted to free unallocated framebuffer!");
         let byte_size = allocation.descriptor.byte_size();
-        self.bytes_committed -= byte_size;
+        self.bytes.free(byte_size);
         self.free_objects.push_back(FreeObject {
             timestamp: Instant::now(),
             kind: FreeObjectKind::Framebuffer { id, allocation },
@@ -432,12 +554,62 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
 
     #[inline]
     pub fn bytes_allocated(&self) -> u64 {
-        self.bytes_allocated
+        self.bytes.allocated
     }
 
     #[inline]
     pub fn bytes_committed(&self) -> u64 {
-        self.bytes_committed
+        self.bytes.committed
+    }
+
+    /// Returns the number of bytes of new GPU objects created since the allocator was created or
+    /// since the last call to `reset_total_bytes_allocated()`.
+    ///
+    /// Reused objects are not counted, and purged objects are not subtracted.
+    #[inline]
+    pub fn total_bytes_allocated(&self) -> u64 {
+        self.bytes.total_allocated
+    }
+
+    #[inline]
+    pub fn reset_total_bytes_allocated(&mut self) {
+        self.bytes.total_allocated = 0;
+    }
+
+    /// Returns the largest number of bytes that were allocated at once since the allocator was
+    /// created or since the last call to `reset_peak_bytes_allocated()`.
+    ///
+    /// Freed objects that are cached for reuse count as allocated until they're purged.
+    #[inline]
+    pub fn peak_bytes_allocated(&self) -> u64 {
+        self.bytes.peak_allocated
+    }
+
+    /// Resets the value returned by `peak_bytes_allocated()` to the number of bytes currently
+    /// allocated.
+    #[inline]
+    pub fn reset_peak_bytes_allocated(&mut self) {
+        self.bytes.peak_allocated = self.bytes.allocated;
+    }
+
+    /// Returns the number of allocations satisfied by reusing a freed object since the allocator
+    /// was created or since the last call to `reset_reuse_counts()`.
+    #[inline]
+    pub fn reuse_hits(&self) -> u32 {
+        self.reuse_hits
+    }
+
+    /// Returns the number of allocations that had to create a new GPU object since the allocator
+    /// was created or since the last call to `reset_reuse_counts()`.
+    #[inline]
+    pub fn reuse_misses(&self) -> u32 {
+        self.reuse_misses
+    }
+
+    #[inline]
+    pub fn reset_reuse_counts(&mut self) {
+        self.reuse_hits = 0;
+        self.reuse_misses = 0;
     }
 
     #[allow(dead_code)]
@@ -491,8 +663,140 @@ impl<D> GPUMemoryAllocator<D> where D: Device {
     }
 }
 
+impl<D> FreeObjectKind<D> where D: Device {
+    fn byte_size(&self) -> u64 {
+        match *self {
+            FreeObjectKind::GeneralBuffer { ref allocation, .. } |
+            FreeObjectKind::IndexBuffer { ref allocation, .. } => allocation.size,
+            FreeObjectKind::Texture { ref allocation, .. } => allocation.descriptor.byte_size(),
+            FreeObjectKind::Framebuffer { ref allocation, .. } => {
+                allocation.descriptor.byte_size()
+            }
+        }
+    }
+}
+
+// How many bytes of GPU objects the allocator holds. Objects count as allocated from creation until
+// they're purged, and as committed while they're in use.
+#[derive(Clone, Copy, Debug, Default)]
+struct ByteCounts {
+    allocated: u64,
+    committed: u64,
+    total_allocated: u64,
+    peak_allocated: u64,
+}
+
+impl ByteCounts {
+    // Records a newly created object.
+    fn allocate(&mut self, byte_size: u64) {
+        self.allocated += byte_size;
+        self.total_allocated += byte_size;
+        self.peak_allocated = self.peak_allocated.max(self.allocated);
+        self.committed += byte_size;
+    }
+
+    // Records a free object being put back into use.
+    fn reuse(&mut self, byte_size: u64) {
+        self.committed += byte_size;
+    }
+
+    // Records an object being freed and cached for reuse.
+    fn free(&mut self, byte_size: u64) {
+        self.committed -= byte_size;
+    }
+
+    // Records a free object being destroyed.
+    fn purge(&mut self, byte_size: u64) {
+        self.allocated -= byte_size;
+    }
+
+    // Returns the number of bytes of free objects cached for reuse.
+    fn cached(&self) -> u64 {
+        self.allocated - self.committed
+    }
+}
+
 impl TextureDescriptor {
     fn byte_size(&self) -> u64 {
         self.width as u64 * self.height as u64 * self.format.bytes_per_pixel() as u64
     }
 }
+
+// Given the sizes of the free objects from least to most recently freed, returns how many must be
+// purged from the front of the free list to bring the cached bytes within the budget.
+fn free_objects_over_budget<I>(free_object_sizes: I, mut cached_bytes: u64, max_cached_bytes: u64)
+                               -> usize where I: IntoIterator<Item = u64> {
+    let mut purge_count = 0;
+    for size in free_object_sizes {
+        if cached_bytes <= max_cached_bytes {
+            break;
+        }
+        cached_bytes -= size;
+        purge_count += 1;
+    }
+    purge_count
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ByteCounts, TextureDescriptor, free_objects_over_budget};
+    use crate::TextureFormat;
+
+    #[test]
+    fn test_purge_least_recently_freed_first() {
+        // Oldest first: 4 KB, 1 KB, 8 KB, 2 KB.
+        let sizes: [u64; 4] = [4096, 1024, 8192, 2048];
+        let cached_bytes: u64 = sizes.iter().sum();
+
+        // Within the budget, nothing is purged.
+        assert_eq!(free_objects_over_budget(sizes.iter().cloned(), cached_bytes, 16384), 0);
+
+        // Dropping the oldest object is enough to fit 11 KB, even though a newer one is larger.
+        assert_eq!(free_objects_over_budget(sizes.iter().cloned(), cached_bytes, 11264), 1);
+
+        // Purging continues in order until the rest fit.
+        assert_eq!(free_objects_over_budget(sizes.iter().cloned(), cached_bytes, 10240), 2);
+        assert_eq!(free_objects_over_budget(sizes.iter().cloned(), cached_bytes, 2048), 3);
+
+        // A zero budget purges everything.
+        assert_eq!(free_objects_over_budget(sizes.iter().cloned(), cached_bytes, 0), 4);
+    }
+
+    #[test]
+    fn test_peak_bytes_accounting() {
+        // This replays the byte counting for a D3D9 mask framebuffer that stays at one page or
+        // grows to two. Rendering real scenes and comparing their `gpu_bytes_peak` would need a
+        // `Device`, and every implementation in the tree needs a GPU context, so that comparison
+        // can't be tested here.
+        //
+        // A mask framebuffer of the given number of pages at the D3D9 level.
+        let page = |page_count: u32| {
+            let format = TextureFormat::RGBA16F;
+            TextureDescriptor { width: 4096, height: 1024 * page_count, format }
+        };
+        let page_bytes = page(1).byte_size();
+
+        // Limited to one page, the mask framebuffer is allocated once, recycled, and freed.
+        let mut one_page = ByteCounts::default();
+        one_page.allocate(page_bytes);
+        one_page.free(page_bytes);
+        assert_eq!(one_page.peak_allocated, page_bytes);
+        assert_eq!(one_page.cached(), page_bytes);
+
+        // Growing to two pages allocates the larger framebuffer before the old one is freed, and
+        // the old one stays cached until it's purged.
+        let mut two_pages = ByteCounts::default();
+        two_pages.allocate(page_bytes);
+        two_pages.allocate(page(2).byte_size());
+        two_pages.free(page_bytes);
+        two_pages.free(page(2).byte_size());
+        two_pages.purge(page_bytes);
+        assert_eq!(two_pages.peak_allocated, page_bytes * 3);
+        assert_eq!(two_pages.allocated, page_bytes * 2);
+        assert_eq!(two_pages.committed, 0);
+
+        assert!(one_page.peak_allocated < two_pages.peak_allocated);
+        assert_eq!(one_page.total_allocated, page_bytes);
+        assert_eq!(two_pages.total_allocated, page_bytes * 3);
+    }
+}
@@ -25,6 +25,8 @@ use pathfinder_geometry::transform3d::Transform4F;
 use pathfinder_geometry::vector::{Vector2I, vec2i};
 use pathfinder_resources::ResourceLoader;
 use pathfinder_simd::default::{F32x2, F32x4, I32x2};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::ops::Range;
 use std::os::raw::c_void;
 use std::time::Duration;
@@ -49,6 +51,13 @@ pub trait Device: Sized {
     fn backend_name(&self) -> &'static str;
     fn device_name(&self) -> String;
     fn feature_level(&self) -> FeatureLevel;
+    /// Returns the largest width and height, in texels, that a texture may have.
+    ///
+    /// The default is a conservative 4096, the width of the renderer's mask framebuffer. Devices
+    /// that allow larger textures should override this so that the mask can grow taller.
+    fn max_texture_size(&self) -> i32 {
+        4096
+    }
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> Self::Texture;
     fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
                                 -> Self::Texture;
@@ -121,6 +130,32 @@ pub trait Device: Sized {
     fn try_recv_buffer(&self, receiver: &Self::BufferDataReceiver) -> Option<Vec<u8>>;
     fn recv_buffer(&self, receiver: &Self::BufferDataReceiver) -> Vec<u8>;
 
+    /// Like `create_texture()`, but returns `None` instead of a dead texture if the device ran
+    /// out of memory while allocating it.
+    fn try_create_texture(&self, format: TextureFormat, size: Vector2I) -> Option<Self::Texture> {
+        Some(self.create_texture(format, size))
+    }
+
+    /// Like `create_framebuffer()`, but returns an error instead of panicking or producing an
+    /// incomplete framebuffer if the texture can't be rendered to.
+    fn try_create_framebuffer(&self, texture: Self::Texture)
+                              -> Result<Self::Framebuffer, FramebufferError> {
+        Ok(self.create_framebuffer(texture))
+    }
+
+    /// Returns the set of texture formats that can both be rendered to and sampled from with
+    /// linear filtering.
+    fn supported_texture_formats(&self) -> TextureFormatSet {
+        TextureFormatSet::all()
+    }
+
+    /// Returns true if the rectangles passed to `read_pixels()` and viewports are measured from
+    /// the lower left corner of the render target, as in OpenGL, or false if they're measured
+    /// from the upper left corner.
+    fn framebuffer_origin_is_lower_left(&self) -> bool {
+        true
+    }
+
     fn create_texture_from_png(&self,
                                resources: &dyn ResourceLoader,
                                name: &str,
@@ -215,6 +250,14 @@ pub enum TextureFormat {
     RGBA32F,
 }
 
+/// The reason a framebuffer couldn't be created.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FramebufferError {
+    /// The device can't render to textures of this format, usually because the extension that
+    /// enables it is unavailable.
+    UnsupportedFormat(TextureFormat),
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum VertexAttrType {
     F32,
@@ -238,10 +281,12 @@ pub enum BufferTarget {
     Storage,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BufferUploadMode {
     Static,
     Dynamic,
+    /// The contents are uploaded once and used for a single frame.
+    Stream,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -499,6 +544,31 @@ impl TextureFormat {
     }
 }
 
+impl Display for FramebufferError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            FramebufferError::UnsupportedFormat(format) => {
+                write!(formatter, "{:?} textures can't be rendered to on this device", format)
+            }
+        }
+    }
+}
+
+impl Error for FramebufferError {}
+
+impl TextureFormatSet {
+    #[inline]
+    pub fn contains_format(self, format: TextureFormat) -> bool {
+        self.contains(match format {
+            TextureFormat::R8 => TextureFormatSet::R8,
+            TextureFormat::R16F => TextureFormatSet::R16F,
+            TextureFormat::RGBA8 => TextureFormatSet::RGBA8,
+            TextureFormat::RGBA16F => TextureFormatSet::RGBA16F,
+            TextureFormat::RGBA32F => TextureFormatSet::RGBA32F,
+        })
+    }
+}
+
 impl ClearOps {
     #[inline]
     pub fn has_ops(&self) -> bool {
@@ -520,6 +590,17 @@ impl Default for BlendState {
 }
 
 bitflags! {
+    pub struct TextureFormatSet: u8 {
+        const R8      = 0x01;
+        const R16F    = 0x02;
+        const RGBA8   = 0x04;
+        const RGBA16F = 0x08;
+        const RGBA32F = 0x10;
+    }
+}
+
+bitflags! {
+    #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
     pub struct TextureSamplingFlags: u8 {
         const REPEAT_U    = 0x01;
         const REPEAT_V    = 0x02;
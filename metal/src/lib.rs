@@ -383,6 +383,16 @@ impl Device for MetalDevice {
         FeatureLevel::D3D11
     }
 
+    // All GPU families that support the features we require allow 16384x16384 textures.
+    fn max_texture_size(&self) -> i32 {
+        16384
+    }
+
+    #[inline]
+    fn framebuffer_origin_is_lower_left(&self) -> bool {
+        false
+    }
+
     // TODO: Add texture usage hint.
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> MetalTexture {
         let descriptor = create_texture_descriptor(format, size);
@@ -2055,7 +2065,9 @@ impl BufferUploadModeExt for BufferUploadMode {
     #[inline]
     fn to_metal_resource_options(self) -> MTLResourceOptions {
         let mut options = match self {
-            BufferUploadMode::Static => MTLResourceOptions::CPUCacheModeWriteCombined,
+            BufferUploadMode::Static | BufferUploadMode::Stream => {
+                MTLResourceOptions::CPUCacheModeWriteCombined
+            }
             BufferUploadMode::Dynamic => MTLResourceOptions::CPUCacheModeDefaultCache,
         };
         options |= MTLResourceOptions::StorageModePrivate;
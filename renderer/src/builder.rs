@@ -11,17 +11,17 @@
 //! Packs data onto the GPU.
 
 use crate::concurrent::executor::Executor;
-use crate::gpu::blend::BlendModeExt;
 use crate::gpu::options::RendererLevel;
 use crate::gpu_data::{AlphaTileId, BackdropInfoD3D11, Clip, ClippedPathInfo, DiceMetadataD3D11};
-use crate::gpu_data::{DrawTileBatch, DrawTileBatchD3D9, DrawTileBatchD3D11, Fill, GlobalPathId};
-use crate::gpu_data::{PathBatchIndex, PathSource, PrepareTilesInfoD3D11, PropagateMetadataD3D11};
+use crate::gpu_data::{DrawMeshBatch, DrawTileBatch, DrawTileBatchD3D9, DrawTileBatchD3D11, Fill};
+use crate::gpu_data::{GlobalPathId, MeshVertex, PathBatchIndex, PathSource, PrepareTilesInfoD3D11};
+use crate::gpu_data::PropagateMetadataD3D11;
 use crate::gpu_data::{RenderCommand, SegmentIndicesD3D11, SegmentsD3D11, TileBatchDataD3D11};
 use crate::gpu_data::{TileBatchId, TileBatchTexture, TileObjectPrimitive, TilePathInfoD3D11};
 use crate::options::{PrepareMode, PreparedBuildOptions, PreparedRenderTransform};
 use crate::paint::{PaintId, PaintInfo, PaintMetadata};
 use crate::scene::{ClipPathId, DisplayItem, DrawPath, DrawPathId, LastSceneInfo, PathId};
-use crate::scene::{Scene, SceneSink};
+use crate::scene::{Mesh, Scene, SceneSink, TrivialRenderTargets};
 use crate::tile_map::DenseTileMap;
 use crate::tiler::Tiler;
 use crate::tiles::{self, DrawTilingPathInfo, TILE_HEIGHT, TILE_WIDTH, TilingPathInfo};
@@ -34,7 +34,6 @@ use pathfinder_geometry::line_segment::{LineSegment2F, LineSegmentU16};
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::{Vector2I, vec2i};
-use pathfinder_gpu::TextureSamplingFlags;
 use pathfinder_simd::default::F32x4;
 use std::borrow::Cow;
 use std::ops::Range;
@@ -51,7 +50,7 @@ const MAX_CLIP_BATCHES: u32 = 32;
 
 pub(crate) struct SceneBuilder<'a, 'b, 'c, 'd> {
     pub(crate) scene: &'a mut Scene,
-    built_options: &'b PreparedBuildOptions,
+    pub(crate) built_options: &'b PreparedBuildOptions,
     next_alpha_tile_indices: [AtomicUsize; ALPHA_TILE_LEVEL_COUNT],
     pub(crate) sink: &'c mut SceneSink<'d>,
 }
@@ -71,7 +70,7 @@ struct BuiltDrawPath {
     blend_mode: BlendMode,
     filter: Filter,
     color_texture: Option<TileBatchTexture>,
-    sampling_flags_1: TextureSamplingFlags,
+    color_texture_1: Option<TileBatchTexture>,
     mask_0_fill_rule: FillRule,
     occludes: bool,
 }
@@ -86,7 +85,7 @@ impl BuiltDrawPath {
             clip_path_id: path_object.clip_path(),
             filter: paint_metadata.filter(),
             color_texture: paint_metadata.tile_batch_texture(),
-            sampling_flags_1: TextureSamplingFlags::empty(),
+            color_texture_1: paint_metadata.tile_batch_texture_1(),
             mask_0_fill_rule: path_object.fill_rule(),
             blend_mode,
             occludes,
@@ -170,18 +169,27 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
             _ => Transform2F::default()
         };
 
+        // Render targets with no visible effect are skipped, so that their contents can be
+        // batched together with the paths around them.
+        let trivial_render_targets = self.scene.find_trivial_render_targets();
+
         // Build paint data.
         let PaintInfo {
             render_commands,
             paint_metadata,
-        } = self.scene.build_paint_info(&mut self.sink.paint_texture_manager, render_transform);
+        } = self.scene.build_paint_info(&mut self.sink.paint_texture_manager,
+                                        render_transform,
+                                        &trivial_render_targets);
         for render_command in render_commands {
             self.sink.listener.send(render_command);
         }
 
         let built_paths = match prepare_mode {
             PrepareMode::CPU | PrepareMode::TransformCPUBinGPU => {
-                Some(self.build_paths_on_cpu(executor, &paint_metadata, &prepare_mode))
+                Some(self.build_paths_on_cpu(executor,
+                                             &paint_metadata,
+                                             &prepare_mode,
+                                             &trivial_render_targets))
             }
             PrepareMode::GPU { .. } => None,
         };
@@ -211,7 +219,7 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
             });
         }
 
-        self.finish_building(&paint_metadata, built_paths, &prepare_mode);
+        self.finish_building(&paint_metadata, built_paths, &prepare_mode, &trivial_render_targets);
 
         let cpu_build_time = Instant::now() - start_time;
         self.sink.listener.send(RenderCommand::Finish { cpu_build_time });
@@ -220,7 +228,8 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
     fn build_paths_on_cpu<E>(&mut self,
                              executor: &E,
                              paint_metadata: &[PaintMetadata],
-                             prepare_mode: &PrepareMode)
+                             prepare_mode: &PrepareMode,
+                             trivial_render_targets: &TrivialRenderTargets)
                              -> BuiltPaths
                              where E: Executor {
         let clip_path_count = self.scene.clip_paths().len();
@@ -250,6 +259,7 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
                 },
                 paint_metadata: &paint_metadata,
                 built_clip_paths: &built_clip_paths,
+                trivial_render_targets,
             })
         });
 
@@ -287,10 +297,17 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
             },
             paint_metadata,
             built_clip_paths,
+            trivial_render_targets,
         } = params;
 
-        let path_object = scene.get_draw_path(path_id.to_draw_path_id());
-        let mut outline = scene.apply_render_options(path_object.outline(), built_options);
+        // Paths that composite trivial render targets aren't drawn, so don't tile them.
+        let draw_path_id = path_id.to_draw_path_id();
+        let path_object = scene.get_draw_path(draw_path_id);
+        let mut outline = if trivial_render_targets.is_composite_path(draw_path_id) {
+            Outline::new()
+        } else {
+            scene.apply_render_options(path_object.outline(), built_options)
+        };
         outline.clip_against_rect(view_box);
 
         let paint_id = path_object.paint();
@@ -325,30 +342,61 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
     fn build_tile_batches(&mut self,
                           paint_metadata: &[PaintMetadata],
                           prepare_mode: &PrepareMode,
-                          built_paths: Option<BuiltPaths>) {
+                          built_paths: Option<BuiltPaths>,
+                          trivial_render_targets: &TrivialRenderTargets) {
         let mut tile_batch_builder = TileBatchBuilder::new(built_paths);
 
-        // Prepare display items.
+        // Prepare display items. Paths are accumulated until a render target or mesh intervenes,
+        // so that the contents of trivial render targets, which are skipped, are batched together
+        // with the paths around them.
+        let mut render_target_stack = vec![];
+        let mut draw_path_ids = vec![];
         for display_item in self.scene.display_list() {
-            match *display_item {
+            let render_command = match *display_item {
                 DisplayItem::PushRenderTarget(render_target_id) => {
-                    tile_batch_builder.draw_commands
-                                      .push(RenderCommand::PushRenderTarget(render_target_id))
+                    let trivial = trivial_render_targets.contains_render_target(render_target_id);
+                    render_target_stack.push(trivial);
+                    if trivial {
+                        continue;
+                    }
+                    RenderCommand::PushRenderTarget(render_target_id)
                 }
                 DisplayItem::PopRenderTarget => {
-                    tile_batch_builder.draw_commands.push(RenderCommand::PopRenderTarget)
+                    if render_target_stack.pop().unwrap_or(false) {
+                        continue;
+                    }
+                    RenderCommand::PopRenderTarget
                 }
                 DisplayItem::DrawPaths(ref path_id_range) => {
-                    tile_batch_builder.build_tile_batches_for_draw_path_display_item(
-                        &self.scene,
-                        &self.sink,
-                        self.built_options,
-                        path_id_range.start..path_id_range.end,
-                        paint_metadata,
-                        prepare_mode);
+                    for draw_path_id in path_id_range.start.0..path_id_range.end.0 {
+                        let draw_path_id = DrawPathId(draw_path_id);
+                        if !trivial_render_targets.is_composite_path(draw_path_id) {
+                            draw_path_ids.push(draw_path_id);
+                        }
+                    }
+                    continue;
                 }
-            }
+                DisplayItem::DrawMesh(mesh_id) => {
+                    let mesh = self.scene.get_mesh(mesh_id);
+                    RenderCommand::DrawMesh(build_mesh_batch(mesh, self.built_options))
+                }
+            };
+
+            tile_batch_builder.build_tile_batches_for_draw_paths(&self.scene,
+                                                                 &self.sink,
+                                                                 self.built_options,
+                                                                 &draw_path_ids,
+                                                                 paint_metadata,
+                                                                 prepare_mode);
+            draw_path_ids.clear();
+            tile_batch_builder.draw_commands.push(render_command);
         }
+        tile_batch_builder.build_tile_batches_for_draw_paths(&self.scene,
+                                                             &self.sink,
+                                                             self.built_options,
+                                                             &draw_path_ids,
+                                                             paint_metadata,
+                                                             prepare_mode);
 
         // Send commands.
         tile_batch_builder.send_to(&self.sink);
@@ -357,13 +405,14 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
     fn finish_building(&mut self,
                        paint_metadata: &[PaintMetadata],
                        built_paths: Option<BuiltPaths>,
-                       prepare_mode: &PrepareMode) {
+                       prepare_mode: &PrepareMode,
+                       trivial_render_targets: &TrivialRenderTargets) {
         match self.sink.renderer_level {
             RendererLevel::D3D9 => self.sink.listener.send(RenderCommand::FlushFillsD3D9),
             RendererLevel::D3D11 => {}
         }
 
-        self.build_tile_batches(paint_metadata, prepare_mode, built_paths);
+        self.build_tile_batches(paint_metadata, prepare_mode, built_paths, trivial_render_targets);
     }
 
     fn needs_readable_framebuffer(&self) -> bool {
@@ -372,6 +421,7 @@ impl<'a, 'b, 'c, 'd> SceneBuilder<'a, 'b, 'c, 'd> {
             match *display_item {
                 DisplayItem::PushRenderTarget(_) => framebuffer_nesting += 1,
                 DisplayItem::PopRenderTarget => framebuffer_nesting -= 1,
+                DisplayItem::DrawMesh(_) => {}
                 DisplayItem::DrawPaths(ref draw_path_id_range) => {
                     if framebuffer_nesting > 0 {
                         continue;
@@ -406,6 +456,7 @@ struct DrawPathBuildParams<'a> {
     path_build_params: PathBuildParams<'a>,
     paint_metadata: &'a [PaintMetadata],
     built_clip_paths: &'a [BuiltPath],
+    trivial_render_targets: &'a TrivialRenderTargets,
 }
 
 impl BuiltPath {
@@ -872,16 +923,15 @@ impl TileBatchBuilder {
         }
     }
 
-    fn build_tile_batches_for_draw_path_display_item(&mut self,
-                                                     scene: &Scene,
-                                                     sink: &SceneSink,
-                                                     built_options: &PreparedBuildOptions,
-                                                     draw_path_id_range: Range<DrawPathId>,
-                                                     paint_metadata: &[PaintMetadata],
-                                                     prepare_mode: &PrepareMode) {
+    fn build_tile_batches_for_draw_paths(&mut self,
+                                         scene: &Scene,
+                                         sink: &SceneSink,
+                                         built_options: &PreparedBuildOptions,
+                                         draw_path_ids: &[DrawPathId],
+                                         paint_metadata: &[PaintMetadata],
+                                         prepare_mode: &PrepareMode) {
         let mut draw_tile_batch = None;
-        for draw_path_id in draw_path_id_range.start.0..draw_path_id_range.end.0 {
-            let draw_path_id = DrawPathId(draw_path_id);
+        for &draw_path_id in draw_path_ids {
             let draw_path = match self.level {
                 TileBatchBuilderLevel::D3D11 { .. } => {
                     match self.prepare_draw_path_for_gpu_binning(scene,
@@ -902,10 +952,12 @@ impl TileBatchBuilder {
             let flush_needed = match draw_tile_batch {
                 Some(DrawTileBatch::D3D11(ref mut existing_batch)) => {
                     !fixup_batch_for_new_path_if_possible(&mut existing_batch.color_texture,
+                                                          &mut existing_batch.color_texture_1,
                                                           &draw_path)
                 }
                 Some(DrawTileBatch::D3D9(ref mut existing_batch)) => {
                     !fixup_batch_for_new_path_if_possible(&mut existing_batch.color_texture,
+                                                          &mut existing_batch.color_texture_1,
                                                           &draw_path)
                 }
                 None => false,
@@ -928,12 +980,14 @@ impl TileBatchBuilder {
             if draw_tile_batch.is_none() {
                 draw_tile_batch = match self.level {
                     TileBatchBuilderLevel::D3D9 { .. } => {
-                        let tile_bounds = tiles::round_rect_out_to_tile_bounds(scene.view_box());
+                        let view_box = scene.render_view_box(built_options);
+                        let tile_bounds = tiles::round_rect_out_to_tile_bounds(view_box);
                         Some(DrawTileBatch::D3D9(DrawTileBatchD3D9 {
                             tiles: vec![],
                             clips: vec![],
                             z_buffer_data: DenseTileMap::from_builder(|_| 0, tile_bounds),
                             color_texture: draw_path.color_texture,
+                            color_texture_1: draw_path.color_texture_1,
                             filter: draw_path.filter,
                             blend_mode: draw_path.blend_mode,
                         }))
@@ -944,6 +998,7 @@ impl TileBatchBuilder {
                                                                      &prepare_mode,
                                                                      PathSource::Draw),
                             color_texture: draw_path.color_texture,
+                            color_texture_1: draw_path.color_texture_1,
                         }))
                     }
                 };
@@ -1092,6 +1147,22 @@ struct ClipBatchesD3D11 {
     clip_id_to_path_batch_index: FxHashMap<ClipPathId, PathBatchIndex>,
 }
 
+// Meshes aren't tiled, so their vertices are transformed here, on the CPU, regardless of the
+// prepare mode.
+fn build_mesh_batch(mesh: &Mesh, built_options: &PreparedBuildOptions) -> DrawMeshBatch {
+    let vertices = mesh.positions().iter().zip(mesh.colors().iter()).map(|(&position, &color)| {
+        let position = match built_options.transform {
+            PreparedRenderTransform::None => position,
+            PreparedRenderTransform::Transform2D(transform) => transform * position,
+            PreparedRenderTransform::Perspective { ref perspective, .. } => {
+                *perspective * position
+            }
+        };
+        MeshVertex { x: position.x(), y: position.y(), color }
+    }).collect();
+    DrawMeshBatch { vertices, indices: mesh.indices().to_vec() }
+}
+
 fn add_clip_path_to_batch(scene: &Scene,
                           sink: &SceneSink,
                           built_options: &PreparedBuildOptions,
@@ -1195,19 +1266,30 @@ struct PreparedClipPath {
 }
 
 fn fixup_batch_for_new_path_if_possible(batch_color_texture: &mut Option<TileBatchTexture>,
+                                        batch_color_texture_1: &mut Option<TileBatchTexture>,
                                         draw_path: &BuiltDrawPath)
                                         -> bool {
+    if !color_texture_fits_batch(draw_path.color_texture, *batch_color_texture) ||
+            !color_texture_fits_batch(draw_path.color_texture_1, *batch_color_texture_1) {
+        debug!("batch break: path color textures {:?}, {:?} batch color textures {:?}, {:?}",
+               draw_path.color_texture,
+               draw_path.color_texture_1,
+               batch_color_texture,
+               batch_color_texture_1);
+        return false;
+    }
     if draw_path.color_texture.is_some() {
-        if batch_color_texture.is_none() {
-            *batch_color_texture = draw_path.color_texture;
-            return true;
-        }
-        if draw_path.color_texture != *batch_color_texture {
-            debug!("batch break: path color texture {:?} batch color texture {:?}",
-                   draw_path.color_texture,
-                   batch_color_texture);
-            return false;
-        }
+        *batch_color_texture = draw_path.color_texture;
+    }
+    if draw_path.color_texture_1.is_some() {
+        *batch_color_texture_1 = draw_path.color_texture_1;
     }
     true
 }
+
+fn color_texture_fits_batch(path_color_texture: Option<TileBatchTexture>,
+                            batch_color_texture: Option<TileBatchTexture>)
+                            -> bool {
+    path_color_texture.is_none() || batch_color_texture.is_none() ||
+        path_color_texture == batch_color_texture
+}
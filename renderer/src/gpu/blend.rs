@@ -157,7 +157,8 @@ impl ToBlendState for BlendMode {
             BlendMode::Saturation |
             BlendMode::Color |
             BlendMode::Luminosity => {
-                // Blending is done manually in the shader.
+                // Blending is disabled. For `Copy`, that makes the source replace the
+                // destination; the other modes blend manually in the shader.
                 None
             }
         }
@@ -212,44 +213,6 @@ impl ToCompositeCtrl for ColorCombineMode {
     }
 }
 
-pub trait BlendModeExt {
-    fn needs_readable_framebuffer(self) -> bool;
-}
-
-impl BlendModeExt for BlendMode {
-    fn needs_readable_framebuffer(self) -> bool {
-        match self {
-            BlendMode::Clear |
-            BlendMode::SrcOver |
-            BlendMode::DestOver |
-            BlendMode::SrcIn |
-            BlendMode::DestIn |
-            BlendMode::SrcOut |
-            BlendMode::DestOut |
-            BlendMode::SrcAtop |
-            BlendMode::DestAtop |
-            BlendMode::Xor |
-            BlendMode::Lighter |
-            BlendMode::Copy => false,
-            BlendMode::Lighten |
-            BlendMode::Darken |
-            BlendMode::Multiply |
-            BlendMode::Screen |
-            BlendMode::HardLight |
-            BlendMode::Overlay |
-            BlendMode::ColorDodge |
-            BlendMode::ColorBurn |
-            BlendMode::SoftLight |
-            BlendMode::Difference |
-            BlendMode::Exclusion |
-            BlendMode::Hue |
-            BlendMode::Saturation |
-            BlendMode::Color |
-            BlendMode::Luminosity => true,
-        }
-    }
-}
-
 pub(crate) trait ToCombineMode {
     fn to_combine_mode(self) -> i32;
 }
@@ -262,3 +225,15 @@ impl ToCombineMode for PaintCompositeOp {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ToBlendState;
+    use pathfinder_content::effects::BlendMode;
+
+    #[test]
+    fn test_copy_skips_blending() {
+        assert!(BlendMode::Copy.to_blend_state().is_none());
+        assert!(!BlendMode::Copy.needs_readable_framebuffer());
+    }
+}
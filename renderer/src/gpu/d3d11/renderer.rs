@@ -31,6 +31,9 @@ use pathfinder_simd::default::{F32x4, I32x2};
 use std::ops::Range;
 use vec_map::VecMap;
 
+#[cfg(feature = "debug")]
+use std::mem;
+
 const FILL_INDIRECT_DRAW_PARAMS_INSTANCE_COUNT_INDEX:   usize = 1;
 const FILL_INDIRECT_DRAW_PARAMS_ALPHA_TILE_COUNT_INDEX: usize = 4;
 const FILL_INDIRECT_DRAW_PARAMS_SIZE:                   usize = 8;
@@ -43,12 +46,18 @@ const LOAD_ACTION_LOAD:  i32 = 1;
 const INITIAL_ALLOCATED_MICROLINE_COUNT: u32 = 1024 * 16;
 const INITIAL_ALLOCATED_FILL_COUNT: u32 = 1024 * 16;
 
+// The largest power of two that can be passed to the shaders as an `int` uniform.
+const MAX_ALLOCATED_COUNT: u32 = 1 << 30;
+
 pub(crate) struct RendererD3D11<D> where D: Device {
     programs: ProgramsD3D11<D>,
     allocated_microline_count: u32,
     allocated_fill_count: u32,
     scene_buffers: SceneBuffers,
     tile_batch_info: VecMap<TileBatchInfoD3D11>,
+    // The number of alpha tiles whose masks must survive when mask pages are recycled, because
+    // they belong to clip paths that later batches may still read.
+    clip_alpha_tile_count: u32,
 }
 
 impl<D> RendererD3D11<D> where D: Device {
@@ -57,10 +66,15 @@ impl<D> RendererD3D11<D> where D: Device {
         let programs = ProgramsD3D11::new(&core.device, resources);
         RendererD3D11 {
             programs,
-            allocated_fill_count: INITIAL_ALLOCATED_FILL_COUNT,
-            allocated_microline_count: INITIAL_ALLOCATED_MICROLINE_COUNT,
+            allocated_fill_count:
+                initial_allocated_count(core.options.initial_fill_count,
+                                        INITIAL_ALLOCATED_FILL_COUNT),
+            allocated_microline_count:
+                initial_allocated_count(core.options.initial_microline_count,
+                                        INITIAL_ALLOCATED_MICROLINE_COUNT),
             scene_buffers: SceneBuffers::new(),
             tile_batch_info: VecMap::<TileBatchInfoD3D11>::new(),
+            clip_alpha_tile_count: 0,
         }
     }
 
@@ -113,6 +127,77 @@ impl<D> RendererD3D11<D> where D: Device {
         core.allocator.free_general_buffer(path_info_buffer_id);
     }
 
+    // Checks that the tile count computed on CPU agrees with the tile bounds of the batch's paths,
+    // and reads back the tiles that the bound shader initialized to check that each one was
+    // assigned to the path that the CPU expects. Disagreement here shows up as missing geometry,
+    // so mismatches are logged. The readback stalls the pipeline, so this is only compiled in
+    // with the `debug` feature.
+    #[cfg(feature = "debug")]
+    fn validate_tile_bounds(&self,
+                            core: &RendererCore<D>,
+                            tiles_d3d11_buffer_id: GeneralBufferID,
+                            tile_count: u32,
+                            tile_path_info: &[TilePathInfoD3D11]) {
+        let mut expected_tile_count = 0;
+        for (path_index, path_info) in tile_path_info.iter().enumerate() {
+            if path_info.first_tile_index != expected_tile_count {
+                error!("tile bounds mismatch: path {} starts at tile {}, but the preceding paths \
+                        cover {} tiles",
+                       path_index,
+                       path_info.first_tile_index,
+                       expected_tile_count);
+            }
+            expected_tile_count = path_info.first_tile_index + tile_path_info_area(path_info);
+        }
+        if expected_tile_count != tile_count {
+            error!("tile bounds mismatch: batch has {} tiles, but its paths cover {} tiles",
+                   tile_count,
+                   expected_tile_count);
+        }
+        if tile_count == 0 {
+            return;
+        }
+
+        let tiles_buffer = core.allocator.get_general_buffer(tiles_d3d11_buffer_id);
+        let byte_size = tile_count as usize * mem::size_of::<TileD3D11>();
+        let tiles_receiver = core.device.read_buffer(tiles_buffer,
+                                                     BufferTarget::Storage,
+                                                     0..byte_size);
+        let tiles = core.device.recv_buffer(&tiles_receiver);
+        let tiles: &[u32] = tiles.as_slice_of().unwrap();
+
+        let mut mismatch_count = 0;
+        for (path_index, path_info) in tile_path_info.iter().enumerate() {
+            let expected_control = path_info.color as u32 |
+                ((path_info.ctrl as u32) << 16) |
+                ((path_info.backdrop as u8 as u32) << 24);
+            let first_tile_index = path_info.first_tile_index;
+            let tile_range = first_tile_index..(first_tile_index + tile_path_info_area(path_info));
+            for tile_index in tile_range.take_while(|&tile_index| tile_index < tile_count) {
+                let tile = &tiles[(tile_index as usize * 4)..(tile_index as usize * 4 + 4)];
+                if tile[0] == !0 && tile[3] == expected_control {
+                    continue;
+                }
+                if mismatch_count == 0 {
+                    error!("tile bounds mismatch: GPU tile {} was not initialized for path {} \
+                            (tile bounds {},{} to {},{})",
+                           tile_index,
+                           path_index,
+                           path_info.tile_min_x,
+                           path_info.tile_min_y,
+                           path_info.tile_max_x,
+                           path_info.tile_max_y);
+                }
+                mismatch_count += 1;
+            }
+        }
+        if mismatch_count > 0 {
+            error!("tile bounds mismatch: {} of {} GPU tiles disagree with the CPU tile bounds",
+                   mismatch_count,
+                   tile_count);
+        }
+    }
+
     fn upload_propagate_metadata(&mut self,
                                  core: &mut RendererCore<D>,
                                  propagate_metadata: &[PropagateMetadataD3D11],
@@ -223,12 +308,11 @@ impl<D> RendererD3D11<D> where D: Device {
 
         let needed_fill_count =
             indirect_draw_params[FILL_INDIRECT_DRAW_PARAMS_INSTANCE_COUNT_INDEX];
-        if needed_fill_count > self.allocated_fill_count {
-            self.allocated_fill_count = needed_fill_count.next_power_of_two();
+        if !fit_allocated_count(&mut self.allocated_fill_count, needed_fill_count) {
             return None;
         }
 
-        core.stats.fill_count += needed_fill_count as usize;
+        core.stats.record_fills(needed_fill_count as usize);
 
         Some(FillBufferInfoD3D11 { fill_vertex_buffer_id })
     }
@@ -347,8 +431,7 @@ impl<D> RendererD3D11<D> where D: Device {
 
         let microline_count =
             indirect_compute_params[BIN_INDIRECT_DRAW_PARAMS_MICROLINE_COUNT_INDEX];
-        if microline_count > self.allocated_microline_count {
-            self.allocated_microline_count = microline_count.next_power_of_two();
+        if !fit_allocated_count(&mut self.allocated_microline_count, microline_count) {
             return None;
         }
 
@@ -420,14 +503,20 @@ impl<D> RendererD3D11<D> where D: Device {
         self.draw_tiles(core,
                         batch_info.tiles_d3d11_buffer_id,
                         batch_info.first_tile_map_buffer_id,
-                        batch.color_texture);
+                        batch.color_texture,
+                        batch.color_texture_1);
     }
 
     // Computes backdrops, performs clipping, and populates Z buffers on GPU.
     pub(crate) fn prepare_tiles(&mut self,
                                 core: &mut RendererCore<D>,
                                 batch: &TileBatchDataD3D11) {
-        core.stats.total_tile_count += batch.tile_count as usize;
+        // If this batch's masks might not fit in the mask framebuffer, recycle the pages of the
+        // batches that have already been drawn. The masks of clip paths are kept.
+        if batch.path_source == PathSource::Draw &&
+                core.alpha_tile_count + batch.tile_count > core.max_alpha_tile_count() {
+            core.alpha_tile_count = self.clip_alpha_tile_count;
+        }
 
         // Upload tiles to GPU or allocate them as appropriate.
         let tiles_d3d11_buffer_id = self.allocate_tiles(core, batch.tile_count);
@@ -481,6 +570,11 @@ impl<D> RendererD3D11<D> where D: Device {
                        tiles_d3d11_buffer_id,
                        batch.tile_count,
                        &batch.prepare_info.tile_path_info);
+            #[cfg(feature = "debug")]
+            self.validate_tile_bounds(core,
+                                      tiles_d3d11_buffer_id,
+                                      batch.tile_count,
+                                      &batch.prepare_info.tile_path_info);
 
             self.upload_initial_backdrops(core,
                                           propagate_metadata_buffer_ids.backdrops,
@@ -518,16 +612,21 @@ impl<D> RendererD3D11<D> where D: Device {
         core.allocator.free_general_buffer(propagate_metadata_buffer_ids.backdrops);
 
         // FIXME(pcwalton): Don't unconditionally pass true for copying here.
-        core.reallocate_alpha_tile_pages_if_necessary(true);
-        self.draw_fills(core,
-                        &fill_buffer_info,
-                        tiles_d3d11_buffer_id,
-                        alpha_tiles_buffer_id,
-                        &propagate_tiles_info);
+        if core.reallocate_alpha_tile_pages_if_necessary(true) {
+            self.draw_fills(core,
+                            &fill_buffer_info,
+                            tiles_d3d11_buffer_id,
+                            alpha_tiles_buffer_id,
+                            &propagate_tiles_info);
+        }
 
         core.allocator.free_general_buffer(fill_buffer_info.fill_vertex_buffer_id);
         core.allocator.free_general_buffer(alpha_tiles_buffer_id);
 
+        if batch.path_source == PathSource::Clip {
+            self.clip_alpha_tile_count = core.alpha_tile_count;
+        }
+
         // FIXME(pcwalton): This seems like the wrong place to do this...
         self.sort_tiles(core, tiles_d3d11_buffer_id, first_tile_map_buffer_id, z_buffer_id);
 
@@ -712,7 +811,8 @@ impl<D> RendererD3D11<D> where D: Device {
                              core: &mut RendererCore<D>,
                              tiles_d3d11_buffer_id: GeneralBufferID,
                              first_tile_map_buffer_id: GeneralBufferID,
-                             color_texture_0: Option<TileBatchTexture>) {
+                             color_texture_0: Option<TileBatchTexture>,
+                             color_texture_1: Option<TileBatchTexture>) {
         let timer_query = core.timer_query_cache.start_timing_draw_call(&core.device,
                                                                         &core.options);
 
@@ -723,7 +823,8 @@ impl<D> RendererD3D11<D> where D: Device {
         core.set_uniforms_for_drawing_tiles(&tile_program.common,
                                             &mut textures,
                                             &mut uniforms,
-                                            color_texture_0);
+                                            color_texture_0,
+                                            color_texture_1);
 
         uniforms.push((&tile_program.framebuffer_tile_size_uniform,
                        UniformData::IVec2(core.framebuffer_tile_size().0)));
@@ -781,6 +882,10 @@ impl<D> RendererD3D11<D> where D: Device {
         core.preserve_draw_framebuffer();
     }
 
+    pub(crate) fn begin_frame(&mut self) {
+        self.clip_alpha_tile_count = 0;
+    }
+
     pub(crate) fn end_frame(&mut self, core: &mut RendererCore<D>) {
         self.free_tile_batch_buffers(core);
     }
@@ -906,3 +1011,67 @@ impl SceneSourceBuffers {
         self.point_indices_count = segments.indices.len() as u32;
     }
 }
+
+#[cfg(feature = "debug")]
+fn tile_path_info_area(path_info: &TilePathInfoD3D11) -> u32 {
+    let width = path_info.tile_max_x as i32 - path_info.tile_min_x as i32;
+    let height = path_info.tile_max_y as i32 - path_info.tile_min_y as i32;
+    (width.max(0) * height.max(0)) as u32
+}
+
+// Returns the number of fills or microlines to allocate room for before the first frame. Like the
+// counts the renderer grows to on overflow, the result is a power of two.
+fn initial_allocated_count(requested: Option<u32>, default: u32) -> u32 {
+    match requested {
+        None => default,
+        Some(count) => allocated_count_for(count.max(1)),
+    }
+}
+
+// Returns true if `needed` fills or microlines fit in the `allocated` count. Otherwise, grows the
+// count to fit them, so that the caller can retry the pass that ran out of space.
+fn fit_allocated_count(allocated: &mut u32, needed: u32) -> bool {
+    if needed <= *allocated {
+        return true;
+    }
+    *allocated = allocated_count_for(needed);
+    false
+}
+
+fn allocated_count_for(count: u32) -> u32 {
+    count.checked_next_power_of_two().unwrap_or(MAX_ALLOCATED_COUNT).min(MAX_ALLOCATED_COUNT)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{INITIAL_ALLOCATED_MICROLINE_COUNT, MAX_ALLOCATED_COUNT};
+    use super::{fit_allocated_count, initial_allocated_count};
+
+    #[test]
+    fn test_initial_allocated_count() {
+        assert_eq!(initial_allocated_count(None, 1024), 1024);
+        assert_eq!(initial_allocated_count(Some(65536), 1024), 65536);
+        assert_eq!(initial_allocated_count(Some(100000), 1024), 131072);
+        assert_eq!(initial_allocated_count(Some(0), 1024), 1);
+
+        // Counts too large to round up to a power of two are clamped rather than overflowing.
+        assert_eq!(initial_allocated_count(Some(u32::MAX), 1024), MAX_ALLOCATED_COUNT);
+        assert_eq!(initial_allocated_count(Some((1 << 31) + 1), 1024), MAX_ALLOCATED_COUNT);
+    }
+
+    #[test]
+    fn test_initial_allocated_count_avoids_second_dice_pass() {
+        let needed_microline_count = 100000;
+
+        let default_count = INITIAL_ALLOCATED_MICROLINE_COUNT;
+
+        // With the default count, the first dice pass runs out of space and has to be retried.
+        let mut allocated = initial_allocated_count(None, default_count);
+        assert!(!fit_allocated_count(&mut allocated, needed_microline_count));
+        assert!(fit_allocated_count(&mut allocated, needed_microline_count));
+
+        // With a high enough initial count, the first pass succeeds.
+        let mut allocated = initial_allocated_count(Some(needed_microline_count), default_count);
+        assert!(fit_allocated_count(&mut allocated, needed_microline_count));
+    }
+}
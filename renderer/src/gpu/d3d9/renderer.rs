@@ -13,14 +13,15 @@
 //! This renderer supports OpenGL at least 3.0, OpenGL ES at least 3.0, Metal of any version, and
 //! WebGL at least 2.0.
 
-use crate::gpu::blend::{BlendModeExt, ToBlendState};
+use crate::gpu::blend::ToBlendState;
 use crate::gpu::perf::TimeCategory;
 use crate::gpu::renderer::{FramebufferFlags, MASK_FRAMEBUFFER_HEIGHT, MASK_FRAMEBUFFER_WIDTH};
-use crate::gpu::renderer::{RendererCore, RendererFlags};
+use crate::gpu::renderer::{MASK_TILES_ACROSS, RendererCore, RendererFlags};
 use crate::gpu::d3d9::shaders::{ClipTileCombineVertexArrayD3D9, ClipTileCopyVertexArrayD3D9};
 use crate::gpu::d3d9::shaders::{CopyTileVertexArray, FillVertexArrayD3D9};
 use crate::gpu::d3d9::shaders::{ProgramsD3D9, TileVertexArrayD3D9};
-use crate::gpu_data::{Clip, DrawTileBatchD3D9, Fill, TileBatchTexture, TileObjectPrimitive};
+use crate::gpu_data::{AlphaTileId, Clip, DrawTileBatchD3D9, Fill, TileBatchTexture};
+use crate::gpu_data::TileObjectPrimitive;
 use crate::tile_map::DenseTileMap;
 use crate::tiles::{TILE_HEIGHT, TILE_WIDTH};
 use byte_slice_cast::AsByteSlice;
@@ -31,11 +32,12 @@ use pathfinder_geometry::transform3d::Transform4F;
 use pathfinder_geometry::vector::{Vector2I, Vector4F, vec2i};
 use pathfinder_gpu::allocator::{BufferTag, FramebufferID, FramebufferTag, GeneralBufferID};
 use pathfinder_gpu::allocator::{IndexBufferID, TextureID, TextureTag};
-use pathfinder_gpu::{BlendFactor, BlendState, BufferTarget, ClearOps, Device, Primitive};
-use pathfinder_gpu::{RenderOptions, RenderState, RenderTarget, StencilFunc, StencilState};
-use pathfinder_gpu::{TextureDataRef, TextureFormat, UniformData};
+use pathfinder_gpu::{BlendFactor, BlendState, BufferTarget, BufferUploadMode, ClearOps};
+use pathfinder_gpu::{Device, Primitive, RenderOptions, RenderState, RenderTarget};
+use pathfinder_gpu::{StencilFunc, StencilState, TextureDataRef, TextureFormat, UniformData};
 use pathfinder_resources::ResourceLoader;
 use pathfinder_simd::default::F32x2;
+use std::collections::HashMap;
 use std::u32;
 
 const MAX_FILLS_PER_BATCH: usize = 0x10000;
@@ -50,6 +52,9 @@ pub(crate) struct RendererD3D9<D> where D: Device {
     buffered_fills: Vec<Fill>,
     pending_fills: Vec<Fill>,
 
+    // Fills of the current scene.
+    scene_fills: SceneFills,
+
     // Temporary framebuffers
     dest_blend_framebuffer_id: FramebufferID,
 }
@@ -74,38 +79,90 @@ impl<D> RendererD3D9<D> where D: Device {
             buffered_fills: vec![],
             pending_fills: vec![],
 
+            scene_fills: SceneFills::default(),
+
             dest_blend_framebuffer_id,
         }
     }
 
+    pub(crate) fn begin_frame(&mut self) {
+        self.scene_fills.clear();
+    }
+
     pub(crate) fn upload_and_draw_tiles(&mut self,
                                         core: &mut RendererCore<D>,
                                         batch: &DrawTileBatchD3D9) {
-        if !batch.clips.is_empty() {
-            let clip_buffer_info = self.upload_clip_tiles(core, &batch.clips);
+        let z_buffer_texture_id = self.upload_z_buffer(core, &batch.z_buffer_data);
+
+        // If the mask framebuffer can't hold all the alpha tiles of the scene, draw the batch in
+        // passes, each of which rasterizes the masks it needs into recycled pages.
+        let max_alpha_tile_count = core.max_alpha_tile_count();
+        if !self.scene_fills.needs_mask_passes(max_alpha_tile_count) {
+            self.clip_and_draw_tiles(core, &batch.tiles, &batch.clips, batch, z_buffer_texture_id);
+        } else {
+            for mask_pass in split_into_mask_passes(&batch.tiles,
+                                                    &batch.clips,
+                                                    max_alpha_tile_count) {
+                self.rasterize_mask_pass(core, &mask_pass);
+                self.clip_and_draw_tiles(core,
+                                         &mask_pass.tiles,
+                                         &mask_pass.clips,
+                                         batch,
+                                         z_buffer_texture_id);
+            }
+        }
+
+        core.allocator.free_texture(z_buffer_texture_id);
+    }
+
+    fn clip_and_draw_tiles(&mut self,
+                           core: &mut RendererCore<D>,
+                           tiles: &[TileObjectPrimitive],
+                           clips: &[Clip],
+                           batch: &DrawTileBatchD3D9,
+                           z_buffer_texture_id: TextureID) {
+        if !clips.is_empty() {
+            let clip_buffer_info = self.upload_clip_tiles(core, clips);
             self.clip_tiles(core, &clip_buffer_info);
             core.allocator.free_general_buffer(clip_buffer_info.clip_buffer_id);
         }
 
-        let tile_buffer = self.upload_tiles(core, &batch.tiles);
-        let z_buffer_texture_id = self.upload_z_buffer(core, &batch.z_buffer_data);
+        let tile_buffer = self.upload_tiles(core, tiles);
 
         self.draw_tiles(core,
-                        batch.tiles.len() as u32,
+                        tiles.len() as u32,
                         tile_buffer.tile_vertex_buffer_id,
                         batch.color_texture,
+                        batch.color_texture_1,
                         batch.blend_mode,
                         z_buffer_texture_id);
 
-        core.allocator.free_texture(z_buffer_texture_id);
         core.allocator.free_general_buffer(tile_buffer.tile_vertex_buffer_id);
     }
 
+    // Clears the mask framebuffer and rasterizes the fills of the alpha tiles that a pass uses
+    // into the slots assigned to them.
+    fn rasterize_mask_pass(&mut self, core: &mut RendererCore<D>, mask_pass: &MaskPass) {
+        debug_assert!(self.buffered_fills.is_empty());
+        core.framebuffer_flags.remove(FramebufferFlags::MASK_FRAMEBUFFER_IS_DIRTY);
+
+        let mask_pass_fills: Vec<Fill> = self.scene_fills.mask_pass_fills(mask_pass).collect();
+        for fill in mask_pass_fills {
+            if self.buffered_fills.len() == MAX_FILLS_PER_BATCH {
+                self.draw_buffered_fills(core);
+            }
+            self.buffered_fills.push(fill);
+        }
+        self.draw_buffered_fills(core);
+    }
+
     fn upload_tiles(&mut self, core: &mut RendererCore<D>, tiles: &[TileObjectPrimitive])
                     -> TileBufferD3D9 {
         let tile_vertex_buffer_id =
-            core.allocator.allocate_general_buffer::<TileObjectPrimitive>(&core.device,
+            core.allocator
+                .allocate_general_buffer_with_mode::<TileObjectPrimitive>(&core.device,
                                                                           tiles.len() as u64,
+                                                                          BufferUploadMode::Stream,
                                                                           BufferTag("TileD3D9"));
         let tile_vertex_buffer = &core.allocator.get_general_buffer(tile_vertex_buffer_id);
         core.device.upload_to_buffer(tile_vertex_buffer, 0, tiles, BufferTarget::Vertex);
@@ -152,19 +209,24 @@ impl<D> RendererD3D9<D> where D: Device {
             return;
         }
 
-        core.stats.fill_count += fill_batch.len();
-
         let preserve_alpha_mask_contents = core.alpha_tile_count > 0;
 
+        // Mask pages are only recycled if the caller limited them. Fills of alpha tiles that
+        // don't fit in the mask framebuffer are then rasterized later, when its pages are
+        // recycled.
+        let max_alpha_tile_count = match core.options.max_mask_pages {
+            Some(_) => Some(core.max_alpha_tile_count()),
+            None => None,
+        };
         self.pending_fills.reserve(fill_batch.len());
-        for fill in fill_batch {
-            core.alpha_tile_count = core.alpha_tile_count.max(fill.link + 1);
-            self.pending_fills.push(*fill);
-        }
-
-        core.stats.alpha_tile_count = core.alpha_tile_count as usize;
+        core.alpha_tile_count =
+            self.scene_fills.add(fill_batch, max_alpha_tile_count, &mut self.pending_fills);
+        core.stats.alpha_tile_count = self.scene_fills.alpha_tile_count as usize;
 
-        core.reallocate_alpha_tile_pages_if_necessary(preserve_alpha_mask_contents);
+        if !core.reallocate_alpha_tile_pages_if_necessary(preserve_alpha_mask_contents) {
+            self.pending_fills.clear();
+            return;
+        }
 
         if self.buffered_fills.len() + self.pending_fills.len() > MAX_FILLS_PER_BATCH {
             self.draw_buffered_fills(core);
@@ -187,10 +249,12 @@ impl<D> RendererD3D9<D> where D: Device {
         let buffered_fills = &mut self.buffered_fills;
         debug_assert!(!buffered_fills.is_empty());
 
-        let fill_buffer_id = core.allocator
-                                 .allocate_general_buffer::<Fill>(&core.device,
-                                                                  MAX_FILLS_PER_BATCH as u64,
-                                                                  BufferTag("Fill"));
+        let fill_buffer_id =
+            core.allocator
+                .allocate_general_buffer_with_mode::<Fill>(&core.device,
+                                                           MAX_FILLS_PER_BATCH as u64,
+                                                           BufferUploadMode::Stream,
+                                                           BufferTag("Fill"));
         let fill_vertex_buffer = core.allocator.get_general_buffer(fill_buffer_id);
         debug_assert!(buffered_fills.len() <= u32::MAX as usize);
         core.device.upload_to_buffer(fill_vertex_buffer, 0, &buffered_fills, BufferTarget::Vertex);
@@ -270,10 +334,11 @@ impl<D> RendererD3D9<D> where D: Device {
     }
 
     fn clip_tiles(&mut self, core: &mut RendererCore<D>, clip_buffer_info: &ClipBufferInfo) {
-        // Allocate temp mask framebuffer.
+        // Allocate temp mask framebuffer, just big enough for the tiles that the clips read.
+        let mask_temp_framebuffer_size = clip_temp_framebuffer_size(clip_buffer_info.clip_count);
         let mask_temp_framebuffer_id =
             core.allocator.allocate_framebuffer(&core.device,
-                                                self.mask_viewport(core).size(),
+                                                mask_temp_framebuffer_size,
                                                 core.mask_texture_format(),
                                                 FramebufferTag("TempClipMaskD3D9"));
         let mask_temp_framebuffer = core.allocator.get_framebuffer(mask_temp_framebuffer_id);
@@ -322,10 +387,12 @@ impl<D> RendererD3D9<D> where D: Device {
             images: &[],
             uniforms: &[
                 (&self.programs.tile_clip_copy_program.framebuffer_size_uniform,
+                 UniformData::Vec2(mask_temp_framebuffer_size.to_f32().0)),
+                (&self.programs.tile_clip_copy_program.src_framebuffer_size_uniform,
                  UniformData::Vec2(mask_texture_size.to_f32().0)),
             ],
             storage_buffers: &[],
-            viewport: RectI::new(Vector2I::zero(), mask_texture_size),
+            viewport: RectI::new(Vector2I::zero(), mask_temp_framebuffer_size),
             options: RenderOptions::default(),
         });
 
@@ -349,6 +416,8 @@ impl<D> RendererD3D9<D> where D: Device {
             uniforms: &[
                 (&self.programs.tile_clip_combine_program.framebuffer_size_uniform,
                  UniformData::Vec2(mask_texture_size.to_f32().0)),
+                (&self.programs.tile_clip_combine_program.src_framebuffer_size_uniform,
+                 UniformData::Vec2(mask_temp_framebuffer_size.to_f32().0)),
             ],
             storage_buffers: &[],
             viewport: RectI::new(Vector2I::zero(), mask_texture_size),
@@ -392,6 +461,7 @@ impl<D> RendererD3D9<D> where D: Device {
                   tile_count: u32,
                   tile_vertex_buffer_id: GeneralBufferID,
                   color_texture_0: Option<TileBatchTexture>,
+                  color_texture_1: Option<TileBatchTexture>,
                   blend_mode: BlendMode,
                   z_buffer_texture_id: TextureID) {
         // TODO(pcwalton): Disable blend for solid tiles.
@@ -400,8 +470,6 @@ impl<D> RendererD3D9<D> where D: Device {
             return;
         }
 
-        core.stats.total_tile_count += tile_count as usize;
-
         let needs_readable_framebuffer = blend_mode.needs_readable_framebuffer();
         if needs_readable_framebuffer {
             self.copy_alpha_tiles_to_dest_blend_texture(core, tile_count, tile_vertex_buffer_id);
@@ -428,7 +496,8 @@ impl<D> RendererD3D9<D> where D: Device {
         core.set_uniforms_for_drawing_tiles(&tile_raster_program.common,
                                             &mut textures,
                                             &mut uniforms,
-                                            color_texture_0);
+                                            color_texture_0,
+                                            color_texture_1);
 
         uniforms.push((&tile_raster_program.transform_uniform,
                        UniformData::Mat4(self.tile_transform(core).to_columns())));
@@ -581,3 +650,275 @@ struct ClipBufferInfo {
     clip_buffer_id: GeneralBufferID,
     clip_count: u32,
 }
+
+// The tiles of a batch that are drawn together after recycling the mask framebuffer.
+#[derive(Default)]
+struct MaskPass {
+    tiles: Vec<TileObjectPrimitive>,
+    clips: Vec<Clip>,
+    // Maps the index of each alpha tile that this pass uses to the slot in the mask framebuffer
+    // that its mask is rasterized to.
+    alpha_tile_slots: HashMap<u32, u32>,
+}
+
+// The fills of the current scene and the number of alpha tiles they cover.
+//
+// If the caller limits the number of mask pages and the alpha tiles don't all fit in the mask
+// framebuffer, its pages are recycled, and the fills are rasterized again for each pass, so they
+// have to be kept until the end of the scene. Without a limit, they aren't kept.
+#[derive(Default)]
+struct SceneFills {
+    fills: Vec<Fill>,
+    alpha_tile_count: u32,
+}
+
+impl SceneFills {
+    fn clear(&mut self) {
+        self.fills.clear();
+        self.alpha_tile_count = 0;
+    }
+
+    // Records a batch of fills and appends those to rasterize right away to `pending_fills`.
+    // Returns the number of alpha tiles that the mask framebuffer must hold.
+    //
+    // With a `max_alpha_tile_count`, fills of alpha tiles beyond it are left for the passes that
+    // recycle the mask pages. Without one, every fill is rasterized now, so the mask framebuffer
+    // must hold every alpha tile of the scene.
+    fn add(&mut self,
+           fill_batch: &[Fill],
+           max_alpha_tile_count: Option<u32>,
+           pending_fills: &mut Vec<Fill>)
+           -> u32 {
+        for fill in fill_batch {
+            self.alpha_tile_count = self.alpha_tile_count.max(fill.link + 1);
+        }
+
+        match max_alpha_tile_count {
+            None => {
+                pending_fills.extend_from_slice(fill_batch);
+                self.alpha_tile_count
+            }
+            Some(max_alpha_tile_count) => {
+                self.fills.extend_from_slice(fill_batch);
+                pending_fills.extend(fill_batch.iter().filter(|fill| {
+                    fill.link < max_alpha_tile_count
+                }));
+                self.alpha_tile_count.min(max_alpha_tile_count)
+            }
+        }
+    }
+
+    // Returns true if the alpha tiles of the scene don't all fit in the mask framebuffer, so its
+    // tiles have to be drawn in mask passes.
+    fn needs_mask_passes(&self, max_alpha_tile_count: u32) -> bool {
+        self.alpha_tile_count > max_alpha_tile_count
+    }
+
+    // Returns the fills of the alpha tiles that a mask pass uses, renumbered to their slots.
+    fn mask_pass_fills<'a>(&'a self, mask_pass: &'a MaskPass) -> impl Iterator<Item = Fill> + 'a {
+        self.fills.iter().filter_map(move |fill| {
+            mask_pass.alpha_tile_slots.get(&fill.link).map(|&slot| Fill { link: slot, ..*fill })
+        })
+    }
+}
+
+impl MaskPass {
+    fn slot(&mut self, alpha_tile_id: AlphaTileId) -> AlphaTileId {
+        let next_slot = self.alpha_tile_slots.len() as u32;
+        AlphaTileId(*self.alpha_tile_slots.entry(alpha_tile_id.0).or_insert(next_slot))
+    }
+
+    fn new_slot_count(&self, tile: &TileObjectPrimitive, clip: Option<&Clip>) -> usize {
+        let src_tile_id = clip.map(|clip| clip.src_tile_id);
+        [Some(tile.alpha_tile_id), src_tile_id].iter().flatten().filter(|alpha_tile_id| {
+            !self.alpha_tile_slots.contains_key(&alpha_tile_id.0)
+        }).count()
+    }
+}
+
+// Splits the tiles of a batch, in order, into passes that each use at most
+// `max_alpha_tile_count` masks, renumbering their alpha tiles and clips to match.
+fn split_into_mask_passes(tiles: &[TileObjectPrimitive],
+                          clips: &[Clip],
+                          max_alpha_tile_count: u32)
+                          -> Vec<MaskPass> {
+    let clips: HashMap<u32, &Clip> = clips.iter().map(|clip| (clip.dest_tile_id.0, clip)).collect();
+
+    let mut mask_passes = vec![MaskPass::default()];
+    for tile in tiles {
+        let mut tile = *tile;
+        if tile.alpha_tile_id.is_valid() {
+            let clip = clips.get(&tile.alpha_tile_id.0).cloned();
+
+            let mask_pass = mask_passes.last().unwrap();
+            if mask_pass.alpha_tile_slots.len() + mask_pass.new_slot_count(&tile, clip) >
+                    max_alpha_tile_count as usize {
+                mask_passes.push(MaskPass::default());
+            }
+
+            let mask_pass = mask_passes.last_mut().unwrap();
+            tile.alpha_tile_id = mask_pass.slot(tile.alpha_tile_id);
+            if let Some(clip) = clip {
+                let src_tile_id = mask_pass.slot(clip.src_tile_id);
+                let dest_tile_id = tile.alpha_tile_id;
+                mask_pass.clips.push(Clip { dest_tile_id, src_tile_id, ..*clip });
+            }
+        }
+        mask_passes.last_mut().unwrap().tiles.push(tile);
+    }
+    mask_passes
+}
+
+// Returns the size of the temporary framebuffer that `clip_tiles` copies the destination and
+// source tiles of `clip_count` clips into, packed two per clip in mask tile order.
+fn clip_temp_framebuffer_size(clip_count: u32) -> Vector2I {
+    let tile_count = (clip_count * 2).max(1);
+    let tiles_across = tile_count.min(MASK_TILES_ACROSS);
+    let tiles_down = (tile_count + MASK_TILES_ACROSS - 1) / MASK_TILES_ACROSS;
+    vec2i(TILE_WIDTH as i32 * tiles_across as i32, TILE_HEIGHT as i32 / 4 * tiles_down as i32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MaskPass, SceneFills, clip_temp_framebuffer_size, split_into_mask_passes};
+    use crate::gpu::renderer::{MASK_FRAMEBUFFER_HEIGHT, MASK_FRAMEBUFFER_WIDTH};
+    use crate::gpu::renderer::{MASK_TILES_ACROSS, MASK_TILES_DOWN, max_mask_pages};
+    use crate::gpu_data::{AlphaTileId, Clip, Fill, TileObjectPrimitive};
+    use crate::scene::PathId;
+    use crate::tiles::{TILE_HEIGHT, TILE_WIDTH};
+    use pathfinder_geometry::vector::{Vector2I, vec2i};
+
+    #[test]
+    fn test_split_into_mask_passes() {
+        // Ten alpha tiles, every other one clipped by one of two clip tiles, and a solid tile.
+        let tile = |alpha_tile_id| {
+            TileObjectPrimitive {
+                tile_x: 0,
+                tile_y: 0,
+                alpha_tile_id: AlphaTileId(alpha_tile_id),
+                path_id: PathId(0),
+                color: 0,
+                ctrl: 0,
+                backdrop: 0,
+            }
+        };
+        let mut tiles: Vec<_> = (2..12).map(tile).collect();
+        tiles.push(tile(!0));
+        let clips: Vec<_> = (2..12).step_by(2).map(|alpha_tile_id| {
+            Clip {
+                dest_tile_id: AlphaTileId(alpha_tile_id),
+                dest_backdrop: 0,
+                src_tile_id: AlphaTileId(alpha_tile_id % 4 / 2),
+                src_backdrop: 0,
+            }
+        }).collect();
+
+        let max_alpha_tile_count = 4;
+        let mask_passes = split_into_mask_passes(&tiles, &clips, max_alpha_tile_count);
+        assert!(mask_passes.len() > 1);
+
+        let mut tile_count = 0;
+        let mut clip_count = 0;
+        for mask_pass in &mask_passes {
+            // The masks of each pass fit in the mask framebuffer.
+            assert!(mask_pass.alpha_tile_slots.len() <= max_alpha_tile_count as usize);
+            assert!(mask_pass.alpha_tile_slots.values().all(|&slot| slot < max_alpha_tile_count));
+
+            // Tiles and clips are renumbered consistently.
+            let original_id = |slot: AlphaTileId| {
+                *mask_pass.alpha_tile_slots.iter().find(|&(_, &value)| value == slot.0).unwrap().0
+            };
+            for pass_tile in &mask_pass.tiles {
+                let original_tile = &tiles[tile_count];
+                if original_tile.alpha_tile_id.is_valid() {
+                    assert_eq!(original_id(pass_tile.alpha_tile_id), original_tile.alpha_tile_id.0);
+                } else {
+                    assert!(!pass_tile.alpha_tile_id.is_valid());
+                }
+                tile_count += 1;
+            }
+            for pass_clip in &mask_pass.clips {
+                let dest_tile_id = original_id(pass_clip.dest_tile_id);
+                let original_clip = clips.iter().find(|clip| clip.dest_tile_id.0 == dest_tile_id);
+                assert_eq!(original_id(pass_clip.src_tile_id),
+                           original_clip.unwrap().src_tile_id.0);
+                clip_count += 1;
+            }
+        }
+
+        // Every tile and clip is drawn, in order.
+        assert_eq!(tile_count, tiles.len());
+        assert_eq!(clip_count, clips.len());
+    }
+
+    #[test]
+    fn test_mask_page_limit_recycles_pages() {
+        // Alpha tiles that need a page and a half of masks, with fills on both pages.
+        let alpha_tile_count = 0x18000;
+        let fills: Vec<_> = [0, 0x10000, 0x17fff].iter().map(|&link| {
+            Fill { link, ..Fill::default() }
+        }).collect();
+        let tiles: Vec<_> = (0..alpha_tile_count).map(|alpha_tile_id| {
+            TileObjectPrimitive {
+                tile_x: 0,
+                tile_y: 0,
+                alpha_tile_id: AlphaTileId(alpha_tile_id),
+                path_id: PathId(0),
+                color: 0,
+                ctrl: 0,
+                backdrop: 0,
+            }
+        }).collect();
+        let max_texture_size = MASK_FRAMEBUFFER_HEIGHT * 16;
+        let pages = |alpha_tile_count: u32| (alpha_tile_count + 0xffff) >> 16;
+
+        // Without a limit, two pages are allocated, every fill is rasterized right away, and no
+        // fills are kept.
+        let max_alpha_tile_count = max_mask_pages(max_texture_size, None) << 16;
+        let mut scene_fills = SceneFills::default();
+        let mut pending_fills = vec![];
+        let mask_alpha_tile_count = scene_fills.add(&fills, None, &mut pending_fills);
+        assert_eq!(pages(mask_alpha_tile_count), 2);
+        assert_eq!(pending_fills.len(), fills.len());
+        assert!(scene_fills.fills.is_empty());
+        assert!(!scene_fills.needs_mask_passes(max_alpha_tile_count));
+
+        // With a limit of one page, only that page is allocated, and it's recycled for a second
+        // pass. Only the fills of the first pass are rasterized right away.
+        let max_alpha_tile_count = max_mask_pages(max_texture_size, Some(1)) << 16;
+        let mut scene_fills = SceneFills::default();
+        let mut pending_fills = vec![];
+        let mask_alpha_tile_count =
+            scene_fills.add(&fills, Some(max_alpha_tile_count), &mut pending_fills);
+        assert_eq!(pages(mask_alpha_tile_count), 1);
+        assert_eq!(pending_fills.iter().map(|fill| fill.link).collect::<Vec<_>>(), vec![0]);
+        assert!(scene_fills.needs_mask_passes(max_alpha_tile_count));
+
+        let mask_passes = split_into_mask_passes(&tiles, &[], max_alpha_tile_count);
+        assert_eq!(mask_passes.len(), 2);
+        let mask_pass_links = |mask_pass: &MaskPass| {
+            scene_fills.mask_pass_fills(mask_pass).map(|fill| fill.link).collect::<Vec<_>>()
+        };
+        assert_eq!(mask_pass_links(&mask_passes[0]), vec![0]);
+        assert_eq!(mask_pass_links(&mask_passes[1]), vec![0, 0x7fff]);
+    }
+
+    #[test]
+    fn test_clip_temp_framebuffer_size() {
+        let mask_area = MASK_FRAMEBUFFER_WIDTH as i64 * MASK_FRAMEBUFFER_HEIGHT as i64;
+        let area = |size: Vector2I| size.x() as i64 * size.y() as i64;
+
+        // A single clipped path covering 4×4 tiles needs only a sliver of the mask.
+        let size = clip_temp_framebuffer_size(16);
+        assert_eq!(size, vec2i(TILE_WIDTH as i32 * 32, TILE_HEIGHT as i32 / 4));
+        assert!(area(size) * 1000 < mask_area);
+
+        // Tiles wrap onto new rows like they do in the mask.
+        let size = clip_temp_framebuffer_size(MASK_TILES_ACROSS / 2 + 1);
+        assert_eq!(size, vec2i(MASK_FRAMEBUFFER_WIDTH, TILE_HEIGHT as i32 / 4 * 2));
+
+        // Even the most clips a mask page can hold fit in one page.
+        let size = clip_temp_framebuffer_size(MASK_TILES_ACROSS * MASK_TILES_DOWN / 2);
+        assert_eq!(size, vec2i(MASK_FRAMEBUFFER_WIDTH, MASK_FRAMEBUFFER_HEIGHT));
+    }
+}
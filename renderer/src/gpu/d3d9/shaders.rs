@@ -351,6 +351,7 @@ pub(crate) struct ClipTileCombineProgramD3D9<D> where D: Device {
     pub(crate) program: D::Program,
     pub(crate) src_texture: D::TextureParameter,
     pub(crate) framebuffer_size_uniform: D::Uniform,
+    pub(crate) src_framebuffer_size_uniform: D::Uniform,
 }
 
 impl<D> ClipTileCombineProgramD3D9<D> where D: Device {
@@ -359,7 +360,13 @@ impl<D> ClipTileCombineProgramD3D9<D> where D: Device {
         let program = device.create_raster_program(resources, "d3d9/tile_clip_combine");
         let src_texture = device.get_texture_parameter(&program, "Src");
         let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
-        ClipTileCombineProgramD3D9 { program, src_texture, framebuffer_size_uniform }
+        let src_framebuffer_size_uniform = device.get_uniform(&program, "SrcFramebufferSize");
+        ClipTileCombineProgramD3D9 {
+            program,
+            src_texture,
+            framebuffer_size_uniform,
+            src_framebuffer_size_uniform,
+        }
     }
 }
 
@@ -367,6 +374,7 @@ pub(crate) struct ClipTileCopyProgramD3D9<D> where D: Device {
     pub(crate) program: D::Program,
     pub(crate) src_texture: D::TextureParameter,
     pub(crate) framebuffer_size_uniform: D::Uniform,
+    pub(crate) src_framebuffer_size_uniform: D::Uniform,
 }
 
 impl<D> ClipTileCopyProgramD3D9<D> where D: Device {
@@ -374,7 +382,13 @@ impl<D> ClipTileCopyProgramD3D9<D> where D: Device {
         let program = device.create_raster_program(resources, "d3d9/tile_clip_copy");
         let src_texture = device.get_texture_parameter(&program, "Src");
         let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
-        ClipTileCopyProgramD3D9 { program, src_texture, framebuffer_size_uniform }
+        let src_framebuffer_size_uniform = device.get_uniform(&program, "SrcFramebufferSize");
+        ClipTileCopyProgramD3D9 {
+            program,
+            src_texture,
+            framebuffer_size_uniform,
+            src_framebuffer_size_uniform,
+        }
     }
 }
 
@@ -17,6 +17,7 @@
 
 use crate::gpu::options::RendererLevel;
 use crate::gpu::perf::{RenderStats, RenderTime};
+use pathfinder_color::ColorU;
 use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::vector::{Vector2I, vec2i};
 use pathfinder_gpu::Device;
@@ -39,6 +40,8 @@ const PERFORMANCE_WINDOW_HEIGHT_D3D11: i32 = LINE_HEIGHT * 10 + PADDING + 2;
 const INFO_WINDOW_WIDTH: i32 = 425;
 const INFO_WINDOW_HEIGHT: i32 = LINE_HEIGHT * 2 + PADDING + 2;
 
+const TILE_OUTLINE_COLOR: ColorU = ColorU { r: 0, g: 255, b: 0, a: 96 };
+
 /// Manages the debug UI.
 pub struct DebugUIPresenter<D> where D: Device {
     /// The general UI presenter object.
@@ -81,6 +84,13 @@ impl<D> DebugUIPresenter<D> where D: Device {
         self.draw_info_window(device, allocator);
     }
 
+    pub(crate) fn draw_tile_outlines(&self,
+                                     device: &D,
+                                     allocator: &mut GPUMemoryAllocator<D>,
+                                     tile_rects: &[RectI]) {
+        self.ui_presenter.draw_rect_outlines(device, allocator, tile_rects, TILE_OUTLINE_COLOR);
+    }
+
     #[inline]
     pub(crate) fn set_framebuffer_size(&mut self, new_framebuffer_size: Vector2I) {
         self.ui_presenter.set_framebuffer_size(new_framebuffer_size)
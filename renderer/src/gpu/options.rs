@@ -12,6 +12,7 @@
 
 use pathfinder_color::ColorF;
 use pathfinder_geometry::rect::RectI;
+use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::Vector2I;
 use pathfinder_gpu::{Device, FeatureLevel};
 
@@ -28,8 +29,107 @@ pub struct RendererOptions<D> where D: Device {
     pub dest: DestFramebuffer<D>,
     /// The background color. If not present, transparent is assumed.
     pub background_color: Option<ColorF>,
+    /// Whether the color channels of the output are premultiplied by alpha.
+    ///
+    /// Pathfinder blends in premultiplied alpha, and by default writes it to the destination
+    /// directly, which is what a WebGL canvas with the default `premultipliedAlpha: true`
+    /// expects. If this is false, the output is converted to straight alpha in a final blit,
+    /// which requires an intermediate framebuffer.
+    pub premultiplied_output: bool,
+    /// A transform applied to the output as it's composited into the destination framebuffer,
+    /// for example to rotate it to match the device orientation or to place it in an arbitrary
+    /// quad of a larger surface.
+    ///
+    /// The transform is in pixels, with the origin at the upper left, and is applied after the
+    /// output has been placed at the viewport. If it isn't the identity, the output is rendered
+    /// to an intermediate framebuffer the size of the viewport, which is then drawn into the
+    /// destination as a transformed quad. Pixels of the destination outside that quad are left
+    /// untouched.
+    pub output_transform: Transform2F,
+    /// The maximum number of pages the mask atlas may grow to, or `None` to allow as many as the
+    /// device's maximum texture size permits.
+    ///
+    /// Each page holds the masks of 65,536 alpha tiles in a 4096×1024 texture, which is 32 MB at
+    /// the D3D9 level. Lowering the limit bounds the video memory the renderer uses for masks,
+    /// which can matter on mobile GPUs. Once the pages are full, the renderer draws the tiles
+    /// whose masks they hold and then reuses them, at the cost of extra passes and of keeping the
+    /// scene's fills around. A limit of zero is treated as one.
+    ///
+    /// At the D3D9 level, pages are only reused when a limit is set. Without one, a scene whose
+    /// masks don't fit in the device's maximum texture size is abandoned with
+    /// `RenderError::TextureTooLarge`.
+    ///
+    /// The limit is a page count rather than a texture size because the mask framebuffer only
+    /// ever grows by whole pages: its width is fixed, and each page adds a fixed height. A size
+    /// would be rounded down to a page count anyway, and a count maps directly to memory.
+    pub max_mask_pages: Option<u32>,
+    /// The maximum number of bytes of video memory that freed GPU objects may occupy while they
+    /// are kept around for reuse, or `None` for no limit.
+    ///
+    /// Without a limit, freed objects are only destroyed once they've gone unused for a short
+    /// while, so scenes whose texture sizes change every frame can accumulate a lot of cached
+    /// memory. With a limit, the least recently freed objects are destroyed at the end of each
+    /// frame until the cache fits. `RenderStats::gpu_reuse_hits` and `gpu_reuse_misses` can help
+    /// with tuning this.
+    pub max_cached_gpu_bytes: Option<u64>,
+    /// How many fills to allocate room for at the D3D11 level before the first frame, or `None`
+    /// for the default of 16,384.
+    ///
+    /// The renderer grows its fill buffer when a scene overflows it, but each overflow costs an
+    /// extra binning pass. Applications that know their scenes are large can avoid that on the
+    /// first frames by raising this. It's rounded up to a power of two, and it's only read when
+    /// the renderer is created.
+    pub initial_fill_count: Option<u32>,
+    /// How many microlines to allocate room for at the D3D11 level before the first frame, or
+    /// `None` for the default of 16,384.
+    ///
+    /// As with `initial_fill_count`, raising this avoids extra dicing passes on the first frames
+    /// of large scenes. It's rounded up to a power of two, and it's only read when the renderer
+    /// is created.
+    pub initial_microline_count: Option<u32>,
+    /// Whether text may be composited with gamma correction.
+    ///
+    /// If this is set, which is the default, text whose filter asks for gamma correction is
+    /// gamma-corrected with the renderer's lookup table. Turning it off disables gamma correction
+    /// for all text, which suits pipelines that work in linear space, and the lookup table is
+    /// never sampled. The change takes effect the next time the scene is built.
+    pub text_gamma_correction: bool,
+    /// A custom lookup table for text gamma correction, or `None` for the built-in one.
+    ///
+    /// The table is 256×8 single-byte texels in row-major order: the column is the foreground
+    /// value and the row is one minus the background value, per color channel.
+    /// Tables of any other size are ignored with a warning. It's only read when the renderer is
+    /// created.
+    pub text_gamma_lut: Option<Vec<u8>>,
+    /// Whether to dither gradients.
+    ///
+    /// Smooth gradients drawn to 8-bit targets can show visible bands. If this is set, the color
+    /// of every pixel of a gradient is perturbed by less than one 8-bit step with an ordered 4×4
+    /// pattern, which breaks up the bands. It's off by default, because it adds noise that
+    /// exact-match image comparisons will see. The change takes effect the next time the scene is
+    /// built.
+    pub dither_gradients: bool,
     /// Whether to display the debug UI.
     pub show_debug_ui: bool,
+    /// Whether to outline the tiles that paths were split into, for debugging.
+    ///
+    /// This can help diagnose paths that don't fill as expected. At the D3D9 level every tile is
+    /// outlined; at the D3D11 level, where tiles are allocated on the GPU, the tile bounds of each
+    /// path are. Only tiles drawn to the destination, not to render targets, are shown. The
+    /// outlines are drawn along with the debug UI, so this has no effect unless `show_debug_ui`
+    /// is set.
+    ///
+    /// Only tile rectangles are drawn, not the outlines of the paths themselves. The renderer
+    /// receives paths already split into fills or segments, so drawing their outlines would
+    /// require keeping per-path segment data around on the CPU for debugging alone. Use
+    /// `DrawPath::outline()` to inspect the geometry of a path instead.
+    pub show_tile_outlines: bool,
+    /// Whether to break down GPU time by render command.
+    ///
+    /// If set, `Renderer::last_render_command_times()` reports how much GPU time each kind of
+    /// render command took. This turns on GPU timer queries even if the debug UI is hidden, which
+    /// has some overhead.
+    pub time_render_commands: bool,
 }
 
 /// The GPU API level that Pathfinder will use.
@@ -37,6 +137,15 @@ pub struct RendererOptions<D> where D: Device {
 /// Note that this is a *level*, not a *backend*. Levels describe rough GPU feature requirements
 /// instead of specific APIs. "D3D9" doesn't mean "Direct3D 9" specifically: rather, it's a more
 /// convenient way to write something like "Direct3D 9/OpenGL 3.0/Metal/WebGL 2.0".
+///
+/// The level also decides where paths are tiled, and it is fixed for the lifetime of a renderer,
+/// because the two levels produce different render commands. At the D3D9 level, paths are
+/// transformed and tiled on the CPU every time the scene is built, which keeps latency low for
+/// small scenes. At the D3D11 level, the scene is uploaded once and transformed, diced, and
+/// binned on the GPU, which scales much better to scenes with many segments but adds fixed
+/// per-frame compute work. The D3D11 level requires a D3D11-class device (see
+/// `default_for_device()`); applications that render mostly small scenes on such a device may
+/// still prefer to create their renderer at the D3D9 level.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RendererLevel {
     /// Direct3D 9/OpenGL 3.0/WebGL 2.0 compatibility. Bin on CPU, fill and composite on GPU.
@@ -59,7 +168,18 @@ impl<D> Default for RendererOptions<D> where D: Device {
         RendererOptions {
             dest: DestFramebuffer::default(),
             background_color: None,
+            premultiplied_output: true,
+            output_transform: Transform2F::default(),
+            max_mask_pages: None,
+            max_cached_gpu_bytes: None,
+            initial_fill_count: None,
+            initial_microline_count: None,
+            text_gamma_correction: true,
+            text_gamma_lut: None,
+            dither_gradients: false,
             show_debug_ui: false,
+            show_tile_outlines: false,
+            time_render_commands: false,
         }
     }
 }
@@ -85,6 +205,10 @@ pub enum DestFramebuffer<D> where D: Device {
         window_size: Vector2I,
     },
     /// The rendered content should go to a non-default framebuffer (off-screen, typically).
+    ///
+    /// Any depth-stencil attachment the framebuffer has is owned by the application and is used
+    /// by the deprecated `Renderer::enable_depth()` mode, which needs at least 8 bits of
+    /// stencil. In that mode the renderer clears the stencil buffer at the start of every frame.
     Other(D::Framebuffer),
 }
 
@@ -11,7 +11,9 @@
 //! Performance monitoring infrastructure.
 
 use crate::gpu::options::RendererOptions;
+use crate::gpu_data::RenderCommand;
 use pathfinder_gpu::Device;
+use std::collections::HashMap;
 use std::mem;
 use std::ops::{Add, Div};
 use std::time::Duration;
@@ -32,15 +34,72 @@ pub struct RenderStats {
     pub total_tile_count: usize,
     /// The amount of CPU time it took to build the scene.
     pub cpu_build_time: Duration,
+    /// The number of 16x16 tiles that were hidden behind opaque tiles of later paths and so were
+    /// culled instead of drawn.
+    ///
+    /// At the D3D11 level, tiles are culled on the GPU, so this is always zero.
+    pub occluded_tile_count: usize,
+    /// The number of 16x16 tiles that were actually drawn, after occlusion culling.
+    ///
+    /// This is `total_tile_count` minus `occluded_tile_count`.
+    pub drawn_tile_count: usize,
     /// The number of GPU API draw calls it took to render the scene.
     pub drawcall_count: u32,
-    /// The number of bytes of VRAM Pathfinder has allocated.
+    /// The number of bytes of VRAM Pathfinder had allocated at the end of the frame.
     /// 
     /// This may be higher than `gpu_bytes_committed` because Pathfinder caches some data for
-    /// faster reuse.
+    /// faster reuse. This is a snapshot, not a running total; see
+    /// `Renderer::total_gpu_bytes_allocated()` for the latter.
     pub gpu_bytes_allocated: u64,
     /// The number of bytes of VRAM Pathfinder actually used for the frame.
     pub gpu_bytes_committed: u64,
+    /// The largest number of bytes of VRAM Pathfinder had allocated at once, as of the end of the
+    /// frame.
+    ///
+    /// This is a snapshot of `Renderer::peak_gpu_bytes_allocated()`. Adding stats together keeps
+    /// the larger peak, and dividing them leaves it alone, so averaged stats report the highest
+    /// peak of any frame.
+    pub gpu_bytes_peak: u64,
+    /// The number of GPU objects allocated during the frame by reusing cached ones.
+    pub gpu_reuse_hits: u32,
+    /// The number of GPU objects allocated during the frame that had to be newly created.
+    pub gpu_reuse_misses: u32,
+}
+
+impl RenderStats {
+    // Records the statistics that a render command carries, before the renderer processes it.
+    // Fills at the D3D11 level are generated on the GPU, so that renderer records them itself.
+    pub(crate) fn record_command(&mut self, command: &RenderCommand) {
+        match *command {
+            RenderCommand::Start { path_count, .. } => self.path_count = path_count,
+            RenderCommand::AddFillsD3D9(ref fills) => self.record_fills(fills.len()),
+            RenderCommand::DrawTilesD3D9(ref batch) => {
+                self.record_tile_batch(batch.tiles.len(), batch.occluded_tile_count())
+            }
+            RenderCommand::PrepareClipTilesD3D11(ref batch) => {
+                self.record_tile_batch(batch.tile_count as usize, 0)
+            }
+            RenderCommand::DrawTilesD3D11(ref batch) => {
+                self.record_tile_batch(batch.tile_batch_data.tile_count as usize, 0)
+            }
+            RenderCommand::Finish { cpu_build_time } => self.cpu_build_time = cpu_build_time,
+            _ => {}
+        }
+    }
+
+    // Records a batch of tiles. Occluded tiles are counted but not drawn.
+    #[inline]
+    pub(crate) fn record_tile_batch(&mut self, tile_count: usize, occluded_tile_count: usize) {
+        self.total_tile_count += tile_count;
+        self.occluded_tile_count += occluded_tile_count;
+        self.drawn_tile_count += tile_count - occluded_tile_count;
+    }
+
+    // Records fills as they're rasterized.
+    #[inline]
+    pub(crate) fn record_fills(&mut self, fill_count: usize) {
+        self.fill_count += fill_count;
+    }
 }
 
 impl Add<RenderStats> for RenderStats {
@@ -51,10 +110,15 @@ impl Add<RenderStats> for RenderStats {
             alpha_tile_count: self.alpha_tile_count + other.alpha_tile_count,
             total_tile_count: self.total_tile_count + other.total_tile_count,
             fill_count: self.fill_count + other.fill_count,
+            occluded_tile_count: self.occluded_tile_count + other.occluded_tile_count,
+            drawn_tile_count: self.drawn_tile_count + other.drawn_tile_count,
             cpu_build_time: self.cpu_build_time + other.cpu_build_time,
             drawcall_count: self.drawcall_count + other.drawcall_count,
             gpu_bytes_allocated: self.gpu_bytes_allocated + other.gpu_bytes_allocated,
             gpu_bytes_committed: self.gpu_bytes_committed + other.gpu_bytes_committed,
+            gpu_bytes_peak: self.gpu_bytes_peak.max(other.gpu_bytes_peak),
+            gpu_reuse_hits: self.gpu_reuse_hits + other.gpu_reuse_hits,
+            gpu_reuse_misses: self.gpu_reuse_misses + other.gpu_reuse_misses,
         }
     }
 }
@@ -67,10 +131,15 @@ impl Div<usize> for RenderStats {
             alpha_tile_count: self.alpha_tile_count / divisor,
             total_tile_count: self.total_tile_count / divisor,
             fill_count: self.fill_count / divisor,
+            occluded_tile_count: self.occluded_tile_count / divisor,
+            drawn_tile_count: self.drawn_tile_count / divisor,
             cpu_build_time: self.cpu_build_time / divisor as u32,
             drawcall_count: self.drawcall_count / divisor as u32,
             gpu_bytes_allocated: self.gpu_bytes_allocated / divisor as u64,
             gpu_bytes_committed: self.gpu_bytes_committed / divisor as u64,
+            gpu_bytes_peak: self.gpu_bytes_peak,
+            gpu_reuse_hits: self.gpu_reuse_hits / divisor as u32,
+            gpu_reuse_misses: self.gpu_reuse_misses / divisor as u32,
         }
     }
 }
@@ -85,6 +154,12 @@ pub(crate) struct PendingTimer<D> where D: Device {
     pub(crate) fill_times: Vec<TimerFuture<D>>,
     pub(crate) composite_times: Vec<TimerFuture<D>>,
     pub(crate) other_times: Vec<TimerFuture<D>>,
+    // The render command that timer queries are currently attributed to, if they're being
+    // attributed to commands.
+    pub(crate) current_command: Option<&'static str>,
+    // The name of the render command each attributed query belongs to, along with the query's
+    // category and index within that category.
+    command_queries: Vec<(&'static str, TimeCategory, usize)>,
 }
 
 pub(crate) enum TimerFuture<D> where D: Device {
@@ -116,7 +191,7 @@ impl<D> TimerQueryCache<D> where D: Device {
 
     pub(crate) fn start_timing_draw_call(&mut self, device: &D, options: &RendererOptions<D>)
                                          -> Option<D::TimerQuery> {
-        if !options.show_debug_ui {
+        if !options.show_debug_ui && !options.time_render_commands {
             return None;
         }
 
@@ -134,6 +209,8 @@ impl<D> PendingTimer<D> where D: Device {
             fill_times: vec![],
             composite_times: vec![],
             other_times: vec![],
+            current_command: None,
+            command_queries: vec![],
         }
     }
 
@@ -168,6 +245,21 @@ impl<D> PendingTimer<D> where D: Device {
         }
     }
 
+    /// Returns the total GPU time spent in each render command, by command name, once all the
+    /// timer queries have been resolved.
+    pub(crate) fn command_times(&self) -> Option<HashMap<&'static str, Duration>> {
+        let mut command_times = HashMap::new();
+        for &(command_name, time_category, index) in &self.command_queries {
+            match self.times(time_category)[index] {
+                TimerFuture::Pending(_) => return None,
+                TimerFuture::Resolved(time) => {
+                    *command_times.entry(command_name).or_insert_with(Duration::default) += time;
+                }
+            }
+        }
+        Some(command_times)
+    }
+
     pub(crate) fn push_query(&mut self,
                              time_category: TimeCategory,
                              timer_query: Option<D::TimerQuery>) {
@@ -175,12 +267,30 @@ impl<D> PendingTimer<D> where D: Device {
             None => return,
             Some(timer_query) => TimerFuture::new(timer_query),
         };
+        if let Some(command_name) = self.current_command {
+            let index = self.times(time_category).len();
+            self.command_queries.push((command_name, time_category, index));
+        }
+        self.times_mut(time_category).push(timer_future);
+    }
+
+    fn times(&self, time_category: TimeCategory) -> &[TimerFuture<D>] {
         match time_category {
-            TimeCategory::Dice => self.dice_times.push(timer_future),
-            TimeCategory::Bin => self.bin_times.push(timer_future),
-            TimeCategory::Fill => self.fill_times.push(timer_future),
-            TimeCategory::Composite => self.composite_times.push(timer_future),
-            TimeCategory::Other => self.other_times.push(timer_future),
+            TimeCategory::Dice => &self.dice_times,
+            TimeCategory::Bin => &self.bin_times,
+            TimeCategory::Fill => &self.fill_times,
+            TimeCategory::Composite => &self.composite_times,
+            TimeCategory::Other => &self.other_times,
+        }
+    }
+
+    fn times_mut(&mut self, time_category: TimeCategory) -> &mut Vec<TimerFuture<D>> {
+        match time_category {
+            TimeCategory::Dice => &mut self.dice_times,
+            TimeCategory::Bin => &mut self.bin_times,
+            TimeCategory::Fill => &mut self.fill_times,
+            TimeCategory::Composite => &mut self.composite_times,
+            TimeCategory::Other => &mut self.other_times,
         }
     }
 }
@@ -289,3 +399,141 @@ impl Div<usize> for RenderTime {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::RenderStats;
+    use crate::gpu_data::{AlphaTileId, DrawTileBatchD3D11, DrawTileBatchD3D9, Fill, PathSource};
+    use crate::gpu_data::{PrepareTilesInfoD3D11, RenderCommand, TileBatchDataD3D11, TileBatchId};
+    use crate::gpu_data::TileObjectPrimitive;
+    use crate::scene::PathId;
+    use crate::tile_map::DenseTileMap;
+    use pathfinder_content::effects::{BlendMode, Filter};
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{Vector4F, vec2i};
+    use std::time::Duration;
+
+    #[test]
+    fn test_estimated_stats_match_final_stats() {
+        // Ten tiles of paths 0 to 9 at the same spot, the first four of which the opaque tile of
+        // path 4 hides, and then six tiles that nothing hides.
+        let tile_batch = |tile_count: u32, z_value: i32| {
+            let tile_rect = RectI::new(vec2i(0, 0), vec2i(1, 1));
+            DrawTileBatchD3D9 {
+                tiles: (0..tile_count).map(|path_index| {
+                    TileObjectPrimitive {
+                        tile_x: 0,
+                        tile_y: 0,
+                        alpha_tile_id: AlphaTileId(!0),
+                        path_id: PathId(path_index),
+                        color: 0,
+                        ctrl: 0,
+                        backdrop: 0,
+                    }
+                }).collect(),
+                clips: vec![],
+                z_buffer_data: DenseTileMap::from_builder(|_| z_value, tile_rect),
+                color_texture: None,
+                color_texture_1: None,
+                filter: Filter::None,
+                blend_mode: BlendMode::SrcOver,
+            }
+        };
+        let commands = vec![
+            RenderCommand::Start {
+                path_count: 10,
+                bounding_quad: [Vector4F::default(); 4],
+                needs_readable_framebuffer: false,
+            },
+            RenderCommand::AddFillsD3D9(vec![Fill::default(); 40]),
+            RenderCommand::FlushFillsD3D9,
+            RenderCommand::DrawTilesD3D9(tile_batch(10, 4)),
+            RenderCommand::AddFillsD3D9(vec![Fill::default(); 8]),
+            RenderCommand::FlushFillsD3D9,
+            RenderCommand::DrawTilesD3D9(tile_batch(6, 0)),
+            RenderCommand::Finish { cpu_build_time: Duration::from_millis(2) },
+        ];
+
+        let mut stats = RenderStats::default();
+        let mut estimated_stats = vec![];
+        for command in &commands {
+            stats.record_command(command);
+            estimated_stats.push(stats);
+        }
+        let final_stats = stats;
+
+        // The path count is known from the start. Fills are counted as they're added, and the tiles
+        // of a batch as soon as its command arrives, before the batch is drawn.
+        assert_eq!(estimated_stats[0].path_count, 10);
+        assert_eq!(estimated_stats[0].fill_count, 0);
+        assert_eq!(estimated_stats[0].drawn_tile_count, 0);
+        assert_eq!(estimated_stats[3].fill_count, 40);
+        assert_eq!(estimated_stats[3].total_tile_count, 10);
+        assert_eq!(estimated_stats[3].drawn_tile_count, 6);
+        assert_eq!(estimated_stats[3].occluded_tile_count, 4);
+
+        // After the last tile batch, the estimate matches the final stats.
+        let last_estimate = &estimated_stats[6];
+        assert_eq!(last_estimate.path_count, final_stats.path_count);
+        assert_eq!(last_estimate.fill_count, 48);
+        assert_eq!(last_estimate.fill_count, final_stats.fill_count);
+        assert_eq!(last_estimate.total_tile_count, 16);
+        assert_eq!(last_estimate.drawn_tile_count, 12);
+        assert_eq!(last_estimate.drawn_tile_count, final_stats.drawn_tile_count);
+        assert_eq!(last_estimate.occluded_tile_count, final_stats.occluded_tile_count);
+        assert_eq!(final_stats.cpu_build_time, Duration::from_millis(2));
+    }
+
+    #[test]
+    fn test_estimated_stats_count_d3d11_tiles_on_arrival() {
+        let tile_batch_data = |batch_id: u32, tile_count: u32, path_source: PathSource| {
+            TileBatchDataD3D11 {
+                batch_id: TileBatchId(batch_id),
+                path_count: 1,
+                tile_count,
+                segment_count: 4,
+                prepare_info: PrepareTilesInfoD3D11 {
+                    backdrops: vec![],
+                    propagate_metadata: vec![],
+                    dice_metadata: vec![],
+                    tile_path_info: vec![],
+                    transform: Transform2F::default(),
+                },
+                path_source,
+                clipped_path_info: None,
+            }
+        };
+        let commands = vec![
+            RenderCommand::PrepareClipTilesD3D11(tile_batch_data(0, 3, PathSource::Clip)),
+            RenderCommand::DrawTilesD3D11(DrawTileBatchD3D11 {
+                tile_batch_data: tile_batch_data(1, 5, PathSource::Draw),
+                color_texture: None,
+                color_texture_1: None,
+            }),
+        ];
+
+        // Both clip and draw batches count their tiles when the command arrives. Tiles are culled
+        // on the GPU, so none are counted as occluded.
+        let mut stats = RenderStats::default();
+        stats.record_command(&commands[0]);
+        assert_eq!(stats.total_tile_count, 3);
+        assert_eq!(stats.drawn_tile_count, 3);
+        stats.record_command(&commands[1]);
+        assert_eq!(stats.total_tile_count, 8);
+        assert_eq!(stats.drawn_tile_count, 8);
+        assert_eq!(stats.occluded_tile_count, 0);
+    }
+
+    #[test]
+    fn test_gpu_bytes_peak_is_not_summed() {
+        let first = RenderStats { gpu_bytes_peak: 300, path_count: 2, ..RenderStats::default() };
+        let second = RenderStats { gpu_bytes_peak: 500, path_count: 4, ..RenderStats::default() };
+        let sum = first + second;
+        assert_eq!(sum.gpu_bytes_peak, 500);
+        assert_eq!(sum.path_count, 6);
+        let mean = sum / 2;
+        assert_eq!(mean.gpu_bytes_peak, 500);
+        assert_eq!(mean.path_count, 3);
+    }
+}
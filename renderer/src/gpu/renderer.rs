@@ -17,9 +17,11 @@ use crate::gpu::debug::DebugUIPresenter;
 use crate::gpu::options::{DestFramebuffer, RendererLevel, RendererMode, RendererOptions};
 use crate::gpu::perf::{PendingTimer, RenderStats, RenderTime, TimeCategory, TimerQueryCache};
 use crate::gpu::shaders::{BlitProgram, BlitVertexArray, ClearProgram, ClearVertexArray};
-use crate::gpu::shaders::{ProgramsCore, ReprojectionProgram, ReprojectionVertexArray};
+use crate::gpu::shaders::{MeshProgram, MeshVertexArray, ProgramsCore, ReprojectionProgram};
+use crate::gpu::shaders::ReprojectionVertexArray;
 use crate::gpu::shaders::{StencilProgram, StencilVertexArray, TileProgramCommon, VertexArraysCore};
-use crate::gpu_data::{ColorCombineMode, RenderCommand, TextureLocation, TextureMetadataEntry};
+use crate::gpu_data::{ColorCombineMode, DrawMeshBatch, MeshVertex, RenderCommand, TextureLocation};
+use crate::gpu_data::TextureMetadataEntry;
 use crate::gpu_data::{TexturePageDescriptor, TexturePageId, TileBatchTexture};
 use crate::options::BoundingQuad;
 use crate::tiles::{TILE_HEIGHT, TILE_WIDTH};
@@ -28,6 +30,7 @@ use pathfinder_color::{self as color, ColorF, ColorU};
 use pathfinder_content::effects::{BlendMode, BlurDirection, Filter, PatternFilter};
 use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::transform3d::Transform4F;
 use pathfinder_geometry::util;
 use pathfinder_geometry::vector::{Vector2F, Vector2I, Vector4F, vec2f, vec2i};
@@ -35,14 +38,36 @@ use pathfinder_gpu::allocator::{BufferTag, FramebufferID, FramebufferTag, Genera
 use pathfinder_gpu::allocator::{GPUMemoryAllocator, IndexBufferID, TextureID, TextureTag};
 use pathfinder_gpu::{BufferData, BufferTarget, ClearOps, DepthFunc, DepthState, Device, Primitive};
 use pathfinder_gpu::{RenderOptions, RenderState, RenderTarget, StencilFunc, StencilState};
-use pathfinder_gpu::{TextureBinding, TextureDataRef, TextureFormat, UniformBinding, UniformData};
+use pathfinder_gpu::{TextureBinding, TextureData, TextureDataRef, TextureFormat, TextureFormatSet};
+use pathfinder_gpu::{BlendFactor, BlendState, BufferUploadMode, UniformBinding, UniformData};
 use pathfinder_resources::ResourceLoader;
 use pathfinder_simd::default::{F32x2, F32x4, I32x2};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::f32;
+use std::mem;
 use std::time::Duration;
 use std::u32;
 
+#[cfg(feature = "png")]
+use image::ColorType;
+#[cfg(feature = "png")]
+use image::png::PngEncoder;
+#[cfg(feature = "png")]
+use std::io;
+#[cfg(all(feature = "png", not(target_arch = "wasm32")))]
+use std::fs;
+#[cfg(all(feature = "png", not(target_arch = "wasm32")))]
+use std::path::Path;
+
+#[cfg(feature = "debug")]
+use crate::concurrent::executor::SequentialExecutor;
+#[cfg(feature = "debug")]
+use crate::options::BuildOptions;
+#[cfg(feature = "debug")]
+use crate::paint::Paint;
+#[cfg(feature = "debug")]
+use crate::scene::{DrawPathId, Scene};
+
 static QUAD_VERTEX_POSITIONS: [u16; 8] = [0, 0, 1, 0, 1, 1, 0, 1];
 static QUAD_VERTEX_INDICES: [u32; 6] = [0, 1, 3, 1, 2, 3];
 
@@ -52,6 +77,9 @@ pub(crate) const MASK_TILES_DOWN: u32 = 256;
 // 1.0 / sqrt(2*pi)
 const SQRT_2_PI_INV: f32 = 0.3989422804014327;
 
+const GAMMA_LUT_WIDTH:  i32 = 256;
+const GAMMA_LUT_HEIGHT: i32 = 8;
+
 const TEXTURE_METADATA_ENTRIES_PER_ROW: i32 = 128;
 const TEXTURE_METADATA_TEXTURE_WIDTH:   i32 = TEXTURE_METADATA_ENTRIES_PER_ROW * 10;
 const TEXTURE_METADATA_TEXTURE_HEIGHT:  i32 = 65536 / TEXTURE_METADATA_ENTRIES_PER_ROW;
@@ -64,10 +92,12 @@ const COMBINER_CTRL_FILTER_RADIAL_GRADIENT: i32 =   0x1;
 const COMBINER_CTRL_FILTER_TEXT: i32 =              0x2;
 const COMBINER_CTRL_FILTER_BLUR: i32 =              0x3;
 const COMBINER_CTRL_FILTER_COLOR_MATRIX: i32 =      0x4;
+const COMBINER_CTRL_FILTER_CONIC_GRADIENT: i32 =    0x5;
 
 const COMBINER_CTRL_COLOR_FILTER_SHIFT: i32 =       4;
 const COMBINER_CTRL_COLOR_COMBINE_SHIFT: i32 =      8;
 const COMBINER_CTRL_COMPOSITE_SHIFT: i32 =         10;
+const COMBINER_CTRL_DITHER: i32 =                  0x4000;
 
 /// The GPU renderer that processes commands necessary to render a scene.
 pub struct Renderer<D> where D: Device {
@@ -77,7 +107,9 @@ pub struct Renderer<D> where D: Device {
 
     // Shaders
     blit_program: BlitProgram<D>,
+    blit_unpremultiply_program: BlitProgram<D>,
     clear_program: ClearProgram<D>,
+    mesh_program: MeshProgram<D>,
     stencil_program: StencilProgram<D>,
     reprojection_program: ReprojectionProgram<D>,
 
@@ -88,8 +120,19 @@ pub struct Renderer<D> where D: Device {
     current_cpu_build_time: Option<Duration>,
     pending_timers: VecDeque<PendingTimer<D>>,
     debug_ui_presenter: Option<DebugUIPresenter<D>>,
+    debug_tile_rects: Vec<RectI>,
     last_stats: VecDeque<RenderStats>,
     last_rendering_time: Option<RenderTime>,
+    last_render_command_times: HashMap<&'static str, Duration>,
+}
+
+/// An error that caused the renderer to abandon the current scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderError {
+    /// The GPU ran out of memory while allocating a texture.
+    OutOfMemory,
+    /// A texture larger than the device's maximum texture size was needed.
+    TextureTooLarge,
 }
 
 enum RendererLevelImpl<D> where D: Device {
@@ -104,6 +147,9 @@ pub(crate) struct RendererCore<D> where D: Device {
     pub(crate) mode: RendererMode,
     pub(crate) options: RendererOptions<D>,
     pub(crate) renderer_flags: RendererFlags,
+    render_error: Option<RenderError>,
+    mask_texture_format: TextureFormat,
+    max_texture_size: i32,
 
     // Performance monitoring
     pub(crate) stats: RenderStats,
@@ -119,6 +165,7 @@ pub(crate) struct RendererCore<D> where D: Device {
     pub(crate) quad_vertex_indices_buffer_id: IndexBufferID,
     pub(crate) area_lut_texture_id: TextureID,
     pub(crate) gamma_lut_texture_id: TextureID,
+    pub(crate) placeholder_texture_id: TextureID,
 
     // Read-write static core resources
     intermediate_dest_framebuffer_id: FramebufferID,
@@ -137,6 +184,7 @@ pub(crate) struct RendererCore<D> where D: Device {
 // TODO(pcwalton): Remove this.
 struct Frame<D> where D: Device {
     blit_vertex_array: BlitVertexArray<D>,
+    blit_unpremultiply_vertex_array: BlitVertexArray<D>,
     clear_vertex_array: ClearVertexArray<D>,
     stencil_vertex_array: StencilVertexArray<D>,
     reprojection_vertex_array: ReprojectionVertexArray<D>,
@@ -195,18 +243,37 @@ impl<D> Renderer<D> where D: Device {
                                                              Vector2I::splat(256),
                                                              TextureFormat::RGBA8,
                                                              TextureTag("AreaLUT"));
+        let gamma_lut_size = vec2i(GAMMA_LUT_WIDTH, GAMMA_LUT_HEIGHT);
         let gamma_lut_texture_id = allocator.allocate_texture(&device,
-                                                              vec2i(256, 8),
+                                                              gamma_lut_size,
                                                               TextureFormat::R8,
                                                               TextureTag("GammaLUT"));
         device.upload_png_to_texture(resources,
                                      "area-lut",
                                      allocator.get_texture(area_lut_texture_id),
                                      TextureFormat::RGBA8);
-        device.upload_png_to_texture(resources,
-                                     "gamma-lut",
-                                     allocator.get_texture(gamma_lut_texture_id),
-                                     TextureFormat::R8);
+        match custom_gamma_lut(&options.text_gamma_lut) {
+            Some(texels) => {
+                device.upload_to_texture(allocator.get_texture(gamma_lut_texture_id),
+                                         RectI::new(Vector2I::zero(), gamma_lut_size),
+                                         TextureDataRef::U8(texels));
+            }
+            None => {
+                device.upload_png_to_texture(resources,
+                                             "gamma-lut",
+                                             allocator.get_texture(gamma_lut_texture_id),
+                                             TextureFormat::R8);
+            }
+        }
+
+        // A transparent texel to bind to color texture slots that a batch doesn't use.
+        let placeholder_texture_id = allocator.allocate_texture(&device,
+                                                                vec2i(1, 1),
+                                                                TextureFormat::RGBA8,
+                                                                TextureTag("Placeholder"));
+        device.upload_to_texture(allocator.get_texture(placeholder_texture_id),
+                                 RectI::new(Vector2I::zero(), vec2i(1, 1)),
+                                 TextureDataRef::U8(&[0; 4]));
 
         let window_size = options.dest.window_size(&device);
         let intermediate_dest_framebuffer_id =
@@ -230,15 +297,22 @@ impl<D> Renderer<D> where D: Device {
                                    allocator.get_general_buffer(quad_vertex_positions_buffer_id),
                                    allocator.get_index_buffer(quad_vertex_indices_buffer_id));
 
+        let mask_texture_format =
+            choose_mask_texture_format(mode.level, device.supported_texture_formats());
+        let max_texture_size = device.max_texture_size();
+
         let mut core = RendererCore {
             device,
             allocator,
             mode,
             options,
+            mask_texture_format,
+            max_texture_size,
             stats: RenderStats::default(),
             current_timer: None,
             timer_query_cache: TimerQueryCache::new(),
             renderer_flags: RendererFlags::empty(),
+            render_error: None,
 
             programs: core_programs,
             vertex_arrays: core_vertex_arrays,
@@ -247,6 +321,7 @@ impl<D> Renderer<D> where D: Device {
             quad_vertex_indices_buffer_id,
             area_lut_texture_id,
             gamma_lut_texture_id,
+            placeholder_texture_id,
 
             intermediate_dest_framebuffer_id,
             intermediate_dest_framebuffer_size: window_size,
@@ -270,7 +345,9 @@ impl<D> Renderer<D> where D: Device {
         };
 
         let blit_program = BlitProgram::new(&core.device, resources);
+        let blit_unpremultiply_program = BlitProgram::new_unpremultiply(&core.device, resources);
         let clear_program = ClearProgram::new(&core.device, resources);
+        let mesh_program = MeshProgram::new(&core.device, resources);
         let stencil_program = StencilProgram::new(&core.device, resources);
         let reprojection_program = ReprojectionProgram::new(&core.device, resources);
 
@@ -283,6 +360,7 @@ impl<D> Renderer<D> where D: Device {
         let frame = Frame::new(&core.device,
                                &mut core.allocator,
                                &blit_program,
+                               &blit_unpremultiply_program,
                                &clear_program,
                                &reprojection_program,
                                &stencil_program,
@@ -296,7 +374,9 @@ impl<D> Renderer<D> where D: Device {
             level_impl,
 
             blit_program,
+            blit_unpremultiply_program,
             clear_program,
+            mesh_program,
 
             frame,
 
@@ -306,8 +386,10 @@ impl<D> Renderer<D> where D: Device {
             current_cpu_build_time: None,
             pending_timers: VecDeque::new(),
             debug_ui_presenter,
+            debug_tile_rects: vec![],
             last_stats: VecDeque::new(),
             last_rendering_time: None,
+            last_render_command_times: HashMap::new(),
         }
     }
 
@@ -325,8 +407,15 @@ impl<D> Renderer<D> where D: Device {
         self.core.device.begin_commands();
         self.core.current_timer = Some(PendingTimer::new());
         self.core.stats = RenderStats::default();
+        self.core.render_error = None;
 
         self.core.alpha_tile_count = 0;
+        self.debug_tile_rects.clear();
+
+        match self.level_impl {
+            RendererLevelImpl::D3D9(ref mut d3d9_renderer) => d3d9_renderer.begin_frame(),
+            RendererLevelImpl::D3D11(ref mut d3d11_renderer) => d3d11_renderer.begin_frame(),
+        }
     }
 
     /// Issues a rendering command to the renderer.
@@ -336,12 +425,28 @@ impl<D> Renderer<D> where D: Device {
     /// `begin_scene()` must have been called first.
     pub fn render_command(&mut self, command: &RenderCommand) {
         debug!("render command: {:?}", command);
+
+        // If an earlier command failed, the remaining ones can't be executed meaningfully.
+        if self.core.render_error.is_some() {
+            return;
+        }
+
+        self.core.stats.record_command(command);
+
+        if self.core.options.time_render_commands {
+            if let Some(ref mut current_timer) = self.core.current_timer {
+                current_timer.current_command = Some(command.name());
+            }
+        }
+
         match *command {
-            RenderCommand::Start { bounding_quad, path_count, needs_readable_framebuffer } => {
-                self.start_rendering(bounding_quad, path_count, needs_readable_framebuffer);
+            RenderCommand::Start { bounding_quad, needs_readable_framebuffer, .. } => {
+                self.start_rendering(bounding_quad, needs_readable_framebuffer);
             }
             RenderCommand::AllocateTexturePage { page_id, ref descriptor } => {
-                self.allocate_pattern_texture_page(page_id, descriptor)
+                if let Err(err) = self.allocate_pattern_texture_page(page_id, descriptor) {
+                    self.core.render_error = Some(err);
+                }
             }
             RenderCommand::UploadTexelData { ref texels, location } => {
                 self.upload_texel_data(texels, location)
@@ -371,14 +476,30 @@ impl<D> Renderer<D> where D: Device {
                 self.level_impl.require_d3d11().prepare_tiles(&mut self.core, batch)
             }
             RenderCommand::DrawTilesD3D9(ref batch) => {
+                if self.debug_tile_outlines_needed() {
+                    self.debug_tile_rects.extend(batch.tiles.iter().map(|tile| {
+                        tile_rect(vec2i(tile.tile_x as i32, tile.tile_y as i32), vec2i(1, 1))
+                    }));
+                }
                 self.level_impl.require_d3d9().upload_and_draw_tiles(&mut self.core, batch)
             }
             RenderCommand::DrawTilesD3D11(ref batch) => {
+                if self.debug_tile_outlines_needed() {
+                    let tile_path_info = &batch.tile_batch_data.prepare_info.tile_path_info;
+                    self.debug_tile_rects.extend(tile_path_info.iter().map(|path_info| {
+                        let min = vec2i(path_info.tile_min_x as i32, path_info.tile_min_y as i32);
+                        let max = vec2i(path_info.tile_max_x as i32, path_info.tile_max_y as i32);
+                        tile_rect(min, max - min)
+                    }));
+                }
                 self.level_impl.require_d3d11().prepare_and_draw_tiles(&mut self.core, batch)
             }
-            RenderCommand::Finish { cpu_build_time } => {
-                self.core.stats.cpu_build_time = cpu_build_time;
-            }
+            RenderCommand::DrawMesh(ref batch) => self.draw_mesh(batch),
+            RenderCommand::Finish { .. } => {}
+        }
+
+        if let Some(ref mut current_timer) = self.core.current_timer {
+            current_timer.current_command = None;
         }
     }
 
@@ -393,8 +514,8 @@ impl<D> Renderer<D> where D: Device {
         self.clear_dest_framebuffer_if_necessary();
         self.blit_intermediate_dest_framebuffer_if_necessary();
 
-        self.core.stats.gpu_bytes_allocated = self.core.allocator.bytes_allocated();
-        self.core.stats.gpu_bytes_committed = self.core.allocator.bytes_committed();
+        self.core.stats = self.stats_with_gpu_memory();
+        self.core.allocator.reset_reuse_counts();
 
         match self.level_impl {
             RendererLevelImpl::D3D9(_) => {}
@@ -413,6 +534,7 @@ impl<D> Renderer<D> where D: Device {
             self.draw_debug_ui();
         }
 
+        self.core.allocator.set_max_cached_bytes(self.core.options.max_cached_gpu_bytes);
         self.core.allocator.purge_if_needed();
 
         self.core.device.end_commands();
@@ -420,15 +542,24 @@ impl<D> Renderer<D> where D: Device {
 
     fn start_rendering(&mut self,
                        bounding_quad: BoundingQuad,
-                       path_count: usize,
                        needs_readable_framebuffer: bool) {
         match (&self.core.options.dest, self.core.mode.level) {
+            _ if !self.core.options.premultiplied_output ||
+                    !self.core.options.output_transform.is_identity() => {
+                // Converting to straight alpha or transforming the output requires a final blit.
+                self.core
+                    .renderer_flags
+                    .insert(RendererFlags::INTERMEDIATE_DEST_FRAMEBUFFER_NEEDED);
+            }
             (&DestFramebuffer::Other(_), _) => {
                 self.core
                     .renderer_flags
                     .remove(RendererFlags::INTERMEDIATE_DEST_FRAMEBUFFER_NEEDED);
             }
             (&DestFramebuffer::Default { .. }, RendererLevel::D3D11) => {
+                // The D3D11 level writes tiles with a compute shader, which needs a texture to
+                // store to, and the default framebuffer has none. So this is needed regardless
+                // of blend mode.
                 self.core
                     .renderer_flags
                     .insert(RendererFlags::INTERMEDIATE_DEST_FRAMEBUFFER_NEEDED);
@@ -445,8 +576,6 @@ impl<D> Renderer<D> where D: Device {
             self.draw_stencil(&bounding_quad);
         }
 
-        self.core.stats.path_count = path_count;
-
         self.core.render_targets.clear();
     }
 
@@ -470,10 +599,24 @@ impl<D> Renderer<D> where D: Device {
         if let Some(ref mut debug_ui_presenter) = self.debug_ui_presenter {
             let window_size = self.core.options.dest.window_size(&self.core.device);
             debug_ui_presenter.set_framebuffer_size(window_size);
+            if self.core.options.show_tile_outlines {
+                let viewport_origin = self.core.main_viewport().origin();
+                let tile_rects: Vec<RectI> = self.debug_tile_rects.iter().map(|tile_rect| {
+                    RectI::new(tile_rect.origin() + viewport_origin, tile_rect.size())
+                }).collect();
+                debug_ui_presenter.draw_tile_outlines(&self.core.device,
+                                                      &mut self.core.allocator,
+                                                      &tile_rects);
+            }
             debug_ui_presenter.draw(&self.core.device, &mut self.core.allocator);
         }
     }
 
+    fn debug_tile_outlines_needed(&self) -> bool {
+        self.core.options.show_tile_outlines && self.debug_ui_presenter.is_some() &&
+            self.core.render_target_stack.is_empty()
+    }
+
     fn shift_rendering_time(&mut self) {
         if let Some(mut pending_timer) = self.pending_timers.pop_front() {
             for old_query in pending_timer.poll(&self.core.device) {
@@ -481,6 +624,9 @@ impl<D> Renderer<D> where D: Device {
             }
             if let Some(render_time) = pending_timer.total_time() {
                 self.last_rendering_time = Some(render_time);
+                if let Some(command_times) = pending_timer.command_times() {
+                    self.last_render_command_times = command_times;
+                }
                 return;
             }
             self.pending_timers.push_front(pending_timer);
@@ -488,11 +634,372 @@ impl<D> Renderer<D> where D: Device {
         self.last_rendering_time = None;
     }
 
+    /// Renders a stream of commands into a newly-allocated off-screen texture of the given size
+    /// and returns that texture.
+    ///
+    /// This is useful for rendering a complex, static layer once and then compositing it cheaply
+    /// on subsequent frames, for example as the source of a pattern. The output is RGBA8 on a
+    /// transparent background, and the debug UI is never drawn into it.
+    ///
+    /// `commands` must form a complete scene, as produced by building a `Scene` whose view box
+    /// has the given size. This method issues its own `begin_scene()` and `end_scene()`, so it
+    /// must not be called between those of another scene. The other rendering options, such as
+    /// `premultiplied_output` and `output_transform`, apply as they do to the destination
+    /// framebuffer, which is restored before returning.
+    ///
+    /// The returned texture is owned by the caller and is independent of the renderer's
+    /// internal allocations: it stays valid until the caller destroys it, but it must not
+    /// outlive the device that created it. If `render_error()` returns an error afterward, the
+    /// contents of the texture are incomplete. Returns `None`, without rendering anything, if
+    /// the device runs out of memory allocating the texture.
+    pub fn render_scene_to_texture(&mut self, commands: &[RenderCommand], size: Vector2I)
+                                   -> Option<D::Texture> {
+        let texture = self.core.device.try_create_texture(TextureFormat::RGBA8, size)?;
+        let framebuffer = self.core.device.create_framebuffer(texture);
+        let framebuffer = self.render_offscreen(framebuffer, |renderer| {
+            renderer.begin_scene();
+            for command in commands {
+                renderer.render_command(command);
+            }
+            renderer.end_scene();
+        });
+        Some(self.core.device.destroy_framebuffer(framebuffer))
+    }
+
+    // Calls `render` with `framebuffer` as the destination, on a transparent background and
+    // without the debug UI. The rest of the options are left as they are.
+    fn render_offscreen<F>(&mut self, framebuffer: D::Framebuffer, render: F) -> D::Framebuffer
+                           where F: FnOnce(&mut Self) {
+        let old_dest = mem::replace(&mut self.core.options.dest,
+                                    DestFramebuffer::Other(framebuffer));
+        let old_background_color = mem::replace(&mut self.core.options.background_color,
+                                                Some(ColorF::transparent_black()));
+        let old_show_debug_ui = mem::replace(&mut self.core.options.show_debug_ui, false);
+
+        render(self);
+
+        self.core.options.show_debug_ui = old_show_debug_ui;
+        self.core.options.background_color = old_background_color;
+        match mem::replace(&mut self.core.options.dest, old_dest) {
+            DestFramebuffer::Other(framebuffer) => framebuffer,
+            DestFramebuffer::Default { .. } => unreachable!(),
+        }
+    }
+
+    /// Clears a rectangular region of the current render target to the given color, leaving the
+    /// rest of its contents intact.
+    ///
+    /// The current render target is the one most recently pushed by a `PushRenderTarget`
+    /// command, or the destination framebuffer if none is active. `rect` is relative to the
+    /// origin of that target and is clipped to its bounds. `color` is in straight alpha; it is
+    /// premultiplied before being written.
+    ///
+    /// This must be called between `begin_scene()` and `end_scene()`. If the target has not yet
+    /// been drawn to in this scene, the clear it would normally receive is performed first, so
+    /// the contents outside `rect` remain well-defined.
+    pub fn clear_region(&mut self, rect: RectI, color: ColorF) {
+        if self.core.render_error.is_some() {
+            return;
+        }
+
+        let target_rect = RectI::new(Vector2I::default(), self.core.draw_viewport().size());
+        if let Some(pending_clear_color) = self.core.clear_color_for_draw_operation() {
+            self.draw_clear_rect(target_rect, pending_clear_color);
+        }
+        if let Some(rect) = rect.intersection(target_rect) {
+            self.draw_clear_rect(rect, color);
+        }
+
+        self.core.preserve_draw_framebuffer();
+    }
+
+    fn draw_clear_rect(&mut self, rect: RectI, color: ColorF) {
+        let draw_viewport = self.core.draw_viewport();
+        let uniforms = [
+            (&self.clear_program.rect_uniform, UniformData::Vec4(rect.to_f32().0)),
+            (&self.clear_program.framebuffer_size_uniform,
+             UniformData::Vec2(draw_viewport.size().to_f32().0)),
+            (&self.clear_program.color_uniform, UniformData::Vec4(color.0)),
+        ];
+
+        self.core.device.draw_elements(6, &RenderState {
+            target: &self.core.draw_render_target(),
+            program: &self.clear_program.program,
+            vertex_array: &self.frame.clear_vertex_array.vertex_array,
+            primitive: Primitive::Triangles,
+            textures: &[],
+            images: &[],
+            storage_buffers: &[],
+            uniforms: &uniforms[..],
+            viewport: draw_viewport,
+            options: RenderOptions::default(),
+        });
+
+        self.core.stats.drawcall_count += 1;
+    }
+
+    // Meshes are drawn directly as triangles, without going through tiling, so they aren't
+    // antialiased.
+    fn draw_mesh(&mut self, batch: &DrawMeshBatch) {
+        if batch.indices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer_id = self.core
+                                   .allocator
+                                   .allocate_general_buffer_with_mode::<MeshVertex>(
+                                       &self.core.device,
+                                       batch.vertices.len() as u64,
+                                       BufferUploadMode::Stream,
+                                       BufferTag("MeshVertex"));
+        let index_buffer_id =
+            self.core.allocator.allocate_index_buffer::<u32>(&self.core.device,
+                                                             batch.indices.len() as u64,
+                                                             BufferTag("MeshIndex"));
+        let vertex_buffer = self.core.allocator.get_general_buffer(vertex_buffer_id);
+        let index_buffer = self.core.allocator.get_index_buffer(index_buffer_id);
+        self.core.device.upload_to_buffer(vertex_buffer, 0, &batch.vertices, BufferTarget::Vertex);
+        self.core.device.upload_to_buffer(index_buffer, 0, &batch.indices, BufferTarget::Index);
+
+        let vertex_array = MeshVertexArray::new(&self.core.device,
+                                                &self.mesh_program,
+                                                vertex_buffer,
+                                                index_buffer);
+
+        let clear_color = self.core.clear_color_for_draw_operation();
+        let draw_viewport = self.core.draw_viewport();
+        let uniforms = [
+            (&self.mesh_program.framebuffer_size_uniform,
+             UniformData::Vec2(draw_viewport.size().to_f32().0)),
+        ];
+
+        self.core.device.draw_elements(batch.indices.len() as u32, &RenderState {
+            target: &self.core.draw_render_target(),
+            program: &self.mesh_program.program,
+            vertex_array: &vertex_array.vertex_array,
+            primitive: Primitive::Triangles,
+            textures: &[],
+            images: &[],
+            storage_buffers: &[],
+            uniforms: &uniforms[..],
+            viewport: draw_viewport,
+            options: RenderOptions {
+                blend: BlendMode::SrcOver.to_blend_state(),
+                clear_ops: ClearOps { color: clear_color, ..ClearOps::default() },
+                ..RenderOptions::default()
+            },
+        });
+
+        self.core.stats.drawcall_count += 1;
+        self.core.preserve_draw_framebuffer();
+
+        self.core.allocator.free_general_buffer(vertex_buffer_id);
+        self.core.allocator.free_index_buffer(index_buffer_id);
+    }
+
+    /// Renders the coverage of a single path of `scene` in isolation and reads it back.
+    ///
+    /// The path is filled with opaque white, without clipping or blending, into an off-screen
+    /// framebuffer covering the tiles that the path touches, so the result reflects only the
+    /// coverage that the tiler computed. Returns the tile-aligned bounds of the path in scene
+    /// coordinates along with one coverage value per pixel within those bounds, in row-major
+    /// order. A path whose outline has no area covers nothing, so it yields empty bounds and no
+    /// coverage values, and nothing is rendered.
+    ///
+    /// This must not be called between `begin_scene()` and `end_scene()`.
+    #[cfg(feature = "debug")]
+    pub fn debug_path_coverage(&mut self, scene: &Scene, draw_path_id: DrawPathId)
+                               -> (RectI, Vec<u8>) {
+        let draw_path = scene.get_draw_path(draw_path_id);
+        let tile_size = vec2f(TILE_WIDTH as f32, TILE_HEIGHT as f32);
+        let path_bounds = draw_path.outline().bounds();
+        if path_bounds.width() <= 0.0 || path_bounds.height() <= 0.0 {
+            return (RectI::default(), vec![]);
+        }
+
+        let tile_origin = (path_bounds.origin() / tile_size).floor() * tile_size;
+        let tile_lower_right = (path_bounds.lower_right() / tile_size).ceil() * tile_size;
+        let tile_bounds = RectF::from_points(tile_origin, tile_lower_right).to_i32();
+
+        let translation = Transform2F::from_translation(-tile_bounds.origin().to_f32());
+        let outline = draw_path.outline().clone().transformed(&translation);
+        let view_box = RectF::new(Vector2F::zero(), tile_bounds.size().to_f32());
+        let paint = Paint::from_color(ColorU::white());
+        let mut coverage_scene =
+            Scene::from_paths(view_box, Some((outline, paint, draw_path.fill_rule())));
+
+        let texture = self.core.device.create_texture(TextureFormat::RGBA8, tile_bounds.size());
+        let framebuffer = self.core.device.create_framebuffer(texture);
+        let framebuffer = self.render_offscreen(framebuffer, |renderer| {
+            // The coverage is read back in scene coordinates, so it must not be transformed.
+            let output_transform = mem::replace(&mut renderer.core.options.output_transform,
+                                                Transform2F::default());
+            coverage_scene.build_and_render(renderer, BuildOptions::default(), SequentialExecutor);
+            renderer.core.options.output_transform = output_transform;
+        });
+        let viewport = RectI::new(Vector2I::default(), tile_bounds.size());
+        let receiver = self.core.device.read_pixels(&RenderTarget::Framebuffer(&framebuffer),
+                                                    viewport);
+        let coverage = match self.core.device.recv_texture_data(&receiver) {
+            TextureData::U8(pixels) => pixels.chunks(4).map(|pixel| pixel[3]).collect(),
+            _ => panic!("Unexpected pixel format for RGBA8 framebuffer!"),
+        };
+        (tile_bounds, coverage)
+    }
+
+    /// Reads back the color of a single pixel of the rendered output.
+    ///
+    /// `point` is in device pixels relative to the upper left corner of the destination viewport.
+    /// This reads from the destination framebuffer, so it should be called after `end_scene()`,
+    /// once any intermediate framebuffer has been resolved. The color is returned as stored,
+    /// which is premultiplied unless `premultiplied_output` is false. Only one pixel is
+    /// transferred, but the call still blocks until the GPU has finished rendering.
+    pub fn read_pixel(&self, point: Vector2I) -> ColorU {
+        let main_viewport = self.core.main_viewport();
+        let mut origin = main_viewport.origin() + point;
+        if self.core.device.framebuffer_origin_is_lower_left() {
+            origin = vec2i(origin.x(), main_viewport.max_y() - 1 - point.y());
+        }
+
+        let render_target = self.core.dest_render_target();
+        let receiver = self.core.device.read_pixels(&render_target,
+                                                    RectI::new(origin, vec2i(1, 1)));
+        match self.core.device.recv_texture_data(&receiver) {
+            TextureData::U8(pixel) => ColorU::new(pixel[0], pixel[1], pixel[2], pixel[3]),
+            TextureData::F16(pixel) => {
+                ColorF::new(pixel[0].to_f32(), pixel[1].to_f32(), pixel[2].to_f32(),
+                            pixel[3].to_f32()).to_u8()
+            }
+            TextureData::F32(pixel) => ColorF::new(pixel[0], pixel[1], pixel[2], pixel[3]).to_u8(),
+            TextureData::U16(pixel) => {
+                ColorU::new(unorm16_to_u8(pixel[0]),
+                            unorm16_to_u8(pixel[1]),
+                            unorm16_to_u8(pixel[2]),
+                            unorm16_to_u8(pixel[3]))
+            }
+        }
+    }
+
+    /// Reads back a rectangle of the rendered output, in the format of the destination
+    /// framebuffer.
+    ///
+    /// `rect` is in device pixels relative to the upper left corner of the destination viewport,
+    /// and is clamped to the viewport; the returned pixels cover the clamped rectangle, which is
+    /// empty if `rect` lies entirely outside. As with `read_pixel()`, this reads from the
+    /// destination framebuffer, so it should be called after `end_scene()`, once any intermediate
+    /// framebuffer has been resolved. The pixels are returned as stored: rows run bottom to top
+    /// if the device's framebuffer origin is at the lower left, and colors are premultiplied
+    /// unless `premultiplied_output` is false.
+    ///
+    /// This forces a GPU sync: the call blocks until the GPU has finished rendering.
+    pub fn snapshot(&self, rect: RectI) -> TextureData {
+        let read_rect = snapshot_read_rect(self.core.main_viewport(),
+                                           rect,
+                                           self.core.device.framebuffer_origin_is_lower_left());
+        let render_target = self.core.dest_render_target();
+        let receiver = self.core.device.read_pixels(&render_target, read_rect);
+        self.core.device.recv_texture_data(&receiver)
+    }
+
+    /// Reads back the whole destination viewport as straight-alpha RGBA8 pixels, in row-major
+    /// order from top to bottom.
+    ///
+    /// As with `read_pixel()`, this should be called after `end_scene()`, and it blocks until the
+    /// GPU has finished rendering. Rows are flipped if the device stores them bottom to top, and
+    /// premultiplied output is converted to straight alpha.
+    ///
+    /// The pixels are those of the destination, so they reflect `output_transform`: a rotated or
+    /// scaled output reads back rotated or scaled, and whatever the transform moved outside the
+    /// viewport is missing. The inverse transform isn't applied, because resampling the output
+    /// would not reproduce the untransformed rendering exactly.
+    pub fn read_output_rgba8(&self) -> Vec<u8> {
+        let main_viewport = self.core.main_viewport();
+        let mut pixels = match self.snapshot(RectI::new(Vector2I::default(),
+                                                        main_viewport.size())) {
+            TextureData::U8(pixels) => pixels,
+            TextureData::F16(pixels) => {
+                pixels.iter().map(|channel| unit_float_to_u8(channel.to_f32())).collect()
+            }
+            TextureData::F32(pixels) => {
+                pixels.iter().map(|&channel| unit_float_to_u8(channel)).collect()
+            }
+            TextureData::U16(pixels) => {
+                pixels.iter().map(|&channel| unorm16_to_u8(channel)).collect()
+            }
+        };
+
+        if self.core.device.framebuffer_origin_is_lower_left() {
+            let stride = main_viewport.width() as usize * 4;
+            pixels = pixels.chunks(stride).rev().flatten().cloned().collect();
+        }
+
+        if self.core.options.premultiplied_output {
+            for pixel in pixels.chunks_mut(4) {
+                let alpha = pixel[3] as u32;
+                if alpha == 0 {
+                    continue;
+                }
+                for channel in &mut pixel[0..3] {
+                    *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+                }
+            }
+        }
+
+        pixels
+    }
+
+    /// Reads back the destination viewport and encodes it as a PNG image.
+    ///
+    /// See `read_output_rgba8()` for when this may be called. This is available with the `png`
+    /// feature and is suitable for platforms without a filesystem, such as the Web.
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self) -> io::Result<Vec<u8>> {
+        let size = self.core.main_viewport().size();
+        let pixels = self.read_output_rgba8();
+        let mut png = vec![];
+        PngEncoder::new(&mut png).encode(&pixels,
+                                         size.x() as u32,
+                                         size.y() as u32,
+                                         ColorType::Rgba8)
+                                 .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(png)
+    }
+
+    /// Reads back the destination viewport and writes it to a PNG file at `path`.
+    ///
+    /// See `read_output_rgba8()` for when this may be called. This is available with the `png`
+    /// feature.
+    #[cfg(all(feature = "png", not(target_arch = "wasm32")))]
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.encode_png()?)
+    }
+
+    /// Returns the error that caused the current scene to be abandoned, if any.
+    ///
+    /// This should be checked after `end_scene()`. If this returns an error, the output is
+    /// incomplete; the caller may want to reduce the size or complexity of the scene and render it
+    /// again.
+    #[inline]
+    pub fn render_error(&self) -> Option<RenderError> {
+        self.core.render_error
+    }
+
     /// Returns GPU timing information for the last frame, if present.
     pub fn last_rendering_time(&self) -> Option<RenderTime> {
         self.last_rendering_time
     }
 
+    /// Returns the GPU time spent in each kind of render command during the last frame whose
+    /// timings are available, keyed by `RenderCommand::name()`.
+    ///
+    /// This is empty unless `RendererOptions::time_render_commands` is set. Like
+    /// `last_rendering_time()`, it lags behind the current frame while the GPU catches up. Work
+    /// done outside `render_command()`, such as the final blit in `end_scene()`, isn't included.
+    #[inline]
+    pub fn last_render_command_times(&self) -> &HashMap<&'static str, Duration> {
+        &self.last_render_command_times
+    }
+
     /// Returns a reference to the GPU device.
     /// 
     /// This can be useful to issue GPU commands manually via the low-level `pathfinder_gpu`
@@ -568,6 +1075,11 @@ impl<D> Renderer<D> where D: Device {
     }
 
     /// Turns on Pathfinder's use of the depth buffer.
+    ///
+    /// The depth-stencil attachment of the destination framebuffer is used, so it must have at
+    /// least 8 bits of stencil. When rendering goes through an intermediate framebuffer (see
+    /// `RendererOptions::output_transform`), that framebuffer has no depth-stencil attachment and
+    /// the stencil test has no effect.
     #[inline]
     #[deprecated]
     pub fn enable_depth(&mut self) {
@@ -580,6 +1092,76 @@ impl<D> Renderer<D> where D: Device {
         &self.core.stats
     }
 
+    /// Returns the statistics accumulated so far for the scene being rendered.
+    ///
+    /// This can be called between `render_command()` calls, so that apps can abort or simplify
+    /// scenes that exceed a budget before the frame is finished. The path count is known once the
+    /// `Start` command has been processed. The tiles of each batch are counted as soon as its
+    /// command arrives, before the batch is drawn, and D3D9 fills as they're added, so a scene that
+    /// goes over budget can be abandoned before its remaining batches are drawn. After the last
+    /// tile batch, the counts match the final ones. D3D11 fills are generated on the GPU, so they
+    /// are counted only once the GPU has produced them. The GPU memory figures reflect the
+    /// allocations made so far.
+    #[inline]
+    pub fn estimated_stats(&self) -> RenderStats {
+        self.stats_with_gpu_memory()
+    }
+
+    fn stats_with_gpu_memory(&self) -> RenderStats {
+        RenderStats {
+            gpu_bytes_allocated: self.core.allocator.bytes_allocated(),
+            gpu_bytes_committed: self.core.allocator.bytes_committed(),
+            gpu_bytes_peak: self.core.allocator.peak_bytes_allocated(),
+            gpu_reuse_hits: self.core.allocator.reuse_hits(),
+            gpu_reuse_misses: self.core.allocator.reuse_misses(),
+            ..self.core.stats
+        }
+    }
+
+    /// Returns the number of bytes of VRAM Pathfinder has allocated since the renderer was created
+    /// or since the last call to `reset_total_gpu_bytes_allocated()`.
+    ///
+    /// Unlike `RenderStats::gpu_bytes_allocated`, this is cumulative: it only counts allocations of
+    /// new GPU objects, never decreases when objects are freed or purged, and does not count reuse
+    /// of cached objects. It is unaffected by the per-frame statistics.
+    #[inline]
+    pub fn total_gpu_bytes_allocated(&self) -> u64 {
+        self.core.allocator.total_bytes_allocated()
+    }
+
+    /// Resets the counter returned by `total_gpu_bytes_allocated()` to zero.
+    #[inline]
+    pub fn reset_total_gpu_bytes_allocated(&mut self) {
+        self.core.allocator.reset_total_bytes_allocated()
+    }
+
+    /// Returns the largest number of bytes of VRAM Pathfinder has held at once since the renderer
+    /// was created or since the last call to `reset_peak_gpu_bytes_allocated()`.
+    ///
+    /// Objects cached for reuse are included, because they occupy VRAM until they're purged. This
+    /// is the figure to watch when tuning for memory-constrained devices.
+    #[inline]
+    pub fn peak_gpu_bytes_allocated(&self) -> u64 {
+        self.core.allocator.peak_bytes_allocated()
+    }
+
+    /// Resets the value returned by `peak_gpu_bytes_allocated()` to the number of bytes of VRAM
+    /// currently held.
+    #[inline]
+    pub fn reset_peak_gpu_bytes_allocated(&mut self) {
+        self.core.allocator.reset_peak_bytes_allocated()
+    }
+
+    /// Returns the number of bytes of VRAM currently held by Pathfinder, including objects cached
+    /// for reuse.
+    ///
+    /// This reflects live allocations at the time of the call, rather than at the end of the last
+    /// frame as `RenderStats::gpu_bytes_allocated` does.
+    #[inline]
+    pub fn current_gpu_bytes_resident(&self) -> u64 {
+        self.core.allocator.bytes_allocated()
+    }
+
     /// Returns a GPU-side vertex buffer containing 2D vertices of a unit square.
     /// 
     /// This can be handy for custom rendering.
@@ -599,7 +1181,8 @@ impl<D> Renderer<D> where D: Device {
 
     fn allocate_pattern_texture_page(&mut self,
                                      page_id: TexturePageId,
-                                     descriptor: &TexturePageDescriptor) {
+                                     descriptor: &TexturePageDescriptor)
+                                     -> Result<(), RenderError> {
         // Fill in IDs up to the requested page ID.
         let page_index = page_id.0 as usize;
         while self.core.pattern_texture_pages.len() < page_index + 1 {
@@ -615,14 +1198,16 @@ impl<D> Renderer<D> where D: Device {
         let texture_size = descriptor.size;
         let framebuffer_id = self.core
                                  .allocator
-                                 .allocate_framebuffer(&self.core.device,
-                                                       texture_size,
-                                                       TextureFormat::RGBA8,
-                                                       FramebufferTag("PatternPage"));
+                                 .try_allocate_framebuffer(&self.core.device,
+                                                           texture_size,
+                                                           TextureFormat::RGBA8,
+                                                           FramebufferTag("PatternPage"))
+                                 .ok_or(RenderError::OutOfMemory)?;
         self.core.pattern_texture_pages[page_index] = Some(PatternTexturePage {
             framebuffer_id,
             must_preserve_contents: false,
         });
+        Ok(())
     }
 
     fn upload_texel_data(&mut self, texels: &[ColorU], location: TextureLocation) {
@@ -659,9 +1244,12 @@ impl<D> Renderer<D> where D: Device {
         let mut texels = Vec::with_capacity(padded_texel_size);
         for entry in metadata {
             let base_color = entry.base_color.to_f32();
-            let filter_params = self.compute_filter_params(&entry.filter,
-                                                           entry.blend_mode,
-                                                           entry.color_0_combine_mode);
+            let dither = entry.color_0_is_gradient && self.core.options.dither_gradients;
+            let filter_params = compute_filter_params(&entry.filter,
+                                                      entry.blend_mode,
+                                                      entry.color_0_combine_mode,
+                                                      self.core.options.text_gamma_correction,
+                                                      dither);
             texels.extend_from_slice(&[
                 // 0
                 f16::from_f32(entry.color_0_transform.m11()),
@@ -671,8 +1259,8 @@ impl<D> Renderer<D> where D: Device {
                 // 1
                 f16::from_f32(entry.color_0_transform.m13()),
                 f16::from_f32(entry.color_0_transform.m23()),
-                f16::default(),
-                f16::default(),
+                f16::from_f32(entry.color_1_transform.m13()),
+                f16::from_f32(entry.color_1_transform.m23()),
                 // 2
                 f16::from_f32(base_color.r()),
                 f16::from_f32(base_color.g()),
@@ -705,14 +1293,14 @@ impl<D> Renderer<D> where D: Device {
                 f16::from_f32(filter_params.p4.w()),
                 // 8
                 f16::from_f32(filter_params.ctrl as f32),
-                f16::default(),
+                f16::from_f32(entry.color_1_combine_mode.to_composite_ctrl() as f32),
                 f16::default(),
                 f16::default(),
                 // 9
-                f16::default(),
-                f16::default(),
-                f16::default(),
-                f16::default(),
+                f16::from_f32(entry.color_1_transform.m11()),
+                f16::from_f32(entry.color_1_transform.m21()),
+                f16::from_f32(entry.color_1_transform.m12()),
+                f16::from_f32(entry.color_1_transform.m22()),
             ]);
         }
         while texels.len() < padded_texel_size {
@@ -819,7 +1407,9 @@ impl<D> Renderer<D> where D: Device {
     fn clear_dest_framebuffer_if_necessary(&mut self) {
         let background_color = match self.core.options.background_color {
             None => return,
-            Some(background_color) => background_color,
+            Some(background_color) => {
+                clear_shader_color(background_color, self.core.options.premultiplied_output)
+            }
         };
 
         if self.core.framebuffer_flags.contains(FramebufferFlags::DEST_FRAMEBUFFER_IS_DIRTY) {
@@ -857,6 +1447,11 @@ impl<D> Renderer<D> where D: Device {
             return;
         }
 
+        // If nothing was drawn, the intermediate framebuffer holds nothing from this frame.
+        if !self.core.framebuffer_flags.contains(FramebufferFlags::DEST_FRAMEBUFFER_IS_DIRTY) {
+            return;
+        }
+
         let main_viewport = self.core.main_viewport();
 
         if self.core.intermediate_dest_framebuffer_size != main_viewport.size() {
@@ -872,33 +1467,69 @@ impl<D> Renderer<D> where D: Device {
         let intermediate_dest_framebuffer =
             self.core.allocator.get_framebuffer(self.core.intermediate_dest_framebuffer_id);
 
+        let (blit_program, blit_vertex_array) = if self.core.options.premultiplied_output {
+            (&self.blit_program, &self.frame.blit_vertex_array)
+        } else {
+            (&self.blit_unpremultiply_program, &self.frame.blit_unpremultiply_vertex_array)
+        };
+
+        let dest_render_target = match self.core.options.dest {
+            DestFramebuffer::Default { .. } => RenderTarget::Default,
+            DestFramebuffer::Other(ref framebuffer) => RenderTarget::Framebuffer(framebuffer),
+        };
+
         let textures = [
-            (&self.blit_program.src_texture,
+            (&blit_program.src_texture,
              self.core.device.framebuffer_texture(intermediate_dest_framebuffer))
         ];
 
+        // A transformed output is drawn as a quad anywhere in the destination, so the rest of the
+        // destination must be preserved.
+        let output_transform = self.core.options.output_transform;
+        let (blit_transform, blit_viewport) = if output_transform.is_identity() {
+            (Transform2F::default(), main_viewport)
+        } else {
+            let dest_size = self.core.options.dest.window_size(&self.core.device);
+            (self.core.output_blit_transform(main_viewport, dest_size),
+             RectI::new(Vector2I::zero(), dest_size))
+        };
+
+        // With a background color, the intermediate framebuffer was cleared to it, so the output
+        // replaces the destination. Without one, the intermediate framebuffer was cleared to
+        // transparent, and the output is composited over what the destination already contains.
+        let blend = match (self.core.options.background_color,
+                           self.core.options.premultiplied_output) {
+            (Some(_), _) => None,
+            (None, true) => BlendMode::SrcOver.to_blend_state(),
+            (None, false) => {
+                Some(BlendState {
+                    src_rgb_factor: BlendFactor::SrcAlpha,
+                    dest_rgb_factor: BlendFactor::OneMinusSrcAlpha,
+                    src_alpha_factor: BlendFactor::One,
+                    dest_alpha_factor: BlendFactor::OneMinusSrcAlpha,
+                    ..BlendState::default()
+                })
+            }
+        };
+
         self.core.device.draw_elements(6, &RenderState {
-            target: &RenderTarget::Default,
-            program: &self.blit_program.program,
-            vertex_array: &self.frame.blit_vertex_array.vertex_array,
+            target: &dest_render_target,
+            program: &blit_program.program,
+            vertex_array: &blit_vertex_array.vertex_array,
             primitive: Primitive::Triangles,
             textures: &textures[..],
             images: &[],
             storage_buffers: &[],
             uniforms: &[
-                (&self.blit_program.framebuffer_size_uniform,
-                 UniformData::Vec2(main_viewport.size().to_f32().0)),
-                (&self.blit_program.dest_rect_uniform,
+                (&blit_program.framebuffer_size_uniform,
+                 UniformData::Vec2(blit_viewport.size().to_f32().0)),
+                (&blit_program.dest_rect_uniform,
                  UniformData::Vec4(RectF::new(Vector2F::zero(), main_viewport.size().to_f32()).0)),
+                (&blit_program.transform_uniform, UniformData::Mat2(blit_transform.matrix.0)),
+                (&blit_program.translation_uniform, UniformData::Vec2(blit_transform.vector.0)),
             ],
-            viewport: main_viewport,
-            options: RenderOptions {
-                clear_ops: ClearOps {
-                    color: Some(ColorF::new(0.0, 0.0, 0.0, 1.0)),
-                    ..ClearOps::default()
-                },
-                ..RenderOptions::default()
-            },
+            viewport: blit_viewport,
+            options: RenderOptions { blend, ..RenderOptions::default() },
         });
 
         self.core.stats.drawcall_count += 1;
@@ -916,105 +1547,44 @@ impl<D> Renderer<D> where D: Device {
     pub fn draw_render_target(&self) -> RenderTarget<D> {
         self.core.draw_render_target()
     }
-
-    fn compute_filter_params(&self,
-                             filter: &Filter,
-                             blend_mode: BlendMode,
-                             color_0_combine_mode: ColorCombineMode)
-                             -> FilterParams {
-        let mut ctrl = 0;
-        ctrl |= blend_mode.to_composite_ctrl() << COMBINER_CTRL_COMPOSITE_SHIFT;
-        ctrl |= color_0_combine_mode.to_composite_ctrl() << COMBINER_CTRL_COLOR_COMBINE_SHIFT;
-
-        match *filter {
-            Filter::RadialGradient { line, radii, uv_origin } => {
-                FilterParams {
-                    p0: line.from().0.concat_xy_xy(line.vector().0),
-                    p1: radii.concat_xy_xy(uv_origin.0),
-                    p2: F32x4::default(),
-                    p3: F32x4::default(),
-                    p4: F32x4::default(),
-                    ctrl: ctrl | (COMBINER_CTRL_FILTER_RADIAL_GRADIENT <<
-                                  COMBINER_CTRL_COLOR_FILTER_SHIFT)
-                }
-            }
-            Filter::PatternFilter(PatternFilter::Blur { sigma, direction }) => {
-                let sigma_inv = 1.0 / sigma;
-                let gauss_coeff_x = SQRT_2_PI_INV * sigma_inv;
-                let gauss_coeff_y = f32::exp(-0.5 * sigma_inv * sigma_inv);
-                let gauss_coeff_z = gauss_coeff_y * gauss_coeff_y;
-
-                let src_offset = match direction {
-                    BlurDirection::X => vec2f(1.0, 0.0),
-                    BlurDirection::Y => vec2f(0.0, 1.0),
-                };
-
-                let support = f32::ceil(1.5 * sigma) * 2.0;
-
-                FilterParams {
-                    p0: src_offset.0.concat_xy_xy(F32x2::new(support, 0.0)),
-                    p1: F32x4::new(gauss_coeff_x, gauss_coeff_y, gauss_coeff_z, 0.0),
-                    p2: F32x4::default(),
-                    p3: F32x4::default(),
-                    p4: F32x4::default(),
-                    ctrl: ctrl | (COMBINER_CTRL_FILTER_BLUR << COMBINER_CTRL_COLOR_FILTER_SHIFT),
-                }
-            }
-            Filter::PatternFilter(PatternFilter::Text { 
-                fg_color,
-                bg_color,
-                defringing_kernel,
-                gamma_correction,
-            }) => {
-                let mut p2 = fg_color.0;
-                p2.set_w(gamma_correction as i32 as f32);
-
-                FilterParams {
-                    p0: match defringing_kernel {
-                        Some(ref kernel) => F32x4::from_slice(&kernel.0),
-                        None => F32x4::default(),
-                    },
-                    p1: bg_color.0,
-                    p2,
-                    p3: F32x4::default(),
-                    p4: F32x4::default(),
-                    ctrl: ctrl | (COMBINER_CTRL_FILTER_TEXT << COMBINER_CTRL_COLOR_FILTER_SHIFT),
-                }
-            }
-            Filter::PatternFilter(PatternFilter::ColorMatrix(matrix)) => {
-                let [p0, p1, p2, p3, p4] = matrix.0;
-                FilterParams {
-                    p0, p1, p2, p3, p4,
-                    ctrl: ctrl | (COMBINER_CTRL_FILTER_COLOR_MATRIX << COMBINER_CTRL_COLOR_FILTER_SHIFT),
-                }
-            }
-            Filter::None => {
-                FilterParams {
-                    p0: F32x4::default(),
-                    p1: F32x4::default(),
-                    p2: F32x4::default(),
-                    p3: F32x4::default(),
-                    p4: F32x4::default(),
-                    ctrl,
-                }
-            }
-        }
-    }
 }
 
 impl<D> RendererCore<D> where D: Device {
     pub(crate) fn mask_texture_format(&self) -> TextureFormat {
-        match self.mode.level {
-            RendererLevel::D3D9 => TextureFormat::RGBA16F,
-            RendererLevel::D3D11 => TextureFormat::RGBA8,
-        }
+        self.mask_texture_format
     }
 
-    pub(crate) fn reallocate_alpha_tile_pages_if_necessary(&mut self, copy_existing: bool) {
+    // Returns the number of alpha tiles that the mask framebuffer can hold at most, given the
+    // device's maximum texture size and the caller's limit on its pages.
+    pub(crate) fn max_alpha_tile_count(&self) -> u32 {
+        max_mask_pages(self.max_texture_size, self.options.max_mask_pages) << 16
+    }
+
+    // Grows the mask framebuffer to hold `alpha_tile_count` alpha tiles. Returns false, and
+    // reports `RenderError::TextureTooLarge` or `RenderError::OutOfMemory`, if it can't be made
+    // that large or the device couldn't allocate it, in which case the old mask framebuffer, if
+    // any, is kept.
+    pub(crate) fn reallocate_alpha_tile_pages_if_necessary(&mut self, copy_existing: bool)
+                                                          -> bool {
         let alpha_tile_pages_needed = ((self.alpha_tile_count + 0xffff) >> 16) as u32;
+
+        // Don't grow the mask framebuffer beyond what the device supports or the caller allows.
+        // The renderers recycle mask pages once they're full, so this only happens if a single
+        // batch needs more alpha tiles than the mask framebuffer can hold, or, at the D3D9 level
+        // without a page limit, if the scene's masks exceed the device's maximum texture size.
+        // Rather than drop the tiles that don't fit, abandon the scene.
+        let max_alpha_tile_pages = self.max_alpha_tile_count() >> 16;
+        if alpha_tile_pages_needed > max_alpha_tile_pages {
+            warn!("{} alpha tile pages needed, but only {} are allowed",
+                  alpha_tile_pages_needed,
+                  max_alpha_tile_pages);
+            self.render_error = Some(RenderError::TextureTooLarge);
+            return false;
+        }
+
         if let Some(ref mask_storage) = self.mask_storage {
             if alpha_tile_pages_needed <= mask_storage.allocated_page_count {
-                return;
+                return true;
             }
         }
 
@@ -1022,10 +1592,16 @@ impl<D> RendererCore<D> where D: Device {
                              MASK_FRAMEBUFFER_HEIGHT * alpha_tile_pages_needed as i32);
         let format = self.mask_texture_format();
         let mask_framebuffer_id =
-            self.allocator.allocate_framebuffer(&self.device,
-                                                new_size,
-                                                format,
-                                                FramebufferTag("TileAlphaMask"));
+            match self.allocator.try_allocate_framebuffer(&self.device,
+                                                          new_size,
+                                                          format,
+                                                          FramebufferTag("TileAlphaMask")) {
+                Some(mask_framebuffer_id) => mask_framebuffer_id,
+                None => {
+                    self.render_error = Some(RenderError::OutOfMemory);
+                    return false;
+                }
+            };
         let mask_framebuffer = self.allocator.get_framebuffer(mask_framebuffer_id);
         let old_mask_storage = self.mask_storage.take();
         self.mask_storage = Some(MaskStorage {
@@ -1036,7 +1612,7 @@ impl<D> RendererCore<D> where D: Device {
         // Copy over existing content if needed.
         let old_mask_framebuffer_id = match old_mask_storage {
             Some(old_storage) if copy_existing => old_storage.framebuffer_id,
-            Some(_) | None => return,
+            Some(_) | None => return true,
         };
         let old_mask_framebuffer = self.allocator.get_framebuffer(old_mask_framebuffer_id);
         let old_mask_texture = self.device.framebuffer_texture(old_mask_framebuffer);
@@ -1058,6 +1634,10 @@ impl<D> RendererCore<D> where D: Device {
                  UniformData::Vec2(new_size.to_f32().0)),
                 (&self.programs.blit_program.dest_rect_uniform,
                  UniformData::Vec4(RectF::new(Vector2F::zero(), old_size.to_f32()).0)),
+                (&self.programs.blit_program.transform_uniform,
+                 UniformData::Mat2(Transform2F::default().matrix.0)),
+                (&self.programs.blit_program.translation_uniform,
+                 UniformData::Vec2(Vector2F::zero().0)),
             ],
             viewport: RectI::new(Vector2I::default(), new_size),
             options: RenderOptions {
@@ -1072,6 +1652,7 @@ impl<D> RendererCore<D> where D: Device {
         self.stats.drawcall_count += 1;
         self.finish_timing_draw_call(&timer_query);
         self.current_timer.as_mut().unwrap().push_query(TimeCategory::Other, timer_query);
+        true
     }
 
     pub(crate) fn set_uniforms_for_drawing_tiles<'a>(
@@ -1079,17 +1660,25 @@ impl<D> RendererCore<D> where D: Device {
             tile_program: &'a TileProgramCommon<D>,
             textures: &mut Vec<TextureBinding<'a, D::TextureParameter, D::Texture>>,
             uniforms: &mut Vec<UniformBinding<'a, D::Uniform>>,
-            color_texture_0: Option<TileBatchTexture>) {
+            color_texture_0: Option<TileBatchTexture>,
+            color_texture_1: Option<TileBatchTexture>) {
         let draw_viewport = self.draw_viewport();
 
-        let gamma_lut_texture = self.allocator.get_texture(self.gamma_lut_texture_id);
-            self.allocator.get_texture(self.texture_metadata_texture_id);
-        textures.push((&tile_program.gamma_lut_texture, gamma_lut_texture));
-
         let texture_metadata_texture =
             self.allocator.get_texture(self.texture_metadata_texture_id);
         textures.push((&tile_program.texture_metadata_texture, texture_metadata_texture));
 
+        // Without gamma correction the lookup table is never sampled, but the binding can't be
+        // skipped. The tile shaders declare the gamma LUT sampler regardless, so its slot is taken
+        // either way, and Metal's validation layer rejects draws that leave a declared texture
+        // unbound. Attach a texture that's bound anyway instead, so that the LUT needn't be.
+        let gamma_lut_texture = if self.options.text_gamma_correction {
+            self.allocator.get_texture(self.gamma_lut_texture_id)
+        } else {
+            texture_metadata_texture
+        };
+        textures.push((&tile_program.gamma_lut_texture, gamma_lut_texture));
+
         uniforms.push((&tile_program.tile_size_uniform,
                        UniformData::Vec2(F32x2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32))));
         uniforms.push((&tile_program.framebuffer_size_uniform,
@@ -1124,6 +1713,20 @@ impl<D> RendererCore<D> where D: Device {
                                UniformData::Vec2(F32x2::default())));
             }
         }
+
+        // The second color texture is sampled without a filter, so it needs no size.
+        match color_texture_1 {
+            Some(color_texture) => {
+                let color_texture_page = self.texture_page(color_texture.page);
+                self.device.set_texture_sampling_mode(color_texture_page,
+                                                      color_texture.sampling_flags);
+                textures.push((&tile_program.color_texture_1, color_texture_page));
+            }
+            None => {
+                let placeholder_texture = self.allocator.get_texture(self.placeholder_texture_id);
+                textures.push((&tile_program.color_texture_1, placeholder_texture));
+            }
+        }
     }
 
     // Pattern textures
@@ -1157,7 +1760,15 @@ impl<D> RendererCore<D> where D: Device {
         if must_preserve_contents {
             None
         } else if self.render_target_stack.is_empty() {
-            self.options.background_color
+            // Unlike the destination, the intermediate framebuffer holds nothing that should
+            // show through, so it's cleared even if there's no background color.
+            match self.options.background_color {
+                None if self.renderer_flags
+                            .contains(RendererFlags::INTERMEDIATE_DEST_FRAMEBUFFER_NEEDED) => {
+                    Some(ColorF::transparent_black())
+                }
+                background_color => background_color,
+            }
         } else {
             Some(ColorF::default())
         }
@@ -1188,6 +1799,13 @@ impl<D> RendererCore<D> where D: Device {
         }
     }
 
+    fn output_blit_transform(&self, main_viewport: RectI, dest_size: Vector2I) -> Transform2F {
+        output_blit_transform(self.options.output_transform,
+                              main_viewport,
+                              dest_size,
+                              self.device.framebuffer_origin_is_lower_left())
+    }
+
     pub(crate) fn draw_viewport(&self) -> RectI {
         match self.render_target_stack.last() {
             Some(&render_target_id) => self.render_target_location(render_target_id).rect,
@@ -1209,17 +1827,19 @@ impl<D> RendererCore<D> where D: Device {
                         self.allocator.get_framebuffer(self.intermediate_dest_framebuffer_id);
                     RenderTarget::Framebuffer(intermediate_dest_framebuffer)
                 } else {
-                    match self.options.dest {
-                        DestFramebuffer::Default { .. } => RenderTarget::Default,
-                        DestFramebuffer::Other(ref framebuffer) => {
-                            RenderTarget::Framebuffer(framebuffer)
-                        }
-                    }
+                    self.dest_render_target()
                 }
             }
         }
     }
 
+    pub(crate) fn dest_render_target(&self) -> RenderTarget<'_, D> {
+        match self.options.dest {
+            DestFramebuffer::Default { .. } => RenderTarget::Default,
+            DestFramebuffer::Other(ref framebuffer) => RenderTarget::Framebuffer(framebuffer),
+        }
+    }
+
     pub(crate) fn preserve_draw_framebuffer(&mut self) {
         match self.render_target_stack.last() {
             Some(&render_target_id) => {
@@ -1251,6 +1871,7 @@ impl<D> Frame<D> where D: Device {
     fn new(device: &D,
            allocator: &mut GPUMemoryAllocator<D>,
            blit_program: &BlitProgram<D>,
+           blit_unpremultiply_program: &BlitProgram<D>,
            clear_program: &ClearProgram<D>,
            reprojection_program: &ReprojectionProgram<D>,
            stencil_program: &StencilProgram<D>,
@@ -1266,6 +1887,11 @@ impl<D> Frame<D> where D: Device {
                                                      &blit_program,
                                                      &quad_vertex_positions_buffer,
                                                      &quad_vertex_indices_buffer);
+        let blit_unpremultiply_vertex_array =
+            BlitVertexArray::new(device,
+                                 &blit_unpremultiply_program,
+                                 &quad_vertex_positions_buffer,
+                                 &quad_vertex_indices_buffer);
         let clear_vertex_array = ClearVertexArray::new(device,
                                                        &clear_program,
                                                        &quad_vertex_positions_buffer,
@@ -1278,6 +1904,7 @@ impl<D> Frame<D> where D: Device {
 
         Frame {
             blit_vertex_array,
+            blit_unpremultiply_vertex_array,
             clear_vertex_array,
             reprojection_vertex_array,
             stencil_vertex_array,
@@ -1339,6 +1966,220 @@ fn pixel_size_to_tile_size(pixel_size: Vector2I) -> Vector2I {
     vec2i(size.x() / TILE_WIDTH as i32, size.y() / TILE_HEIGHT as i32)
 }
 
+// The clear shader premultiplies its color, so for straight-alpha output the color channels are
+// divided by alpha up front to land on the requested color.
+fn clear_shader_color(background_color: ColorF, premultiplied_output: bool) -> ColorF {
+    let alpha = background_color.a();
+    if premultiplied_output || alpha == 0.0 {
+        return background_color;
+    }
+    ColorF::new(background_color.r() / alpha,
+                background_color.g() / alpha,
+                background_color.b() / alpha,
+                alpha)
+}
+
+// Returns the format of the mask framebuffer for the given level: the preferred one if the device
+// can render to it, or else the first fallback that it can render to. The D3D9 fill shaders add
+// and subtract coverage, so its 8-bit fallback clamps the negative contributions to zero and can
+// render paths wrongly, but it lets devices without float render targets draw something.
+fn choose_mask_texture_format(level: RendererLevel, supported_formats: TextureFormatSet)
+                              -> TextureFormat {
+    let formats: &[TextureFormat] = match level {
+        RendererLevel::D3D9 => &[TextureFormat::RGBA16F, TextureFormat::RGBA8],
+        RendererLevel::D3D11 => &[TextureFormat::RGBA8],
+    };
+    match formats.iter().cloned().find(|&format| supported_formats.contains_format(format)) {
+        Some(format) => {
+            if format != formats[0] {
+                warn!("mask texture format {:?} is not renderable on this device; falling back \
+                       to {:?}, which clamps negative coverage, so paths may render wrongly",
+                      formats[0],
+                      format);
+            }
+            format
+        }
+        None => {
+            warn!("no mask texture format is renderable on this device; output will be incorrect");
+            formats[0]
+        }
+    }
+}
+
+// Returns the transform that the blit shader applies to output pixels to implement the output
+// transform. Blit positions are measured upward from the bottom of the framebuffer, while the
+// output transform is measured downward from the top, so flip on the way in and out.
+fn output_blit_transform(output_transform: Transform2F,
+                         main_viewport: RectI,
+                         dest_size: Vector2I,
+                         origin_is_lower_left: bool)
+                         -> Transform2F {
+    let flip = |height: f32| Transform2F::row_major(1.0, 0.0, 0.0, 0.0, -1.0, height);
+    let (origin, size) = (main_viewport.origin().to_f32(), main_viewport.size().to_f32());
+    let dest_height = dest_size.y() as f32;
+    let origin = if origin_is_lower_left {
+        vec2f(origin.x(), dest_height - origin.y() - size.y())
+    } else {
+        origin
+    };
+    flip(dest_height) * output_transform * Transform2F::from_translation(origin) * flip(size.y())
+}
+
+// Converts a rectangle in tiles to one in device pixels.
+fn tile_rect(origin: Vector2I, size: Vector2I) -> RectI {
+    let tile_size = vec2i(TILE_WIDTH as i32, TILE_HEIGHT as i32);
+    RectI::new(origin * tile_size, size * tile_size)
+}
+
+// `text_gamma_correction` is the global switch from the renderer options. When it's off, text is
+// composited without gamma correction even if its filter asks for it. `dither` asks the tile
+// shader to dither the final color. The ctrl word is uploaded as an f16, which represents every
+// integer up to 2048 exactly but only multiples of 16 from 0x4000 up. That's enough, because the
+// bits below the color filter shift are never set.
+fn compute_filter_params(filter: &Filter,
+                         blend_mode: BlendMode,
+                         color_0_combine_mode: ColorCombineMode,
+                         text_gamma_correction: bool,
+                         dither: bool)
+                         -> FilterParams {
+    let mut ctrl = 0;
+    ctrl |= blend_mode.to_composite_ctrl() << COMBINER_CTRL_COMPOSITE_SHIFT;
+    ctrl |= color_0_combine_mode.to_composite_ctrl() << COMBINER_CTRL_COLOR_COMBINE_SHIFT;
+    if dither {
+        ctrl |= COMBINER_CTRL_DITHER;
+    }
+
+    match *filter {
+        Filter::RadialGradient { line, radii, uv_origin } => {
+            FilterParams {
+                p0: line.from().0.concat_xy_xy(line.vector().0),
+                p1: radii.concat_xy_xy(uv_origin.0),
+                p2: F32x4::default(),
+                p3: F32x4::default(),
+                p4: F32x4::default(),
+                ctrl: ctrl | (COMBINER_CTRL_FILTER_RADIAL_GRADIENT <<
+                              COMBINER_CTRL_COLOR_FILTER_SHIFT)
+            }
+        }
+        Filter::ConicGradient { center, start_angle, uv_origin } => {
+            FilterParams {
+                p0: center.0.concat_xy_xy(F32x2::new(start_angle, 0.0)),
+                p1: F32x2::default().concat_xy_xy(uv_origin.0),
+                p2: F32x4::default(),
+                p3: F32x4::default(),
+                p4: F32x4::default(),
+                ctrl: ctrl | (COMBINER_CTRL_FILTER_CONIC_GRADIENT <<
+                              COMBINER_CTRL_COLOR_FILTER_SHIFT)
+            }
+        }
+        Filter::PatternFilter(PatternFilter::Blur { sigma, direction }) => {
+            let sigma_inv = 1.0 / sigma;
+            let gauss_coeff_x = SQRT_2_PI_INV * sigma_inv;
+            let gauss_coeff_y = f32::exp(-0.5 * sigma_inv * sigma_inv);
+            let gauss_coeff_z = gauss_coeff_y * gauss_coeff_y;
+
+            let src_offset = match direction {
+                BlurDirection::X => vec2f(1.0, 0.0),
+                BlurDirection::Y => vec2f(0.0, 1.0),
+            };
+
+            let support = f32::ceil(1.5 * sigma) * 2.0;
+
+            FilterParams {
+                p0: src_offset.0.concat_xy_xy(F32x2::new(support, 0.0)),
+                p1: F32x4::new(gauss_coeff_x, gauss_coeff_y, gauss_coeff_z, 0.0),
+                p2: F32x4::default(),
+                p3: F32x4::default(),
+                p4: F32x4::default(),
+                ctrl: ctrl | (COMBINER_CTRL_FILTER_BLUR << COMBINER_CTRL_COLOR_FILTER_SHIFT),
+            }
+        }
+        Filter::PatternFilter(PatternFilter::Text { 
+            fg_color,
+            bg_color,
+            defringing_kernel,
+            gamma_correction,
+        }) => {
+            let mut p2 = fg_color.0;
+            p2.set_w((gamma_correction && text_gamma_correction) as i32 as f32);
+
+            FilterParams {
+                p0: match defringing_kernel {
+                    Some(ref kernel) => F32x4::from_slice(&kernel.0),
+                    None => F32x4::default(),
+                },
+                p1: bg_color.0,
+                p2,
+                p3: F32x4::default(),
+                p4: F32x4::default(),
+                ctrl: ctrl | (COMBINER_CTRL_FILTER_TEXT << COMBINER_CTRL_COLOR_FILTER_SHIFT),
+            }
+        }
+        Filter::PatternFilter(PatternFilter::ColorMatrix(matrix)) => {
+            let [p0, p1, p2, p3, p4] = matrix.0;
+            let filter_ctrl =
+                COMBINER_CTRL_FILTER_COLOR_MATRIX << COMBINER_CTRL_COLOR_FILTER_SHIFT;
+            FilterParams { p0, p1, p2, p3, p4, ctrl: ctrl | filter_ctrl }
+        }
+        Filter::None => {
+            FilterParams {
+                p0: F32x4::default(),
+                p1: F32x4::default(),
+                p2: F32x4::default(),
+                p3: F32x4::default(),
+                p4: F32x4::default(),
+                ctrl,
+            }
+        }
+    }
+}
+
+// Returns the number of pages the mask framebuffer may have, given the device's maximum texture
+// size and the caller's limit, if any. At least one page is always allowed, even if the device
+// reports a maximum texture size smaller than a page.
+pub(crate) fn max_mask_pages(max_texture_size: i32, limit: Option<u32>) -> u32 {
+    let device_max = ((max_texture_size / MASK_FRAMEBUFFER_HEIGHT) as u32).max(1);
+    match limit {
+        Some(limit) => device_max.min(limit.max(1)),
+        None => device_max,
+    }
+}
+
+// Returns the rectangle of the destination framebuffer that `Renderer::snapshot()` reads for
+// `rect`, which is relative to the upper left corner of `main_viewport` and is clamped to it.
+fn snapshot_read_rect(main_viewport: RectI, rect: RectI, origin_is_lower_left: bool) -> RectI {
+    let viewport_rect = RectI::new(Vector2I::default(), main_viewport.size());
+    let rect = rect.intersection(viewport_rect).unwrap_or_default();
+    let mut origin = main_viewport.origin() + rect.origin();
+    if origin_is_lower_left {
+        origin = vec2i(origin.x(), main_viewport.max_y() - rect.max_y());
+    }
+    RectI::new(origin, rect.size())
+}
+
+// Returns the texels of the caller's text gamma lookup table, or `None` to use the built-in one if
+// there's no custom table or it's the wrong size.
+fn custom_gamma_lut(texels: &Option<Vec<u8>>) -> Option<&[u8]> {
+    let texels = texels.as_ref()?;
+    let expected_len = (GAMMA_LUT_WIDTH * GAMMA_LUT_HEIGHT) as usize;
+    if texels.len() != expected_len {
+        warn!("text gamma lookup table has {} texels, but {} are needed; using the default",
+              texels.len(),
+              expected_len);
+        return None;
+    }
+    Some(texels)
+}
+
+fn unit_float_to_u8(value: f32) -> u8 {
+    (value.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+// Converts a 16-bit normalized channel to 8 bits, rounding to the nearest value.
+fn unorm16_to_u8(value: u16) -> u8 {
+    ((value as u32 * 255 + 32767) / 65535) as u8
+}
+
 struct FilterParams {
     p0: F32x4,
     p1: F32x4,
@@ -1364,3 +2205,207 @@ pub struct DebugUIPresenterInfo<'a, D> where D: Device {
     /// The debug UI presenter, useful for drawing custom debug widgets on screen.
     pub debug_ui_presenter: &'a mut DebugUIPresenter<D>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::{COMBINER_CTRL_COLOR_FILTER_SHIFT, COMBINER_CTRL_DITHER};
+    use super::{COMBINER_CTRL_FILTER_CONIC_GRADIENT, ColorCombineMode, MASK_FRAMEBUFFER_HEIGHT};
+    use super::{choose_mask_texture_format, clear_shader_color, compute_filter_params};
+    use super::{custom_gamma_lut, max_mask_pages, output_blit_transform, snapshot_read_rect};
+    use super::unorm16_to_u8;
+    use crate::gpu::options::RendererLevel;
+    use half::f16;
+    use pathfinder_color::ColorF;
+    use pathfinder_content::effects::{BlendMode, BlurDirection, Filter, PatternFilter};
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{vec2f, vec2i};
+    use pathfinder_gpu::{TextureFormat, TextureFormatSet};
+    use pathfinder_simd::default::F32x4;
+
+    #[test]
+    fn test_max_mask_pages() {
+        let max_texture_size = MASK_FRAMEBUFFER_HEIGHT * 16;
+        assert_eq!(max_mask_pages(max_texture_size, None), 16);
+        assert_eq!(max_mask_pages(max_texture_size, Some(2)), 2);
+        // The limit can't raise the device's maximum, and at least one page is always allowed.
+        assert_eq!(max_mask_pages(max_texture_size, Some(64)), 16);
+        assert_eq!(max_mask_pages(max_texture_size, Some(0)), 1);
+        // So does a device whose maximum texture size is smaller than a page.
+        assert_eq!(max_mask_pages(MASK_FRAMEBUFFER_HEIGHT / 2, None), 1);
+        assert_eq!(max_mask_pages(0, Some(4)), 1);
+    }
+
+    #[test]
+    fn test_clear_color_with_straight_alpha_output() {
+        // The clear shader multiplies the color channels by alpha.
+        fn premultiply(color: ColorF) -> ColorF {
+            let alpha = color.a();
+            ColorF::new(color.r() * alpha, color.g() * alpha, color.b() * alpha, alpha)
+        }
+
+        let straight = ColorF::new(1.0, 0.5, 0.25, 0.5);
+        assert_eq!(premultiply(clear_shader_color(straight, false)), straight);
+
+        // With premultiplied output, the color is passed through for the shader to premultiply.
+        assert_eq!(clear_shader_color(straight, true), straight);
+        assert_eq!(premultiply(clear_shader_color(straight, true)),
+                   ColorF::new(0.5, 0.25, 0.125, 0.5));
+
+        // Fully transparent colors are left alone instead of dividing by zero.
+        assert_eq!(clear_shader_color(ColorF::transparent_black(), false),
+                   ColorF::transparent_black());
+    }
+
+    #[test]
+    fn test_output_blit_transform_rotation() {
+        // A 200×100 viewport rotated 90° clockwise into a 100×200 destination.
+        let output_transform = Transform2F::row_major(0.0, -1.0, 100.0, 1.0, 0.0, 0.0);
+        let viewport = RectI::new(vec2i(0, 0), vec2i(200, 100));
+        let dest_size = vec2i(100, 200);
+
+        // Blit positions run upward from the bottom of both framebuffers, so the output's upper
+        // left corner is at (0, 100), and the destination's upper right corner at (100, 200).
+        let transform = output_blit_transform(output_transform, viewport, dest_size, false);
+        assert_eq!(transform * vec2f(0.0, 100.0), vec2f(100.0, 200.0));
+        assert_eq!(transform * vec2f(200.0, 100.0), vec2f(100.0, 0.0));
+        assert_eq!(transform * vec2f(200.0, 0.0), vec2f(0.0, 0.0));
+        assert_eq!(transform * vec2f(0.0, 0.0), vec2f(0.0, 200.0));
+
+        // With a lower left origin, a viewport at the top of the destination is measured from its
+        // bottom, so the same placement gives the same transform.
+        let viewport = RectI::new(vec2i(0, 100), vec2i(200, 100));
+        let lower_left_transform = output_blit_transform(output_transform,
+                                                         viewport,
+                                                         dest_size,
+                                                         true);
+        assert_eq!(lower_left_transform * vec2f(0.0, 100.0), vec2f(100.0, 200.0));
+        assert_eq!(lower_left_transform * vec2f(200.0, 0.0), vec2f(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_snapshot_read_rect() {
+        let viewport = RectI::new(vec2i(10, 20), vec2i(100, 50));
+
+        // A rect inside the viewport is offset by its origin, and flipped if rows run upward.
+        let rect = RectI::new(vec2i(5, 10), vec2i(20, 10));
+        assert_eq!(snapshot_read_rect(viewport, rect, false),
+                   RectI::new(vec2i(15, 30), vec2i(20, 10)));
+        assert_eq!(snapshot_read_rect(viewport, rect, true),
+                   RectI::new(vec2i(15, 50), vec2i(20, 10)));
+
+        // A rect that overhangs the viewport is clamped to it.
+        let rect = RectI::new(vec2i(-5, 40), vec2i(200, 20));
+        assert_eq!(snapshot_read_rect(viewport, rect, false),
+                   RectI::new(vec2i(10, 60), vec2i(100, 10)));
+
+        // One that lies entirely outside reads nothing.
+        let rect = RectI::new(vec2i(200, 0), vec2i(10, 10));
+        assert_eq!(snapshot_read_rect(viewport, rect, false).size(), vec2i(0, 0));
+    }
+
+    #[test]
+    fn test_mask_texture_format_fallback() {
+        let all_formats = TextureFormatSet::all();
+        assert_eq!(choose_mask_texture_format(RendererLevel::D3D9, all_formats),
+                   TextureFormat::RGBA16F);
+        assert_eq!(choose_mask_texture_format(RendererLevel::D3D11, all_formats),
+                   TextureFormat::RGBA8);
+
+        // A device that can't render to half floats falls back to 8-bit masks.
+        let formats = all_formats - TextureFormatSet::RGBA16F;
+        assert_eq!(choose_mask_texture_format(RendererLevel::D3D9, formats), TextureFormat::RGBA8);
+
+        // With nothing renderable, the preferred format is kept.
+        let formats = TextureFormatSet::empty();
+        assert_eq!(choose_mask_texture_format(RendererLevel::D3D9, formats),
+                   TextureFormat::RGBA16F);
+    }
+
+    #[test]
+    fn test_unorm16_to_u8() {
+        assert_eq!(unorm16_to_u8(0), 0);
+        assert_eq!(unorm16_to_u8(0xffff), 0xff);
+        assert_eq!(unorm16_to_u8(0x8080), 0x80);
+        assert_eq!(unorm16_to_u8(0x0080), 0);
+        assert_eq!(unorm16_to_u8(0x0081), 1);
+    }
+
+    #[test]
+    fn test_custom_gamma_lut() {
+        assert!(custom_gamma_lut(&None).is_none());
+        assert!(custom_gamma_lut(&Some(vec![0; 256])).is_none());
+        let lut = Some((0..2048).map(|index| index as u8).collect::<Vec<u8>>());
+        assert_eq!(custom_gamma_lut(&lut).map(|texels| texels.len()), Some(2048));
+        assert_eq!(custom_gamma_lut(&lut).map(|texels| texels[257]), Some(1));
+    }
+
+    #[test]
+    fn test_gradient_dithering() {
+        let dither_flag = |dither| {
+            let params = compute_filter_params(&Filter::None,
+                                               BlendMode::SrcOver,
+                                               ColorCombineMode::SrcIn,
+                                               true,
+                                               dither);
+            params.ctrl & COMBINER_CTRL_DITHER != 0
+        };
+        assert!(dither_flag(true));
+        assert!(!dither_flag(false));
+
+        // The ctrl word is uploaded as an f16, which loses the low bits of values from 0x4000 up.
+        // Check that those bits are clear, so that it survives the trip with the dither bit set.
+        let filter = Filter::PatternFilter(PatternFilter::Blur {
+            sigma: 1.0,
+            direction: BlurDirection::X,
+        });
+        for &blend_mode in &[BlendMode::SrcOver, BlendMode::Lighter, BlendMode::Luminosity] {
+            let ctrl = compute_filter_params(&filter,
+                                             blend_mode,
+                                             ColorCombineMode::DestIn,
+                                             true,
+                                             true).ctrl;
+            assert_eq!(ctrl & ((1 << COMBINER_CTRL_COLOR_FILTER_SHIFT) - 1), 0);
+            assert_eq!(f16::from_f32(ctrl as f32).to_f32() as i32, ctrl);
+        }
+    }
+
+    #[test]
+    fn test_conic_gradient_filter_params() {
+        let filter = Filter::ConicGradient {
+            center: vec2f(10.0, 20.0),
+            start_angle: 0.5,
+            uv_origin: vec2f(0.25, 0.75),
+        };
+        let params = compute_filter_params(&filter,
+                                           BlendMode::SrcOver,
+                                           ColorCombineMode::None,
+                                           true,
+                                           false);
+        assert_eq!(params.ctrl >> COMBINER_CTRL_COLOR_FILTER_SHIFT & 0xf,
+                   COMBINER_CTRL_FILTER_CONIC_GRADIENT);
+        assert_eq!(params.p0, F32x4::new(10.0, 20.0, 0.5, 0.0));
+        assert_eq!((params.p1.z(), params.p1.w()), (0.25, 0.75));
+    }
+
+    #[test]
+    fn test_text_gamma_correction() {
+        let filter = Filter::PatternFilter(PatternFilter::Text {
+            fg_color: ColorF::black(),
+            bg_color: ColorF::white(),
+            defringing_kernel: None,
+            gamma_correction: true,
+        });
+        let gamma_flag = |text_gamma_correction| {
+            let params = compute_filter_params(&filter,
+                                               BlendMode::SrcOver,
+                                               ColorCombineMode::None,
+                                               text_gamma_correction,
+                                               false);
+            params.p2.w()
+        };
+        assert_eq!(gamma_flag(true), 1.0);
+        // Turning gamma correction off globally overrides the filter.
+        assert_eq!(gamma_flag(false), 0.0);
+    }
+}
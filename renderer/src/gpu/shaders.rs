@@ -9,11 +9,12 @@
 // except according to those terms.
 
 use pathfinder_gpu::{BufferTarget, BufferUploadMode, Device, VertexAttrClass};
-use pathfinder_gpu::{VertexAttrDescriptor, VertexAttrType};
+use pathfinder_gpu::{ProgramKind, VertexAttrDescriptor, VertexAttrType};
 use pathfinder_resources::ResourceLoader;
 
 // TODO(pcwalton): Replace with `mem::size_of` calls?
 pub(crate) const TILE_INSTANCE_SIZE: usize = 16;
+const MESH_VERTEX_SIZE: usize = 12;
 
 pub(crate) struct BlitVertexArray<D> where D: Device {
     pub(crate) vertex_array: D::VertexArray,
@@ -92,20 +93,82 @@ impl<D> ClearVertexArray<D> where D: Device {
     }
 }
 
+pub(crate) struct MeshVertexArray<D> where D: Device {
+    pub(crate) vertex_array: D::VertexArray,
+}
+
+impl<D> MeshVertexArray<D> where D: Device {
+    pub(crate) fn new(device: &D,
+                      mesh_program: &MeshProgram<D>,
+                      mesh_vertex_buffer: &D::Buffer,
+                      mesh_index_buffer: &D::Buffer)
+                      -> MeshVertexArray<D> {
+        let vertex_array = device.create_vertex_array();
+        let position_attr = device.get_vertex_attr(&mesh_program.program, "Position").unwrap();
+        let color_attr = device.get_vertex_attr(&mesh_program.program, "Color").unwrap();
+
+        device.bind_buffer(&vertex_array, mesh_vertex_buffer, BufferTarget::Vertex);
+        device.configure_vertex_attr(&vertex_array, &position_attr, &VertexAttrDescriptor {
+            size: 2,
+            class: VertexAttrClass::Float,
+            attr_type: VertexAttrType::F32,
+            stride: MESH_VERTEX_SIZE,
+            offset: 0,
+            divisor: 0,
+            buffer_index: 0,
+        });
+        device.configure_vertex_attr(&vertex_array, &color_attr, &VertexAttrDescriptor {
+            size: 4,
+            class: VertexAttrClass::FloatNorm,
+            attr_type: VertexAttrType::U8,
+            stride: MESH_VERTEX_SIZE,
+            offset: 8,
+            divisor: 0,
+            buffer_index: 0,
+        });
+        device.bind_buffer(&vertex_array, mesh_index_buffer, BufferTarget::Index);
+
+        MeshVertexArray { vertex_array }
+    }
+}
+
 pub(crate) struct BlitProgram<D> where D: Device {
     pub(crate) program: D::Program,
     pub(crate) dest_rect_uniform: D::Uniform,
+    pub(crate) transform_uniform: D::Uniform,
+    pub(crate) translation_uniform: D::Uniform,
     pub(crate) framebuffer_size_uniform: D::Uniform,
     pub(crate) src_texture: D::TextureParameter,
 }
 
 impl<D> BlitProgram<D> where D: Device {
     pub(crate) fn new(device: &D, resources: &dyn ResourceLoader) -> BlitProgram<D> {
-        let program = device.create_raster_program(resources, "blit");
+        BlitProgram::with_fragment_shader(device, resources, "blit")
+    }
+
+    // Converts premultiplied alpha to straight alpha while blitting.
+    pub(crate) fn new_unpremultiply(device: &D, resources: &dyn ResourceLoader)
+                                    -> BlitProgram<D> {
+        BlitProgram::with_fragment_shader(device, resources, "blit_unpremultiply")
+    }
+
+    fn with_fragment_shader(device: &D, resources: &dyn ResourceLoader, fragment: &str)
+                            -> BlitProgram<D> {
+        let shaders = ProgramKind::Raster { vertex: "blit", fragment };
+        let program = device.create_program_from_shader_names(resources, fragment, shaders);
         let dest_rect_uniform = device.get_uniform(&program, "DestRect");
+        let transform_uniform = device.get_uniform(&program, "Transform");
+        let translation_uniform = device.get_uniform(&program, "Translation");
         let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
         let src_texture = device.get_texture_parameter(&program, "Src");
-        BlitProgram { program, dest_rect_uniform, framebuffer_size_uniform, src_texture }
+        BlitProgram {
+            program,
+            dest_rect_uniform,
+            transform_uniform,
+            translation_uniform,
+            framebuffer_size_uniform,
+            src_texture,
+        }
     }
 }
 
@@ -138,6 +201,20 @@ impl<D> ClearProgram<D> where D: Device {
     }
 }
 
+// Draws triangles with colors interpolated between their vertices.
+pub(crate) struct MeshProgram<D> where D: Device {
+    pub(crate) program: D::Program,
+    pub(crate) framebuffer_size_uniform: D::Uniform,
+}
+
+impl<D> MeshProgram<D> where D: Device {
+    pub(crate) fn new(device: &D, resources: &dyn ResourceLoader) -> MeshProgram<D> {
+        let program = device.create_raster_program(resources, "mesh");
+        let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
+        MeshProgram { program, framebuffer_size_uniform }
+    }
+}
+
 pub(crate) struct TileProgramCommon<D> where D: Device {
     pub(crate) program: D::Program,
     pub(crate) tile_size_uniform: D::Uniform,
@@ -147,6 +224,7 @@ pub(crate) struct TileProgramCommon<D> where D: Device {
     pub(crate) z_buffer_texture_size_uniform: D::Uniform,
     pub(crate) color_texture_0: D::TextureParameter,
     pub(crate) color_texture_size_0_uniform: D::Uniform,
+    pub(crate) color_texture_1: D::TextureParameter,
     pub(crate) mask_texture_0: D::TextureParameter,
     pub(crate) mask_texture_size_0_uniform: D::Uniform,
     pub(crate) gamma_lut_texture: D::TextureParameter,
@@ -162,6 +240,7 @@ impl<D> TileProgramCommon<D> where D: Device {
         let z_buffer_texture_size_uniform = device.get_uniform(&program, "ZBufferSize");
         let color_texture_0 = device.get_texture_parameter(&program, "ColorTexture0");
         let color_texture_size_0_uniform = device.get_uniform(&program, "ColorTextureSize0");
+        let color_texture_1 = device.get_texture_parameter(&program, "ColorTexture1");
         let mask_texture_0 = device.get_texture_parameter(&program, "MaskTexture0");
         let mask_texture_size_0_uniform = device.get_uniform(&program, "MaskTextureSize0");
         let gamma_lut_texture = device.get_texture_parameter(&program, "GammaLUT");
@@ -176,6 +255,7 @@ impl<D> TileProgramCommon<D> where D: Device {
             z_buffer_texture_size_uniform,
             color_texture_0,
             color_texture_size_0_uniform,
+            color_texture_1,
             mask_texture_0,
             mask_texture_size_0_uniform,
             gamma_lut_texture,
@@ -21,7 +21,7 @@ use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::line_segment::{LineSegment2F, LineSegmentU16};
 use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::transform2d::Transform2F;
-use pathfinder_geometry::vector::{Vector2F, Vector2I};
+use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2i};
 use pathfinder_gpu::TextureSamplingFlags;
 use std::fmt::{Debug, Formatter, Result as DebugResult};
 use std::sync::Arc;
@@ -34,6 +34,7 @@ pub const TILE_CTRL_MASK_EVEN_ODD: i32 = 0x2;
 
 pub const TILE_CTRL_MASK_0_SHIFT:  i32 = 0;
 
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum RenderCommand {
     // Starts rendering a frame.
     Start {
@@ -94,19 +95,25 @@ pub enum RenderCommand {
     // Draws a batch of tiles to the render target on top of the stack.
     DrawTilesD3D11(DrawTileBatchD3D11),
 
+    // Draws a mesh of vertex-colored triangles to the render target on top of the stack.
+    DrawMesh(DrawMeshBatch),
+
     // Presents a rendered frame.
     Finish { cpu_build_time: Duration },
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TexturePageId(pub u32);
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TexturePageDescriptor {
     pub size: Vector2I,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextureLocation {
     pub page: TexturePageId,
     pub rect: RectI,
@@ -114,6 +121,7 @@ pub struct TextureLocation {
 
 /// Information about a batch of tiles to be prepared (postprocessed).
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileBatchDataD3D11 {
     /// The ID of this batch.
     /// 
@@ -135,6 +143,7 @@ pub struct TileBatchDataD3D11 {
 
 /// Where a path should come from (draw or clip).
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum PathSource {
     Draw,
     Clip,
@@ -142,6 +151,7 @@ pub enum PathSource {
 
 /// Information about a batch of tiles to be prepared on GPU.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrepareTilesInfoD3D11 {
     /// Initial backdrop values for each tile column, packed together.
     pub backdrops: Vec<BackdropInfoD3D11>,
@@ -162,12 +172,14 @@ pub struct PrepareTilesInfoD3D11 {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct SegmentsD3D11 {
     pub points: Vec<Vector2F>,
     pub indices: Vec<SegmentIndicesD3D11>,
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct SegmentIndicesD3D11 {
     pub first_point_index: u32,
@@ -176,6 +188,7 @@ pub struct SegmentIndicesD3D11 {
 
 /// Information about clips applied to paths in a batch.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClippedPathInfo {
     /// The ID of the batch containing the clips.
     pub clip_batch_id: TileBatchId,
@@ -196,10 +209,12 @@ pub struct ClippedPathInfo {
 /// 
 /// Generally, `PathIndex(!0)` represents no path.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct PathBatchIndex(pub u32);
 
 /// Unique ID that identifies a batch of tiles.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileBatchId(pub u32);
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -216,12 +231,15 @@ pub enum DrawTileBatch {
 
 /// Information needed to draw a batch of tiles in D3D9.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct DrawTileBatchD3D9 {
     pub tiles: Vec<TileObjectPrimitive>,
     pub clips: Vec<Clip>,
     pub z_buffer_data: DenseTileMap<i32>,
     /// The color texture to use.
     pub color_texture: Option<TileBatchTexture>,
+    /// The second color texture to use, if any.
+    pub color_texture_1: Option<TileBatchTexture>,
     /// The filter to use.
     pub filter: Filter,
     /// The blend mode to composite these tiles with.
@@ -230,14 +248,38 @@ pub struct DrawTileBatchD3D9 {
 
 /// Information needed to draw a batch of tiles in D3D11.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct DrawTileBatchD3D11 {
     /// Data for the tile batch.
     pub tile_batch_data: TileBatchDataD3D11,
     /// The color texture to use.
     pub color_texture: Option<TileBatchTexture>,
+    /// The second color texture to use, if any.
+    pub color_texture_1: Option<TileBatchTexture>,
+}
+
+/// Information needed to draw a mesh of vertex-colored triangles.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrawMeshBatch {
+    /// The vertices, already transformed to device pixels.
+    pub vertices: Vec<MeshVertex>,
+    /// Indices into `vertices`, three per triangle.
+    pub indices: Vec<u32>,
+}
+
+/// A single vertex of a mesh, in device pixels.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct MeshVertex {
+    pub x: f32,
+    pub y: f32,
+    pub color: ColorU,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileBatchTexture {
     pub page: TexturePageId,
     pub sampling_flags: TextureSamplingFlags,
@@ -254,6 +296,7 @@ pub struct FillId(pub i32);
 
 // TODO(pcwalton): Pack better.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TileObjectPrimitive {
     pub tile_x: i16,
@@ -287,6 +330,7 @@ pub struct AlphaTileD3D11 {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TilePathInfoD3D11 {
     pub tile_min_x: i16,
@@ -302,6 +346,7 @@ pub struct TilePathInfoD3D11 {
 
 // TODO(pcwalton): Pack better!
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct PropagateMetadataD3D11 {
     pub tile_rect: RectI,
@@ -317,6 +362,7 @@ pub struct PropagateMetadataD3D11 {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct DiceMetadataD3D11 {
     pub global_path_id: PathId,
@@ -326,16 +372,22 @@ pub struct DiceMetadataD3D11 {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TextureMetadataEntry {
     pub color_0_transform: Transform2F,
     pub color_0_combine_mode: ColorCombineMode,
+    /// Whether color 0 is a gradient, which the renderer may dither.
+    pub color_0_is_gradient: bool,
+    pub color_1_transform: Transform2F,
+    pub color_1_combine_mode: ColorCombineMode,
     pub base_color: ColorU,
     pub filter: Filter,
     pub blend_mode: BlendMode,
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub enum ColorCombineMode {
     None,
@@ -344,6 +396,7 @@ pub enum ColorCombineMode {
 }
 
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Fill {
     pub line_segment: LineSegmentU16,
@@ -366,6 +419,7 @@ pub struct ClipMetadata {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Clip {
     pub dest_tile_id: AlphaTileId,
@@ -397,6 +451,7 @@ pub struct BinSegment {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct BackdropInfoD3D11 {
     pub initial_backdrop: i32,
@@ -426,9 +481,24 @@ pub(crate) struct FirstTileD3D11 {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct AlphaTileId(pub u32);
 
+impl DrawTileBatchD3D9 {
+    /// Returns the number of tiles in this batch that the Z buffer culls, because an opaque tile
+    /// of a later path in the batch covers them.
+    pub fn occluded_tile_count(&self) -> usize {
+        self.tiles.iter().filter(|tile| {
+            let tile_coords = vec2i(tile.tile_x as i32, tile.tile_y as i32);
+            match self.z_buffer_data.get(tile_coords) {
+                Some(&z_value) => (tile.path_id.0 as i32) < z_value,
+                None => false,
+            }
+        }).count()
+    }
+}
+
 impl PathBatchIndex {
     #[inline]
     pub fn none() -> PathBatchIndex {
@@ -513,9 +583,13 @@ impl Debug for RenderCommand {
             }
             RenderCommand::DrawTilesD3D11(ref batch) => {
                 write!(formatter,
-                       "DrawTilesD3D11({:?}, C0 {:?})",
+                       "DrawTilesD3D11({:?}, C0 {:?}, C1 {:?})",
                        batch.tile_batch_data.batch_id,
-                       batch.color_texture)
+                       batch.color_texture,
+                       batch.color_texture_1)
+            }
+            RenderCommand::DrawMesh(ref batch) => {
+                write!(formatter, "DrawMesh(x{})", batch.indices.len() / 3)
             }
             RenderCommand::Finish { cpu_build_time } => {
                 write!(formatter, "Finish({} ms)", cpu_build_time.as_secs_f64() * 1000.0)
@@ -524,6 +598,29 @@ impl Debug for RenderCommand {
     }
 }
 
+impl RenderCommand {
+    /// Returns the name of this kind of command, for profiling.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            RenderCommand::Start { .. } => "Start",
+            RenderCommand::AllocateTexturePage { .. } => "AllocateTexturePage",
+            RenderCommand::UploadTexelData { .. } => "UploadTexelData",
+            RenderCommand::DeclareRenderTarget { .. } => "DeclareRenderTarget",
+            RenderCommand::UploadTextureMetadata(_) => "UploadTextureMetadata",
+            RenderCommand::AddFillsD3D9(_) => "AddFillsD3D9",
+            RenderCommand::FlushFillsD3D9 => "FlushFillsD3D9",
+            RenderCommand::UploadSceneD3D11 { .. } => "UploadSceneD3D11",
+            RenderCommand::PushRenderTarget(_) => "PushRenderTarget",
+            RenderCommand::PopRenderTarget => "PopRenderTarget",
+            RenderCommand::PrepareClipTilesD3D11(_) => "PrepareClipTilesD3D11",
+            RenderCommand::DrawTilesD3D9(_) => "DrawTilesD3D9",
+            RenderCommand::DrawTilesD3D11(_) => "DrawTilesD3D11",
+            RenderCommand::DrawMesh(_) => "DrawMesh",
+            RenderCommand::Finish { .. } => "Finish",
+        }
+    }
+}
+
 impl Default for FirstTileD3D11 {
     #[inline]
     fn default() -> FirstTileD3D11 {
@@ -17,6 +17,11 @@ use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::transform3d::Perspective;
 use pathfinder_geometry::vector::{Vector2F, Vector4F};
 
+#[cfg(feature = "serialize")]
+use std::io::{Read, Write};
+#[cfg(feature = "serialize")]
+use std::sync::Mutex;
+
 #[allow(deprecated)]
 use pathfinder_content::clip::PolygonClipper3D;
 
@@ -48,7 +53,37 @@ impl<'a> RenderCommandListener<'a> {
     }
 }
 
+#[cfg(feature = "serialize")]
+impl<'a> RenderCommandListener<'a> {
+    /// Creates a listener that serializes each render command to `writer` as it's built, so that
+    /// scenes can be recorded and replayed later or sent to a renderer in another process.
+    ///
+    /// Commands are written back to back as JSON values. Use `deserialize_render_commands()` to
+    /// read them back and `Renderer::render_command()` to replay them. A command that fails to
+    /// write is logged and dropped.
+    pub fn serializing<W>(writer: W) -> RenderCommandListener<'a> where W: Write + Send + 'a {
+        let writer = Mutex::new(writer);
+        RenderCommandListener::new(Box::new(move |render_command| {
+            let mut writer = writer.lock().unwrap();
+            if let Err(err) = serde_json::to_writer(&mut *writer, &render_command) {
+                error!("failed to serialize render command: {}", err);
+            }
+        }))
+    }
+}
+
+/// Reads back a stream of render commands written by `RenderCommandListener::serializing()`.
+#[cfg(feature = "serialize")]
+pub fn deserialize_render_commands<R>(reader: R)
+                                      -> impl Iterator<Item = serde_json::Result<RenderCommand>>
+                                      where R: Read {
+    serde_json::Deserializer::from_reader(reader).into_iter()
+}
+
 /// Options that influence scene building.
+///
+/// New options may be added over time, so prefer `BuildOptions::new()` and the setter methods,
+/// or finish struct literals with `..BuildOptions::default()`.
 #[derive(Clone, Default)]
 pub struct BuildOptions {
     /// A global transform to be applied to the scene.
@@ -58,14 +93,81 @@ pub struct BuildOptions {
     pub dilation: Vector2F,
     /// True if subpixel antialiasing for LCD screens is to be performed.
     pub subpixel_aa_enabled: bool,
+    /// The visible region, in device pixels after `transform` has been applied, overriding the
+    /// view box of the scene.
+    ///
+    /// Paths are culled and clipped against this rectangle, so a scene with no view box of its
+    /// own (an infinite canvas, for example) can be panned and zoomed by changing `transform` and
+    /// supplying the current window bounds here, without modifying the scene. If this is `None`,
+    /// the view box of the scene is used instead.
+    pub view_box: Option<RectF>,
 }
 
 impl BuildOptions {
+    /// Creates a new set of build options with the default values: no transform, no dilation,
+    /// no subpixel antialiasing, and the view box of the scene.
+    ///
+    /// The methods below can be chained to change individual options. How the scene is prepared
+    /// (on the CPU or the GPU) is chosen automatically from the level of the renderer.
+    #[inline]
+    pub fn new() -> BuildOptions {
+        BuildOptions::default()
+    }
+
+    /// Sets a 2D transform to be applied to the whole scene.
+    ///
+    /// On the D3D11 level this is applied on the GPU, so changing it costs little. On the D3D9
+    /// level every path is transformed and retiled on the CPU.
+    #[inline]
+    pub fn transform(mut self, transform: Transform2F) -> BuildOptions {
+        self.transform = RenderTransform::Transform2D(transform);
+        self
+    }
+
+    /// Sets a perspective transform to be applied to the whole scene.
+    ///
+    /// Perspective transforms are always applied on the CPU, and paths are clipped against the
+    /// near plane, so they're considerably slower than 2D transforms.
+    #[inline]
+    pub fn perspective(mut self, perspective: Perspective) -> BuildOptions {
+        self.transform = RenderTransform::Perspective(perspective);
+        self
+    }
+
+    /// Expands outlines by the given number of device pixels, for stem darkening of fonts.
+    ///
+    /// Dilation is performed on the CPU for every path and adds to the build time.
+    #[inline]
+    pub fn dilation(mut self, dilation: Vector2F) -> BuildOptions {
+        self.dilation = dilation;
+        self
+    }
+
+    /// Enables or disables subpixel antialiasing for LCD screens.
+    ///
+    /// This improves the sharpness of text on LCD screens, but the scene is tiled at three times
+    /// the horizontal resolution, roughly tripling the fill work.
+    #[inline]
+    pub fn subpixel_aa(mut self, enabled: bool) -> BuildOptions {
+        self.subpixel_aa_enabled = enabled;
+        self
+    }
+
+    /// Overrides the view box of the scene, in device pixels after the transform is applied.
+    ///
+    /// Paths outside this rectangle are culled, so a tight view box reduces tiling work.
+    #[inline]
+    pub fn view_box(mut self, view_box: RectF) -> BuildOptions {
+        self.view_box = Some(view_box);
+        self
+    }
+
     pub(crate) fn prepare(self, bounds: RectF) -> PreparedBuildOptions {
         PreparedBuildOptions {
             transform: self.transform.prepare(bounds),
             dilation: self.dilation,
             subpixel_aa_enabled: self.subpixel_aa_enabled,
+            view_box: self.view_box,
         }
     }
 }
@@ -143,6 +245,7 @@ pub(crate) struct PreparedBuildOptions {
     pub(crate) transform: PreparedRenderTransform,
     pub(crate) dilation: Vector2F,
     pub(crate) subpixel_aa_enabled: bool,
+    pub(crate) view_box: Option<RectF>,
 }
 
 #[derive(Clone, Copy)]
@@ -161,6 +264,9 @@ impl PreparedBuildOptions {
         }
     }
 
+    // The prepare mode can't be chosen per scene (by segment count, say): CPU tiling emits D3D9
+    // commands, which a D3D11 renderer can't execute, and `TransformCPUBinGPU` still dices the
+    // segments uploaded by the last GPU build rather than the CPU-transformed outlines.
     #[inline]
     pub(crate) fn to_prepare_mode(&self, renderer_level: RendererLevel) -> PrepareMode {
         match renderer_level {
@@ -13,7 +13,7 @@
 use crate::allocator::{AllocationMode, TextureAllocator};
 use crate::gpu_data::{ColorCombineMode, RenderCommand, TextureLocation, TextureMetadataEntry};
 use crate::gpu_data::{TexturePageDescriptor, TexturePageId, TileBatchTexture};
-use crate::scene::{RenderTarget, SceneId};
+use crate::scene::{RenderTarget, SceneId, TrivialRenderTargets};
 use hashbrown::{HashMap, HashSet};
 use pathfinder_color::ColorU;
 use pathfinder_content::effects::{BlendMode, Filter, PatternFilter};
@@ -39,7 +39,7 @@ const GRADIENT_TILE_LENGTH: u32 = 256;
 #[derive(Clone)]
 pub(crate) struct Palette {
     pub(crate) paints: Vec<Paint>,
-    render_targets: Vec<RenderTarget>,
+    pub(crate) render_targets: Vec<RenderTarget>,
     cache: HashMap<Paint, PaintId>,
     scene_id: SceneId,
 }
@@ -55,6 +55,7 @@ pub(crate) struct PaintTextureManager {
 pub struct Paint {
     base_color: ColorU,
     overlay: Option<PaintOverlay>,
+    second_overlay: Option<PaintOverlay>,
 }
 
 /// What is to be overlaid on top of a base color.
@@ -86,6 +87,7 @@ pub struct GradientId(pub u32);
 
 /// How an overlay is to be composited over a base color.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum PaintCompositeOp {
     /// The source that overlaps the destination, replaces the destination.
     SrcIn,
@@ -112,13 +114,18 @@ impl Palette {
             scene_id,
         }
     }
+
+    #[inline]
+    pub(crate) fn set_scene_id(&mut self, new_scene_id: SceneId) {
+        self.scene_id = new_scene_id;
+    }
 }
 
 impl Paint {
     /// Creates a simple paint from a single base color.
     #[inline]
     pub fn from_color(color: ColorU) -> Paint {
-        Paint { base_color: color, overlay: None }
+        Paint { base_color: color, overlay: None, second_overlay: None }
     }
 
     /// Creates a paint from a gradient.
@@ -126,10 +133,8 @@ impl Paint {
     pub fn from_gradient(gradient: Gradient) -> Paint {
         Paint {
             base_color: ColorU::white(),
-            overlay: Some(PaintOverlay {
-                composite_op: PaintCompositeOp::SrcIn,
-                contents: PaintContents::Gradient(gradient),
-            }),
+            overlay: Some(PaintOverlay::from_gradient(gradient, PaintCompositeOp::SrcIn)),
+            second_overlay: None,
         }
     }
 
@@ -138,10 +143,8 @@ impl Paint {
     pub fn from_pattern(pattern: Pattern) -> Paint {
         Paint {
             base_color: ColorU::white(),
-            overlay: Some(PaintOverlay {
-                composite_op: PaintCompositeOp::SrcIn,
-                contents: PaintContents::Pattern(pattern),
-            }),
+            overlay: Some(PaintOverlay::from_pattern(pattern, PaintCompositeOp::SrcIn)),
+            second_overlay: None,
         }
     }
 
@@ -165,15 +168,17 @@ impl Paint {
             return false;
         }
 
-        match self.overlay {
-            None => true,
-            Some(ref overlay) => {
-                match overlay.contents {
-                    PaintContents::Gradient(ref gradient) => gradient.is_opaque(),
-                    PaintContents::Pattern(ref pattern) => pattern.is_opaque(),
+        [&self.overlay, &self.second_overlay].iter().all(|overlay| {
+            match **overlay {
+                None => true,
+                Some(ref overlay) => {
+                    match overlay.contents {
+                        PaintContents::Gradient(ref gradient) => gradient.is_opaque(),
+                        PaintContents::Pattern(ref pattern) => pattern.is_opaque(),
+                    }
                 }
             }
-        }
+        })
     }
 
     /// Returns true if this paint is fully transparent, via a quick check.
@@ -198,22 +203,22 @@ impl Paint {
     /// Returns true if this paint represents a solid color.
     #[inline]
     pub fn is_color(&self) -> bool {
-        self.overlay.is_none()
+        self.overlay.is_none() && self.second_overlay.is_none()
     }
 
     /// Applies an affine transform to this paint.
     ///
-    /// This has no effect if this paint is a solid color.
+    /// This has no effect if this paint is a solid color or a pattern positioned in device space.
     pub fn apply_transform(&mut self, transform: &Transform2F) {
         if transform.is_identity() {
             return;
         }
 
         if let Some(ref mut overlay) = self.overlay {
-            match overlay.contents {
-                PaintContents::Gradient(ref mut gradient) => gradient.apply_transform(*transform),
-                PaintContents::Pattern(ref mut pattern) => pattern.apply_transform(*transform),
-            }
+            overlay.apply_transform(transform);
+        }
+        if let Some(ref mut overlay) = self.second_overlay {
+            overlay.apply_transform(transform);
         }
     }
 
@@ -246,6 +251,24 @@ impl Paint {
         &mut self.overlay
     }
 
+    /// Returns the second paint overlay, which is combined with the result of the first overlay
+    /// and the base color in the same pass.
+    ///
+    /// The second overlay is sampled without a filter, so it should be a pattern without a filter
+    /// or a linear gradient. Any other second overlay is drawn as if it had no filter, and a
+    /// warning is logged when the scene is built.
+    #[inline]
+    pub fn second_overlay(&self) -> &Option<PaintOverlay> {
+        &self.second_overlay
+    }
+
+    /// Returns a mutable reference to the second paint overlay, which is combined with the result
+    /// of the first overlay and the base color in the same pass.
+    #[inline]
+    pub fn second_overlay_mut(&mut self) -> &mut Option<PaintOverlay> {
+        &mut self.second_overlay
+    }
+
     /// Returns the pattern, if this paint represents one.
     #[inline]
     pub fn pattern(&self) -> Option<&Pattern> {
@@ -290,11 +313,34 @@ impl Paint {
 }
 
 impl PaintOverlay {
+    /// Creates an overlay from a gradient, composited with the given operation.
+    #[inline]
+    pub fn from_gradient(gradient: Gradient, composite_op: PaintCompositeOp) -> PaintOverlay {
+        PaintOverlay { composite_op, contents: PaintContents::Gradient(gradient) }
+    }
+
+    /// Creates an overlay from a raster pattern, composited with the given operation.
+    #[inline]
+    pub fn from_pattern(pattern: Pattern, composite_op: PaintCompositeOp) -> PaintOverlay {
+        PaintOverlay { composite_op, contents: PaintContents::Pattern(pattern) }
+    }
+
     #[inline]
     pub(crate) fn contents(&self) -> &PaintContents {
         &self.contents
     }
 
+    fn apply_transform(&mut self, transform: &Transform2F) {
+        match self.contents {
+            PaintContents::Gradient(ref mut gradient) => gradient.apply_transform(*transform),
+            PaintContents::Pattern(ref mut pattern) => {
+                if !pattern.device_space() {
+                    pattern.apply_transform(*transform)
+                }
+            }
+        }
+    }
+
     /// Returns the composite operation, which defines how the overlay is to be composited on top
     /// of the base color.
     #[inline]
@@ -323,6 +369,8 @@ pub(crate) struct PaintInfo {
 pub(crate) struct PaintMetadata {
     /// Metadata associated with the color texture, if applicable.
     pub(crate) color_texture_metadata: Option<PaintColorTextureMetadata>,
+    /// Metadata associated with the second color texture, if applicable.
+    pub(crate) color_texture_metadata_1: Option<PaintColorTextureMetadata>,
     /// The base color that the color texture gets mixed into.
     pub(crate) base_color: ColorU,
     pub(crate) blend_mode: BlendMode,
@@ -360,8 +408,8 @@ pub(crate) struct RadialGradientMetadata {
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct RenderTargetMetadata {
-    /// The location of the render target.
-    pub(crate) location: TextureLocation,
+    /// The location of the render target, or `None` if it's trivial and so isn't allocated.
+    pub(crate) location: Option<TextureLocation>,
 }
 
 #[derive(Debug)]
@@ -373,6 +421,12 @@ pub(crate) enum PaintFilter {
         /// The radii of the two circles.
         radii: F32x2,
     },
+    ConicGradient {
+        /// The point that the gradient sweeps around.
+        center: Vector2F,
+        /// The angle at which the gradient starts.
+        start_angle: f32,
+    },
     PatternFilter(PatternFilter),
 }
 
@@ -397,12 +451,15 @@ impl Palette {
 
     pub(crate) fn build_paint_info(&mut self,
                                    texture_manager: &mut PaintTextureManager,
-                                   render_transform: Transform2F)
+                                   render_transform: Transform2F,
+                                   trivial_render_targets: &TrivialRenderTargets)
                                    -> PaintInfo {
         // Assign render target locations.
         let mut transient_paint_locations = vec![];
         let render_target_metadata =
-            self.assign_render_target_locations(texture_manager, &mut transient_paint_locations);
+            self.assign_render_target_locations(texture_manager,
+                                                &mut transient_paint_locations,
+                                                trivial_render_targets);
 
         // Assign paint locations.
         let PaintLocationsInfo {
@@ -439,12 +496,20 @@ impl Palette {
 
     fn assign_render_target_locations(&self,
                                       texture_manager: &mut PaintTextureManager,
-                                      transient_paint_locations: &mut Vec<TextureLocation>)
+                                      transient_paint_locations: &mut Vec<TextureLocation>,
+                                      trivial_render_targets: &TrivialRenderTargets)
                                       -> Vec<RenderTargetMetadata> {
         let mut render_target_metadata = vec![];
-        for render_target in &self.render_targets {
+        for (index, render_target) in self.render_targets.iter().enumerate() {
+            // Trivial render targets are never drawn to, so they need no texture.
+            let id = RenderTargetId { scene: self.scene_id.0, render_target: index as u32 };
+            if trivial_render_targets.contains_render_target(id) {
+                render_target_metadata.push(RenderTargetMetadata { location: None });
+                continue;
+            }
+
             let location = texture_manager.allocator.allocate_image(render_target.size());
-            render_target_metadata.push(RenderTargetMetadata { location });
+            render_target_metadata.push(RenderTargetMetadata { location: Some(location) });
             transient_paint_locations.push(location);
         }
         render_target_metadata
@@ -459,114 +524,134 @@ impl Palette {
         let mut gradient_tile_builder = GradientTileBuilder::new();
         let mut image_texel_info = vec![];
         let mut used_image_hashes = HashSet::new();
-        for paint in &self.paints {
+        let mut assign_overlay_location = |overlay: &PaintOverlay| {
             let allocator = &mut texture_manager.allocator;
-            let color_texture_metadata = match paint.overlay {
-                None => None,
-                Some(ref overlay) => {
-                    match overlay.contents {
-                        PaintContents::Gradient(ref gradient) => {
-                            let mut sampling_flags = TextureSamplingFlags::empty();
-                            match gradient.wrap {
-                                GradientWrap::Repeat => {
-                                    sampling_flags.insert(TextureSamplingFlags::REPEAT_U);
-                                }
-                                GradientWrap::Clamp => {}
-                            }
+            match overlay.contents {
+                PaintContents::Gradient(ref gradient) => {
+                    let mut sampling_flags = TextureSamplingFlags::empty();
+                    match gradient.wrap {
+                        GradientWrap::Repeat => {
+                            sampling_flags.insert(TextureSamplingFlags::REPEAT_U);
+                        }
+                        GradientWrap::Clamp => {}
+                    }
 
-                            // FIXME(pcwalton): The gradient size might not be big enough. Detect
-                            // this.
-                            let location =
-                                gradient_tile_builder.allocate(allocator,
-                                                               transient_paint_locations,
-                                                               gradient);
-                            Some(PaintColorTextureMetadata {
-                                location,
-                                page_scale: allocator.page_scale(location.page),
-                                sampling_flags,
-                                filter: match gradient.geometry {
-                                    GradientGeometry::Linear(_) => PaintFilter::None,
-                                    GradientGeometry::Radial { line, radii, .. } => {
-                                        PaintFilter::RadialGradient { line, radii }
-                                    }
-                                },
-                                transform: Transform2F::default(),
-                                composite_op: overlay.composite_op(),
-                                border: Vector2I::zero(),
-                            })
+                    // FIXME(pcwalton): The gradient size might not be big enough. Detect this.
+                    let location =
+                        gradient_tile_builder.allocate(allocator,
+                                                       transient_paint_locations,
+                                                       gradient);
+                    Some(PaintColorTextureMetadata {
+                        location,
+                        page_scale: allocator.page_scale(location.page),
+                        sampling_flags,
+                        filter: match gradient.geometry {
+                            GradientGeometry::Linear(_) => PaintFilter::None,
+                            GradientGeometry::Radial { line, radii, .. } => {
+                                PaintFilter::RadialGradient { line, radii }
+                            }
+                            GradientGeometry::Conic { center, start_angle, .. } => {
+                                PaintFilter::ConicGradient { center, start_angle }
+                            }
+                        },
+                        transform: Transform2F::default(),
+                        composite_op: overlay.composite_op(),
+                        border: Vector2I::zero(),
+                    })
+                }
+                PaintContents::Pattern(ref pattern) => {
+                    let border = vec2i(if pattern.repeat_x() { 0 } else { 1 },
+                                       if pattern.repeat_y() { 0 } else { 1 });
+
+                    let location;
+                    match *pattern.source() {
+                        PatternSource::RenderTarget { id: render_target_id, .. } => {
+                            let index = render_target_id.render_target as usize;
+                            location = render_target_metadata[index].location;
                         }
-                        PaintContents::Pattern(ref pattern) => {
-                            let border = vec2i(if pattern.repeat_x() { 0 } else { 1 },
-                                               if pattern.repeat_y() { 0 } else { 1 });
-
-                            let location;
-                            match *pattern.source() {
-                                PatternSource::RenderTarget { id: render_target_id, .. } => {
-                                    let index = render_target_id.render_target as usize;
-                                    location = render_target_metadata[index].location;
+                        PatternSource::Image(ref image) => {
+                            // TODO(pcwalton): We should be able to use tile cleverness to repeat
+                            // inside the atlas in some cases.
+                            let image_hash = image.get_hash();
+                            let image_location;
+                            match texture_manager.cached_images.get(&image_hash) {
+                                Some(cached_location) => {
+                                    image_location = *cached_location;
+                                    used_image_hashes.insert(image_hash);
                                 }
-                                PatternSource::Image(ref image) => {
-                                    // TODO(pcwalton): We should be able to use tile cleverness to
-                                    // repeat inside the atlas in some cases.
-                                    let image_hash = image.get_hash();
-                                    match texture_manager.cached_images.get(&image_hash) {
-                                        Some(cached_location) => {
-                                            location = *cached_location;
-                                            used_image_hashes.insert(image_hash);
-                                        }
-                                        None => {
-                                            // Leave a pixel of border on the side.
-                                            let allocation_mode = AllocationMode::OwnPage;
-                                            location = allocator.allocate(
-                                                image.size() + border * 2,
-                                                allocation_mode);
-                                            texture_manager.cached_images.insert(image_hash,
-                                                                                 location);
-                                        }
-                                    }
-                                    image_texel_info.push(ImageTexelInfo {
-                                        location: TextureLocation {
-                                            page: location.page,
-                                            rect: location.rect.contract(border),
-                                        },
-                                        texels: (*image.pixels()).clone(),
-                                    });
+                                None => {
+                                    // Leave a pixel of border on the side.
+                                    let allocation_mode = AllocationMode::OwnPage;
+                                    image_location = allocator.allocate(
+                                        image.size() + border * 2,
+                                        allocation_mode);
+                                    texture_manager.cached_images.insert(image_hash,
+                                                                         image_location);
                                 }
                             }
+                            image_texel_info.push(ImageTexelInfo {
+                                location: TextureLocation {
+                                    page: image_location.page,
+                                    rect: image_location.rect.contract(border),
+                                },
+                                texels: (*image.pixels()).clone(),
+                            });
+                            location = Some(image_location);
+                        }
+                    }
 
-                            let mut sampling_flags = TextureSamplingFlags::empty();
-                            if pattern.repeat_x() {
-                                sampling_flags.insert(TextureSamplingFlags::REPEAT_U);
-                            }
-                            if pattern.repeat_y() {
-                                sampling_flags.insert(TextureSamplingFlags::REPEAT_V);
-                            }
-                            if !pattern.smoothing_enabled() {
-                                sampling_flags.insert(TextureSamplingFlags::NEAREST_MIN |
-                                                    TextureSamplingFlags::NEAREST_MAG);
-                            }
+                    let mut sampling_flags = TextureSamplingFlags::empty();
+                    if pattern.repeat_x() {
+                        sampling_flags.insert(TextureSamplingFlags::REPEAT_U);
+                    }
+                    if pattern.repeat_y() {
+                        sampling_flags.insert(TextureSamplingFlags::REPEAT_V);
+                    }
+                    if !pattern.smoothing_enabled() {
+                        sampling_flags.insert(TextureSamplingFlags::NEAREST_MIN |
+                                              TextureSamplingFlags::NEAREST_MAG);
+                    }
 
-                            let filter = match pattern.filter() {
-                                None => PaintFilter::None,
-                                Some(pattern_filter) => PaintFilter::PatternFilter(pattern_filter),
-                            };
-
-                            Some(PaintColorTextureMetadata {
-                                location,
-                                page_scale: allocator.page_scale(location.page),
-                                sampling_flags,
-                                filter,
-                                transform: Transform2F::from_translation(border.to_f32()),
-                                composite_op: overlay.composite_op(),
-                                border,
-                            })
+                    let filter = match pattern.filter() {
+                        None => PaintFilter::None,
+                        Some(pattern_filter) => PaintFilter::PatternFilter(pattern_filter),
+                    };
+
+                    // The paint of a trivial render target is never drawn, so it needs no texture.
+                    location.map(|location| {
+                        PaintColorTextureMetadata {
+                            location,
+                            page_scale: allocator.page_scale(location.page),
+                            sampling_flags,
+                            filter,
+                            transform: Transform2F::from_translation(border.to_f32()),
+                            composite_op: overlay.composite_op(),
+                            border,
                         }
+                    })
+                }
+            }
+        };
+
+        for paint in &self.paints {
+            let color_texture_metadata =
+                paint.overlay.as_ref().and_then(&mut assign_overlay_location);
+            let color_texture_metadata_1 =
+                paint.second_overlay.as_ref().and_then(&mut assign_overlay_location);
+
+            // Only the first overlay's filter reaches the shader.
+            if let Some(ref color_texture_metadata_1) = color_texture_metadata_1 {
+                match color_texture_metadata_1.filter {
+                    PaintFilter::None => {}
+                    ref filter => {
+                        warn!("the filter of a second paint overlay is ignored: {:?}", filter)
                     }
                 }
-            };
+            }
 
             paint_metadata.push(PaintMetadata {
                 color_texture_metadata,
+                color_texture_metadata_1,
                 is_opaque: paint.is_opaque(),
                 base_color: paint.base_color(),
                 // FIXME(pcwalton)
@@ -587,60 +672,29 @@ impl Palette {
                                     texture_manager: &mut PaintTextureManager,
                                     render_transform: Transform2F) {
         for (paint, metadata) in self.paints.iter().zip(paint_metadata.iter_mut()) {
-            let mut color_texture_metadata = match metadata.color_texture_metadata {
-                None => continue,
-                Some(ref mut color_texture_metadata) => color_texture_metadata,
-            };
-
-            let texture_scale = texture_manager.allocator
-                                               .page_scale(color_texture_metadata.location.page);
-            let texture_rect = color_texture_metadata.location.rect;
-            color_texture_metadata.transform = match paint.overlay    
-                                                          .as_ref()
-                                                          .expect("Why do we have color texture \
-                                                                   metadata but no overlay?")
-                                                          .contents {
-                PaintContents::Gradient(Gradient {
-                    geometry: GradientGeometry::Linear(gradient_line),
-                    ..
-                }) => {
-                    // Project gradient line onto (0.0-1.0, v0).
-                    let v0 = texture_rect.to_f32().center().y() * texture_scale.y();
-                    let dp = gradient_line.vector();
-                    let m0 = dp.0.concat_xy_xy(dp.0) / F32x4::splat(gradient_line.square_length());
-                    let m13 = m0.zw() * -gradient_line.from().0;
-                    Transform2F::row_major(m0.x(), m0.y(), m13.x() + m13.y(), 0.0, 0.0, v0)
-                }
-                PaintContents::Gradient(Gradient {
-                    geometry: GradientGeometry::Radial { ref transform, .. },
-                    ..
-                }) => transform.inverse(),
-                PaintContents::Pattern(ref pattern) => {
-                    match pattern.source() {
-                        PatternSource::Image(_) => {
-                            let texture_origin_uv =
-                                rect_to_uv(texture_rect, texture_scale).origin();
-                            Transform2F::from_scale(texture_scale).translate(texture_origin_uv) *
-                                pattern.transform().inverse()
-                        }
-                        PatternSource::RenderTarget { .. } => {
-                            // FIXME(pcwalton): Only do this in GL, not Metal!
-                            let texture_origin_uv =
-                                rect_to_uv(texture_rect, texture_scale).lower_left();
-                            Transform2F::from_translation(texture_origin_uv) *
-                                Transform2F::from_scale(texture_scale * vec2f(1.0, -1.0)) *
-                                pattern.transform().inverse()
-                        }
-                    }
-                }
-            };
-            color_texture_metadata.transform *= render_transform;
+            let mut overlays = [(&paint.overlay, &mut metadata.color_texture_metadata),
+                            (&paint.second_overlay, &mut metadata.color_texture_metadata_1)];
+            for (overlay, color_texture_metadata) in overlays.iter_mut() {
+                let color_texture_metadata = match **color_texture_metadata {
+                    None => continue,
+                    Some(ref mut color_texture_metadata) => color_texture_metadata,
+                };
+                let overlay = overlay.as_ref().expect("Why do we have color texture metadata but \
+                                                       no overlay?");
+                color_texture_metadata.transform =
+                    overlay_texture_transform(overlay, color_texture_metadata, texture_manager) *
+                    render_transform;
+            }
         }
     }
 
     fn create_texture_metadata(&self, paint_metadata: &[PaintMetadata])
                                -> Vec<TextureMetadataEntry> {
-        paint_metadata.iter().map(|paint_metadata| {
+        paint_metadata.iter().zip(self.paints.iter()).map(|(paint_metadata, paint)| {
+            let color_0_is_gradient = match *paint.overlay() {
+                Some(PaintOverlay { contents: PaintContents::Gradient(_), .. }) => true,
+                _ => false,
+            };
             TextureMetadataEntry {
                 color_0_transform: match paint_metadata.color_texture_metadata {
                     None => Transform2F::default(),
@@ -651,6 +705,20 @@ impl Palette {
                 } else {
                     ColorCombineMode::None
                 },
+                color_0_is_gradient,
+                color_1_transform: match paint_metadata.color_texture_metadata_1 {
+                    None => Transform2F::default(),
+                    Some(ref color_texture_metadata) => color_texture_metadata.transform,
+                },
+                color_1_combine_mode: match paint_metadata.color_texture_metadata_1 {
+                    None => ColorCombineMode::None,
+                    Some(ref color_texture_metadata) => {
+                        match color_texture_metadata.composite_op {
+                            PaintCompositeOp::SrcIn => ColorCombineMode::SrcIn,
+                            PaintCompositeOp::DestIn => ColorCombineMode::DestIn,
+                        }
+                    }
+                },
                 base_color: paint_metadata.base_color,
                 filter: paint_metadata.filter(),
                 blend_mode: paint_metadata.blend_mode,
@@ -678,11 +746,12 @@ impl Palette {
                               gradient_tile_builder: GradientTileBuilder,
                               image_texel_info: Vec<ImageTexelInfo>) {
         for (index, metadata) in render_target_metadata.iter().enumerate() {
+            let location = match metadata.location {
+                None => continue,
+                Some(location) => location,
+            };
             let id = RenderTargetId { scene: self.scene_id.0, render_target: index as u32 };
-            render_commands.push(RenderCommand::DeclareRenderTarget {
-                id,
-                location: metadata.location,
-            });
+            render_commands.push(RenderCommand::DeclareRenderTarget { id, location });
         }
         gradient_tile_builder.create_render_commands(render_commands);
         for image_texel_info in image_texel_info {
@@ -748,7 +817,10 @@ impl Palette {
                                     new_pattern.set_repeat_x(pattern.repeat_x());
                                     new_pattern.set_repeat_y(pattern.repeat_y());
                                     new_pattern.set_smoothing_enabled(pattern.smoothing_enabled());
-                                    self.push_paint(&Paint::from_pattern(new_pattern))
+                                    new_pattern.set_device_space(pattern.device_space());
+                                    let mut new_paint = Paint::from_pattern(new_pattern);
+                                    new_paint.second_overlay = old_paint.second_overlay.clone();
+                                    self.push_paint(&new_paint)
                                 }
                                 _ => self.push_paint(old_paint),
                             }
@@ -764,6 +836,54 @@ impl Palette {
     }
 }
 
+// Returns the transform from scene coordinates to UVs in the texture of the given overlay.
+fn overlay_texture_transform(overlay: &PaintOverlay,
+                             color_texture_metadata: &PaintColorTextureMetadata,
+                             texture_manager: &PaintTextureManager)
+                             -> Transform2F {
+    let texture_scale = texture_manager.allocator.page_scale(color_texture_metadata.location.page);
+    let texture_rect = color_texture_metadata.location.rect;
+    match overlay.contents {
+        PaintContents::Gradient(Gradient {
+            geometry: GradientGeometry::Linear(gradient_line),
+            ..
+        }) => {
+            // Project gradient line onto (0.0-1.0, v0).
+            let v0 = texture_rect.to_f32().center().y() * texture_scale.y();
+            let dp = gradient_line.vector();
+            let m0 = dp.0.concat_xy_xy(dp.0) / F32x4::splat(gradient_line.square_length());
+            let m13 = m0.zw() * -gradient_line.from().0;
+            Transform2F::row_major(m0.x(), m0.y(), m13.x() + m13.y(), 0.0, 0.0, v0)
+        }
+        PaintContents::Gradient(Gradient {
+            geometry: GradientGeometry::Radial { ref transform, .. },
+            ..
+        }) |
+        PaintContents::Gradient(Gradient {
+            geometry: GradientGeometry::Conic { ref transform, .. },
+            ..
+        }) => transform.inverse(),
+        PaintContents::Pattern(ref pattern) => {
+            match pattern.source() {
+                PatternSource::Image(_) => {
+                    let texture_origin_uv =
+                        rect_to_uv(texture_rect, texture_scale).origin();
+                    Transform2F::from_scale(texture_scale).translate(texture_origin_uv) *
+                        pattern.transform().inverse()
+                }
+                PatternSource::RenderTarget { .. } => {
+                    // FIXME(pcwalton): Only do this in GL, not Metal!
+                    let texture_origin_uv =
+                        rect_to_uv(texture_rect, texture_scale).lower_left();
+                    Transform2F::from_translation(texture_origin_uv) *
+                        Transform2F::from_scale(texture_scale * vec2f(1.0, -1.0)) *
+                        pattern.transform().inverse()
+                }
+            }
+        }
+    }
+}
+
 impl PaintTextureManager {
     pub(crate) fn new() -> PaintTextureManager {
         PaintTextureManager {
@@ -791,6 +911,13 @@ impl PaintMetadata {
                             vec2f(0.0, color_metadata.page_scale.y() * 0.5));
                         Filter::RadialGradient { line, radii, uv_origin: uv_rect.origin() }
                     }
+                    PaintFilter::ConicGradient { center, start_angle } => {
+                        let uv_rect = rect_to_uv(color_metadata.location.rect,
+                                                 color_metadata.page_scale).contract(
+                            vec2f(0.0, color_metadata.page_scale.y() * 0.5));
+                        let uv_origin = uv_rect.origin();
+                        Filter::ConicGradient { center, start_angle, uv_origin }
+                    }
                     PaintFilter::PatternFilter(pattern_filter) => {
                         Filter::PatternFilter(pattern_filter)
                     }
@@ -802,6 +929,12 @@ impl PaintMetadata {
     pub(crate) fn tile_batch_texture(&self) -> Option<TileBatchTexture> {
         self.color_texture_metadata.as_ref().map(PaintColorTextureMetadata::as_tile_batch_texture)
     }
+
+    pub(crate) fn tile_batch_texture_1(&self) -> Option<TileBatchTexture> {
+        self.color_texture_metadata_1
+            .as_ref()
+            .map(PaintColorTextureMetadata::as_tile_batch_texture)
+    }
 }
 
 fn rect_to_uv(rect: RectI, texture_scale: Vector2F) -> RectF {
@@ -17,14 +17,17 @@ use crate::gpu::renderer::Renderer;
 use crate::gpu_data::RenderCommand;
 use crate::options::{BuildOptions, PreparedBuildOptions};
 use crate::options::{PreparedRenderTransform, RenderCommandListener};
-use crate::paint::{MergedPaletteInfo, Paint, PaintId, PaintInfo, PaintTextureManager, Palette};
-use pathfinder_content::effects::BlendMode;
+use crate::paint::{MergedPaletteInfo, Paint, PaintCompositeOp, PaintContents, PaintId, PaintInfo};
+use crate::paint::{PaintOverlay, PaintTextureManager, Palette};
+use pathfinder_color::ColorU;
+use pathfinder_content::effects::{BlendMode, BlurDirection, DropShadow, PatternFilter};
 use pathfinder_content::fill::FillRule;
 use pathfinder_content::outline::Outline;
+use pathfinder_content::pattern::{Pattern, PatternSource};
 use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
-use pathfinder_geometry::vector::{Vector2I, vec2f};
+use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f};
 use pathfinder_gpu::Device;
 use std::mem;
 use std::ops::Range;
@@ -40,9 +43,11 @@ pub struct Scene {
     display_list: Vec<DisplayItem>,
     draw_paths: Vec<DrawPath>,
     clip_paths: Vec<ClipPath>,
+    meshes: Vec<Mesh>,
     palette: Palette,
     bounds: RectF,
     view_box: RectF,
+    dirty_bounds: Option<RectF>,
     id: SceneId,
     epoch: SceneEpoch,
 }
@@ -60,14 +65,33 @@ impl Scene {
             display_list: vec![],
             draw_paths: vec![],
             clip_paths: vec![],
+            meshes: vec![],
             palette: Palette::new(scene_id),
             bounds: RectF::default(),
             view_box: RectF::default(),
+            dirty_bounds: None,
             id: scene_id,
             epoch: SceneEpoch::new(0, 1),
         }
     }
 
+    /// Creates a new scene with the given view box, filled with the given paths.
+    ///
+    /// Paths are drawn in iteration order, so later paths are drawn on top of earlier ones.
+    /// Identical paints are only added to the scene once.
+    pub fn from_paths<I>(view_box: RectF, paths: I) -> Scene
+                         where I: IntoIterator<Item = (Outline, Paint, FillRule)> {
+        let mut scene = Scene::new();
+        scene.set_view_box(view_box);
+        for (outline, paint, fill_rule) in paths {
+            let paint_id = scene.push_paint(&paint);
+            let mut draw_path = DrawPath::new(outline, paint_id);
+            draw_path.set_fill_rule(fill_rule);
+            scene.push_draw_path(draw_path);
+        }
+        scene
+    }
+
     /// Adds a path to the scene, to be drawn on top of all previously-added paths.
     ///
     /// If a render target is on the stack (see `push_render_target()`), the path goes to the
@@ -82,8 +106,13 @@ impl Scene {
     }
 
     fn push_draw_path_with_index(&mut self, draw_path_id: DrawPathId) {
-        let new_path_bounds = self.draw_paths[draw_path_id.0 as usize].outline.bounds();
+        let new_path_outline = &self.draw_paths[draw_path_id.0 as usize].outline;
+        let (new_path_bounds, new_path_is_empty) =
+            (new_path_outline.bounds(), new_path_outline.is_empty());
         self.bounds = self.bounds.union_rect(new_path_bounds);
+        if !new_path_is_empty {
+            self.mark_dirty(new_path_bounds);
+        }
 
         let end_path_id = DrawPathId(draw_path_id.0 + 1);
         match self.display_list.last_mut() {
@@ -94,6 +123,95 @@ impl Scene {
         self.epoch.next();
     }
 
+    /// Adds a mesh of triangles with per-vertex colors to the scene, to be drawn on top of all
+    /// previously-added paths and meshes.
+    ///
+    /// Each vertex is a position and a color; colors are interpolated linearly across each
+    /// triangle. Every three entries of `indices` refer to the vertices of one triangle.
+    ///
+    /// Meshes don't participate in tiling: they're rasterized as plain triangles, so their edges
+    /// aren't antialiased, and they can't be clipped, blended with modes other than normal, or
+    /// filtered. They are, however, transformed along with paths when the scene is built.
+    ///
+    /// Like paths, meshes go to the render target on top of the stack, if any.
+    pub fn push_mesh(&mut self, vertices: &[(Vector2F, ColorU)], indices: &[u32]) -> MeshId {
+        debug_assert!(indices.len() % 3 == 0, "Mesh indices must come in groups of three!");
+        debug_assert!(indices.iter().all(|&index| (index as usize) < vertices.len()),
+                      "Mesh index out of bounds!");
+
+        let mesh = Mesh {
+            positions: vertices.iter().map(|&(position, _)| position).collect(),
+            colors: vertices.iter().map(|&(_, color)| color).collect(),
+            indices: indices.to_vec(),
+        };
+        let mesh_id = MeshId(self.meshes.len() as u32);
+        self.push_mesh_with_id(mesh, mesh_id);
+        mesh_id
+    }
+
+    fn push_mesh_with_id(&mut self, mesh: Mesh, mesh_id: MeshId) {
+        if let Some(mesh_bounds) = mesh.bounds() {
+            self.bounds = self.bounds.union_rect(mesh_bounds);
+            self.mark_dirty(mesh_bounds);
+        }
+        self.meshes.push(mesh);
+        self.display_list.push(DisplayItem::DrawMesh(mesh_id));
+        self.epoch.next();
+    }
+
+    /// Replaces the outline of a previously-added draw path.
+    ///
+    /// Both the old and the new bounds of the path are added to the dirty region (see
+    /// `dirty_bounds()`). To remove a path from the output, replace its outline with an empty one;
+    /// its former bounds are then dirtied.
+    pub fn set_draw_path_outline(&mut self, draw_path_id: DrawPathId, new_outline: Outline) {
+        let draw_path = &mut self.draw_paths[draw_path_id.0 as usize];
+        let old_outline = mem::replace(&mut draw_path.outline, new_outline);
+        let new_outline = &draw_path.outline;
+        let (new_bounds, new_is_empty) = (new_outline.bounds(), new_outline.is_empty());
+
+        if !old_outline.is_empty() {
+            self.mark_dirty(old_outline.bounds());
+        }
+        if !new_is_empty {
+            self.bounds = self.bounds.union_rect(new_bounds);
+            self.mark_dirty(new_bounds);
+        }
+        self.epoch.next();
+    }
+
+    /// Returns the union of the bounds of everything drawn or changed since the scene was last
+    /// built or `clear_dirty()` was called, or `None` if nothing changed.
+    ///
+    /// The bounds are in scene coordinates, before the transform supplied in the build options is
+    /// applied. Adding paths or meshes, changing path outlines, and changing the view box all
+    /// expand the dirty region, as do removals, which dirty the former bounds of what was removed.
+    /// The region is conservative: paths drawn into render targets are included even though they
+    /// only become visible when the render target is composited. Because building resets the
+    /// region, query it before calling `build()` if it's needed to limit redrawing.
+    #[inline]
+    pub fn dirty_bounds(&self) -> Option<RectF> {
+        self.dirty_bounds
+    }
+
+    /// Adds a rectangle, in scene coordinates, to the dirty region.
+    ///
+    /// Use this to record changes that the scene can't see; for example, after rebuilding part of
+    /// a scene from scratch.
+    pub fn mark_dirty(&mut self, rect: RectF) {
+        self.dirty_bounds = Some(match self.dirty_bounds {
+            None => rect,
+            Some(dirty_bounds) => dirty_bounds.union_rect(rect),
+        });
+    }
+
+    /// Resets the dirty region, so that `dirty_bounds()` returns `None` until the scene changes
+    /// again.
+    #[inline]
+    pub fn clear_dirty(&mut self) {
+        self.dirty_bounds = None;
+    }
+
     /// Defines a clip path. Returns an ID that can be used to later clip draw paths.
     pub fn push_clip_path(&mut self, clip_path: ClipPath) -> ClipPathId {
         self.bounds = self.bounds.union_rect(clip_path.outline.bounds());
@@ -122,6 +240,83 @@ impl Scene {
         self.display_list.push(DisplayItem::PopRenderTarget);
     }
 
+    /// Adds a path to the scene, with a drop shadow drawn underneath it.
+    ///
+    /// The shadow has the shape of the path, displaced by `shadow.offset`, and the color of the
+    /// shadow, modulated by the alpha of the path's paint. If `shadow.sigma` is positive, the
+    /// shadow is blurred by rendering it into a pair of render targets for the horizontal and
+    /// vertical blur passes. The shadow uses the fill rule, blend mode, and clip path of the path.
+    ///
+    /// Returns the ID of the path itself.
+    pub fn push_draw_path_with_drop_shadow(&mut self, draw_path: DrawPath, shadow: &DropShadow)
+                                           -> DrawPathId {
+        if !shadow.color.is_fully_transparent() {
+            self.push_drop_shadow(&draw_path, shadow);
+        }
+        self.push_draw_path(draw_path)
+    }
+
+    fn push_drop_shadow(&mut self, draw_path: &DrawPath, shadow: &DropShadow) {
+        let mut outline = draw_path.outline.clone();
+        outline.transform(&Transform2F::from_translation(shadow.offset));
+
+        // Per the canvas spec, the shadow must respect the alpha of the shadowed path, but
+        // otherwise have the color of the shadow.
+        let mut shadow_paint = self.get_paint(draw_path.paint).clone();
+        let mut shadow_color = shadow.color.to_f32();
+        shadow_color.set_a(shadow_color.a() * shadow_paint.base_color().a as f32 / 255.0);
+        shadow_paint.set_base_color(shadow_color.to_u8());
+        if let &mut Some(ref mut shadow_paint_overlay) = shadow_paint.overlay_mut() {
+            shadow_paint_overlay.set_composite_op(PaintCompositeOp::DestIn);
+        }
+        let shadow_paint_id = self.push_paint(&shadow_paint);
+
+        if shadow.sigma <= 0.0 {
+            let mut path = DrawPath::new(outline, shadow_paint_id);
+            path.set_clip_path(draw_path.clip_path);
+            path.set_fill_rule(draw_path.fill_rule);
+            path.set_blend_mode(draw_path.blend_mode);
+            self.push_draw_path(path);
+            return;
+        }
+
+        // Leave room for the tails of the blur.
+        let bounds = outline.bounds().dilate(shadow.sigma * 3.0).round_out().to_i32();
+        outline.transform(&Transform2F::from_translation(-bounds.origin().to_f32()));
+
+        let render_target_y = RenderTarget::new(bounds.size(), String::new());
+        let render_target_id_y = self.push_render_target(render_target_y);
+        let render_target_x = RenderTarget::new(bounds.size(), String::new());
+        let render_target_id_x = self.push_render_target(render_target_x);
+
+        let mut path = DrawPath::new(outline, shadow_paint_id);
+        path.set_fill_rule(draw_path.fill_rule);
+        path.set_blend_mode(draw_path.blend_mode);
+        self.push_draw_path(path);
+
+        let mut pattern_x = Pattern::from_render_target(render_target_id_x, bounds.size());
+        let mut pattern_y = Pattern::from_render_target(render_target_id_y, bounds.size());
+        pattern_y.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
+
+        let sigma = shadow.sigma;
+        pattern_x.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::X, sigma }));
+        pattern_y.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::Y, sigma }));
+
+        let paint_id_x = self.push_paint(&Paint::from_pattern(pattern_x));
+        let paint_id_y = self.push_paint(&Paint::from_pattern(pattern_y));
+
+        let outline_x = Outline::from_rect(RectF::new(vec2f(0.0, 0.0), bounds.size().to_f32()));
+        let path_x = DrawPath::new(outline_x, paint_id_x);
+        let outline_y = Outline::from_rect(bounds.to_f32());
+        let mut path_y = DrawPath::new(outline_y, paint_id_y);
+        path_y.set_clip_path(draw_path.clip_path);
+
+        self.pop_render_target();
+        self.push_draw_path(path_x);
+        self.pop_render_target();
+        self.push_draw_path(path_y);
+    }
+
     /// Adds all elements in a scene to this one.
     ///
     /// This includes draw paths, clip paths, render targets, and paints.
@@ -151,10 +346,12 @@ impl Scene {
                 fill_rule: draw_path.fill_rule,
                 blend_mode: draw_path.blend_mode,
                 name: draw_path.name,
+                annotation: draw_path.annotation,
             });
         }
 
         // Merge display items.
+        let mut meshes = scene.meshes.into_iter().map(Some).collect::<Vec<_>>();
         for display_item in scene.display_list {
             match display_item {
                 DisplayItem::PushRenderTarget(old_render_target_id) => {
@@ -170,6 +367,11 @@ impl Scene {
                         self.push_draw_path_with_index(old_draw_path_id);
                     }
                 }
+                DisplayItem::DrawMesh(old_mesh_id) => {
+                    let mesh = meshes[old_mesh_id.0 as usize].take().unwrap();
+                    let new_mesh_id = MeshId(self.meshes.len() as u32);
+                    self.push_mesh_with_id(mesh, new_mesh_id);
+                }
             }
         }
 
@@ -180,9 +382,10 @@ impl Scene {
     #[inline]
     pub(crate) fn build_paint_info(&mut self,
                                    texture_manager: &mut PaintTextureManager,
-                                   render_transform: Transform2F)
+                                   render_transform: Transform2F,
+                                   trivial_render_targets: &TrivialRenderTargets)
                                    -> PaintInfo {
-        self.palette.build_paint_info(texture_manager, render_transform)
+        self.palette.build_paint_info(texture_manager, render_transform, trivial_render_targets)
     }
 
     /// Defines a new paint, which specifies how paths are to be filled or stroked. Returns a paint
@@ -218,9 +421,12 @@ impl Scene {
     }
 
     /// Changes the view box, which defines the visible portion of the scene.
+    ///
+    /// This can be overridden when building the scene with `BuildOptions::view_box`.
     #[inline]
     pub fn set_view_box(&mut self, new_view_box: RectF) {
         self.view_box = new_view_box;
+        self.mark_dirty(new_view_box);
         self.epoch.next();
     }
 
@@ -272,12 +478,20 @@ impl Scene {
         outline
     }
 
+    // The view box to cull against: the one supplied at build time if present, or the scene's own
+    // view box otherwise.
+    #[inline]
+    pub(crate) fn render_view_box(&self, render_options: &PreparedBuildOptions) -> RectF {
+        render_options.view_box.unwrap_or(self.view_box)
+    }
+
     #[inline]
     pub(crate) fn effective_view_box(&self, render_options: &PreparedBuildOptions) -> RectF {
+        let view_box = self.render_view_box(render_options);
         if render_options.subpixel_aa_enabled {
-            self.view_box * vec2f(3.0, 1.0)
+            view_box * vec2f(3.0, 1.0)
         } else {
-            self.view_box
+            view_box
         }
     }
 
@@ -286,6 +500,8 @@ impl Scene {
     /// The given executor will be used to prepare these commands. Typically, this will be a
     /// `SequentialExecutor` to prepare commands on a single thread or `RayonExecutor` to prepare
     /// commands in parallel across multiple threads.
+    ///
+    /// This resets the dirty region; see `dirty_bounds()`.
     #[inline]
     pub fn build<'a, 'b, E>(&mut self,
                             options: BuildOptions,
@@ -293,7 +509,8 @@ impl Scene {
                             executor: &E)
                             where E: Executor {
         let prepared_options = options.prepare(self.bounds);
-        SceneBuilder::new(self, &prepared_options, sink).build(executor)
+        SceneBuilder::new(self, &prepared_options, sink).build(executor);
+        self.clear_dirty();
     }
 
     #[inline]
@@ -329,18 +546,99 @@ impl Scene {
         &self.clip_paths[clip_path_id.0 as usize]
     }
 
+    /// Returns the mesh with the given ID.
+    #[inline]
+    pub fn get_mesh(&self, mesh_id: MeshId) -> &Mesh {
+        &self.meshes[mesh_id.0 as usize]
+    }
+
     /// Returns the paint with the given ID.
     #[inline]
     pub fn get_paint(&self, paint_id: PaintId) -> &Paint {
         self.palette.paints.get(paint_id.0 as usize).expect("No paint with that ID!")
     }
 
+    /// Returns the ID of the topmost path drawn to the destination framebuffer that covers the
+    /// given point, in scene coordinates.
+    ///
+    /// A path covers a point if the point lies inside its outline under its fill rule and inside
+    /// its clip paths, if any. Paths drawn into render targets are not considered directly; the
+    /// path that composites the render target is hit instead. Paint opacity is ignored, and
+    /// meshes are transparent to picking.
+    pub fn pick_draw_path(&self, point: Vector2F) -> Option<DrawPathId> {
+        let mut render_target_depth = 0;
+        let mut hit = None;
+        for display_item in &self.display_list {
+            match *display_item {
+                DisplayItem::PushRenderTarget(_) => render_target_depth += 1,
+                DisplayItem::PopRenderTarget => render_target_depth -= 1,
+                DisplayItem::DrawMesh(_) => {}
+                DisplayItem::DrawPaths(ref range) if render_target_depth == 0 => {
+                    for draw_path_index in range.start.0..range.end.0 {
+                        let draw_path = &self.draw_paths[draw_path_index as usize];
+                        if draw_path.outline.contains_point(point, draw_path.fill_rule) &&
+                                self.clip_path_contains_point(draw_path.clip_path, point) {
+                            hit = Some(DrawPathId(draw_path_index));
+                        }
+                    }
+                }
+                DisplayItem::DrawPaths(_) => {}
+            }
+        }
+        hit
+    }
+
+    /// Returns the annotation of the topmost path that covers the given point, in scene
+    /// coordinates.
+    ///
+    /// This returns `None` if no path covers the point or if the topmost path that does has no
+    /// annotation. See `pick_draw_path()` for the definition of coverage and
+    /// `DrawPath::set_annotation()` for attaching annotations.
+    ///
+    /// Picking runs on the CPU against the scene's outlines. Annotations don't reach the
+    /// `RenderCommand` stream or the tile data, and there is no GPU picking mode that writes
+    /// annotation IDs to a texture: that would need an ID channel through the tiler and both
+    /// levels' tile shaders, plus an integer render target that not every device can render to.
+    #[inline]
+    pub fn pick(&self, point: Vector2F) -> Option<u64> {
+        self.pick_draw_path(point).and_then(|draw_path_id| self.get_draw_path(draw_path_id)
+                                                                .annotation)
+    }
+
+    fn clip_path_contains_point(&self, mut clip_path_id: Option<ClipPathId>, point: Vector2F)
+                                -> bool {
+        while let Some(current_clip_path_id) = clip_path_id {
+            let clip_path = self.get_clip_path(current_clip_path_id);
+            if !clip_path.outline.contains_point(point, clip_path.fill_rule) {
+                return false;
+            }
+            clip_path_id = clip_path.clip_path;
+        }
+        true
+    }
+
     /// Returns the globally-unique ID of the scene.
     #[inline]
     pub fn id(&self) -> SceneId {
         self.id
     }
 
+    /// Changes the ID of the scene.
+    ///
+    /// The renderer uses the scene ID and epoch to determine whether it can reuse the segment
+    /// data that it uploaded for the previous scene. Assigning the same ID to a scene rebuilt
+    /// from scratch lets that data be reused across reconstructions. The caller must guarantee
+    /// that scenes sharing an ID and epoch contain identical geometry; otherwise stale data will
+    /// be rendered.
+    ///
+    /// Render target IDs embed the scene ID, so this should be called before any render targets
+    /// are pushed.
+    #[inline]
+    pub fn set_id(&mut self, new_id: SceneId) {
+        self.id = new_id;
+        self.palette.set_scene_id(new_id);
+    }
+
     #[inline]
     pub(crate) fn epoch(&self) -> SceneEpoch {
         self.epoch
@@ -376,6 +674,185 @@ impl Scene {
         commands.into_iter().for_each(|command| renderer.render_command(&command));
         renderer.end_scene();
     }
+
+    /// Finds the render targets that have no visible effect, so that the scene builder can skip
+    /// them and batch the paths drawn into them together with the paths around them.
+    ///
+    /// A render target is trivial only when it is immediately composited back onto its parent by
+    /// a single axis-aligned rectangle painted with an unfiltered, untransformed pattern of the
+    /// render target at full opacity, with normal blending and no clip path. Furthermore, every
+    /// path drawn into the render target must use normal blending and lie entirely within both
+    /// the render target and the compositing rectangle, and no other path may use the render
+    /// target. Under these conditions, drawing the paths directly produces the same output.
+    pub(crate) fn find_trivial_render_targets(&self) -> TrivialRenderTargets {
+        // Count the uses of each render target and paint up front, so that each candidate can be
+        // checked in constant time.
+        let mut render_target_use_counts = vec![0; self.palette.render_targets.len()];
+        for paint in &self.palette.paints {
+            for overlay in &[paint.overlay(), paint.second_overlay()] {
+                if let Some(render_target_id) = overlay_render_target(overlay) {
+                    if let Some(count) = render_target_use_counts
+                            .get_mut(render_target_id.render_target as usize) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        let mut paint_use_counts = vec![0; self.palette.paints.len()];
+        for draw_path in &self.draw_paths {
+            paint_use_counts[draw_path.paint.0 as usize] += 1;
+        }
+
+        let mut trivial_render_targets = TrivialRenderTargets {
+            render_targets: vec![false; render_target_use_counts.len()],
+            composite_paths: vec![false; self.draw_paths.len()],
+        };
+        for item_index in 0..self.display_list.len().saturating_sub(3) {
+            if let Some((render_target_id, composite_path_id)) =
+                    self.trivial_render_target_at(item_index,
+                                                  &render_target_use_counts,
+                                                  &paint_use_counts) {
+                let render_target_index = render_target_id.render_target as usize;
+                trivial_render_targets.render_targets[render_target_index] = true;
+                trivial_render_targets.composite_paths[composite_path_id.0 as usize] = true;
+            }
+        }
+        trivial_render_targets
+    }
+
+    // If the display item at `item_index` begins a render target that can be skipped, returns
+    // its ID and the ID of the path that composites it.
+    fn trivial_render_target_at(&self,
+                                item_index: usize,
+                                render_target_use_counts: &[u32],
+                                paint_use_counts: &[u32])
+                                -> Option<(RenderTargetId, DrawPathId)> {
+        let render_target_id = match self.display_list[item_index] {
+            DisplayItem::PushRenderTarget(render_target_id) => render_target_id,
+            _ => return None,
+        };
+        let contents = match self.display_list[item_index + 1] {
+            DisplayItem::DrawPaths(ref range) => range.clone(),
+            _ => return None,
+        };
+        match self.display_list[item_index + 2] {
+            DisplayItem::PopRenderTarget => {}
+            _ => return None,
+        }
+        let composite_path_id = match self.display_list[item_index + 3] {
+            DisplayItem::DrawPaths(ref range) if range.start == contents.end => range.start,
+            _ => return None,
+        };
+
+        let composite_path = &self.draw_paths[composite_path_id.0 as usize];
+        if composite_path.blend_mode != BlendMode::SrcOver ||
+                composite_path.clip_path.is_some() ||
+                !outline_is_rect(&composite_path.outline) {
+            return None;
+        }
+
+        let paint = &self.palette.paints[composite_path.paint.0 as usize];
+        if paint.base_color() != ColorU::white() || paint.second_overlay().is_some() {
+            return None;
+        }
+        let size = match *paint.overlay() {
+            Some(ref overlay) if overlay.composite_op() == PaintCompositeOp::SrcIn => {
+                match *overlay.contents() {
+                    PaintContents::Pattern(ref pattern) if pattern.filter().is_none() &&
+                            pattern.transform().is_identity() => {
+                        match *pattern.source() {
+                            PatternSource::RenderTarget { id, size } if id == render_target_id => {
+                                size
+                            }
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // The render target must be used by this path alone.
+        let render_target_index = render_target_id.render_target as usize;
+        if render_target_use_counts.get(render_target_index) != Some(&1) ||
+                paint_use_counts[composite_path.paint.0 as usize] != 1 {
+            return None;
+        }
+
+        let render_target_rect = RectF::new(Vector2F::zero(), size.to_f32());
+        let visible_rect = render_target_rect.intersection(composite_path.outline.bounds())?;
+        for draw_path in &self.draw_paths[(contents.start.0 as usize)..(contents.end.0 as usize)] {
+            if draw_path.blend_mode != BlendMode::SrcOver ||
+                    !visible_rect.contains_rect(draw_path.outline.bounds()) {
+                return None;
+            }
+        }
+
+        Some((render_target_id, composite_path_id))
+    }
+}
+
+/// The render targets of a scene that have no visible effect and are skipped when building it.
+///
+/// See `Scene::find_trivial_render_targets()`.
+pub(crate) struct TrivialRenderTargets {
+    // Indexed by render target.
+    render_targets: Vec<bool>,
+    // Indexed by draw path. Marks the paths that composite the trivial render targets.
+    composite_paths: Vec<bool>,
+}
+
+impl TrivialRenderTargets {
+    /// Returns true if the given render target is skipped, along with its push and pop.
+    #[inline]
+    pub(crate) fn contains_render_target(&self, render_target_id: RenderTargetId) -> bool {
+        self.render_targets.get(render_target_id.render_target as usize) == Some(&true)
+    }
+
+    /// Returns true if the given path composites a skipped render target, and so isn't drawn.
+    #[inline]
+    pub(crate) fn is_composite_path(&self, draw_path_id: DrawPathId) -> bool {
+        self.composite_paths[draw_path_id.0 as usize]
+    }
+}
+
+fn outline_is_rect(outline: &Outline) -> bool {
+    let contours = outline.contours();
+    if contours.len() != 1 || contours[0].len() != 4 {
+        return false;
+    }
+    let (contour, bounds) = (&contours[0], outline.bounds());
+    for point_index in 0..4 {
+        if !contour.flags_of(point_index).is_empty() {
+            return false;
+        }
+        let point = contour.position_of(point_index);
+        let next_point = contour.position_of((point_index + 1) % 4);
+        if (point.x() != bounds.min_x() && point.x() != bounds.max_x()) ||
+                (point.y() != bounds.min_y() && point.y() != bounds.max_y()) ||
+                ((point.x() == next_point.x()) == (point.y() == next_point.y())) {
+            return false;
+        }
+    }
+    true
+}
+
+fn overlay_render_target(overlay: &Option<PaintOverlay>) -> Option<RenderTargetId> {
+    match *overlay {
+        Some(ref overlay) => {
+            match *overlay.contents() {
+                PaintContents::Pattern(ref pattern) => {
+                    match *pattern.source() {
+                        PatternSource::RenderTarget { id, .. } => Some(id),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        None => None,
+    }
 }
 
 /// Receives render commands and delivers them to a `RenderCommandListener`.
@@ -453,9 +930,18 @@ pub struct DrawPath {
     ///
     /// Pass the empty string (which does not allocate) if debugging is not needed.
     pub name: String,
+    /// An opaque value identifying the source object of this path, for picking.
+    ///
+    /// See `Scene::pick()`.
+    pub annotation: Option<u64>,
 }
 
 /// Describes a path that can be used to clip other paths.
+///
+/// Clipping is not a boolean in/out test: the anti-aliased coverage of the clip path is
+/// combined with that of the clipped path by taking the minimum of the two, on both the D3D9 and
+/// D3D11 levels. Content therefore fades out across the clip path's native anti-aliasing ramp,
+/// just as it does at the edges of an unclipped path.
 #[derive(Clone, Debug)]
 pub struct ClipPath {
     /// The actual vector path outline.
@@ -482,6 +968,7 @@ pub struct ClipPathId(pub u32);
 
 /// Either a draw path ID or a clip path ID, depending on context.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct PathId(pub u32);
 
 /// A raster image target that can be rendered to and later reused as a pattern.
@@ -495,6 +982,20 @@ pub struct RenderTarget {
     name: String,
 }
 
+/// A set of triangles with colors interpolated between their vertices.
+///
+/// See `Scene::push_mesh()`.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    positions: Vec<Vector2F>,
+    colors: Vec<ColorU>,
+    indices: Vec<u32>,
+}
+
+/// The ID of a mesh, unique to a single scene.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MeshId(pub u32);
+
 /// High-level drawing commands.
 #[derive(Clone, Debug)]
 pub enum DisplayItem {
@@ -506,13 +1007,16 @@ pub enum DisplayItem {
 
     /// Pops a render target from the stack.
     PopRenderTarget,
+
+    /// Draws a mesh to the render target on top of the stack.
+    DrawMesh(MeshId),
 }
 
 impl DrawPath {
     /// Creates a new draw path with the given outline and paint.
     ///
     /// Initially, there is no clip path, the fill rule is set to winding, the blend mode is set to
-    /// source-over, and the path has no name.
+    /// source-over, and the path has no name and no annotation.
     #[inline]
     pub fn new(outline: Outline, paint: PaintId) -> DrawPath {
         DrawPath {
@@ -522,6 +1026,7 @@ impl DrawPath {
             fill_rule: FillRule::Winding,
             blend_mode: BlendMode::SrcOver,
             name: String::new(),
+            annotation: None,
         }
     }
 
@@ -545,8 +1050,9 @@ impl DrawPath {
         self.clip_path = new_clip_path
     }
 
+    /// Returns the ID of the paint that this path is filled with.
     #[inline]
-    pub(crate) fn paint(&self) -> PaintId {
+    pub fn paint(&self) -> PaintId {
         self.paint
     }
 
@@ -578,6 +1084,19 @@ impl DrawPath {
     pub fn set_name(&mut self, new_name: String) {
         self.name = new_name
     }
+
+    /// Returns the annotation attached to this path, if any.
+    #[inline]
+    pub fn annotation(&self) -> Option<u64> {
+        self.annotation
+    }
+
+    /// Attaches an opaque annotation to this path, typically the ID of the object it was
+    /// generated from. The annotation is returned by `Scene::pick()` when this path is hit.
+    #[inline]
+    pub fn set_annotation(&mut self, new_annotation: Option<u64>) {
+        self.annotation = new_annotation
+    }
 }
 
 impl ClipPath {
@@ -652,6 +1171,35 @@ impl DrawPathId {
     }
 }
 
+impl Mesh {
+    /// Returns the positions of the vertices of this mesh.
+    #[inline]
+    pub fn positions(&self) -> &[Vector2F] {
+        &self.positions
+    }
+
+    /// Returns the colors of the vertices of this mesh.
+    #[inline]
+    pub fn colors(&self) -> &[ColorU] {
+        &self.colors
+    }
+
+    /// Returns the vertex indices of this mesh, three per triangle.
+    #[inline]
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    // Returns the bounding rectangle of the vertices, or `None` if there are none.
+    fn bounds(&self) -> Option<RectF> {
+        let mut positions = self.positions.iter();
+        let first = *positions.next()?;
+        Some(positions.fold(RectF::from_points(first, first), |bounds, &position| {
+            bounds.union_point(position)
+        }))
+    }
+}
+
 impl ClipPathId {
     #[inline]
     pub(crate) fn to_path_id(self) -> PathId {
@@ -670,3 +1218,338 @@ impl PathId {
         DrawPathId(self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::concurrent::executor::SequentialExecutor;
+    use crate::gpu::options::RendererLevel;
+    use crate::gpu_data::{ColorCombineMode, RenderCommand};
+    use crate::options::{BuildOptions, RenderCommandListener};
+    #[cfg(feature = "serialize")]
+    use crate::options::deserialize_render_commands;
+    use crate::paint::{Paint, PaintCompositeOp, PaintOverlay};
+    use crate::scene::{ClipPath, DisplayItem, DrawPath, DrawPathId, PathId, RenderTarget, Scene};
+    use crate::scene::SceneSink;
+    use pathfinder_color::ColorU;
+    use pathfinder_content::effects::DropShadow;
+    use pathfinder_content::fill::FillRule;
+    use pathfinder_content::gradient::Gradient;
+    use pathfinder_content::outline::{Contour, Outline};
+    use pathfinder_content::pattern::Pattern;
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{vec2f, vec2i};
+    use std::sync::Mutex;
+
+    // Builds the scene at the D3D9 level and returns the render commands it produces.
+    fn build_render_commands(scene: &mut Scene) -> Vec<RenderCommand> {
+        let commands = Mutex::new(vec![]);
+        let listener = RenderCommandListener::new(Box::new(|render_command| {
+            commands.lock().unwrap().push(render_command);
+        }));
+        let mut sink = SceneSink::new(listener, RendererLevel::D3D9);
+        scene.build(BuildOptions::default(), &mut sink, &SequentialExecutor);
+        drop(sink);
+        commands.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_dirty_bounds() {
+        let mut scene = Scene::new();
+        let view_box = RectF::new(vec2f(0.0, 0.0), vec2f(256.0, 256.0));
+        scene.set_view_box(view_box);
+        assert_eq!(scene.dirty_bounds(), Some(view_box));
+        scene.clear_dirty();
+        assert_eq!(scene.dirty_bounds(), None);
+
+        // Adding paths dirties their bounds.
+        let paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let first_rect = RectF::new(vec2f(16.0, 16.0), vec2f(32.0, 32.0));
+        let first_path =
+            scene.push_draw_path(DrawPath::new(Outline::from_rect(first_rect), paint_id));
+        let second_rect = RectF::new(vec2f(128.0, 64.0), vec2f(16.0, 16.0));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(second_rect), paint_id));
+        assert_eq!(scene.dirty_bounds(), Some(first_rect.union_rect(second_rect)));
+
+        // Building resets the region.
+        build_render_commands(&mut scene);
+        assert_eq!(scene.dirty_bounds(), None);
+
+        // Moving a path dirties both where it was and where it went.
+        let moved_rect = RectF::new(vec2f(64.0, 16.0), vec2f(32.0, 32.0));
+        scene.set_draw_path_outline(first_path, Outline::from_rect(moved_rect));
+        assert_eq!(scene.dirty_bounds(), Some(first_rect.union_rect(moved_rect)));
+        scene.clear_dirty();
+
+        // Removing a path dirties its former bounds.
+        scene.set_draw_path_outline(first_path, Outline::new());
+        assert_eq!(scene.dirty_bounds(), Some(moved_rect));
+    }
+
+    #[test]
+    fn test_trivial_render_target() {
+        // Builds a scene with a black rect, then a black rect drawn into a render target that is
+        // composited with the given opacity, and returns the names of the commands it produces.
+        let build = |opacity: u8| {
+            let mut scene = Scene::new();
+            scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(128.0, 128.0)));
+            let paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+            let rect = RectF::new(vec2f(8.0, 8.0), vec2f(16.0, 16.0));
+            scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), paint_id));
+
+            let size = vec2i(128, 128);
+            let render_target_id = scene.push_render_target(RenderTarget::new(size, String::new()));
+            let rect = RectF::new(vec2f(32.0, 32.0), vec2f(16.0, 16.0));
+            scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), paint_id));
+            scene.pop_render_target();
+            let pattern = Pattern::from_render_target(render_target_id, size);
+            let mut paint = Paint::from_pattern(pattern);
+            paint.set_base_color(ColorU::new(255, 255, 255, opacity));
+            let paint_id = scene.push_paint(&paint);
+            let rect = RectF::new(vec2f(0.0, 0.0), size.to_f32());
+            scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), paint_id));
+
+            build_render_commands(&mut scene).iter().filter_map(|render_command| {
+                match *render_command {
+                    RenderCommand::DeclareRenderTarget { .. } => Some("DeclareRenderTarget"),
+                    RenderCommand::PushRenderTarget(_) => Some("PushRenderTarget"),
+                    RenderCommand::PopRenderTarget => Some("PopRenderTarget"),
+                    RenderCommand::DrawTilesD3D9(_) => Some("DrawTilesD3D9"),
+                    _ => None,
+                }
+            }).collect::<Vec<_>>()
+        };
+        let count = |command_names: &[&str], name: &str| {
+            command_names.iter().filter(|&&command_name| command_name == name).count()
+        };
+
+        // An opaque, unfiltered render target is skipped, and both rects share one batch.
+        let command_names = build(255);
+        assert_eq!(count(&command_names, "DeclareRenderTarget"), 0);
+        assert_eq!(count(&command_names, "PushRenderTarget"), 0);
+        assert_eq!(count(&command_names, "PopRenderTarget"), 0);
+        assert_eq!(count(&command_names, "DrawTilesD3D9"), 1);
+
+        // A translucent one is kept.
+        let command_names = build(128);
+        assert_eq!(count(&command_names, "DeclareRenderTarget"), 1);
+        assert_eq!(count(&command_names, "PushRenderTarget"), 1);
+        assert_eq!(count(&command_names, "PopRenderTarget"), 1);
+    }
+
+    #[test]
+    fn test_pick_annotations() {
+        let mut scene = Scene::new();
+        let paint = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let background = Outline::from_rect(RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 100.0)));
+        let background = scene.push_draw_path(DrawPath::new(background, paint));
+
+        // An even-odd ring, with a hole in the middle.
+        let mut ring = Outline::new();
+        ring.push_contour(Contour::from_rect(RectF::new(vec2f(10.0, 10.0), vec2f(40.0, 40.0))));
+        ring.push_contour(Contour::from_rect(RectF::new(vec2f(20.0, 20.0), vec2f(20.0, 20.0))));
+        let mut ring = DrawPath::new(ring, paint);
+        ring.set_fill_rule(FillRule::EvenOdd);
+        ring.set_annotation(Some(1));
+        let ring = scene.push_draw_path(ring);
+
+        // A circle clipped to its right half.
+        let clip_outline = Outline::from_rect(RectF::new(vec2f(70.0, 0.0), vec2f(30.0, 100.0)));
+        let clip_path = scene.push_clip_path(ClipPath::new(clip_outline));
+        let mut circle = Contour::new();
+        circle.push_ellipse(&(Transform2F::from_translation(vec2f(70.0, 70.0)) *
+                              Transform2F::from_scale(20.0)));
+        circle.close();
+        let mut circle_outline = Outline::new();
+        circle_outline.push_contour(circle);
+        let mut circle = DrawPath::new(circle_outline, paint);
+        circle.set_clip_path(Some(clip_path));
+        circle.set_annotation(Some(2));
+        scene.push_draw_path(circle);
+
+        assert_eq!(scene.pick_draw_path(vec2f(15.0, 15.0)), Some(ring));
+        assert_eq!(scene.pick(vec2f(15.0, 15.0)), Some(1));
+        assert_eq!(scene.pick_draw_path(vec2f(30.0, 30.0)), Some(background));
+        assert_eq!(scene.pick(vec2f(30.0, 30.0)), None);
+        assert_eq!(scene.pick(vec2f(80.0, 70.0)), Some(2));
+        assert_eq!(scene.pick(vec2f(60.0, 70.0)), None);
+        assert_eq!(scene.pick(vec2f(88.0, 88.0)), None);
+        assert_eq!(scene.pick_draw_path(vec2f(150.0, 50.0)), None);
+    }
+
+    fn push_rect_with_shadow(scene: &mut Scene, sigma: f32) -> DrawPathId {
+        let paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let outline = Outline::from_rect(RectF::new(vec2f(10.0, 10.0), vec2f(20.0, 20.0)));
+        let shadow = DropShadow {
+            offset: vec2f(4.0, 4.0),
+            sigma,
+            color: ColorU::new(255, 0, 0, 128),
+        };
+        scene.push_draw_path_with_drop_shadow(DrawPath::new(outline, paint_id), &shadow)
+    }
+
+    fn render_target_count(scene: &Scene) -> usize {
+        scene.display_list.iter().filter(|display_item| {
+            match **display_item {
+                DisplayItem::PushRenderTarget(_) => true,
+                _ => false,
+            }
+        }).count()
+    }
+
+    #[test]
+    fn test_drop_shadow() {
+        // A sharp shadow is drawn directly, underneath the path.
+        let mut scene = Scene::new();
+        assert_eq!(push_rect_with_shadow(&mut scene, 0.0), DrawPathId(1));
+        assert_eq!(render_target_count(&scene), 0);
+        let shadow_path = scene.get_draw_path(DrawPathId(0));
+        assert_eq!(shadow_path.outline().bounds(),
+                   RectF::new(vec2f(14.0, 14.0), vec2f(20.0, 20.0)));
+        assert_eq!(scene.get_paint(shadow_path.paint()).base_color(),
+                   ColorU::new(255, 0, 0, 128));
+
+        // A blurred shadow is blurred in two passes, and its final pass covers the tails of the
+        // blur.
+        let mut scene = Scene::new();
+        assert_eq!(push_rect_with_shadow(&mut scene, 2.0), DrawPathId(3));
+        assert_eq!(render_target_count(&scene), 2);
+        let blurred_path = scene.get_draw_path(DrawPathId(2));
+        assert_eq!(blurred_path.outline().bounds(),
+                   RectF::new(vec2f(8.0, 8.0), vec2f(32.0, 32.0)));
+        assert!(scene.get_paint(blurred_path.paint()).pattern().is_some());
+    }
+
+    #[test]
+    fn test_occluded_tile_count() {
+        // A large opaque rect is drawn on top of a smaller one.
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(256.0, 256.0)));
+        let paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let small_rect = RectF::new(vec2f(32.0, 32.0), vec2f(64.0, 64.0));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(small_rect), paint_id));
+        let large_rect = RectF::new(vec2f(0.0, 0.0), vec2f(256.0, 256.0));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(large_rect), paint_id));
+
+        let mut batches = vec![];
+        for render_command in build_render_commands(&mut scene) {
+            if let RenderCommand::DrawTilesD3D9(batch) = render_command {
+                batches.push(batch);
+            }
+        }
+
+        // Every tile of the smaller rect is occluded, and none of the larger rect's are.
+        assert_eq!(batches.len(), 1);
+        let small_tile_count =
+            batches[0].tiles.iter().filter(|tile| tile.path_id == PathId(0)).count();
+        assert!(small_tile_count > 0);
+        assert_eq!(batches[0].occluded_tile_count(), small_tile_count);
+    }
+
+    #[test]
+    fn test_gradient_texture_metadata() {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(64.0, 64.0)));
+        let mut gradient = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(64.0, 0.0));
+        gradient.add_color_stop(ColorU::black(), 0.0);
+        gradient.add_color_stop(ColorU::white(), 1.0);
+        let solid_paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let gradient_paint_id = scene.push_paint(&Paint::from_gradient(gradient));
+        let rect = RectF::new(vec2f(0.0, 0.0), vec2f(64.0, 64.0));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), solid_paint_id));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), gradient_paint_id));
+
+        let mut metadata = vec![];
+        for render_command in build_render_commands(&mut scene) {
+            if let RenderCommand::UploadTextureMetadata(entries) = render_command {
+                metadata.extend(entries);
+            }
+        }
+
+        // Only the gradient is marked, so that the renderer dithers it and nothing else.
+        assert!(!metadata[solid_paint_id.0 as usize].color_0_is_gradient);
+        assert!(metadata[gradient_paint_id.0 as usize].color_0_is_gradient);
+    }
+
+    #[test]
+    fn test_second_color_texture() {
+        // Draws two render targets, then a rect whose paint masks the first with the second.
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(64.0, 64.0)));
+        let size = vec2i(64, 64);
+        let black_paint_id = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let mut render_target_ids = vec![];
+        for _ in 0..2 {
+            let render_target_id = scene.push_render_target(RenderTarget::new(size, String::new()));
+            let rect = RectF::new(vec2f(8.0, 8.0), vec2f(16.0, 16.0));
+            scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), black_paint_id));
+            scene.pop_render_target();
+            render_target_ids.push(render_target_id);
+        }
+        let pattern = Pattern::from_render_target(render_target_ids[0], size);
+        let mut paint = Paint::from_pattern(pattern);
+        let mask_pattern = Pattern::from_render_target(render_target_ids[1], size);
+        *paint.second_overlay_mut() =
+            Some(PaintOverlay::from_pattern(mask_pattern, PaintCompositeOp::DestIn));
+        let paint_id = scene.push_paint(&paint);
+        let rect = RectF::new(vec2f(0.0, 0.0), size.to_f32());
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), paint_id));
+
+        let (mut batches, mut metadata) = (vec![], vec![]);
+        for render_command in build_render_commands(&mut scene) {
+            match render_command {
+                RenderCommand::DrawTilesD3D9(batch) => batches.push(batch),
+                RenderCommand::UploadTextureMetadata(entries) => metadata.extend(entries),
+                _ => {}
+            }
+        }
+
+        // Both render targets are sampled by the last batch, each from its own page.
+        let batch = batches.last().unwrap();
+        let color_texture_0 = batch.color_texture.unwrap();
+        let color_texture_1 = batch.color_texture_1.unwrap();
+        assert_ne!(color_texture_0.page, color_texture_1.page);
+
+        match metadata[paint_id.0 as usize].color_1_combine_mode {
+            ColorCombineMode::DestIn => {}
+            _ => panic!("The second color texture should be combined with `DestIn`!"),
+        }
+        match metadata[black_paint_id.0 as usize].color_1_combine_mode {
+            ColorCombineMode::None => {}
+            _ => panic!("A solid color paint should have no second color texture!"),
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_serialize_render_commands() {
+        // The view box is needed for the rect to produce any fills or tiles.
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(64.0, 64.0)));
+        push_rect_with_shadow(&mut scene, 0.0);
+
+        let commands = build_render_commands(&mut scene);
+        assert!(commands.iter().any(|command| {
+            match *command { RenderCommand::AddFillsD3D9(_) => true, _ => false }
+        }));
+        assert!(commands.iter().any(|command| {
+            match *command { RenderCommand::DrawTilesD3D9(_) => true, _ => false }
+        }));
+
+        // `RenderCommand` has no `PartialEq`, so compare the commands by their serialized form.
+        let expected: Vec<String> = commands.iter().map(|command| {
+            serde_json::to_string(command).unwrap()
+        }).collect();
+        let mut bytes = vec![];
+        let listener = RenderCommandListener::serializing(&mut bytes);
+        for command in commands {
+            listener.send(command);
+        }
+        drop(listener);
+
+        let replayed: Vec<String> = deserialize_render_commands(&bytes[..]).map(|command| {
+            serde_json::to_string(&command.unwrap()).unwrap()
+        }).collect();
+        assert_eq!(replayed, expected);
+    }
+}
@@ -12,6 +12,7 @@ use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::vector::{Vector2I, vec2i};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct DenseTileMap<T> where T: Clone + Copy {
     pub data: Vec<T>,
     pub rect: RectI,
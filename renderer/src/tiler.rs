@@ -191,7 +191,7 @@ fn process_segment(segment: &Segment,
 fn process_line_segment(line_segment: LineSegment2F,
                         scene_builder: &SceneBuilder,
                         object_builder: &mut ObjectBuilder) {
-    let view_box = scene_builder.scene.view_box();
+    let view_box = scene_builder.scene.render_view_box(scene_builder.built_options);
     let clip_box = RectF::from_points(vec2f(view_box.min_x(), NEG_INFINITY),
                                       view_box.lower_right());
     let line_segment = match clip::clip_line_segment_to_rect(line_segment, clip_box) {
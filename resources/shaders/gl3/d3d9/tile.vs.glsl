@@ -40,6 +40,7 @@ void computeTileVaryings(vec2 position,
                          sampler2D textureMetadata,
                          ivec2 textureMetadataSize,
                          out vec2 outColorTexCoord0,
+                         out vec2 outColorTexCoord1,
                          out vec4 outBaseColor,
                          out vec4 outFilterParams0,
                          out vec4 outFilterParams1,
@@ -58,14 +59,18 @@ void computeTileVaryings(vec2 position,
     vec4 filterParams3 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 6);
     vec4 filterParams4 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 7);
     vec4 extra = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 8);
+    vec4 colorTexMatrix1 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 9);
     outColorTexCoord0 = mat2(colorTexMatrix0)* position + colorTexOffsets . xy;
+    outColorTexCoord1 = mat2(colorTexMatrix1)* position + colorTexOffsets . zw;
     outBaseColor = baseColor;
     outFilterParams0 = filterParams0;
     outFilterParams1 = filterParams1;
     outFilterParams2 = filterParams2;
     outFilterParams3 = filterParams3;
     outFilterParams4 = filterParams4;
-    outCtrl = int(extra . x);
+
+
+    outCtrl = int(extra . x)|(int(extra . y)<< 16);
 }
 
 
@@ -85,6 +90,7 @@ in int aColor;
 
 out vec3 vMaskTexCoord0;
 out vec2 vColorTexCoord0;
+out vec2 vColorTexCoord1;
 out vec4 vBaseColor;
 out float vTileCtrl;
 out vec4 vFilterParams0;
@@ -117,6 +123,7 @@ void main(){
                         uTextureMetadata,
                         uTextureMetadataSize,
                         vColorTexCoord0,
+                        vColorTexCoord1,
                         vBaseColor,
                         vFilterParams0,
                         vFilterParams1,
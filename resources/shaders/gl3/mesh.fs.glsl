@@ -0,0 +1,28 @@
+#version {{version}}
+// Automatically generated from files in pathfinder/shaders/. Do not edit!
+
+
+
+
+
+
+
+
+
+
+
+
+precision highp float;
+
+
+
+
+
+in vec4 vColor;
+
+out vec4 oFragColor;
+
+void main(){
+    oFragColor = vec4(vColor . rgb, 1.0)* vColor . a;
+}
+
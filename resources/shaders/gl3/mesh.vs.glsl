@@ -0,0 +1,33 @@
+#version {{version}}
+// Automatically generated from files in pathfinder/shaders/. Do not edit!
+
+
+
+
+
+
+
+
+
+
+
+
+precision highp float;
+
+
+
+
+
+uniform vec2 uFramebufferSize;
+
+in vec2 aPosition;
+in vec4 aColor;
+
+out vec4 vColor;
+
+void main(){
+    vec2 position = aPosition / uFramebufferSize * 2.0 - 1.0;
+    vColor = aColor;
+    gl_Position = vec4(position . x, - position . y, 0.0, 1.0);
+}
+
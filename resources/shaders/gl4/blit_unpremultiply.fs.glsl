@@ -0,0 +1,31 @@
+#version {{version}}
+// Automatically generated from files in pathfinder/shaders/. Do not edit!
+
+
+
+
+
+
+
+
+
+
+
+
+precision highp float;
+
+
+
+
+
+uniform sampler2D uSrc;
+
+in vec2 vTexCoord;
+
+out vec4 oFragColor;
+
+void main(){
+    vec4 color = texture(uSrc, vTexCoord);
+    oFragColor = color . a > 0.0 ? vec4(color . rgb / color . a, color . a): vec4(0.0);
+}
+
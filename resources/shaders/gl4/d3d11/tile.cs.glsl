@@ -93,6 +93,9 @@ layout(local_size_x = 16, local_size_y = 4)in;
 
 
 
+
+
+
 
 
 
@@ -331,6 +334,26 @@ vec4 filterRadialGradient(vec2 colorTexCoord,
 
 
 
+
+
+
+vec4 filterConicGradient(vec2 colorTexCoord,
+                         sampler2D colorTexture,
+                         vec4 filterParams0,
+                         vec4 filterParams1){
+    vec2 center = filterParams0 . xy, uvOrigin = filterParams1 . zw;
+    float startAngle = filterParams0 . z;
+
+    vec2 dP = colorTexCoord - center;
+    float t = fract((atan(dP . y, dP . x)- startAngle)*(1.0 /(2.0 * 3.14159265358979)));
+    return texture(colorTexture, uvOrigin + vec2(t, 0.0));
+}
+
+
+
+
+
+
 vec4 filterBlur(vec2 colorTexCoord,
                 sampler2D colorTexture,
                 vec2 colorTextureSize,
@@ -408,6 +431,11 @@ vec4 filterColor(vec2 colorTexCoord,
                                     framebufferSize,
                                     filterParams0,
                                     filterParams1);
+    case 0x5 :
+        return filterConicGradient(colorTexCoord,
+                                   colorTexture,
+                                   filterParams0,
+                                   filterParams1);
     case 0x3 :
         return filterBlur(colorTexCoord,
                           colorTexture,
@@ -582,8 +610,25 @@ float sampleMask(float maskAlpha,
 
 
 
+
+float ditherOffset(vec2 fragCoord){
+    ivec2 position = ivec2(fragCoord)& 3;
+    ivec2 low = position & 1, high = position >> 1;
+    int index =((low . x ^ low . y)<< 3)|(low . y << 2)|((high . x ^ high . y)<< 1)| high . y;
+    return(float(index)+ 0.5)/ 16.0 - 0.5;
+}
+
+
+vec4 dither(vec4 color, vec2 fragCoord){
+    color . rgb = clamp(color . rgb + vec3(ditherOffset(fragCoord)/ 255.0), vec3(0.0), vec3(color . a));
+    return color;
+}
+
+
+
 vec4 calculateColor(vec2 fragCoord,
                     sampler2D colorTexture0,
+                    sampler2D colorTexture1,
                     sampler2D maskTexture0,
                     sampler2D destTexture,
                     sampler2D gammaLUT,
@@ -598,6 +643,7 @@ vec4 calculateColor(vec2 fragCoord,
                     int ctrl,
                     vec3 maskTexCoord0,
                     vec2 colorTexCoord0,
+                    vec2 colorTexCoord1,
                     vec4 baseColor,
                     int tileCtrl){
 
@@ -627,6 +673,14 @@ vec4 calculateColor(vec2 fragCoord,
     }
 
 
+    int color1Combine =(ctrl >> 16)&
+                                       0x3;
+    if(color1Combine != 0){
+        vec4 color1 = sampleColor(colorTexture1, colorTexCoord1);
+        color = combineColor0(color, color1, color1Combine);
+    }
+
+
     color . a *= maskAlpha;
 
 
@@ -635,6 +689,10 @@ vec4 calculateColor(vec2 fragCoord,
 
 
     color . rgb *= color . a;
+
+
+    if((ctrl & 0x4000)!= 0)
+        color = dither(color, fragCoord);
     return color;
 }
 
@@ -658,6 +716,7 @@ void computeTileVaryings(vec2 position,
                          sampler2D textureMetadata,
                          ivec2 textureMetadataSize,
                          out vec2 outColorTexCoord0,
+                         out vec2 outColorTexCoord1,
                          out vec4 outBaseColor,
                          out vec4 outFilterParams0,
                          out vec4 outFilterParams1,
@@ -676,14 +735,18 @@ void computeTileVaryings(vec2 position,
     vec4 filterParams3 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 6);
     vec4 filterParams4 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 7);
     vec4 extra = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 8);
+    vec4 colorTexMatrix1 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 9);
     outColorTexCoord0 = mat2(colorTexMatrix0)* position + colorTexOffsets . xy;
+    outColorTexCoord1 = mat2(colorTexMatrix1)* position + colorTexOffsets . zw;
     outBaseColor = baseColor;
     outFilterParams0 = filterParams0;
     outFilterParams1 = filterParams1;
     outFilterParams2 = filterParams2;
     outFilterParams3 = filterParams3;
     outFilterParams4 = filterParams4;
-    outCtrl = int(extra . x);
+
+
+    outCtrl = int(extra . x)|(int(extra . y)<< 16);
 }
 
 
@@ -703,6 +766,7 @@ uniform ivec2 uTextureMetadataSize;
 uniform sampler2D uZBuffer;
 uniform ivec2 uZBufferSize;
 uniform sampler2D uColorTexture0;
+uniform sampler2D uColorTexture1;
 uniform sampler2D uMaskTexture0;
 uniform sampler2D uGammaLUT;
 uniform vec2 uColorTextureSize0;
@@ -778,7 +842,7 @@ void main(){
 
             vec3 maskTexCoord0 = vec3(vec2(ivec2(maskTileCoord)+ tileSubCoord), backdrop);
 
-            vec2 colorTexCoord0;
+            vec2 colorTexCoord0, colorTexCoord1;
             vec4 baseColor, filterParams0, filterParams1, filterParams2, filterParams3, filterParams4;
             int ctrl;
             computeTileVaryings(fragCoord,
@@ -786,6 +850,7 @@ void main(){
                                 uTextureMetadata,
                                 uTextureMetadataSize,
                                 colorTexCoord0,
+                                colorTexCoord1,
                                 baseColor,
                                 filterParams0,
                                 filterParams1,
@@ -799,6 +864,7 @@ void main(){
 
             vec4 srcColor = calculateColor(fragCoord,
                                            uColorTexture0,
+                                           uColorTexture1,
                                            uMaskTexture0,
                                            uColorTexture0,
                                            uGammaLUT,
@@ -813,6 +879,7 @@ void main(){
                                            ctrl,
                                            maskTexCoord0,
                                            colorTexCoord0,
+                                           colorTexCoord1,
                                            baseColor,
                                            tileCtrl);
 
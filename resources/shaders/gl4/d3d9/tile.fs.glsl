@@ -91,6 +91,9 @@ precision highp float;
 
 
 
+
+
+
 
 
 
@@ -329,6 +332,26 @@ vec4 filterRadialGradient(vec2 colorTexCoord,
 
 
 
+
+
+
+vec4 filterConicGradient(vec2 colorTexCoord,
+                         sampler2D colorTexture,
+                         vec4 filterParams0,
+                         vec4 filterParams1){
+    vec2 center = filterParams0 . xy, uvOrigin = filterParams1 . zw;
+    float startAngle = filterParams0 . z;
+
+    vec2 dP = colorTexCoord - center;
+    float t = fract((atan(dP . y, dP . x)- startAngle)*(1.0 /(2.0 * 3.14159265358979)));
+    return texture(colorTexture, uvOrigin + vec2(t, 0.0));
+}
+
+
+
+
+
+
 vec4 filterBlur(vec2 colorTexCoord,
                 sampler2D colorTexture,
                 vec2 colorTextureSize,
@@ -406,6 +429,11 @@ vec4 filterColor(vec2 colorTexCoord,
                                     framebufferSize,
                                     filterParams0,
                                     filterParams1);
+    case 0x5 :
+        return filterConicGradient(colorTexCoord,
+                                   colorTexture,
+                                   filterParams0,
+                                   filterParams1);
     case 0x3 :
         return filterBlur(colorTexCoord,
                           colorTexture,
@@ -580,8 +608,25 @@ float sampleMask(float maskAlpha,
 
 
 
+
+float ditherOffset(vec2 fragCoord){
+    ivec2 position = ivec2(fragCoord)& 3;
+    ivec2 low = position & 1, high = position >> 1;
+    int index =((low . x ^ low . y)<< 3)|(low . y << 2)|((high . x ^ high . y)<< 1)| high . y;
+    return(float(index)+ 0.5)/ 16.0 - 0.5;
+}
+
+
+vec4 dither(vec4 color, vec2 fragCoord){
+    color . rgb = clamp(color . rgb + vec3(ditherOffset(fragCoord)/ 255.0), vec3(0.0), vec3(color . a));
+    return color;
+}
+
+
+
 vec4 calculateColor(vec2 fragCoord,
                     sampler2D colorTexture0,
+                    sampler2D colorTexture1,
                     sampler2D maskTexture0,
                     sampler2D destTexture,
                     sampler2D gammaLUT,
@@ -596,6 +641,7 @@ vec4 calculateColor(vec2 fragCoord,
                     int ctrl,
                     vec3 maskTexCoord0,
                     vec2 colorTexCoord0,
+                    vec2 colorTexCoord1,
                     vec4 baseColor,
                     int tileCtrl){
 
@@ -625,6 +671,14 @@ vec4 calculateColor(vec2 fragCoord,
     }
 
 
+    int color1Combine =(ctrl >> 16)&
+                                       0x3;
+    if(color1Combine != 0){
+        vec4 color1 = sampleColor(colorTexture1, colorTexCoord1);
+        color = combineColor0(color, color1, color1Combine);
+    }
+
+
     color . a *= maskAlpha;
 
 
@@ -633,11 +687,16 @@ vec4 calculateColor(vec2 fragCoord,
 
 
     color . rgb *= color . a;
+
+
+    if((ctrl & 0x4000)!= 0)
+        color = dither(color, fragCoord);
     return color;
 }
 
 
 uniform sampler2D uColorTexture0;
+uniform sampler2D uColorTexture1;
 uniform sampler2D uMaskTexture0;
 uniform sampler2D uDestTexture;
 uniform sampler2D uGammaLUT;
@@ -647,6 +706,7 @@ uniform vec2 uFramebufferSize;
 
 in vec3 vMaskTexCoord0;
 in vec2 vColorTexCoord0;
+in vec2 vColorTexCoord1;
 in vec4 vBaseColor;
 in float vTileCtrl;
 in vec4 vFilterParams0;
@@ -665,6 +725,7 @@ out vec4 oFragColor;
 void main(){
     oFragColor = calculateColor(gl_FragCoord . xy,
                                 uColorTexture0,
+                                uColorTexture1,
                                 uMaskTexture0,
                                 uDestTexture,
                                 uGammaLUT,
@@ -679,6 +740,7 @@ void main(){
                                 int(vCtrl),
                                 vMaskTexCoord0,
                                 vColorTexCoord0,
+                                vColorTexCoord1,
                                 vBaseColor,
                                 int(vTileCtrl));
 }
@@ -19,6 +19,7 @@ precision highp float;
 
 
 uniform vec2 uFramebufferSize;
+uniform vec2 uSrcFramebufferSize;
 
 in ivec2 aTileOffset;
 in int aDestTileIndex;
@@ -32,18 +33,22 @@ out vec2 vTexCoord1;
 out float vBackdrop1;
 
 void main(){
+
+    int tempTileIndex0 = gl_InstanceID * 2, tempTileIndex1 = tempTileIndex0 + 1;
     vec2 destPosition = vec2(ivec2(aDestTileIndex % 256, aDestTileIndex / 256)+ aTileOffset);
-    vec2 srcPosition = vec2(ivec2(aSrcTileIndex % 256, aSrcTileIndex / 256)+ aTileOffset);
+    vec2 tempPosition0 = vec2(ivec2(tempTileIndex0 % 256, tempTileIndex0 / 256)+ aTileOffset);
+    vec2 tempPosition1 = vec2(ivec2(tempTileIndex1 % 256, tempTileIndex1 / 256)+ aTileOffset);
     destPosition *= vec2(16.0, 4.0)/ uFramebufferSize;
-    srcPosition *= vec2(16.0, 4.0)/ uFramebufferSize;
+    tempPosition0 *= vec2(16.0, 4.0)/ uSrcFramebufferSize;
+    tempPosition1 *= vec2(16.0, 4.0)/ uSrcFramebufferSize;
 
-    vTexCoord0 = destPosition;
-    vTexCoord1 = srcPosition;
+    vTexCoord0 = tempPosition0;
+    vTexCoord1 = tempPosition1;
 
     vBackdrop0 = float(aDestBackdrop);
     vBackdrop1 = float(aSrcBackdrop);
 
-    if(aDestTileIndex < 0)
+    if(aDestTileIndex < 0 || aSrcTileIndex < 0)
         destPosition = vec2(0.0);
 
 
@@ -19,6 +19,7 @@ precision highp float;
 
 
 uniform vec2 uFramebufferSize;
+uniform vec2 uSrcFramebufferSize;
 
 in ivec2 aTileOffset;
 in int aTileIndex;
@@ -26,10 +27,13 @@ in int aTileIndex;
 out vec2 vTexCoord;
 
 void main(){
-    vec2 position = vec2(ivec2(aTileIndex % 256, aTileIndex / 256)+ aTileOffset);
+
+    vec2 srcPosition = vec2(ivec2(aTileIndex % 256, aTileIndex / 256)+ aTileOffset);
+    vec2 position = vec2(ivec2(gl_InstanceID % 256, gl_InstanceID / 256)+ aTileOffset);
+    srcPosition *= vec2(16.0, 4.0)/ uSrcFramebufferSize;
     position *= vec2(16.0, 4.0)/ uFramebufferSize;
 
-    vTexCoord = position;
+    vTexCoord = srcPosition;
 
     if(aTileIndex < 0)
         position = vec2(0.0);
@@ -15,10 +15,11 @@ struct main0_in
     int2 aPosition [[attribute(0)]];
 };
 
-vertex main0_out main0(main0_in in [[stage_in]], constant float4& uDestRect [[buffer(0)]], constant float2& uFramebufferSize [[buffer(1)]])
+vertex main0_out main0(main0_in in [[stage_in]], constant float4& uDestRect [[buffer(0)]], constant float2x2& uTransform [[buffer(1)]], constant float2& uTranslation [[buffer(2)]], constant float2& uFramebufferSize [[buffer(3)]])
 {
     main0_out out = {};
-    float2 position = mix(uDestRect.xy, uDestRect.zw, float2(in.aPosition)) / uFramebufferSize;
+    float2 position = mix(uDestRect.xy, uDestRect.zw, float2(in.aPosition));
+    position = ((uTransform * position) + uTranslation) / uFramebufferSize;
     float2 texCoord = float2(in.aPosition);
     out.vTexCoord = texCoord;
     out.gl_Position = float4(mix(float2(-1.0), float2(1.0), position), 0.0, 1.0);
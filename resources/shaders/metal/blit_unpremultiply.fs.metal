@@ -0,0 +1,33 @@
+// Automatically generated from files in pathfinder/shaders/. Do not edit!
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using namespace metal;
+
+struct main0_out
+{
+    float4 oFragColor [[color(0)]];
+};
+
+struct main0_in
+{
+    float2 vTexCoord [[user(locn0)]];
+};
+
+fragment main0_out main0(main0_in in [[stage_in]], texture2d<float> uSrc [[texture(0)]], sampler uSrcSmplr [[sampler(0)]])
+{
+    main0_out out = {};
+    float4 color = uSrc.sample(uSrcSmplr, in.vTexCoord);
+    float4 _29;
+    if (color.w > 0.0)
+    {
+        _29 = float4(color.xyz / float3(color.w), color.w);
+    }
+    else
+    {
+        _29 = float4(0.0);
+    }
+    out.oFragColor = _29;
+    return out;
+}
+
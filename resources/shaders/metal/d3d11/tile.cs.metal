@@ -40,7 +40,7 @@ float4 fetchUnscaled(thread const texture2d<float> srcTexture, thread const samp
 }
 
 static inline __attribute__((always_inline))
-void computeTileVaryings(thread const float2& position, thread const int& colorEntry, thread const texture2d<float> textureMetadata, thread const sampler textureMetadataSmplr, thread const int2& textureMetadataSize, thread float2& outColorTexCoord0, thread float4& outBaseColor, thread float4& outFilterParams0, thread float4& outFilterParams1, thread float4& outFilterParams2, thread float4& outFilterParams3, thread float4& outFilterParams4, thread int& outCtrl)
+void computeTileVaryings(thread const float2& position, thread const int& colorEntry, thread const texture2d<float> textureMetadata, thread const sampler textureMetadataSmplr, thread const int2& textureMetadataSize, thread float2& outColorTexCoord0, thread float2& outColorTexCoord1, thread float4& outBaseColor, thread float4& outFilterParams0, thread float4& outFilterParams1, thread float4& outFilterParams2, thread float4& outFilterParams3, thread float4& outFilterParams4, thread int& outCtrl)
 {
     float2 metadataScale = float2(1.0) / float2(textureMetadataSize);
     float2 metadataEntryCoord = float2(float((colorEntry % 128) * 10), float(colorEntry / 128));
@@ -80,14 +80,19 @@ void computeTileVaryings(thread const float2& position, thread const int& colorE
     float2 param_25 = metadataEntryCoord;
     int param_26 = 8;
     float4 extra = fetchUnscaled(textureMetadata, textureMetadataSmplr, param_24, param_25, param_26);
+    float2 param_27 = metadataScale;
+    float2 param_28 = metadataEntryCoord;
+    int param_29 = 9;
+    float4 colorTexMatrix1 = fetchUnscaled(textureMetadata, textureMetadataSmplr, param_27, param_28, param_29);
     outColorTexCoord0 = (float2x2(float2(colorTexMatrix0.xy), float2(colorTexMatrix0.zw)) * position) + colorTexOffsets.xy;
+    outColorTexCoord1 = (float2x2(float2(colorTexMatrix1.xy), float2(colorTexMatrix1.zw)) * position) + colorTexOffsets.zw;
     outBaseColor = baseColor;
     outFilterParams0 = filterParams0;
     outFilterParams1 = filterParams1;
     outFilterParams2 = filterParams2;
     outFilterParams3 = filterParams3;
     outFilterParams4 = filterParams4;
-    outCtrl = int(extra.x);
+    outCtrl = int(extra.x) | (int(extra.y) << 16);
 }
 
 static inline __attribute__((always_inline))
@@ -148,6 +153,17 @@ float4 filterRadialGradient(thread const float2& colorTexCoord, thread const tex
     return color;
 }
 
+static inline __attribute__((always_inline))
+float4 filterConicGradient(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float4& filterParams0, thread const float4& filterParams1)
+{
+    float2 center = filterParams0.xy;
+    float2 uvOrigin = filterParams1.zw;
+    float startAngle = filterParams0.z;
+    float2 dP = colorTexCoord - center;
+    float t = fract((atan2(dP.y, dP.x) - startAngle) * 0.15915493667125701904296875);
+    return colorTexture.sample(colorTextureSmplr, (uvOrigin + float2(t, 0.0)), level(0.0));
+}
+
 static inline __attribute__((always_inline))
 float4 filterBlur(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTextureSize, thread const float4& filterParams0, thread const float4& filterParams1)
 {
@@ -331,6 +347,13 @@ float4 filterColor(thread const float2& colorTexCoord, thread const texture2d<fl
             float4 param_5 = filterParams1;
             return filterRadialGradient(param, colorTexture, colorTextureSmplr, param_1, param_2, param_3, param_4, param_5);
         }
+        case 5:
+        {
+            float2 param_22 = colorTexCoord;
+            float4 param_23 = filterParams0;
+            float4 param_24 = filterParams1;
+            return filterConicGradient(param_22, colorTexture, colorTextureSmplr, param_23, param_24);
+        }
         case 3:
         {
             float2 param_6 = colorTexCoord;
@@ -635,7 +658,25 @@ float4 composite(thread const float4& srcColor, thread const texture2d<float> de
 }
 
 static inline __attribute__((always_inline))
-float4 calculateColor(thread const float2& fragCoord, thread const texture2d<float> colorTexture0, thread const sampler colorTexture0Smplr, thread const texture2d<float> maskTexture0, thread const sampler maskTexture0Smplr, thread const texture2d<float> destTexture, thread const sampler destTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize0, thread const float2& maskTextureSize0, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const float4& filterParams3, thread const float4& filterParams4, thread const float2& framebufferSize, thread const int& ctrl, thread const float3& maskTexCoord0, thread const float2& colorTexCoord0, thread const float4& baseColor, thread const int& tileCtrl)
+float ditherOffset(thread const float2& fragCoord)
+{
+    int2 position = int2(fragCoord) & int2(3);
+    int2 low = position & int2(1);
+    int2 high = position >> int2(1);
+    int index = ((((low.x ^ low.y) << 3) | (low.y << 2)) | ((high.x ^ high.y) << 1)) | high.y;
+    return ((float(index) + 0.5) / 16.0) - 0.5;
+}
+
+static inline __attribute__((always_inline))
+float4 dither(thread const float4& color, thread const float2& fragCoord)
+{
+    float2 param = fragCoord;
+    float3 ditheredRGB = fast::clamp(color.xyz + float3(ditherOffset(param) / 255.0), float3(0.0), float3(color.w));
+    return float4(ditheredRGB.x, ditheredRGB.y, ditheredRGB.z, color.w);
+}
+
+static inline __attribute__((always_inline))
+float4 calculateColor(thread const float2& fragCoord, thread const texture2d<float> colorTexture0, thread const sampler colorTexture0Smplr, thread const texture2d<float> colorTexture1, thread const sampler colorTexture1Smplr, thread const texture2d<float> maskTexture0, thread const sampler maskTexture0Smplr, thread const texture2d<float> destTexture, thread const sampler destTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize0, thread const float2& maskTextureSize0, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const float4& filterParams3, thread const float4& filterParams4, thread const float2& framebufferSize, thread const int& ctrl, thread const float3& maskTexCoord0, thread const float2& colorTexCoord0, thread const float2& colorTexCoord1, thread const float4& baseColor, thread const int& tileCtrl)
 {
     int maskCtrl0 = (tileCtrl >> 0) & 3;
     float maskAlpha = 1.0;
@@ -665,6 +706,16 @@ float4 calculateColor(thread const float2& fragCoord, thread const texture2d<flo
         int param_16 = color0Combine;
         color = combineColor0(param_14, param_15, param_16);
     }
+    int color1Combine = (ctrl >> 16) & 3;
+    if (color1Combine != 0)
+    {
+        float2 param_23 = colorTexCoord1;
+        float4 color1 = sampleColor(colorTexture1, colorTexture1Smplr, param_23);
+        float4 param_24 = color;
+        float4 param_25 = color1;
+        int param_26 = color1Combine;
+        color = combineColor0(param_24, param_25, param_26);
+    }
     color.w *= maskAlpha;
     int compositeOp = (ctrl >> 10) & 15;
     float4 param_17 = color;
@@ -674,10 +725,16 @@ float4 calculateColor(thread const float2& fragCoord, thread const texture2d<flo
     color = composite(param_17, destTexture, destTextureSmplr, param_18, param_19, param_20);
     float3 _1435 = color.xyz * color.w;
     color = float4(_1435.x, _1435.y, _1435.z, color.w);
+    if ((ctrl & 16384) != 0)
+    {
+        float4 param_21 = color;
+        float2 param_22 = fragCoord;
+        color = dither(param_21, param_22);
+    }
     return color;
 }
 
-kernel void main0(constant int2& uFramebufferTileSize [[buffer(3)]], constant int& uLoadAction [[buffer(4)]], constant int2& uTextureMetadataSize [[buffer(7)]], constant float2& uFramebufferSize [[buffer(0)]], constant float2& uTileSize [[buffer(1)]], constant float4& uClearColor [[buffer(5)]], constant float2& uColorTextureSize0 [[buffer(8)]], constant float2& uMaskTextureSize0 [[buffer(9)]], const device bFirstTileMap& _1599 [[buffer(2)]], const device bTiles& _1690 [[buffer(6)]], texture2d<float, access::read_write> uDestImage [[texture(0)]], texture2d<float> uTextureMetadata [[texture(1)]], texture2d<float> uColorTexture0 [[texture(2)]], texture2d<float> uMaskTexture0 [[texture(3)]], texture2d<float> uGammaLUT [[texture(4)]], sampler uTextureMetadataSmplr [[sampler(0)]], sampler uColorTexture0Smplr [[sampler(1)]], sampler uMaskTexture0Smplr [[sampler(2)]], sampler uGammaLUTSmplr [[sampler(3)]], uint3 gl_WorkGroupID [[threadgroup_position_in_grid]], uint3 gl_LocalInvocationID [[thread_position_in_threadgroup]])
+kernel void main0(constant int2& uFramebufferTileSize [[buffer(3)]], constant int& uLoadAction [[buffer(4)]], constant int2& uTextureMetadataSize [[buffer(7)]], constant float2& uFramebufferSize [[buffer(0)]], constant float2& uTileSize [[buffer(1)]], constant float4& uClearColor [[buffer(5)]], constant float2& uColorTextureSize0 [[buffer(8)]], constant float2& uMaskTextureSize0 [[buffer(9)]], const device bFirstTileMap& _1599 [[buffer(2)]], const device bTiles& _1690 [[buffer(6)]], texture2d<float, access::read_write> uDestImage [[texture(0)]], texture2d<float> uTextureMetadata [[texture(1)]], texture2d<float> uColorTexture0 [[texture(2)]], texture2d<float> uMaskTexture0 [[texture(3)]], texture2d<float> uGammaLUT [[texture(4)]], texture2d<float> uColorTexture1 [[texture(5)]], sampler uTextureMetadataSmplr [[sampler(0)]], sampler uColorTexture0Smplr [[sampler(1)]], sampler uMaskTexture0Smplr [[sampler(2)]], sampler uGammaLUTSmplr [[sampler(3)]], sampler uColorTexture1Smplr [[sampler(4)]], uint3 gl_WorkGroupID [[threadgroup_position_in_grid]], uint3 gl_LocalInvocationID [[thread_position_in_threadgroup]])
 {
     int2 tileCoord = int2(gl_WorkGroupID.xy);
     int2 firstTileSubCoord = int2(gl_LocalInvocationID.xy) * int2(1, 4);
@@ -711,6 +768,7 @@ kernel void main0(constant int2& uFramebufferTileSize [[buffer(3)]], constant in
     float4 param_9;
     float4 param_10;
     int param_11;
+    float2 param_27;
     while (tileIndex >= 0)
     {
         for (int subY_1 = 0; subY_1 < 4; subY_1++)
@@ -736,8 +794,9 @@ kernel void main0(constant int2& uFramebufferTileSize [[buffer(3)]], constant in
             float2 param_1 = fragCoord;
             int param_2 = int(colorEntry);
             int2 param_3 = uTextureMetadataSize;
-            computeTileVaryings(param_1, param_2, uTextureMetadata, uTextureMetadataSmplr, param_3, param_4, param_5, param_6, param_7, param_8, param_9, param_10, param_11);
+            computeTileVaryings(param_1, param_2, uTextureMetadata, uTextureMetadataSmplr, param_3, param_4, param_27, param_5, param_6, param_7, param_8, param_9, param_10, param_11);
             float2 colorTexCoord0 = param_4;
+            float2 colorTexCoord1 = param_27;
             float4 baseColor = param_5;
             float4 filterParams0 = param_6;
             float4 filterParams1 = param_7;
@@ -757,9 +816,10 @@ kernel void main0(constant int2& uFramebufferTileSize [[buffer(3)]], constant in
             int param_21 = ctrl;
             float3 param_22 = maskTexCoord0;
             float2 param_23 = colorTexCoord0;
+            float2 param_28 = colorTexCoord1;
             float4 param_24 = baseColor;
             int param_25 = tileCtrl;
-            float4 srcColor = calculateColor(param_12, uColorTexture0, uColorTexture0Smplr, uMaskTexture0, uMaskTexture0Smplr, uColorTexture0, uColorTexture0Smplr, uGammaLUT, uGammaLUTSmplr, param_13, param_14, param_15, param_16, param_17, param_18, param_19, param_20, param_21, param_22, param_23, param_24, param_25);
+            float4 srcColor = calculateColor(param_12, uColorTexture0, uColorTexture0Smplr, uColorTexture1, uColorTexture1Smplr, uMaskTexture0, uMaskTexture0Smplr, uColorTexture0, uColorTexture0Smplr, uGammaLUT, uGammaLUTSmplr, param_13, param_14, param_15, param_16, param_17, param_18, param_19, param_20, param_21, param_22, param_23, param_28, param_24, param_25);
             destColors[subY_1] = (destColors[subY_1] * (1.0 - srcColor.w)) + srcColor;
         }
         tileIndex = int(_1690.iTiles[(tileIndex * 4) + 0]);
@@ -25,6 +25,7 @@ struct main0_in
     float4 vFilterParams3 [[user(locn7)]];
     float4 vFilterParams4 [[user(locn8)]];
     float vCtrl [[user(locn9)]];
+    float2 vColorTexCoord1 [[user(locn10)]];
 };
 
 // Implementation of the GLSL mod() function, which is slightly different than Metal fmod()
@@ -92,6 +93,17 @@ float4 filterRadialGradient(thread const float2& colorTexCoord, thread const tex
     return color;
 }
 
+static inline __attribute__((always_inline))
+float4 filterConicGradient(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float4& filterParams0, thread const float4& filterParams1)
+{
+    float2 center = filterParams0.xy;
+    float2 uvOrigin = filterParams1.zw;
+    float startAngle = filterParams0.z;
+    float2 dP = colorTexCoord - center;
+    float t = fract((atan2(dP.y, dP.x) - startAngle) * 0.15915493667125701904296875);
+    return colorTexture.sample(colorTextureSmplr, (uvOrigin + float2(t, 0.0)));
+}
+
 static inline __attribute__((always_inline))
 float4 filterBlur(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTextureSize, thread const float4& filterParams0, thread const float4& filterParams1)
 {
@@ -275,6 +287,13 @@ float4 filterColor(thread const float2& colorTexCoord, thread const texture2d<fl
             float4 param_5 = filterParams1;
             return filterRadialGradient(param, colorTexture, colorTextureSmplr, param_1, param_2, param_3, param_4, param_5);
         }
+        case 5:
+        {
+            float2 param_22 = colorTexCoord;
+            float4 param_23 = filterParams0;
+            float4 param_24 = filterParams1;
+            return filterConicGradient(param_22, colorTexture, colorTextureSmplr, param_23, param_24);
+        }
         case 3:
         {
             float2 param_6 = colorTexCoord;
@@ -579,7 +598,25 @@ float4 composite(thread const float4& srcColor, thread const texture2d<float> de
 }
 
 static inline __attribute__((always_inline))
-float4 calculateColor(thread const float2& fragCoord, thread const texture2d<float> colorTexture0, thread const sampler colorTexture0Smplr, thread const texture2d<float> maskTexture0, thread const sampler maskTexture0Smplr, thread const texture2d<float> destTexture, thread const sampler destTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize0, thread const float2& maskTextureSize0, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const float4& filterParams3, thread const float4& filterParams4, thread const float2& framebufferSize, thread const int& ctrl, thread const float3& maskTexCoord0, thread const float2& colorTexCoord0, thread const float4& baseColor, thread const int& tileCtrl)
+float ditherOffset(thread const float2& fragCoord)
+{
+    int2 position = int2(fragCoord) & int2(3);
+    int2 low = position & int2(1);
+    int2 high = position >> int2(1);
+    int index = ((((low.x ^ low.y) << 3) | (low.y << 2)) | ((high.x ^ high.y) << 1)) | high.y;
+    return ((float(index) + 0.5) / 16.0) - 0.5;
+}
+
+static inline __attribute__((always_inline))
+float4 dither(thread const float4& color, thread const float2& fragCoord)
+{
+    float2 param = fragCoord;
+    float3 ditheredRGB = fast::clamp(color.xyz + float3(ditherOffset(param) / 255.0), float3(0.0), float3(color.w));
+    return float4(ditheredRGB.x, ditheredRGB.y, ditheredRGB.z, color.w);
+}
+
+static inline __attribute__((always_inline))
+float4 calculateColor(thread const float2& fragCoord, thread const texture2d<float> colorTexture0, thread const sampler colorTexture0Smplr, thread const texture2d<float> colorTexture1, thread const sampler colorTexture1Smplr, thread const texture2d<float> maskTexture0, thread const sampler maskTexture0Smplr, thread const texture2d<float> destTexture, thread const sampler destTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize0, thread const float2& maskTextureSize0, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const float4& filterParams3, thread const float4& filterParams4, thread const float2& framebufferSize, thread const int& ctrl, thread const float3& maskTexCoord0, thread const float2& colorTexCoord0, thread const float2& colorTexCoord1, thread const float4& baseColor, thread const int& tileCtrl)
 {
     int maskCtrl0 = (tileCtrl >> 0) & 3;
     float maskAlpha = 1.0;
@@ -609,6 +646,16 @@ float4 calculateColor(thread const float2& fragCoord, thread const texture2d<flo
         int param_16 = color0Combine;
         color = combineColor0(param_14, param_15, param_16);
     }
+    int color1Combine = (ctrl >> 16) & 3;
+    if (color1Combine != 0)
+    {
+        float2 param_23 = colorTexCoord1;
+        float4 color1 = sampleColor(colorTexture1, colorTexture1Smplr, param_23);
+        float4 param_24 = color;
+        float4 param_25 = color1;
+        int param_26 = color1Combine;
+        color = combineColor0(param_24, param_25, param_26);
+    }
     color.w *= maskAlpha;
     int compositeOp = (ctrl >> 10) & 15;
     float4 param_17 = color;
@@ -618,10 +665,16 @@ float4 calculateColor(thread const float2& fragCoord, thread const texture2d<flo
     color = composite(param_17, destTexture, destTextureSmplr, param_18, param_19, param_20);
     float3 _1409 = color.xyz * color.w;
     color = float4(_1409.x, _1409.y, _1409.z, color.w);
+    if ((ctrl & 16384) != 0)
+    {
+        float4 param_21 = color;
+        float2 param_22 = fragCoord;
+        color = dither(param_21, param_22);
+    }
     return color;
 }
 
-fragment main0_out main0(main0_in in [[stage_in]], constant float2& uColorTextureSize0 [[buffer(0)]], constant float2& uMaskTextureSize0 [[buffer(1)]], constant float2& uFramebufferSize [[buffer(2)]], texture2d<float> uColorTexture0 [[texture(0)]], texture2d<float> uMaskTexture0 [[texture(1)]], texture2d<float> uDestTexture [[texture(2)]], texture2d<float> uGammaLUT [[texture(3)]], sampler uColorTexture0Smplr [[sampler(0)]], sampler uMaskTexture0Smplr [[sampler(1)]], sampler uDestTextureSmplr [[sampler(2)]], sampler uGammaLUTSmplr [[sampler(3)]], float4 gl_FragCoord [[position]])
+fragment main0_out main0(main0_in in [[stage_in]], constant float2& uColorTextureSize0 [[buffer(0)]], constant float2& uMaskTextureSize0 [[buffer(1)]], constant float2& uFramebufferSize [[buffer(2)]], texture2d<float> uColorTexture0 [[texture(0)]], texture2d<float> uMaskTexture0 [[texture(1)]], texture2d<float> uDestTexture [[texture(2)]], texture2d<float> uGammaLUT [[texture(3)]], texture2d<float> uColorTexture1 [[texture(4)]], sampler uColorTexture0Smplr [[sampler(0)]], sampler uMaskTexture0Smplr [[sampler(1)]], sampler uDestTextureSmplr [[sampler(2)]], sampler uGammaLUTSmplr [[sampler(3)]], sampler uColorTexture1Smplr [[sampler(4)]], float4 gl_FragCoord [[position]])
 {
     main0_out out = {};
     float2 param = gl_FragCoord.xy;
@@ -636,9 +689,10 @@ fragment main0_out main0(main0_in in [[stage_in]], constant float2& uColorTextur
     int param_9 = int(in.vCtrl);
     float3 param_10 = in.vMaskTexCoord0;
     float2 param_11 = in.vColorTexCoord0;
-    float4 param_12 = in.vBaseColor;
-    int param_13 = int(in.vTileCtrl);
-    out.oFragColor = calculateColor(param, uColorTexture0, uColorTexture0Smplr, uMaskTexture0, uMaskTexture0Smplr, uDestTexture, uDestTextureSmplr, uGammaLUT, uGammaLUTSmplr, param_1, param_2, param_3, param_4, param_5, param_6, param_7, param_8, param_9, param_10, param_11, param_12, param_13);
+    float2 param_12 = in.vColorTexCoord1;
+    float4 param_13 = in.vBaseColor;
+    int param_14 = int(in.vTileCtrl);
+    out.oFragColor = calculateColor(param, uColorTexture0, uColorTexture0Smplr, uColorTexture1, uColorTexture1Smplr, uMaskTexture0, uMaskTexture0Smplr, uDestTexture, uDestTextureSmplr, uGammaLUT, uGammaLUTSmplr, param_1, param_2, param_3, param_4, param_5, param_6, param_7, param_8, param_9, param_10, param_11, param_12, param_13, param_14);
     return out;
 }
 
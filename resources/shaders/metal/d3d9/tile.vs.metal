@@ -18,6 +18,7 @@ struct main0_out
     float4 vFilterParams3 [[user(locn7)]];
     float4 vFilterParams4 [[user(locn8)]];
     float vCtrl [[user(locn9)]];
+    float2 vColorTexCoord1 [[user(locn10)]];
     float4 gl_Position [[position]];
 };
 
@@ -38,7 +39,7 @@ float4 fetchUnscaled(thread const texture2d<float> srcTexture, thread const samp
 }
 
 static inline __attribute__((always_inline))
-void computeTileVaryings(thread const float2& position, thread const int& colorEntry, thread const texture2d<float> textureMetadata, thread const sampler textureMetadataSmplr, thread const int2& textureMetadataSize, thread float2& outColorTexCoord0, thread float4& outBaseColor, thread float4& outFilterParams0, thread float4& outFilterParams1, thread float4& outFilterParams2, thread float4& outFilterParams3, thread float4& outFilterParams4, thread int& outCtrl)
+void computeTileVaryings(thread const float2& position, thread const int& colorEntry, thread const texture2d<float> textureMetadata, thread const sampler textureMetadataSmplr, thread const int2& textureMetadataSize, thread float2& outColorTexCoord0, thread float2& outColorTexCoord1, thread float4& outBaseColor, thread float4& outFilterParams0, thread float4& outFilterParams1, thread float4& outFilterParams2, thread float4& outFilterParams3, thread float4& outFilterParams4, thread int& outCtrl)
 {
     float2 metadataScale = float2(1.0) / float2(textureMetadataSize);
     float2 metadataEntryCoord = float2(float((colorEntry % 128) * 10), float(colorEntry / 128));
@@ -78,14 +79,19 @@ void computeTileVaryings(thread const float2& position, thread const int& colorE
     float2 param_25 = metadataEntryCoord;
     int param_26 = 8;
     float4 extra = fetchUnscaled(textureMetadata, textureMetadataSmplr, param_24, param_25, param_26);
+    float2 param_27 = metadataScale;
+    float2 param_28 = metadataEntryCoord;
+    int param_29 = 9;
+    float4 colorTexMatrix1 = fetchUnscaled(textureMetadata, textureMetadataSmplr, param_27, param_28, param_29);
     outColorTexCoord0 = (float2x2(float2(colorTexMatrix0.xy), float2(colorTexMatrix0.zw)) * position) + colorTexOffsets.xy;
+    outColorTexCoord1 = (float2x2(float2(colorTexMatrix1.xy), float2(colorTexMatrix1.zw)) * position) + colorTexOffsets.zw;
     outBaseColor = baseColor;
     outFilterParams0 = filterParams0;
     outFilterParams1 = filterParams1;
     outFilterParams2 = filterParams2;
     outFilterParams3 = filterParams3;
     outFilterParams4 = filterParams4;
-    outCtrl = int(extra.x);
+    outCtrl = int(extra.x) | (int(extra.y) << 16);
 }
 
 vertex main0_out main0(main0_in in [[stage_in]], constant int2& uZBufferSize [[buffer(1)]], constant int2& uTextureMetadataSize [[buffer(2)]], constant float2& uTileSize [[buffer(0)]], constant float4x4& uTransform [[buffer(3)]], texture2d<float> uZBuffer [[texture(0)]], texture2d<float> uTextureMetadata [[texture(1)]], sampler uZBufferSmplr [[sampler(0)]], sampler uTextureMetadataSmplr [[sampler(1)]])
@@ -121,22 +127,24 @@ vertex main0_out main0(main0_in in [[stage_in]], constant int2& uZBufferSize [[b
     int param_1 = in.aColor;
     int2 param_2 = uTextureMetadataSize;
     float2 param_3;
-    float4 param_4;
+    float2 param_4;
     float4 param_5;
     float4 param_6;
     float4 param_7;
     float4 param_8;
     float4 param_9;
-    int param_10;
-    computeTileVaryings(param, param_1, uTextureMetadata, uTextureMetadataSmplr, param_2, param_3, param_4, param_5, param_6, param_7, param_8, param_9, param_10);
+    float4 param_10;
+    int param_11;
+    computeTileVaryings(param, param_1, uTextureMetadata, uTextureMetadataSmplr, param_2, param_3, param_4, param_5, param_6, param_7, param_8, param_9, param_10, param_11);
     out.vColorTexCoord0 = param_3;
-    out.vBaseColor = param_4;
-    out.vFilterParams0 = param_5;
-    out.vFilterParams1 = param_6;
-    out.vFilterParams2 = param_7;
-    out.vFilterParams3 = param_8;
-    out.vFilterParams4 = param_9;
-    int ctrl = param_10;
+    out.vColorTexCoord1 = param_4;
+    out.vBaseColor = param_5;
+    out.vFilterParams0 = param_6;
+    out.vFilterParams1 = param_7;
+    out.vFilterParams2 = param_8;
+    out.vFilterParams3 = param_9;
+    out.vFilterParams4 = param_10;
+    int ctrl = param_11;
     out.vTileCtrl = float(in.aCtrlBackdrop.x);
     out.vCtrl = float(ctrl);
     out.vMaskTexCoord0 = float3(maskTexCoord0, float(in.aCtrlBackdrop.y));
@@ -22,18 +22,22 @@ struct main0_in
     int aSrcBackdrop [[attribute(4)]];
 };
 
-vertex main0_out main0(main0_in in [[stage_in]], constant float2& uFramebufferSize [[buffer(0)]])
+vertex main0_out main0(main0_in in [[stage_in]], constant float2& uFramebufferSize [[buffer(0)]], constant float2& uSrcFramebufferSize [[buffer(1)]], uint gl_InstanceIndex [[instance_id]])
 {
     main0_out out = {};
+    int tempTileIndex0 = int(gl_InstanceIndex) * 2;
+    int tempTileIndex1 = tempTileIndex0 + 1;
     float2 destPosition = float2(int2(in.aDestTileIndex % 256, in.aDestTileIndex / 256) + in.aTileOffset);
-    float2 srcPosition = float2(int2(in.aSrcTileIndex % 256, in.aSrcTileIndex / 256) + in.aTileOffset);
+    float2 tempPosition0 = float2(int2(tempTileIndex0 % 256, tempTileIndex0 / 256) + in.aTileOffset);
+    float2 tempPosition1 = float2(int2(tempTileIndex1 % 256, tempTileIndex1 / 256) + in.aTileOffset);
     destPosition *= (float2(16.0, 4.0) / uFramebufferSize);
-    srcPosition *= (float2(16.0, 4.0) / uFramebufferSize);
-    out.vTexCoord0 = destPosition;
-    out.vTexCoord1 = srcPosition;
+    tempPosition0 *= (float2(16.0, 4.0) / uSrcFramebufferSize);
+    tempPosition1 *= (float2(16.0, 4.0) / uSrcFramebufferSize);
+    out.vTexCoord0 = tempPosition0;
+    out.vTexCoord1 = tempPosition1;
     out.vBackdrop0 = float(in.aDestBackdrop);
     out.vBackdrop1 = float(in.aSrcBackdrop);
-    if (in.aDestTileIndex < 0)
+    if ((in.aDestTileIndex < 0) || (in.aSrcTileIndex < 0))
     {
         destPosition = float2(0.0);
     }
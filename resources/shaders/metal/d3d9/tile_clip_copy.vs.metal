@@ -16,12 +16,14 @@ struct main0_in
     int aTileIndex [[attribute(1)]];
 };
 
-vertex main0_out main0(main0_in in [[stage_in]], constant float2& uFramebufferSize [[buffer(0)]])
+vertex main0_out main0(main0_in in [[stage_in]], constant float2& uFramebufferSize [[buffer(0)]], constant float2& uSrcFramebufferSize [[buffer(1)]], uint gl_InstanceIndex [[instance_id]])
 {
     main0_out out = {};
-    float2 position = float2(int2(in.aTileIndex % 256, in.aTileIndex / 256) + in.aTileOffset);
+    float2 srcPosition = float2(int2(in.aTileIndex % 256, in.aTileIndex / 256) + in.aTileOffset);
+    float2 position = float2(int2(int(gl_InstanceIndex) % 256, int(gl_InstanceIndex) / 256) + in.aTileOffset);
+    srcPosition *= (float2(16.0, 4.0) / uSrcFramebufferSize);
     position *= (float2(16.0, 4.0) / uFramebufferSize);
-    out.vTexCoord = position;
+    out.vTexCoord = srcPosition;
     if (in.aTileIndex < 0)
     {
         position = float2(0.0);
@@ -0,0 +1,27 @@
+// Automatically generated from files in pathfinder/shaders/. Do not edit!
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using namespace metal;
+
+struct main0_out
+{
+    float4 vColor [[user(locn0)]];
+    float4 gl_Position [[position]];
+};
+
+struct main0_in
+{
+    float2 aPosition [[attribute(0)]];
+    float4 aColor [[attribute(1)]];
+};
+
+vertex main0_out main0(main0_in in [[stage_in]], constant float2& uFramebufferSize [[buffer(0)]])
+{
+    main0_out out = {};
+    float2 position = ((in.aPosition / uFramebufferSize) * 2.0) - float2(1.0);
+    out.vColor = in.aColor;
+    out.gl_Position = float4(position.x, -position.y, 0.0, 1.0);
+    return out;
+}
+
@@ -17,6 +17,8 @@ precision highp sampler2D;
 #endif
 
 uniform vec4 uDestRect;
+uniform mat2 uTransform;
+uniform vec2 uTranslation;
 uniform vec2 uFramebufferSize;
 
 in ivec2 aPosition;
@@ -24,7 +26,8 @@ in ivec2 aPosition;
 out vec2 vTexCoord;
 
 void main() {
-    vec2 position = mix(uDestRect.xy, uDestRect.zw, vec2(aPosition)) / uFramebufferSize;
+    vec2 position = mix(uDestRect.xy, uDestRect.zw, vec2(aPosition));
+    position = (uTransform * position + uTranslation) / uFramebufferSize;
     vec2 texCoord = vec2(aPosition);
     vTexCoord = texCoord;
     gl_Position = vec4(mix(vec2(-1.0), vec2(1.0), position), 0.0, 1.0);
@@ -39,6 +39,7 @@ uniform ivec2 uTextureMetadataSize;
 uniform sampler2D uZBuffer;
 uniform ivec2 uZBufferSize;
 uniform sampler2D uColorTexture0;
+uniform sampler2D uColorTexture1;
 uniform sampler2D uMaskTexture0;
 uniform sampler2D uGammaLUT;
 uniform vec2 uColorTextureSize0;
@@ -114,7 +115,7 @@ void main() {
 
             vec3 maskTexCoord0 = vec3(vec2(ivec2(maskTileCoord) + tileSubCoord), backdrop);
 
-            vec2 colorTexCoord0;
+            vec2 colorTexCoord0, colorTexCoord1;
             vec4 baseColor, filterParams0, filterParams1, filterParams2, filterParams3, filterParams4;
             int ctrl;
             computeTileVaryings(fragCoord,
@@ -122,6 +123,7 @@ void main() {
                                 uTextureMetadata,
                                 uTextureMetadataSize,
                                 colorTexCoord0,
+                                colorTexCoord1,
                                 baseColor,
                                 filterParams0,
                                 filterParams1,
@@ -135,6 +137,7 @@ void main() {
 
             vec4 srcColor = calculateColor(fragCoord,
                                            uColorTexture0,
+                                           uColorTexture1,
                                            uMaskTexture0,
                                            uColorTexture0,
                                            uGammaLUT,
@@ -149,6 +152,7 @@ void main() {
                                            ctrl,
                                            maskTexCoord0,
                                            colorTexCoord0,
+                                           colorTexCoord1,
                                            baseColor,
                                            tileCtrl);
 
@@ -21,6 +21,7 @@ precision highp sampler2D;
 #include "tile_fragment.inc.glsl"
 
 uniform sampler2D uColorTexture0;
+uniform sampler2D uColorTexture1;
 uniform sampler2D uMaskTexture0;
 uniform sampler2D uDestTexture;
 uniform sampler2D uGammaLUT;
@@ -30,6 +31,7 @@ uniform vec2 uFramebufferSize;
 
 in vec3 vMaskTexCoord0;
 in vec2 vColorTexCoord0;
+in vec2 vColorTexCoord1;
 in vec4 vBaseColor;
 in float vTileCtrl;
 in vec4 vFilterParams0;
@@ -48,6 +50,7 @@ out vec4 oFragColor;
 void main() {
     oFragColor = calculateColor(gl_FragCoord.xy,
                                 uColorTexture0,
+                                uColorTexture1,
                                 uMaskTexture0,
                                 uDestTexture,
                                 uGammaLUT,
@@ -62,6 +65,7 @@ void main() {
                                 int(vCtrl),
                                 vMaskTexCoord0,
                                 vColorTexCoord0,
+                                vColorTexCoord1,
                                 vBaseColor,
                                 int(vTileCtrl));
 }
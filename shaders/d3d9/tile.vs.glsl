@@ -36,6 +36,7 @@ in int aColor;
 
 out vec3 vMaskTexCoord0;
 out vec2 vColorTexCoord0;
+out vec2 vColorTexCoord1;
 out vec4 vBaseColor;
 out float vTileCtrl;
 out vec4 vFilterParams0;
@@ -68,6 +69,7 @@ void main() {
                         uTextureMetadata,
                         uTextureMetadataSize,
                         vColorTexCoord0,
+                        vColorTexCoord1,
                         vBaseColor,
                         vFilterParams0,
                         vFilterParams1,
@@ -17,6 +17,7 @@ precision highp sampler2D;
 #endif
 
 uniform vec2 uFramebufferSize;
+uniform vec2 uSrcFramebufferSize;
 
 in ivec2 aTileOffset;
 in int aTileIndex;
@@ -24,10 +25,13 @@ in int aTileIndex;
 out vec2 vTexCoord;
 
 void main() {
-    vec2 position = vec2(ivec2(aTileIndex % 256, aTileIndex / 256) + aTileOffset);
+    // Tiles are packed into the temporary framebuffer in instance order.
+    vec2 srcPosition = vec2(ivec2(aTileIndex % 256, aTileIndex / 256) + aTileOffset);
+    vec2 position = vec2(ivec2(gl_InstanceID % 256, gl_InstanceID / 256) + aTileOffset);
+    srcPosition *= vec2(16.0, 4.0) / uSrcFramebufferSize;
     position *= vec2(16.0, 4.0) / uFramebufferSize;
 
-    vTexCoord = position;
+    vTexCoord = srcPosition;
 
     if (aTileIndex < 0)
         position = vec2(0.0);
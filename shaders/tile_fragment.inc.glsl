@@ -47,6 +47,7 @@
 #define COMBINER_CTRL_FILTER_TEXT               0x2
 #define COMBINER_CTRL_FILTER_BLUR               0x3
 #define COMBINER_CTRL_FILTER_COLOR_MATRIX       0x4
+#define COMBINER_CTRL_FILTER_CONIC_GRADIENT     0x5
 
 #define COMBINER_CTRL_COMPOSITE_MASK            0xf
 #define COMBINER_CTRL_COMPOSITE_NORMAL          0x0
@@ -69,6 +70,8 @@
 #define COMBINER_CTRL_COLOR_FILTER_SHIFT        4
 #define COMBINER_CTRL_COLOR_COMBINE_SHIFT       8
 #define COMBINER_CTRL_COMPOSITE_SHIFT          10
+#define COMBINER_CTRL_DITHER                0x4000
+#define COMBINER_CTRL_COLOR_1_COMBINE_SHIFT     16
 
 // Color sampling
 
@@ -301,6 +304,26 @@ vec4 filterRadialGradient(vec2 colorTexCoord,
     return color;
 }
 
+// Conic gradients sweep clockwise (in Y-down coordinates) around a center point, so the position
+// along the linearized gradient is the fraction of a turn from the start angle.
+//
+//                | x           y           z               w
+//  --------------+-----------------------------------------------------
+//  filterParams0 | center.x    center.y    startAngle      -
+//  filterParams1 | -           -           uvOrigin.x      uvOrigin.y
+//  filterParams2 | -           -           -               -
+vec4 filterConicGradient(vec2 colorTexCoord,
+                         sampler2D colorTexture,
+                         vec4 filterParams0,
+                         vec4 filterParams1) {
+    vec2 center = filterParams0.xy, uvOrigin = filterParams1.zw;
+    float startAngle = filterParams0.z;
+
+    vec2 dP = colorTexCoord - center;
+    float t = fract((atan(dP.y, dP.x) - startAngle) * (1.0 / (2.0 * 3.14159265358979)));
+    return texture(colorTexture, uvOrigin + vec2(t, 0.0));
+}
+
 //                | x             y             z             w
 //  --------------+----------------------------------------------------
 //  filterParams0 | srcOffset.x   srcOffset.y   support       -
@@ -383,6 +406,11 @@ vec4 filterColor(vec2 colorTexCoord,
                                     framebufferSize,
                                     filterParams0,
                                     filterParams1);
+    case COMBINER_CTRL_FILTER_CONIC_GRADIENT:
+        return filterConicGradient(colorTexCoord,
+                                   colorTexture,
+                                   filterParams0,
+                                   filterParams1);
     case COMBINER_CTRL_FILTER_BLUR:
         return filterBlur(colorTexCoord,
                           colorTexture,
@@ -555,10 +583,27 @@ float sampleMask(float maskAlpha,
     return min(maskAlpha, coverage);
 }
 
+// Dithering
+
+// Returns an offset in (-0.5, 0.5) from a 4×4 Bayer matrix, for ordered dithering.
+float ditherOffset(vec2 fragCoord) {
+    ivec2 position = ivec2(fragCoord) & 3;
+    ivec2 low = position & 1, high = position >> 1;
+    int index = ((low.x ^ low.y) << 3) | (low.y << 2) | ((high.x ^ high.y) << 1) | high.y;
+    return (float(index) + 0.5) / 16.0 - 0.5;
+}
+
+// Perturbs a premultiplied color by less than one 8-bit step, so that gradients don't band.
+vec4 dither(vec4 color, vec2 fragCoord) {
+    color.rgb = clamp(color.rgb + vec3(ditherOffset(fragCoord) / 255.0), vec3(0.0), vec3(color.a));
+    return color;
+}
+
 // Main function
 
 vec4 calculateColor(vec2 fragCoord,
                     sampler2D colorTexture0,
+                    sampler2D colorTexture1,
                     sampler2D maskTexture0,
                     sampler2D destTexture,
                     sampler2D gammaLUT,
@@ -573,6 +618,7 @@ vec4 calculateColor(vec2 fragCoord,
                     int ctrl,
                     vec3 maskTexCoord0,
                     vec2 colorTexCoord0,
+                    vec2 colorTexCoord1,
                     vec4 baseColor,
                     int tileCtrl) {
     // Sample mask.
@@ -601,6 +647,14 @@ vec4 calculateColor(vec2 fragCoord,
         color = combineColor0(color, color0, color0Combine);
     }
 
+    // Sample the second color, which is never filtered.
+    int color1Combine = (ctrl >> COMBINER_CTRL_COLOR_1_COMBINE_SHIFT) &
+        COMBINER_CTRL_COLOR_COMBINE_MASK;
+    if (color1Combine != 0) {
+        vec4 color1 = sampleColor(colorTexture1, colorTexCoord1);
+        color = combineColor0(color, color1, color1Combine);
+    }
+
     // Apply mask.
     color.a *= maskAlpha;
 
@@ -610,5 +664,9 @@ vec4 calculateColor(vec2 fragCoord,
 
     // Premultiply alpha.
     color.rgb *= color.a;
+
+    // Dither if requested.
+    if ((ctrl & COMBINER_CTRL_DITHER) != 0)
+        color = dither(color, fragCoord);
     return color;
 }
@@ -17,6 +17,7 @@ void computeTileVaryings(vec2 position,
                          sampler2D textureMetadata,
                          ivec2 textureMetadataSize,
                          out vec2 outColorTexCoord0,
+                         out vec2 outColorTexCoord1,
                          out vec4 outBaseColor,
                          out vec4 outFilterParams0,
                          out vec4 outFilterParams1,
@@ -35,12 +36,16 @@ void computeTileVaryings(vec2 position,
     vec4 filterParams3   = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 6);
     vec4 filterParams4   = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 7);
     vec4 extra           = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 8);
+    vec4 colorTexMatrix1 = fetchUnscaled(textureMetadata, metadataScale, metadataEntryCoord, 9);
     outColorTexCoord0 = mat2(colorTexMatrix0) * position + colorTexOffsets.xy;
+    outColorTexCoord1 = mat2(colorTexMatrix1) * position + colorTexOffsets.zw;
     outBaseColor = baseColor;
     outFilterParams0 = filterParams0;
     outFilterParams1 = filterParams1;
     outFilterParams2 = filterParams2;
     outFilterParams3 = filterParams3;
     outFilterParams4 = filterParams4;
-    outCtrl = int(extra.x);
+    // The color 1 combine mode is stored apart from the rest of the ctrl word, which is too
+    // wide for the low bits to survive being stored as a half float.
+    outCtrl = int(extra.x) | (int(extra.y) << 16);
 }
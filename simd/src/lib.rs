@@ -34,6 +34,8 @@ pub use crate::x86 as default;
 pub mod arm;
 mod extras;
 pub mod scalar;
+#[cfg(feature = "serialize")]
+mod serialize;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod x86;
 
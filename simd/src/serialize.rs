@@ -0,0 +1,68 @@
+// pathfinder/simd/src/serialize.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `serde` support, enabled with the `serialize` feature.
+//!
+//! Vectors are serialized as arrays of their lanes, independently of the SIMD backend in use.
+
+use crate::default::{F32x2, F32x4, I32x2, I32x4};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for F32x2 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        [self[0], self[1]].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for F32x2 {
+    fn deserialize<D>(deserializer: D) -> Result<F32x2, D::Error> where D: Deserializer<'de> {
+        let [a, b] = <[f32; 2]>::deserialize(deserializer)?;
+        Ok(F32x2::new(a, b))
+    }
+}
+
+impl Serialize for F32x4 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        [self[0], self[1], self[2], self[3]].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for F32x4 {
+    fn deserialize<D>(deserializer: D) -> Result<F32x4, D::Error> where D: Deserializer<'de> {
+        let [a, b, c, d] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(F32x4::new(a, b, c, d))
+    }
+}
+
+impl Serialize for I32x2 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        [self[0], self[1]].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for I32x2 {
+    fn deserialize<D>(deserializer: D) -> Result<I32x2, D::Error> where D: Deserializer<'de> {
+        let [a, b] = <[i32; 2]>::deserialize(deserializer)?;
+        Ok(I32x2::new(a, b))
+    }
+}
+
+impl Serialize for I32x4 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        [self[0], self[1], self[2], self[3]].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for I32x4 {
+    fn deserialize<D>(deserializer: D) -> Result<I32x4, D::Error> where D: Deserializer<'de> {
+        let [a, b, c, d] = <[i32; 4]>::deserialize(deserializer)?;
+        Ok(I32x4::new(a, b, c, d))
+    }
+}
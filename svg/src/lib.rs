@@ -9,6 +9,10 @@
 // except according to those terms.
 
 //! Converts a subset of SVG to a Pathfinder scene.
+//!
+//! Text needs no special handling here: `usvg` lays out `<text>` elements and converts them to
+//! paths when the tree is parsed, using the fonts configured in its `Options`, so they arrive as
+//! ordinary path nodes with their fill and stroke.
 
 #[macro_use]
 extern crate bitflags;
@@ -19,29 +23,48 @@ use pathfinder_content::dash::OutlineDash;
 use pathfinder_content::fill::FillRule;
 use pathfinder_content::gradient::{ColorStop, Gradient, GradientWrap};
 use pathfinder_content::outline::Outline;
+use pathfinder_content::pattern::{Image, Pattern};
+use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_content::segment::{Segment, SegmentFlags};
 use pathfinder_content::stroke::{LineCap, LineJoin, OutlineStrokeToFill, StrokeStyle};
 use pathfinder_content::transform::Transform2FPathIter;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
-use pathfinder_geometry::vector::{Vector2F, vec2f};
+use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f, vec2i};
 use pathfinder_renderer::paint::Paint;
-use pathfinder_renderer::scene::{ClipPath, ClipPathId, DrawPath, Scene};
+use pathfinder_renderer::scene::{ClipPath, ClipPathId, DrawPath, RenderTarget, Scene};
 use pathfinder_simd::default::F32x2;
 use std::fmt::{Display, Formatter, Result as FormatResult};
-use usvg::{BaseGradient, Color as SvgColor, FillRule as UsvgFillRule, LineCap as UsvgLineCap};
+use std::fs;
+use usvg::{Align, AspectRatio, BaseGradient, Color as SvgColor, FillRule as UsvgFillRule};
+use usvg::{Image as UsvgImage, ImageData, ImageFormat, ImageRendering, LineCap as UsvgLineCap};
 use usvg::{LineJoin as UsvgLineJoin, Node, NodeExt, NodeKind, Opacity, Paint as UsvgPaint};
 use usvg::{PathSegment as UsvgPathSegment, Rect as UsvgRect, SpreadMethod, Stop};
-use usvg::{Transform as UsvgTransform, Tree, Visibility};
+use usvg::{Transform as UsvgTransform, Tree, Units, Visibility};
 
 const HAIRLINE_STROKE_WIDTH: f32 = 0.0333;
 
+// The maximum number of nested translucent groups that get their own render target. Groups nested
+// more deeply than this are drawn opaque.
+const MAX_GROUP_OPACITY_DEPTH: u32 = 8;
+
 pub struct SVGScene {
     pub scene: Scene,
     pub result_flags: BuildResultFlags,
+    /// The IDs of the elements that weren't fully supported, each with the flags describing why.
+    ///
+    /// Elements appear in document order, and each appears at most once. Their flags are also
+    /// included in `result_flags`. Elements without an `id` attribute are identified by their
+    /// element name and their position in the simplified document instead, such as `g[3]`.
+    pub unsupported_nodes: Vec<(String, BuildResultFlags)>,
     pub clip_paths: HashMap<String, Outline>,
     gradients: HashMap<String, GradientInfo>,
+    pattern_tiles: HashMap<String, PatternTile>,
+    // The index of each entry of `unsupported_nodes`, by key.
+    unsupported_node_indices: HashMap<String, usize>,
+    // The index of each node of the document in document order, by the address of its data.
+    document_indices: HashMap<usize, usize>,
 }
 
 bitflags! {
@@ -56,33 +79,69 @@ bitflags! {
         const UNSUPPORTED_LINK_PAINT             = 0x0020;
         const UNSUPPORTED_FILTER_ATTR            = 0x0040;
         const UNSUPPORTED_MASK_ATTR              = 0x0080;
+        const UNSUPPORTED_CLIP_PATH_ATTR         = 0x0100;
+        const UNSUPPORTED_OPACITY_ATTR           = 0x0200;
     }
 }
 
 impl SVGScene {
-    // TODO(pcwalton): Allow a global transform to be set.
     #[inline]
     pub fn from_tree(tree: &Tree) -> SVGScene {
         SVGScene::from_tree_and_scene(tree, Scene::new())
     }
 
-    // TODO(pcwalton): Allow a global transform to be set.
+    #[inline]
     pub fn from_tree_and_scene(tree: &Tree, scene: Scene) -> SVGScene {
+        SVGScene::from_tree_and_scene_with_transform(tree, scene, Transform2F::default())
+    }
+
+    /// Converts the SVG with all of its contents transformed by `transform`, which allows it to be
+    /// positioned and scaled within a larger scene.
+    #[inline]
+    pub fn from_tree_with_transform(tree: &Tree, transform: Transform2F) -> SVGScene {
+        SVGScene::from_tree_and_scene_with_transform(tree, Scene::new(), transform)
+    }
+
+    /// Appends the SVG to an existing scene, with all of its contents transformed by `transform`.
+    ///
+    /// The SVG view box is fitted to its width and height according to `preserveAspectRatio`, and
+    /// the view box of the scene is set to the bounding box of the transformed viewport.
+    pub fn from_tree_and_scene_with_transform(tree: &Tree, scene: Scene, transform: Transform2F)
+                                              -> SVGScene {
         // TODO(pcwalton): Maybe have a `SVGBuilder` type to hold the clip path IDs and other
         // transient data separate from `SVGScene`?
         let mut built_svg = SVGScene {
             scene,
             result_flags: BuildResultFlags::empty(),
+            unsupported_nodes: vec![],
             clip_paths: HashMap::new(),
             gradients: HashMap::new(),
+            pattern_tiles: HashMap::new(),
+            unsupported_node_indices: HashMap::new(),
+            document_indices: HashMap::new(),
         };
 
         let root = &tree.root();
+        built_svg.document_indices = root.descendants().enumerate().map(|(index, node)| {
+            (node_address(&node), index)
+        }).collect();
+
         match *root.borrow() {
             NodeKind::Svg(ref svg) => {
-                built_svg.scene.set_view_box(usvg_rect_to_euclid_rect(&svg.view_box.rect));
+                // Map the view box onto the viewport per `preserveAspectRatio`.
+                let view_box = usvg_rect_to_euclid_rect(&svg.view_box.rect);
+                let viewport = RectF::new(Vector2F::zero(),
+                                          vec2f(svg.size.width() as f32,
+                                                svg.size.height() as f32));
+                let fitted_rect = fit_to_view_box(view_box.size(), viewport, svg.view_box.aspect);
+                let view_box_transform = Transform2F::from_translation(fitted_rect.origin()) *
+                    Transform2F::from_scale(fitted_rect.size() / view_box.size()) *
+                    Transform2F::from_translation(-view_box.origin());
+                built_svg.scene.set_view_box(transform * viewport);
+                let mut state = State::new();
+                state.transform = transform * view_box_transform;
                 for kid in root.children() {
-                    built_svg.process_node(&kid, &State::new(), &mut None);
+                    built_svg.process_node(&kid, &state, &mut None);
                 }
             }
             _ => unreachable!(),
@@ -101,14 +160,22 @@ impl SVGScene {
         match *node.borrow() {
             NodeKind::Group(ref group) => {
                 if group.filter.is_some() {
-                    self.result_flags.insert(BuildResultFlags::UNSUPPORTED_FILTER_ATTR);
+                    self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_FILTER_ATTR);
                 }
                 if group.mask.is_some() {
-                    self.result_flags.insert(BuildResultFlags::UNSUPPORTED_MASK_ATTR);
+                    self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_MASK_ATTR);
                 }
+                // Clips accumulate: each one is clipped by the clip of the enclosing group.
                 if let Some(ref clip_path_name) = group.clip_path {
-                    if let Some(clip_outline) = self.clip_paths.get(clip_path_name) {
-                        let transformed_outline = clip_outline.clone().transformed(&state.transform);
+                    // usvg places definitions before their uses, so a missing clip path can't be
+                    // resolved at all.
+                    let clip_outline = self.clip_paths.get(clip_path_name).cloned();
+                    if clip_outline.is_none() {
+                        self.report_unsupported(node,
+                                                BuildResultFlags::UNSUPPORTED_CLIP_PATH_ATTR);
+                    }
+                    if let Some(clip_outline) = clip_outline {
+                        let transformed_outline = clip_outline.transformed(&state.transform);
                         let mut clip_path = ClipPath::new(transformed_outline);
                         clip_path.set_clip_path(state.clip_path);
                         clip_path.set_name(format!("ClipPath({})", clip_path_name));
@@ -117,21 +184,38 @@ impl SVGScene {
                     }
                 }
 
+                let opacity = group.opacity.value() as f32;
+                let group_render_target = if opacity < 1.0 {
+                    self.push_group_render_target(node, &mut state)
+                } else {
+                    None
+                };
+
                 for kid in node.children() {
                     self.process_node(&kid, &state, clip_outline)
                 }
+
+                if let Some((render_target_id, size)) = group_render_target {
+                    self.pop_group_render_target(render_target_id, size, opacity);
+                }
             }
             NodeKind::Path(ref path) if state.path_destination == PathDestination::Clip => {
                 // TODO(pcwalton): Multiple clip paths.
                 let path = UsvgPathToSegments::new(path.data.iter().cloned());
                 let path = Transform2FPathIter::new(path, &state.transform);
                 if clip_outline.is_some() {
-                    self.result_flags.insert(BuildResultFlags::UNSUPPORTED_MULTIPLE_CLIP_PATHS);
+                    self.report_unsupported(node,
+                                            BuildResultFlags::UNSUPPORTED_MULTIPLE_CLIP_PATHS);
                 }
                 *clip_outline = Some(Outline::from_segments(path));
             }
             NodeKind::Path(ref path) if state.path_destination == PathDestination::Draw &&
                     path.visibility == Visibility::Visible => {
+                // Gradients in `objectBoundingBox` units are relative to the untransformed bounds
+                // of the path's geometry, even for strokes.
+                let object_bounds = Outline::from_segments(UsvgPathToSegments::new(
+                    path.data.iter().cloned())).bounds();
+
                 if let Some(ref fill) = path.fill {
                     let path = UsvgPathToSegments::new(path.data.iter().cloned());
                     let outline = Outline::from_segments(path);
@@ -139,7 +223,9 @@ impl SVGScene {
                     let name = format!("Fill({})", node.id());
                     self.push_draw_path(outline,
                                         name,
+                                        node,
                                         &state,
+                                        object_bounds,
                                         &fill.paint,
                                         fill.opacity,
                                         fill.rule);
@@ -156,8 +242,10 @@ impl SVGScene {
                     let path = UsvgPathToSegments::new(path.data.iter().cloned());
                     let mut outline = Outline::from_segments(path);
 
-                    if let Some(ref dash_array) = stroke.dasharray {
-                        let dash_array: Vec<f32> = dash_array.iter().map(|&x| x as f32).collect();
+                    let dash_array = stroke.dasharray.as_ref().and_then(|dash_array| {
+                        normalize_dasharray(dash_array)
+                    });
+                    if let Some(dash_array) = dash_array {
                         let mut dash = OutlineDash::new(&outline, &dash_array, stroke.dashoffset);
                         dash.dash();
                         outline = dash.into_outline();
@@ -170,7 +258,9 @@ impl SVGScene {
                     let name = format!("Stroke({})", node.id());
                     self.push_draw_path(outline,
                                         name,
+                                        node,
                                         &state,
+                                        object_bounds,
                                         &stroke.paint,
                                         stroke.opacity,
                                         UsvgFillRule::NonZero);
@@ -178,7 +268,10 @@ impl SVGScene {
             }
             NodeKind::Path(..) => {}
             NodeKind::ClipPath(_) => {
+                // Clip paths are stored in the user space of the elements that reference them,
+                // and are transformed into place when applied.
                 let mut clip_outline = None;
+                state.transform = node_transform;
                 state.path_destination = PathDestination::Clip;
                 for kid in node.children() {
                     self.process_node(&kid, &state, &mut clip_outline);
@@ -211,25 +304,237 @@ impl SVGScene {
                                   &svg_radial_gradient.base)
             }
             NodeKind::Filter(..) => {
-                self.result_flags
-                    .insert(BuildResultFlags::UNSUPPORTED_FILTER_NODE);
+                self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_FILTER_NODE);
             }
-            NodeKind::Image(..) => {
-                self.result_flags
-                    .insert(BuildResultFlags::UNSUPPORTED_IMAGE_NODE);
+            NodeKind::Image(ref image) if state.path_destination == PathDestination::Draw &&
+                    image.visibility == Visibility::Visible => {
+                if !self.push_image(image, &state) {
+                    self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_IMAGE_NODE);
+                }
             }
+            NodeKind::Image(..) => {}
             NodeKind::Mask(..) => {
-                self.result_flags
-                    .insert(BuildResultFlags::UNSUPPORTED_MASK_NODE);
+                self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_MASK_NODE);
             }
             NodeKind::Pattern(..) => {
-                self.result_flags
-                    .insert(BuildResultFlags::UNSUPPORTED_PATTERN_NODE);
+                // Patterns are rendered when a path refers to them; see `pattern_paint()`.
             }
             NodeKind::Svg(..) => unreachable!(),
         }
     }
 
+    fn report_unsupported(&mut self, node: &Node, flags: BuildResultFlags) {
+        self.result_flags.insert(flags);
+        let node_key = self.unsupported_node_key(node);
+        match self.unsupported_node_indices.get(&node_key) {
+            Some(&index) => self.unsupported_nodes[index].1.insert(flags),
+            None => {
+                let index = self.unsupported_nodes.len();
+                self.unsupported_node_indices.insert(node_key.clone(), index);
+                self.unsupported_nodes.push((node_key, flags));
+            }
+        }
+    }
+
+    // Returns the key that identifies the given node in `unsupported_nodes`: its ID, or, if it has
+    // none, its element name and its index in document order. IDs can't contain brackets, so the
+    // two never collide.
+    fn unsupported_node_key(&self, node: &Node) -> String {
+        let id = node.id();
+        if !id.is_empty() {
+            return id.to_string();
+        }
+        let index = self.document_indices.get(&node_address(node)).cloned();
+        format!("{}[{}]", element_name(node), index.unwrap_or(0))
+    }
+
+    // Group opacity applies to the group as a whole, not to each child individually, so that
+    // overlapping children don't show through one another. The group is drawn opaque to a render
+    // target covering the view box, which is then composited with the group's opacity.
+    fn push_group_render_target(&mut self, node: &Node, state: &mut State)
+                                -> Option<(RenderTargetId, Vector2I)> {
+        if state.path_destination != PathDestination::Draw {
+            return None;
+        }
+        if state.group_opacity_depth == MAX_GROUP_OPACITY_DEPTH {
+            self.report_unsupported(node, BuildResultFlags::UNSUPPORTED_OPACITY_ATTR);
+            return None;
+        }
+        state.group_opacity_depth += 1;
+
+        // Render targets share the scene's coordinate system, so they span from the origin.
+        let size = self.scene.view_box().lower_right().ceil().to_i32().max(Vector2I::zero());
+        let render_target = RenderTarget::new(size, "GroupOpacity".to_owned());
+        Some((self.scene.push_render_target(render_target), size))
+    }
+
+    fn pop_group_render_target(&mut self,
+                               render_target_id: RenderTargetId,
+                               size: Vector2I,
+                               opacity: f32) {
+        self.scene.pop_render_target();
+
+        let mut paint = Paint::from_pattern(Pattern::from_render_target(render_target_id, size));
+        let mut base_color = paint.base_color().to_f32();
+        base_color.set_a(base_color.a() * opacity);
+        paint.set_base_color(base_color.to_u8());
+        let paint_id = self.scene.push_paint(&paint);
+
+        let outline = Outline::from_rect(RectF::new(Vector2F::zero(), size.to_f32()));
+        let mut path = DrawPath::new(outline, paint_id);
+        path.set_name("GroupOpacity".to_owned());
+        self.scene.push_draw_path(path);
+    }
+
+    // Draws a raster image as a rectangle filled with an image pattern. Returns false if the image
+    // couldn't be loaded or decoded, including if it's a nested SVG document.
+    fn push_image(&mut self, usvg_image: &UsvgImage, state: &State) -> bool {
+        let data = match (&usvg_image.format, &usvg_image.data) {
+            (ImageFormat::SVG, _) => return false,
+            (_, ImageData::Raw(ref data)) => data.clone(),
+            (_, ImageData::Path(ref path)) => {
+                match fs::read(path) {
+                    Ok(data) => data,
+                    Err(_) => return false,
+                }
+            }
+        };
+        let image_buffer = match image::load_from_memory(&data) {
+            Ok(image) => image.to_rgba(),
+            Err(_) => return false,
+        };
+        let image = Image::from_image_buffer(image_buffer);
+        let image_size = image.size().to_f32();
+        if image_size.x() <= 0.0 || image_size.y() <= 0.0 {
+            return true;
+        }
+
+        // Fit the image into its viewport as `preserveAspectRatio` directs. With `slice`, the
+        // image overflows the viewport, which clips it.
+        let view_box = usvg_rect_to_euclid_rect(&usvg_image.view_box.rect);
+        let image_rect = fit_to_view_box(image_size, view_box, usvg_image.view_box.aspect);
+        let outline = match image_rect.intersection(view_box) {
+            Some(visible_rect) => Outline::from_rect(visible_rect).transformed(&state.transform),
+            None => return true,
+        };
+
+        let mut pattern = Pattern::from_image(image);
+        pattern.apply_transform(state.transform *
+                                Transform2F::from_scale(image_rect.size() / image_size)
+                                            .translate(image_rect.origin()));
+        pattern.set_smoothing_enabled(usvg_image.rendering_mode ==
+                                      ImageRendering::OptimizeQuality);
+        let paint_id = self.scene.push_paint(&Paint::from_pattern(pattern));
+
+        let mut path = DrawPath::new(outline, paint_id);
+        path.set_clip_path(state.clip_path);
+        path.set_name(format!("Image({})", usvg_image.id));
+        self.scene.push_draw_path(path);
+        true
+    }
+
+    // Renders one tile of a `<pattern>` to a render target, at roughly the device resolution of the
+    // path being painted, and returns a paint that repeats it. Tiles that don't depend on the
+    // bounds of the path are rendered once and shared. Returns `None` if the tile is empty, in
+    // which case nothing is drawn.
+    fn pattern_paint(&mut self,
+                     pattern_node: &Node,
+                     state: &State,
+                     object_bounds: RectF,
+                     opacity: Opacity)
+                     -> Option<Paint> {
+        let (id, tile_rect, content_transform, pattern_transform) = match *pattern_node.borrow() {
+            NodeKind::Pattern(ref usvg_pattern) => {
+                // In `objectBoundingBox` units, (0, 0) and (1, 1) are the corners of the bounding
+                // box.
+                let mut tile_rect = usvg_rect_to_euclid_rect(&usvg_pattern.rect);
+                if let Units::ObjectBoundingBox = usvg_pattern.units {
+                    tile_rect = RectF::new(object_bounds.origin() +
+                                           tile_rect.origin() * object_bounds.size(),
+                                           tile_rect.size() * object_bounds.size());
+                }
+                if tile_rect.width() <= 0.0 || tile_rect.height() <= 0.0 {
+                    return None;
+                }
+
+                // The tile's content is positioned relative to the tile's origin. A view box
+                // overrides `patternContentUnits`.
+                let content_transform = match usvg_pattern.view_box {
+                    Some(ref view_box) => {
+                        let view_box_rect = usvg_rect_to_euclid_rect(&view_box.rect);
+                        let tile_bounds = RectF::new(Vector2F::zero(), tile_rect.size());
+                        let fitted_rect =
+                            fit_to_view_box(view_box_rect.size(), tile_bounds, view_box.aspect);
+                        Transform2F::from_translation(-view_box_rect.origin())
+                            .scale(fitted_rect.size() / view_box_rect.size())
+                            .translate(fitted_rect.origin())
+                    }
+                    None => {
+                        match usvg_pattern.content_units {
+                            Units::UserSpaceOnUse => Transform2F::default(),
+                            Units::ObjectBoundingBox => {
+                                Transform2F::from_scale(object_bounds.size())
+                            }
+                        }
+                    }
+                };
+
+                let pattern_transform = usvg_transform_to_transform_2d(&usvg_pattern.transform);
+                (usvg_pattern.id.clone(), tile_rect, content_transform, pattern_transform)
+            }
+            _ => unreachable!(),
+        };
+
+        let tile_to_scene_transform = state.transform * pattern_transform;
+        let size = (tile_rect.size() * tile_to_scene_transform.extract_scale()).ceil()
+                                                                              .to_i32()
+                                                                              .max(vec2i(1, 1));
+        let tile_scale = size.to_f32() / tile_rect.size();
+        let tile_content_transform = Transform2F::from_scale(tile_scale) * content_transform;
+
+        let cached_render_target_id = match self.pattern_tiles.get(&id) {
+            Some(tile) if tile.size == size && tile.content_transform == tile_content_transform => {
+                Some(tile.render_target_id)
+            }
+            _ => None,
+        };
+        let render_target_id = match cached_render_target_id {
+            Some(render_target_id) => render_target_id,
+            None => {
+                let mut tile_state = state.clone();
+                tile_state.transform = tile_content_transform;
+                tile_state.clip_path = None;
+
+                let render_target = RenderTarget::new(size, format!("Pattern({})", id));
+                let render_target_id = self.scene.push_render_target(render_target);
+                for kid in pattern_node.children() {
+                    self.process_node(&kid, &tile_state, &mut None);
+                }
+                self.scene.pop_render_target();
+
+                self.pattern_tiles.insert(id, PatternTile {
+                    render_target_id,
+                    size,
+                    content_transform: tile_content_transform,
+                });
+                render_target_id
+            }
+        };
+
+        let mut pattern = Pattern::from_render_target(render_target_id, size);
+        pattern.set_repeat_x(true);
+        pattern.set_repeat_y(true);
+        pattern.apply_transform(tile_to_scene_transform *
+                                Transform2F::from_scale(tile_scale.recip())
+                                            .translate(tile_rect.origin()));
+
+        let mut paint = Paint::from_pattern(pattern);
+        let mut base_color = paint.base_color().to_f32();
+        base_color.set_a(base_color.a() * opacity.value() as f32);
+        paint.set_base_color(base_color.to_u8());
+        Some(paint)
+    }
+
     fn add_gradient(&mut self,
                     mut gradient: Gradient,
                     id: String,
@@ -259,24 +564,45 @@ impl SVGScene {
         }
 
         let transform = usvg_transform_to_transform_2d(&usvg_base_gradient.transform);
-
-        // TODO(pcwalton): What should we do with `gradientUnits`?
-        self.gradients.insert(id, GradientInfo { gradient, transform });
+        let units = usvg_base_gradient.units;
+        self.gradients.insert(id, GradientInfo { gradient, transform, units });
     }
 
     fn push_draw_path(&mut self,
                       mut outline: Outline,
                       name: String,
+                      node: &Node,
                       state: &State,
+                      object_bounds: RectF,
                       paint: &UsvgPaint,
                       opacity: Opacity,
                       fill_rule: UsvgFillRule) {
         outline.transform(&state.transform);
-        let paint = Paint::from_svg_paint(paint,
-                                          &state.transform,
-                                          opacity,
-                                          &self.gradients,
-                                          &mut self.result_flags);
+        let pattern_node = match *paint {
+            UsvgPaint::Link(ref id) => find_pattern_node(node, id),
+            UsvgPaint::Color(_) => None,
+        };
+        let paint = match pattern_node {
+            Some(pattern_node) => {
+                match self.pattern_paint(&pattern_node, state, object_bounds, opacity) {
+                    Some(paint) => paint,
+                    None => return,
+                }
+            }
+            None => {
+                let mut paint_result_flags = BuildResultFlags::empty();
+                let paint = Paint::from_svg_paint(paint,
+                                                  &state.transform,
+                                                  object_bounds,
+                                                  opacity,
+                                                  &self.gradients,
+                                                  &mut paint_result_flags);
+                if !paint_result_flags.is_empty() {
+                    self.report_unsupported(node, paint_result_flags);
+                }
+                paint
+            }
+        };
         let style = self.scene.push_paint(&paint);
         let fill_rule = FillRule::from_usvg_fill_rule(fill_rule);
         let mut path = DrawPath::new(outline, style);
@@ -318,6 +644,8 @@ impl Display for BuildResultFlags {
             "non-color paint",
             "filter attribute",
             "mask attribute",
+            "unresolved clip path attribute",
+            "deeply nested opacity attribute",
         ];
     }
 }
@@ -325,6 +653,7 @@ impl Display for BuildResultFlags {
 trait PaintExt {
     fn from_svg_paint(svg_paint: &UsvgPaint,
                       transform: &Transform2F,
+                      object_bounds: RectF,
                       opacity: Opacity,
                       gradients: &HashMap<String, GradientInfo>,
                       result_flags: &mut BuildResultFlags)
@@ -335,6 +664,7 @@ impl PaintExt for Paint {
     #[inline]
     fn from_svg_paint(svg_paint: &UsvgPaint,
                       transform: &Transform2F,
+                      object_bounds: RectF,
                       opacity: Opacity,
                       gradients: &HashMap<String, GradientInfo>,
                       result_flags: &mut BuildResultFlags)
@@ -345,8 +675,19 @@ impl PaintExt for Paint {
             UsvgPaint::Link(ref id) => {
                 match gradients.get(id) {
                     Some(ref gradient_info) => {
+                        // In `objectBoundingBox` units, (0, 0) and (1, 1) are the corners of the
+                        // bounding box.
+                        let units_transform = match gradient_info.units {
+                            Units::UserSpaceOnUse => Transform2F::default(),
+                            Units::ObjectBoundingBox => {
+                                Transform2F::from_scale(object_bounds.size())
+                                    .translate(object_bounds.origin())
+                            }
+                        };
                         paint = Paint::from_gradient(gradient_info.gradient.clone());
-                        paint.apply_transform(&(*transform * gradient_info.transform));
+                        paint.apply_transform(&(*transform *
+                                                units_transform *
+                                                gradient_info.transform));
                     }
                     None => {
                         // TODO(pcwalton)
@@ -365,6 +706,84 @@ impl PaintExt for Paint {
     }
 }
 
+// Returns the name of the SVG element that the given node was simplified from.
+fn element_name(node: &Node) -> &'static str {
+    match *node.borrow() {
+        NodeKind::Svg(..) => "svg",
+        NodeKind::Defs => "defs",
+        NodeKind::LinearGradient(..) => "linearGradient",
+        NodeKind::RadialGradient(..) => "radialGradient",
+        NodeKind::ClipPath(..) => "clipPath",
+        NodeKind::Mask(..) => "mask",
+        NodeKind::Pattern(..) => "pattern",
+        NodeKind::Filter(..) => "filter",
+        NodeKind::Path(..) => "path",
+        NodeKind::Image(..) => "image",
+        NodeKind::Group(..) => "g",
+    }
+}
+
+// Returns an address that identifies the given node for as long as the tree is alive. Nodes
+// compare by identity but can't be hashed, so the address of their data stands in for them.
+fn node_address(node: &Node) -> usize {
+    &*node.borrow() as *const NodeKind as usize
+}
+
+// Looks up the `<pattern>` element with the given ID. usvg keeps patterns in the `<defs>` element,
+// which is the first child of the root.
+fn find_pattern_node(node: &Node, id: &str) -> Option<Node> {
+    let defs = node.root().first_child()?;
+    let pattern_node = defs.children().find(|kid| {
+        match *kid.borrow() {
+            NodeKind::Pattern(ref usvg_pattern) => usvg_pattern.id == id,
+            _ => false,
+        }
+    });
+    pattern_node
+}
+
+// Applies the `stroke-dasharray` rules: a list with an odd number of values is repeated to yield an
+// even number, and a list with a negative value or summing to zero renders the stroke solid.
+fn normalize_dasharray(dash_array: &[f64]) -> Option<Vec<f32>> {
+    let mut dash_array: Vec<f32> = dash_array.iter().map(|&x| x as f32).collect();
+    let total: f32 = dash_array.iter().sum();
+    if dash_array.iter().any(|&dash| dash < 0.0) || !(total > 0.0) {
+        return None;
+    }
+    if dash_array.len() % 2 == 1 {
+        let repeated = dash_array.clone();
+        dash_array.extend(repeated);
+    }
+    Some(dash_array)
+}
+
+// Returns the rectangle that an image of the given size occupies when fitted into `view_box` per
+// the `preserveAspectRatio` attribute.
+fn fit_to_view_box(size: Vector2F, view_box: RectF, aspect: AspectRatio) -> RectF {
+    let (x_align, y_align) = match aspect.align {
+        Align::None => return view_box,
+        Align::XMinYMin => (0.0, 0.0),
+        Align::XMidYMin => (0.5, 0.0),
+        Align::XMaxYMin => (1.0, 0.0),
+        Align::XMinYMid => (0.0, 0.5),
+        Align::XMidYMid => (0.5, 0.5),
+        Align::XMaxYMid => (1.0, 0.5),
+        Align::XMinYMax => (0.0, 1.0),
+        Align::XMidYMax => (0.5, 1.0),
+        Align::XMaxYMax => (1.0, 1.0),
+    };
+
+    let scales = view_box.size() / size;
+    let scale = if aspect.slice {
+        f32::max(scales.x(), scales.y())
+    } else {
+        f32::min(scales.x(), scales.y())
+    };
+    let fitted_size = size * scale;
+    let origin = view_box.origin() + (view_box.size() - fitted_size) * vec2f(x_align, y_align);
+    RectF::new(origin, fitted_size)
+}
+
 fn usvg_rect_to_euclid_rect(rect: &UsvgRect) -> RectF {
     RectF::new(vec2f(rect.x() as f32, rect.y() as f32),
                vec2f(rect.width() as f32, rect.height() as f32))
@@ -533,6 +952,8 @@ struct State {
     transform: Transform2F,
     // The current clip path in effect.
     clip_path: Option<ClipPathId>,
+    // The number of enclosing translucent groups being drawn to render targets.
+    group_opacity_depth: u32,
 }
 
 impl State {
@@ -541,6 +962,7 @@ impl State {
             path_destination: PathDestination::Draw,
             transform: Transform2F::default(),
             clip_path: None,
+            group_opacity_depth: 0,
         }
     }
 }
@@ -552,7 +974,276 @@ enum PathDestination {
     Clip,
 }
 
+// A pattern tile that has already been rendered.
+struct PatternTile {
+    render_target_id: RenderTargetId,
+    size: Vector2I,
+    // The transform from the pattern's content to the pixels of the render target.
+    content_transform: Transform2F,
+}
+
 struct GradientInfo {
     gradient: Gradient,
     transform: Transform2F,
+    // Whether the gradient's coordinates are in user space or relative to the bounding box of the
+    // path it paints.
+    units: Units,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{BuildResultFlags, SVGScene};
+    use pathfinder_content::gradient::GradientGeometry;
+    use pathfinder_content::pattern::PatternSource;
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{vec2f, vec2i};
+    use pathfinder_renderer::concurrent::executor::SequentialExecutor;
+    use pathfinder_renderer::gpu::options::RendererLevel;
+    use pathfinder_renderer::options::{BuildOptions, RenderCommandListener};
+    use pathfinder_renderer::scene::{DrawPathId, SceneSink};
+    use std::sync::Mutex;
+    use usvg::{Options, Tree};
+
+    #[test]
+    fn test_linear_gradient_in_bounding_box_units() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+            <linearGradient id="g">
+                <stop offset="0" stop-color="#ff0000"/>
+                <stop offset="1" stop-color="#0000ff"/>
+            </linearGradient>
+            <rect x="10" y="20" width="100" height="50" fill="url(#g)"/>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+        assert!(!svg_scene.result_flags.contains(BuildResultFlags::UNSUPPORTED_LINK_PAINT));
+
+        let scene = &svg_scene.scene;
+        let paint = scene.get_paint(scene.get_draw_path(DrawPathId(0)).paint());
+        let gradient = paint.gradient().expect("expected a gradient paint");
+        assert_eq!(gradient.stops().len(), 2);
+        match gradient.geometry {
+            GradientGeometry::Linear(line) => {
+                let expected = LineSegment2F::new(vec2f(10.0, 20.0), vec2f(110.0, 20.0));
+                assert!((line.from() - expected.from()).length() < 0.001);
+                assert!((line.to() - expected.to()).length() < 0.001);
+            }
+            _ => panic!("expected a linear gradient"),
+        }
+    }
+
+    #[test]
+    fn test_global_transform() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <rect x="10" y="20" width="30" height="40" fill="#000000"/>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let plain = SVGScene::from_tree(&tree).scene;
+        let scaled = SVGScene::from_tree_with_transform(&tree, Transform2F::from_scale(2.0)).scene;
+        let plain_bounds = plain.get_draw_path(DrawPathId(0)).outline().bounds();
+        let scaled_bounds = scaled.get_draw_path(DrawPathId(0)).outline().bounds();
+        assert_eq!(scaled_bounds, RectF::new(vec2f(20.0, 40.0), vec2f(60.0, 80.0)));
+        assert_eq!(scaled_bounds, plain_bounds * 2.0);
+        assert_eq!(scaled.view_box(), RectF::new(vec2f(0.0, 0.0), vec2f(200.0, 200.0)));
+    }
+
+    #[test]
+    fn test_view_box_preserve_aspect_ratio() {
+        let bounds = |aspect: &str| {
+            let svg = format!(r##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"
+                                     viewBox="0 0 10 10" {}>
+                <rect x="0" y="0" width="10" height="10" fill="#000000"/>
+            </svg>"##, aspect);
+            let tree = Tree::from_data(svg.as_bytes(), &Options::default()).unwrap();
+            let scene = SVGScene::from_tree(&tree).scene;
+            assert_eq!(scene.view_box(), RectF::new(vec2f(0.0, 0.0), vec2f(200.0, 100.0)));
+            scene.get_draw_path(DrawPathId(0)).outline().bounds()
+        };
+
+        // The default is `xMidYMid meet`: uniformly scaled and centered horizontally.
+        assert_eq!(bounds(""), RectF::new(vec2f(50.0, 0.0), vec2f(100.0, 100.0)));
+        assert_eq!(bounds(r#"preserveAspectRatio="xMinYMin meet""#),
+                   RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 100.0)));
+        assert_eq!(bounds(r#"preserveAspectRatio="none""#),
+                   RectF::new(vec2f(0.0, 0.0), vec2f(200.0, 100.0)));
+    }
+
+    #[test]
+    fn test_stroke_dasharray() {
+        let contour_count = |stroke_attributes: &str| {
+            let svg = format!(r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <rect x="10" y="10" width="40" height="40" fill="none" stroke="#000000" {}/>
+            </svg>"##, stroke_attributes);
+            let tree = Tree::from_data(svg.as_bytes(), &Options::default()).unwrap();
+            let scene = SVGScene::from_tree(&tree).scene;
+            scene.get_draw_path(DrawPathId(0)).outline().contours().len()
+        };
+
+        let solid = contour_count("");
+        assert!(contour_count(r#"stroke-dasharray="4 2""#) > solid);
+        assert!(contour_count(r#"stroke-dasharray="4 2" stroke-dashoffset="3""#) > solid);
+        // An all-zero dash list draws a solid stroke.
+        assert_eq!(contour_count(r#"stroke-dasharray="0 0""#), solid);
+    }
+
+    #[test]
+    fn test_nested_clip_paths() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <clipPath id="left"><rect x="0" y="0" width="60" height="100"/></clipPath>
+            <clipPath id="right"><rect x="40" y="0" width="60" height="100"/></clipPath>
+            <g clip-path="url(#left)">
+                <g clip-path="url(#right)">
+                    <rect x="0" y="0" width="100" height="100" fill="#000000"/>
+                </g>
+            </g>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+        assert!(svg_scene.result_flags.is_empty());
+
+        // Only the intersection of the two clips is drawn.
+        let scene = &svg_scene.scene;
+        assert!(scene.pick_draw_path(vec2f(50.0, 50.0)).is_some());
+        assert!(scene.pick_draw_path(vec2f(20.0, 50.0)).is_none());
+        assert!(scene.pick_draw_path(vec2f(80.0, 50.0)).is_none());
+    }
+
+    #[test]
+    fn test_group_opacity() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <g opacity="0.5">
+                <rect x="10" y="10" width="50" height="50" fill="#ff0000" fill-opacity="0.8"/>
+                <rect x="30" y="30" width="50" height="50" fill="#0000ff" fill-opacity="0.8"/>
+            </g>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+        assert!(svg_scene.result_flags.is_empty());
+
+        // The children keep their own opacity; the group's is applied once, when compositing.
+        let scene = &svg_scene.scene;
+        assert_eq!(scene.draw_path_count(), 3);
+        let child_paint = scene.get_paint(scene.get_draw_path(DrawPathId(0)).paint());
+        assert_eq!(child_paint.base_color().a, 204);
+        let group_paint = scene.get_paint(scene.get_draw_path(DrawPathId(2)).paint());
+        assert!(group_paint.pattern().is_some());
+        assert_eq!(group_paint.base_color().a, 128);
+    }
+
+    #[test]
+    fn test_image() {
+        // A 1×1 PNG.
+        let png = concat!("iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJ",
+                          "AAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==");
+        let svg = format!(r##"<svg xmlns="http://www.w3.org/2000/svg"
+                xmlns:xlink="http://www.w3.org/1999/xlink" width="100" height="100">
+            <image x="10" y="10" width="40" height="20"
+                   xlink:href="data:image/png;base64,{}"/>
+        </svg>"##, png);
+        let tree = Tree::from_data(svg.as_bytes(), &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+        assert!(svg_scene.result_flags.is_empty());
+
+        // The square image is centered in its viewport by the default `xMidYMid meet`.
+        let scene = &svg_scene.scene;
+        let draw_path = scene.get_draw_path(DrawPathId(0));
+        assert_eq!(draw_path.outline().bounds(),
+                   RectF::new(vec2f(20.0, 10.0), vec2f(20.0, 20.0)));
+        let pattern = scene.get_paint(draw_path.paint()).pattern().expect("expected a pattern");
+        assert_eq!(pattern.size(), vec2i(1, 1));
+
+        // Building the scene declares a texture page for the image and uploads its one texel.
+        let command_names = Mutex::new(vec![]);
+        let listener = RenderCommandListener::new(Box::new(|render_command| {
+            command_names.lock().unwrap().push(format!("{:?}", render_command));
+        }));
+        let mut sink = SceneSink::new(listener, RendererLevel::D3D9);
+        let mut scene = svg_scene.scene;
+        scene.build(BuildOptions::default(), &mut sink, &SequentialExecutor);
+        drop(sink);
+        let command_names = command_names.into_inner().unwrap();
+        assert!(command_names.iter().any(|name| name.starts_with("AllocateTexturePage(")));
+        assert!(command_names.iter().any(|name| name.starts_with("UploadTexelData(x1,")));
+    }
+
+    #[test]
+    fn test_unsupported_node_ids() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <mask id="fade"><rect width="100" height="100" fill="#ffffff"/></mask>
+            <pattern id="dots" width="10" height="10" patternUnits="userSpaceOnUse">
+                <circle cx="5" cy="5" r="2" fill="#000000"/>
+            </pattern>
+            <g id="masked" mask="url(#fade)">
+                <rect x="0" y="0" width="50" height="50" fill="#ff0000"/>
+            </g>
+            <rect id="dotted" x="50" y="50" width="50" height="50" fill="url(#dots)"/>
+            <rect id="plain" x="0" y="50" width="50" height="50" fill="#00ff00"/>
+            <g mask="url(#fade)">
+                <rect x="50" y="0" width="50" height="50" fill="#0000ff"/>
+            </g>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+
+        let flags_for = |id: &str| {
+            svg_scene.unsupported_nodes
+                     .iter()
+                     .find(|(node_id, _)| node_id == id)
+                     .map(|&(_, flags)| flags)
+        };
+        assert_eq!(flags_for("fade"), Some(BuildResultFlags::UNSUPPORTED_MASK_NODE));
+        assert_eq!(flags_for("masked"), Some(BuildResultFlags::UNSUPPORTED_MASK_ATTR));
+        assert_eq!(flags_for("dots"), None);
+        assert_eq!(flags_for("dotted"), None);
+        assert_eq!(flags_for("plain"), None);
+
+        // The anonymous masked group gets a positional key rather than an empty one.
+        assert!(flags_for("").is_none());
+        let anonymous_keys: Vec<&str> = svg_scene.unsupported_nodes
+                                                 .iter()
+                                                 .map(|(key, _)| key.as_str())
+                                                 .filter(|key| key.starts_with("g["))
+                                                 .collect();
+        assert_eq!(anonymous_keys.len(), 1);
+        assert!(anonymous_keys[0].ends_with(']'));
+        assert_eq!(flags_for(anonymous_keys[0]), Some(BuildResultFlags::UNSUPPORTED_MASK_ATTR));
+
+        // The aggregate flags are still reported.
+        let all_flags = svg_scene.unsupported_nodes
+                                 .iter()
+                                 .fold(BuildResultFlags::empty(), |all, &(_, flags)| all | flags);
+        assert_eq!(svg_scene.result_flags, all_flags);
+    }
+
+    #[test]
+    fn test_pattern_fill() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <pattern id="checkerboard" width="20" height="20" patternUnits="userSpaceOnUse">
+                <rect x="0" y="0" width="10" height="10" fill="#000000"/>
+                <rect x="10" y="10" width="10" height="10" fill="#000000"/>
+            </pattern>
+            <rect x="0" y="0" width="100" height="100" fill="url(#checkerboard)"/>
+            <rect x="20" y="20" width="40" height="40" fill="url(#checkerboard)"/>
+        </svg>"##;
+        let tree = Tree::from_data(svg, &Options::default()).unwrap();
+        let svg_scene = SVGScene::from_tree(&tree);
+        assert!(svg_scene.unsupported_nodes.is_empty());
+
+        // The tile is rendered once, ahead of both paths that repeat it.
+        let scene = &svg_scene.scene;
+        assert_eq!(scene.draw_path_count(), 4);
+        let first_pattern = scene.get_paint(scene.get_draw_path(DrawPathId(2)).paint())
+                                 .pattern()
+                                 .expect("expected a pattern paint");
+        assert!(first_pattern.repeat_x() && first_pattern.repeat_y());
+        match *first_pattern.source() {
+            PatternSource::RenderTarget { size, .. } => assert_eq!(size, vec2i(20, 20)),
+            _ => panic!("expected a render target pattern"),
+        }
+        let second_pattern = scene.get_paint(scene.get_draw_path(DrawPathId(3)).paint())
+                                  .pattern()
+                                  .expect("expected a pattern paint");
+        assert_eq!(second_pattern.source(), first_pattern.source());
+    }
 }
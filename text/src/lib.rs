@@ -77,15 +77,14 @@ impl<F> FontContext<F> where F: Loader {
         FontContext { font_info: HashMap::new() }
     }
 
-    fn push_glyph(&mut self,
-                  scene: &mut Scene,
-                  font: &F,
-                  font_key: Option<&str>,
-                  glyph_id: GlyphId,
-                  glyph_offset: Vector2F,
-                  font_size: f32,
-                  render_options: &FontRenderOptions)
-                  -> Result<(), GlyphLoadingError> {
+    fn glyph_outline(&mut self,
+                     font: &F,
+                     font_key: Option<&str>,
+                     glyph_id: GlyphId,
+                     glyph_offset: Vector2F,
+                     font_size: f32,
+                     render_options: &FontRenderOptions)
+                     -> Result<Outline, GlyphLoadingError> {
         // Insert the font into the cache if needed.
         let mut font_info = match font_key {
             Some(font_key) => {
@@ -148,12 +147,7 @@ impl<F> FontContext<F> where F: Loader {
             outline = stroke_to_fill.into_outline();
         }
 
-        let mut path = DrawPath::new(outline, render_options.paint_id);
-        path.set_clip_path(render_options.clip_path);
-        path.set_blend_mode(render_options.blend_mode);
-
-        scene.push_draw_path(path);
-        Ok(())
+        Ok(outline)
     }
 
     /// Attempts to look up a font in the font cache.
@@ -170,6 +164,38 @@ impl FontContext<DefaultLoader> {
                        style: &TextStyle,
                        render_options: &FontRenderOptions)
                        -> Result<(), GlyphLoadingError> {
+        self.process_layout(layout, style, render_options, |outline| {
+            let mut path = DrawPath::new(outline, render_options.paint_id);
+            path.set_clip_path(render_options.clip_path);
+            path.set_blend_mode(render_options.blend_mode);
+            scene.push_draw_path(path);
+        })
+    }
+
+    /// Returns the outlines of all the glyphs in the layout, combined into one outline, instead
+    /// of adding them to a scene.
+    ///
+    /// The transform and render mode of `render_options` are applied. Its paint, clip path, and
+    /// blend mode are ignored.
+    pub fn layout_outline(&mut self,
+                          layout: &Layout,
+                          style: &TextStyle,
+                          render_options: &FontRenderOptions)
+                          -> Result<Outline, GlyphLoadingError> {
+        let mut outline = Outline::new();
+        self.process_layout(layout, style, render_options, |glyph_outline| {
+            outline.push_outline(glyph_outline)
+        })?;
+        Ok(outline)
+    }
+
+    fn process_layout<P>(&mut self,
+                         layout: &Layout,
+                         style: &TextStyle,
+                         render_options: &FontRenderOptions,
+                         mut process_glyph_outline: P)
+                         -> Result<(), GlyphLoadingError>
+                         where P: FnMut(Outline) {
         let mut cached_font_key: Option<CachedFontKey<DefaultLoader>> = None;
         for glyph in &layout.glyphs {
             match cached_font_key {
@@ -183,13 +209,12 @@ impl FontContext<DefaultLoader> {
                 }
             }
             let cached_font_key = cached_font_key.as_ref().unwrap();
-            self.push_glyph(scene,
-                            &*cached_font_key.font,
-                            cached_font_key.key.as_ref().map(|key| &**key),
-                            GlyphId(glyph.glyph_id),
-                            glyph.offset,
-                            style.size,
-                            &render_options)?;
+            process_glyph_outline(self.glyph_outline(&*cached_font_key.font,
+                                                     cached_font_key.key.as_ref().map(|key| &**key),
+                                                     GlyphId(glyph.glyph_id),
+                                                     glyph.offset,
+                                                     style.size,
+                                                     &render_options)?);
         }
         Ok(())
     }
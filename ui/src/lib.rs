@@ -139,6 +139,37 @@ impl<D> UIPresenter<D> where D: Device {
         self.draw_rect(device, allocator, rect, color, false);
     }
 
+    /// Draws the outlines of many rectangles at once, in a single draw call.
+    pub fn draw_rect_outlines(&self,
+                              device: &D,
+                              allocator: &mut GPUMemoryAllocator<D>,
+                              rects: &[RectI],
+                              color: ColorU) {
+        if rects.is_empty() {
+            return;
+        }
+
+        let mut vertex_data = Vec::with_capacity(rects.len() * 4);
+        let mut index_data = Vec::with_capacity(rects.len() * RECT_LINE_INDICES.len());
+        for rect in rects {
+            let first_vertex_index = vertex_data.len() as u32;
+            vertex_data.extend_from_slice(&[
+                DebugSolidVertex::new(rect.origin()),
+                DebugSolidVertex::new(rect.upper_right()),
+                DebugSolidVertex::new(rect.lower_right()),
+                DebugSolidVertex::new(rect.lower_left()),
+            ]);
+            index_data.extend(RECT_LINE_INDICES.iter().map(|index| first_vertex_index + index));
+        }
+
+        self.draw_solid_rects_with_vertex_data(device,
+                                               allocator,
+                                               &vertex_data,
+                                               &index_data,
+                                               color,
+                                               false);
+    }
+
     fn draw_rect(&self,
                  device: &D,
                  allocator: &mut GPUMemoryAllocator<D>,
@@ -18,33 +18,99 @@ use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::vector::Vector2I;
 use pathfinder_gpu::{BlendFactor, BlendOp, BufferData, BufferTarget, BufferUploadMode, ClearOps};
 use pathfinder_gpu::{ComputeDimensions, ComputeState, DepthFunc, Device, FeatureLevel};
+use pathfinder_gpu::FramebufferError;
 use pathfinder_gpu::{ImageBinding, Primitive, ProgramKind, RenderOptions, RenderState};
 use pathfinder_gpu::{RenderTarget, ShaderKind, StencilFunc, TextureBinding, TextureData};
-use pathfinder_gpu::{TextureDataRef, TextureFormat, TextureSamplingFlags, UniformData};
-use pathfinder_gpu::{VertexAttrClass, VertexAttrDescriptor, VertexAttrType};
+use pathfinder_gpu::{TextureDataRef, TextureFormat, TextureFormatSet, TextureSamplingFlags};
+use pathfinder_gpu::{UniformData, VertexAttrClass, VertexAttrDescriptor, VertexAttrType};
 use pathfinder_resources::ResourceLoader;
 use std::cell::RefCell;
 use std::mem;
 use std::ops::Range;
 use std::str;
 use std::time::Duration;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys::WebGl2RenderingContext as WebGl;
 
+// Enums from `EXT_disjoint_timer_query_webgl2`, which `web_sys` doesn't define.
+const TIME_ELAPSED_EXT: u32 = 0x88bf;
+const GPU_DISJOINT_EXT: u32 = 0x8fbb;
+
+/// A device that renders with a WebGL 2 context.
+///
+/// The browser may lose the context at any time, for example on a GPU reset, invalidating every
+/// GPU resource. While the context is lost, this device's methods do nothing rather than panic, so
+/// that a frame in progress can finish harmlessly. To recover:
+///
+/// 1. Listen for the `webglcontextlost` event on the canvas and call `preventDefault()` on it.
+///    Otherwise, the browser will never restore the context.
+///
+/// 2. Skip rendering while `context_lost()` returns true.
+///
+/// 3. On the `webglcontextrestored` event, drop the old `Renderer` and device, and create a new
+///    `WebGlDevice` and `Renderer` from the same context. This recreates all programs, buffers, and
+///    textures. Scenes live in CPU memory, so they can simply be rendered again.
 pub struct WebGlDevice {
     context: web_sys::WebGl2RenderingContext,
+    supported_texture_formats: TextureFormatSet,
+    max_texture_size: i32,
+    timer_queries_supported: bool,
 }
 
 impl WebGlDevice {
     pub fn new(context: web_sys::WebGl2RenderingContext) -> Self {
-        context.get_extension("EXT_color_buffer_float").unwrap();
-        WebGlDevice { context }
+        let supported_texture_formats = probe_supported_texture_formats(&context);
+        let max_texture_size = context.get_parameter(WebGl::MAX_TEXTURE_SIZE)
+                                      .ok()
+                                      .and_then(|value| value.as_f64())
+                                      .map_or(4096, |value| value as i32);
+        // Requesting the extension is what enables it.
+        let timer_queries_supported =
+            context.get_extension("EXT_disjoint_timer_query_webgl2").unwrap_or(None).is_some();
+        WebGlDevice {
+            context,
+            supported_texture_formats,
+            max_texture_size,
+            timer_queries_supported,
+        }
+    }
+
+    /// Returns true if 16-bit float textures can be rendered to and blended into.
+    ///
+    /// This requires the `EXT_color_buffer_float` extension, which is enabled when the device is
+    /// created. Without it, the renderer can't use a float mask framebuffer, so callers should
+    /// choose options that avoid one.
+    #[inline]
+    pub fn supports_float_blending(&self) -> bool {
+        self.supported_texture_formats.contains(TextureFormatSet::R16F | TextureFormatSet::RGBA16F)
+    }
+
+    /// Returns true if the WebGL context has been lost and not yet restored.
+    ///
+    /// See the documentation of `WebGlDevice` for how to recover.
+    #[inline]
+    pub fn context_lost(&self) -> bool {
+        self.context.is_context_lost()
+    }
+
+    // WebGL returns null from object creation while the context is lost. A null handle stands in
+    // for the object: the device only passes it to methods that accept null, and never queries it.
+    fn null_handle_if_context_lost<T>(&self, kind: &str) -> T where T: JsCast {
+        if !self.context_lost() {
+            panic!("could not create {}", kind);
+        }
+        warn!("could not create {} because the WebGL context is lost", kind);
+        JsValue::NULL.unchecked_into()
     }
 
     // Error checking
 
     #[cfg(debug_assertions)]
     fn ck(&self) {
+        // Every call reports `CONTEXT_LOST_WEBGL` once the context is lost.
+        if self.context_lost() {
+            return;
+        }
         let mut num_errors = 0;
         loop {
             let err = self.context.get_error();
@@ -396,6 +462,7 @@ unsafe fn check_and_extract_data(
 ) -> Object {
     let channels = match (format, data_ref) {
         (TextureFormat::R8, TextureDataRef::U8(_)) => 1,
+        (TextureFormat::R16F, TextureDataRef::F16(_)) => 1,
         (TextureFormat::RGBA8, TextureDataRef::U8(_)) => 4,
         (TextureFormat::RGBA16F, TextureDataRef::F16(_)) => 4,
         (TextureFormat::RGBA32F, TextureDataRef::F32(_)) => 4,
@@ -453,8 +520,18 @@ impl Device for WebGlDevice {
         FeatureLevel::D3D10
     }
 
+    fn max_texture_size(&self) -> i32 {
+        self.max_texture_size
+    }
+
+    fn supported_texture_formats(&self) -> TextureFormatSet {
+        self.supported_texture_formats
+    }
+
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> WebGlTexture {
-        let texture = self.context.create_texture().unwrap();
+        let texture = self.context
+                          .create_texture()
+                          .unwrap_or_else(|| self.null_handle_if_context_lost("texture"));
         let texture = WebGlTexture {
             texture,
             format,
@@ -480,6 +557,35 @@ impl Device for WebGlDevice {
         texture
     }
 
+    fn try_create_texture(&self, format: TextureFormat, size: Vector2I) -> Option<WebGlTexture> {
+        let texture = WebGlTexture {
+            texture: self.context.create_texture()?,
+            format,
+            size,
+            context: self.context.clone(),
+        };
+        self.bind_texture(&texture, 0);
+        self.context
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                WebGl::TEXTURE_2D,
+                0,
+                format.gl_internal_format() as i32,
+                size.x(),
+                size.y(),
+                0,
+                format.gl_format(),
+                format.gl_type(),
+                None,
+            )
+            .ok()?;
+        if self.context.get_error() == WebGl::OUT_OF_MEMORY {
+            return None;
+        }
+
+        self.set_texture_sampling_mode(&texture, TextureSamplingFlags::empty());
+        Some(texture)
+    }
+
     fn create_texture_from_data(
         &self,
         format: TextureFormat,
@@ -490,7 +596,9 @@ impl Device for WebGlDevice {
             check_and_extract_data(data_ref, size, format)
         };
 
-        let texture = self.context.create_texture().unwrap();
+        let texture = self.context
+                          .create_texture()
+                          .unwrap_or_else(|| self.null_handle_if_context_lost("texture"));
         let texture = WebGlTexture {
             texture,
             format,
@@ -541,13 +649,16 @@ impl Device for WebGlDevice {
         let gl_shader = self
             .context
             .create_shader(gl_shader_kind)
-            .expect("could not create shader");
+            .unwrap_or_else(|| self.null_handle_if_context_lost("shader"));
+        if self.context_lost() {
+            return WebGlShader { gl_shader };
+        }
         self.context.shader_source(&gl_shader, &source);
         self.context.compile_shader(&gl_shader);
         let compile_status = self
             .context
             .get_shader_parameter(&gl_shader, WebGl::COMPILE_STATUS);
-        if !compile_status.as_bool().unwrap_or(false) {
+        if !compile_status.as_bool().unwrap_or(false) && !self.context_lost() {
             if let Some(info_log) = self.context.get_shader_info_log(&gl_shader) {
                 info!("Shader info log:\n{}", info_log);
             }
@@ -566,7 +677,16 @@ impl Device for WebGlDevice {
         let gl_program = self
             .context
             .create_program()
-            .expect("unable to create program object");
+            .unwrap_or_else(|| self.null_handle_if_context_lost("program object"));
+        let parameters = WebGlProgramParameters { textures: vec![] };
+        if self.context_lost() {
+            return WebGlProgram {
+                context: self.context.clone(),
+                gl_program,
+                parameters: RefCell::new(parameters),
+            };
+        }
+
         match shaders {
             ProgramKind::Raster { ref vertex, ref fragment } => {
                 self.context.attach_shader(&gl_program, &vertex.gl_shader);
@@ -581,7 +701,7 @@ impl Device for WebGlDevice {
             .context
             .get_program_parameter(&gl_program, WebGl::LINK_STATUS)
             .as_bool()
-            .unwrap_or(false)
+            .unwrap_or(false) && !self.context_lost()
         {
             if let Some(info_log) = self.context.get_program_info_log(&gl_program) {
                 info!("Program info log for {}:\n{}", name, info_log);
@@ -589,8 +709,6 @@ impl Device for WebGlDevice {
             panic!("Program {:?} linking failed", name);
         }
 
-        let parameters = WebGlProgramParameters { textures: vec![] };
-
         WebGlProgram {
             context: self.context.clone(),
             gl_program,
@@ -607,11 +725,17 @@ impl Device for WebGlDevice {
     fn create_vertex_array(&self) -> WebGlVertexArray {
         WebGlVertexArray {
             context: self.context.clone(),
-            gl_vertex_array: self.context.create_vertex_array().unwrap(),
+            gl_vertex_array: self.context.create_vertex_array().unwrap_or_else(|| {
+                self.null_handle_if_context_lost("vertex array")
+            }),
         }
     }
 
     fn get_vertex_attr(&self, program: &WebGlProgram, name: &str) -> Option<WebGlVertexAttr> {
+        // Attributes can't be queried while the context is lost, but callers expect them to exist.
+        if self.context_lost() {
+            return Some(WebGlVertexAttr { attr: 0 });
+        }
         let name = format!("a{}", name);
         let attr = self.context.get_attrib_location(&program.gl_program, &name);
         if attr < 0 {
@@ -621,6 +745,9 @@ impl Device for WebGlDevice {
     }
 
     fn get_uniform(&self, program: &WebGlProgram, name: &str) -> WebGlUniform {
+        if self.context_lost() {
+            return WebGlUniform { location: None };
+        }
         let name = format!("u{}", name);
         let location = self.context.get_uniform_location(&program.gl_program, &name);
         self.ck();
@@ -691,11 +818,29 @@ impl Device for WebGlDevice {
     }
 
     fn create_framebuffer(&self, texture: WebGlTexture) -> WebGlFramebuffer {
+        match self.try_create_framebuffer(texture) {
+            Ok(framebuffer) => framebuffer,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    // Rendering to float textures requires the `EXT_color_buffer_float` extension (and, for
+    // 32-bit formats, `OES_texture_float_linear`). Attaching one without it would only produce an
+    // incomplete framebuffer, so report the actual cause. The texture is dropped, and thereby
+    // deleted, with the error; callers that can check the format beforehand, as the allocator
+    // does, avoid creating it in the first place.
+    fn try_create_framebuffer(&self, texture: WebGlTexture)
+                              -> Result<WebGlFramebuffer, FramebufferError> {
         debug!(
             "texture size = {:?}, format = {:?}",
             texture.size, texture.format
         );
-        let gl_framebuffer = self.context.create_framebuffer().unwrap();
+        if !self.supported_texture_formats.contains_format(texture.format) {
+            return Err(FramebufferError::UnsupportedFormat(texture.format));
+        }
+        let gl_framebuffer = self.context.create_framebuffer().unwrap_or_else(|| {
+            self.null_handle_if_context_lost("framebuffer")
+        });
         self.context
             .bind_framebuffer(WebGl::FRAMEBUFFER, Some(&gl_framebuffer));
         self.bind_texture(&texture, 0);
@@ -710,6 +855,7 @@ impl Device for WebGlDevice {
         self.ck();
         match self.context.check_framebuffer_status(WebGl::FRAMEBUFFER) {
             WebGl::FRAMEBUFFER_COMPLETE => {}
+            _ if self.context_lost() => {}
             WebGl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => panic!("FRAMEBUFFER_INCOMPLETE_ATTACHMENT"),
             WebGl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
                 panic!("FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT")
@@ -723,10 +869,10 @@ impl Device for WebGlDevice {
             code => panic!("unknown code {}", code),
         }
 
-        WebGlFramebuffer {
+        Ok(WebGlFramebuffer {
             framebuffer: gl_framebuffer,
             texture,
-        }
+        })
     }
 
     fn destroy_framebuffer(&self, framebuffer: Self::Framebuffer) -> Self::Texture {
@@ -736,7 +882,9 @@ impl Device for WebGlDevice {
     }
 
     fn create_buffer(&self, mode: BufferUploadMode) -> WebGlBuffer {
-        let buffer = self.context.create_buffer().unwrap();
+        let buffer = self.context
+                         .create_buffer()
+                         .unwrap_or_else(|| self.null_handle_if_context_lost("buffer"));
         WebGlBuffer {
             buffer,
             context: self.context.clone(),
@@ -926,30 +1074,60 @@ impl Device for WebGlDevice {
         panic!("Compute shader is unsupported in WebGL!")
     }
 
-    #[inline]
     fn create_timer_query(&self) -> WebGlTimerQuery {
-        // FIXME use performance timers
-        WebGlTimerQuery {}
+        let mut gl_query = None;
+        if self.timer_queries_supported {
+            gl_query = self.context.create_query();
+            self.ck();
+        }
+        WebGlTimerQuery { context: self.context.clone(), gl_query }
     }
 
     #[inline]
-    fn begin_timer_query(&self, _query: &Self::TimerQuery) {
-        // FIXME use performance timers
+    fn begin_timer_query(&self, query: &Self::TimerQuery) {
+        if let Some(ref gl_query) = query.gl_query {
+            self.context.begin_query(TIME_ELAPSED_EXT, gl_query);
+            self.ck();
+        }
     }
 
     #[inline]
-    fn end_timer_query(&self, _: &Self::TimerQuery) {
-        // FIXME use performance timers
+    fn end_timer_query(&self, query: &Self::TimerQuery) {
+        if query.gl_query.is_some() {
+            self.context.end_query(TIME_ELAPSED_EXT);
+            self.ck();
+        }
     }
 
-    #[inline]
-    fn try_recv_timer_query(&self, _query: &WebGlTimerQuery) -> Option<Duration> {
-        None
+    // Without `EXT_disjoint_timer_query_webgl2`, queries never complete.
+    fn try_recv_timer_query(&self, query: &WebGlTimerQuery) -> Option<Duration> {
+        let gl_query = query.gl_query.as_ref()?;
+        let available = self.context.get_query_parameter(gl_query, WebGl::QUERY_RESULT_AVAILABLE);
+        self.ck();
+        if available.as_bool() == Some(true) {
+            Some(self.recv_timer_query(query))
+        } else {
+            None
+        }
     }
 
-    #[inline]
-    fn recv_timer_query(&self, _query: &WebGlTimerQuery) -> Duration {
-        Duration::from_millis(0)
+    // WebGL can't block on a query, so this returns zero if the result isn't available yet. A
+    // disjoint operation, such as a GPU frequency change, invalidates results, which are also
+    // reported as zero.
+    fn recv_timer_query(&self, query: &WebGlTimerQuery) -> Duration {
+        let gl_query = match query.gl_query {
+            None => return Duration::from_millis(0),
+            Some(ref gl_query) => gl_query,
+        };
+        let disjoint = self.context.get_parameter(GPU_DISJOINT_EXT).ok()
+                                   .and_then(|value| value.as_bool())
+                                   .unwrap_or(false);
+        if disjoint {
+            return Duration::from_millis(0);
+        }
+        let result = self.context.get_query_parameter(gl_query, WebGl::QUERY_RESULT);
+        self.ck();
+        Duration::from_nanos(result.as_f64().map_or(0, |nanos| nanos as u64))
     }
 
     fn try_recv_buffer(&self, _: &()) -> Option<Vec<u8>> {
@@ -1088,7 +1266,19 @@ impl Drop for WebGlTexture {
     }
 }
 
-pub struct WebGlTimerQuery {}
+pub struct WebGlTimerQuery {
+    context: web_sys::WebGl2RenderingContext,
+    // `None` if timer queries are unsupported.
+    gl_query: Option<web_sys::WebGlQuery>,
+}
+
+impl Drop for WebGlTimerQuery {
+    fn drop(&mut self) {
+        if let Some(ref gl_query) = self.gl_query {
+            self.context.delete_query(Some(gl_query));
+        }
+    }
+}
 
 trait BufferTargetExt {
     fn to_gl_target(self) -> u32;
@@ -1113,6 +1303,7 @@ impl BufferUploadModeExt for BufferUploadMode {
         match self {
             BufferUploadMode::Static => WebGl::STATIC_DRAW,
             BufferUploadMode::Dynamic => WebGl::DYNAMIC_DRAW,
+            BufferUploadMode::Stream => WebGl::STREAM_DRAW,
         }
     }
 }
@@ -1156,6 +1347,21 @@ impl StencilFuncExt for StencilFunc {
     }
 }
 
+// Float formats are only renderable with `EXT_color_buffer_float`, and 32-bit float formats are
+// only filterable with `OES_texture_float_linear`.
+fn probe_supported_texture_formats(context: &web_sys::WebGl2RenderingContext)
+                                   -> TextureFormatSet {
+    let has_extension = |name: &str| context.get_extension(name).unwrap_or(None).is_some();
+    let mut formats = TextureFormatSet::R8 | TextureFormatSet::RGBA8;
+    if has_extension("EXT_color_buffer_float") {
+        formats |= TextureFormatSet::R16F | TextureFormatSet::RGBA16F;
+        if has_extension("OES_texture_float_linear") {
+            formats |= TextureFormatSet::RGBA32F;
+        }
+    }
+    formats
+}
+
 trait TextureFormatExt {
     fn gl_internal_format(self) -> u32;
     fn gl_format(self) -> u32;